@@ -48,37 +48,6 @@ impl BlockMem {
             file.write_all(block).unwrap();
         }
     }
-    /// Make an ext4 filesystem on the block device
-    pub fn mkfs(&self) {
-        let path = "tmp.img";
-        let mut mem = self.0.lock().unwrap();
-        // Create a temp block file
-        std::process::Command::new("dd")
-            .args([
-                "if=/dev/zero",
-                &format!("of={}", path),
-                &format!("bs={}", BLOCK_SIZE),
-                &format!("count={}", mem.len()),
-            ])
-            .status()
-            .expect("Failed to create temp file");
-        // Make ext4 fs
-        std::process::Command::new("mkfs.ext4")
-            // Enable 64-bit feature and use 256-bit inode size
-            .args([path, &format!("-b {}", BLOCK_SIZE), "-O 64bit", "-I 256"])
-            .status()
-            .expect("Failed to make ext4 fs");
-        // Open the temp file and copy data to memory
-        let mut file = OpenOptions::new().read(true).open(path).unwrap();
-        for block in mem.iter_mut() {
-            file.read(block).expect("Read failed");
-        }
-        // Remove the temp file
-        std::process::Command::new("rm")
-            .args(["-rf", path])
-            .status()
-            .expect("Failed to remove temp file");
-    }
 }
 
 impl BlockDevice for BlockMem {
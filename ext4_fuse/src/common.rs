@@ -1,4 +1,6 @@
-use another_ext4::{FileAttr as Ext4FileAttr, FileType as Ext4FileType, INODE_BLOCK_SIZE};
+use another_ext4::{
+    FileAttr as Ext4FileAttr, FileType as Ext4FileType, TimeSource, Timestamp, INODE_BLOCK_SIZE,
+};
 use fuser::{FileAttr, FileType, TimeOrNow};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -20,10 +22,10 @@ pub fn translate_attr(attr: Ext4FileAttr) -> FileAttr {
         ino: attr.ino as u64,
         size: attr.size,
         blocks: attr.blocks,
-        atime: second2sys_time(attr.atime),
-        mtime: second2sys_time(attr.mtime),
-        ctime: second2sys_time(attr.ctime),
-        crtime: second2sys_time(attr.crtime),
+        atime: timestamp2sys_time(attr.atime),
+        mtime: timestamp2sys_time(attr.mtime),
+        ctime: timestamp2sys_time(attr.ctime),
+        crtime: timestamp2sys_time(attr.crtime),
         kind: translate_ftype(attr.ftype),
         perm: attr.perm.bits(),
         nlink: attr.links as u32,
@@ -35,17 +37,42 @@ pub fn translate_attr(attr: Ext4FileAttr) -> FileAttr {
     }
 }
 
-pub fn sys_time2second(time: SystemTime) -> u32 {
-    time.duration_since(UNIX_EPOCH).unwrap().as_secs() as u32
+pub fn sys_time2timestamp(time: SystemTime) -> Timestamp {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => Timestamp::new(d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => {
+            // Before the epoch, round down to the previous whole second
+            let d = e.duration();
+            if d.subsec_nanos() == 0 {
+                Timestamp::new(-(d.as_secs() as i64), 0)
+            } else {
+                Timestamp::new(-(d.as_secs() as i64) - 1, 1_000_000_000 - d.subsec_nanos())
+            }
+        }
+    }
 }
 
-pub fn second2sys_time(time: u32) -> SystemTime {
-    SystemTime::UNIX_EPOCH + Duration::from_secs(time as u64)
+pub fn timestamp2sys_time(time: Timestamp) -> SystemTime {
+    if time.sec >= 0 {
+        UNIX_EPOCH + Duration::new(time.sec as u64, time.nsec)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(time.sec.unsigned_abs())
+            + Duration::from_nanos(time.nsec as u64)
+    }
 }
 
-pub fn time_or_now2second(time_or_now: TimeOrNow) -> u32 {
+pub fn time_or_now2timestamp(time_or_now: TimeOrNow) -> Timestamp {
     match time_or_now {
-        fuser::TimeOrNow::Now => sys_time2second(SystemTime::now()),
-        fuser::TimeOrNow::SpecificTime(time) => sys_time2second(time),
+        fuser::TimeOrNow::Now => sys_time2timestamp(SystemTime::now()),
+        fuser::TimeOrNow::SpecificTime(time) => sys_time2timestamp(time),
+    }
+}
+
+/// Time source backed by the system wall clock
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> Timestamp {
+        sys_time2timestamp(SystemTime::now())
     }
 }
@@ -15,9 +15,14 @@
 //! can save and restore checkpoint states like `RefFS`, and thus support
 //! Metis model check.
 
-use super::common::{sys_time2second, time_or_now2second, translate_attr, translate_ftype};
+use super::common::{
+    sys_time2timestamp, time_or_now2timestamp, translate_attr, translate_ftype,
+};
 use crate::block_dev::StateBlockDevice;
-use another_ext4::{ErrCode, Ext4, Ext4Error, FileType as Ext4FileType, InodeMode};
+use another_ext4::{
+    AccessMode, Credentials, ErrCode, Ext4, Ext4Error, FileType as Ext4FileType, InodeFlags,
+    InodeMode, MountOptions, SetAttr, TimeSource,
+};
 use fuser::{
     FileAttr, FileType, Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty,
     ReplyEntry, ReplyOpen, ReplyWrite, Request,
@@ -46,15 +51,19 @@ pub struct StateExt4FuseFs<T> {
 impl<T: 'static> StateExt4FuseFs<T> {
     const CHECKPOINT_IOC: u32 = 1;
     const RESTORE_IOC: u32 = 2;
-
-    /// Create a file system on a block device
-    /// 
-    /// `init` - If true, initialize the filesystem
-    pub fn new(block_dev: Arc<dyn StateBlockDevice<T>>, init: bool) -> Self {
-        let mut fs = Ext4::load(block_dev.clone()).expect("Failed to load ext4 filesystem");
-        if init {
-            fs.init().expect("Failed to init ext4 filesystem");
-        }
+    /// `FS_IOC_GETFLAGS`
+    const GETFLAGS_IOC: u32 = 0x80086601;
+    /// `FS_IOC_SETFLAGS`
+    const SETFLAGS_IOC: u32 = 0x40086602;
+
+    /// Create a file system on a formatted block device
+    pub fn new(
+        block_dev: Arc<dyn StateBlockDevice<T>>,
+        time_source: Arc<dyn TimeSource>,
+        options: MountOptions,
+    ) -> Self {
+        let fs = Ext4::load(block_dev.clone(), time_source, options)
+            .expect("Failed to load ext4 filesystem");
         Self {
             fs,
             block_dev,
@@ -97,8 +106,11 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         self.fs.flush_all();
     }
 
-    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
-        match self.fs.lookup(parent as u32, name.to_str().unwrap()) {
+    fn lookup(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match self
+            .fs
+            .lookup_as(&get_cred(req), parent as u32, name.to_str().unwrap())
+        {
             Ok(inode_id) => reply.entry(&get_ttl(), &self.get_attr(inode_id).unwrap(), 0),
             Err(e) => reply.error(e.code() as i32),
         }
@@ -113,7 +125,7 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
 
     fn setattr(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         ino: u64,
         mode: Option<u32>,
         uid: Option<u32>,
@@ -129,17 +141,17 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         _flags: Option<u32>,
         reply: ReplyAttr,
     ) {
-        match self.fs.setattr(
-            ino as u32,
-            mode.map(|m| InodeMode::from_bits_truncate(m as u16)),
+        let attr = SetAttr {
+            mode: mode.map(|m| InodeMode::from_bits_truncate(m as u16)),
             uid,
             gid,
             size,
-            atime.map(|t| time_or_now2second(t)),
-            mtime.map(|t| time_or_now2second(t)),
-            ctime.map(|t| sys_time2second(t)),
-            crtime.map(|t| sys_time2second(t)),
-        ) {
+            atime: atime.map(|t| time_or_now2timestamp(t)),
+            mtime: mtime.map(|t| time_or_now2timestamp(t)),
+            ctime: ctime.map(|t| sys_time2timestamp(t)),
+            crtime: crtime.map(|t| sys_time2timestamp(t)),
+        };
+        match self.fs.setattr_as(&get_cred(req), ino as u32, attr) {
             Ok(_) => reply.attr(&get_ttl(), &self.get_attr(ino as u32).unwrap()),
             Err(e) => reply.error(e.code() as i32),
         }
@@ -147,7 +159,7 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
 
     fn create(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
         mode: u32,
@@ -159,7 +171,8 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         if let Ok(_) = self.fs.lookup(parent as u32, name.to_str().unwrap()) {
             return reply.error(ErrCode::EEXIST as i32);
         }
-        match self.fs.create(
+        match self.fs.create_as(
+            &get_cred(req),
             parent as u32,
             name.to_str().unwrap(),
             InodeMode::from_bits_truncate(mode as u16),
@@ -262,8 +275,11 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         }
     }
 
-    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
-        match self.fs.unlink(parent as u32, name.to_str().unwrap()) {
+    fn unlink(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        match self
+            .fs
+            .unlink_as(&get_cred(req), parent as u32, name.to_str().unwrap())
+        {
             Ok(_) => reply.ok(),
             Err(e) => reply.error(e.code() as i32),
         }
@@ -271,7 +287,7 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
 
     fn rename(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
         newparent: u64,
@@ -300,7 +316,8 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         } else {
             return reply.error(ErrCode::ENOENT as i32);
         }
-        match self.fs.rename(
+        match self.fs.rename_as(
+            &get_cred(req),
             parent as u32,
             name.to_str().unwrap(),
             newparent as u32,
@@ -313,7 +330,7 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
 
     fn mkdir(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
         mode: u32,
@@ -324,7 +341,8 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
         if let Ok(_) = self.fs.lookup(parent as u32, name.to_str().unwrap()) {
             return reply.error(ErrCode::EEXIST as i32);
         }
-        match self.fs.mkdir(
+        match self.fs.mkdir_as(
+            &get_cred(req),
             parent as u32,
             name.to_str().unwrap(),
             InodeMode::from_bits_truncate(mode as u16),
@@ -398,31 +416,17 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
     }
 
     fn access(&mut self, req: &Request<'_>, ino: u64, mask: i32, reply: ReplyEmpty) {
-        let attr = self.fs.getattr(ino as u32).unwrap();
-        let mask = mask as u16;
-        // Check other
-        if attr.perm.contains(InodeMode::from_bits_truncate(mask)) {
-            return reply.ok();
-        }
-        // Check group
-        if attr.gid == req.gid() {
-            if attr.perm.contains(InodeMode::from_bits_truncate(mask << 3)) {
-                return reply.ok();
-            }
-        }
-        // Check user
-        if attr.uid == req.uid() {
-            if attr.perm.contains(InodeMode::from_bits_truncate(mask << 6)) {
-                return reply.ok();
-            }
+        let mask = AccessMode::from_bits_truncate(mask as u16);
+        match self.fs.access(ino as u32, &get_cred(req), mask) {
+            Ok(_) => reply.ok(),
+            Err(e) => reply.error(e.code() as i32),
         }
-        reply.error(ErrCode::EACCES as i32);
     }
 
     fn ioctl(
         &mut self,
-        _req: &Request<'_>,
-        _ino: u64,
+        req: &Request<'_>,
+        ino: u64,
         _fh: u64,
         _flags: u32,
         cmd: u32,
@@ -447,6 +451,21 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
                     reply.error(-1);
                 }
             }
+            Self::GETFLAGS_IOC => match self.fs.get_flags(ino as u32) {
+                Ok(flags) => reply.ioctl(0, &flags.bits().to_ne_bytes()),
+                Err(e) => reply.error(e.code() as i32),
+            },
+            Self::SETFLAGS_IOC => {
+                if in_data.len() < 4 {
+                    return reply.error(ErrCode::EINVAL as i32);
+                }
+                let flags = u32::from_ne_bytes(in_data[0..4].try_into().unwrap());
+                let flags = InodeFlags::from_bits_retain(flags);
+                match self.fs.set_flags_as(&get_cred(req), ino as u32, flags) {
+                    Ok(_) => reply.ioctl(0, &[]),
+                    Err(e) => reply.error(e.code() as i32),
+                }
+            }
             _ => {
                 log::error!("Unknown ioctl command: {}", cmd);
                 reply.error(ErrCode::ENOTSUP as i32);
@@ -531,8 +550,38 @@ impl<T: 'static> Filesystem for StateExt4FuseFs<T> {
             Err(e) => reply.error(e.code() as i32),
         }
     }
+
+    fn statfs(&mut self, _req: &Request<'_>, ino: u64, reply: fuser::ReplyStatfs) {
+        match self.fs.statfs(ino as u32) {
+            Ok(st) => reply.statfs(
+                st.blocks, st.bfree, st.bavail, st.files, st.ffree, st.bsize, st.namelen, st.bsize,
+            ),
+            Err(e) => reply.error(e.code() as i32),
+        }
+    }
 }
 
 fn get_ttl() -> Duration {
     Duration::from_secs(1)
 }
+
+/// Get the credentials of the requesting process
+fn get_cred(req: &Request<'_>) -> Credentials {
+    Credentials::new(req.uid(), req.gid(), get_groups(req.pid()))
+}
+
+/// Get the supplementary groups of a process from `/proc/<pid>/status`,
+/// none if the process is gone
+fn get_groups(pid: u32) -> Vec<u32> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap_or_default();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Groups:"))
+        .map(|groups| {
+            groups
+                .split_whitespace()
+                .filter_map(|gid| gid.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -4,8 +4,12 @@ mod block_dev;
 mod common;
 mod fuse_fs;
 
+use another_ext4::{
+    AtimePolicy, DataMode, Ext4, FixedTime, FormatOptions, MountOptions, TimeSource,
+};
 use block_dev::BlockMem;
 use clap::Parser;
+use common::SystemClock;
 use fuse_fs::StateExt4FuseFs;
 use fuser::MountOption;
 use log::LevelFilter;
@@ -30,6 +34,15 @@ struct Args {
     /// Log level
     #[arg(short, long, default_value_t = String::from("info"))]
     log: String,
+    /// Access time policy: strictatime, relatime or noatime
+    #[arg(short, long, default_value_t = String::from("relatime"))]
+    atime: String,
+    /// Data journaling mode: writeback, ordered or journal
+    #[arg(short, long, default_value_t = String::from("ordered"))]
+    data: String,
+    /// Use a fixed clock instead of the system time, for reproducible states
+    #[arg(short, long)]
+    fixed_time: bool,
 }
 
 fn parse_log_level(level_str: &str) -> LevelFilter {
@@ -44,6 +57,22 @@ fn parse_log_level(level_str: &str) -> LevelFilter {
     }
 }
 
+fn parse_atime_policy(policy_str: &str) -> AtimePolicy {
+    match policy_str.to_lowercase().as_str() {
+        "strictatime" => AtimePolicy::Strict,
+        "noatime" => AtimePolicy::NoAtime,
+        _ => AtimePolicy::Relative,
+    }
+}
+
+fn parse_data_mode(mode_str: &str) -> DataMode {
+    match mode_str.to_lowercase().as_str() {
+        "writeback" => DataMode::Writeback,
+        "journal" => DataMode::Journal,
+        _ => DataMode::Ordered,
+    }
+}
+
 /// Global exit flag
 static EXIT_FLAG: OnceLock<bool> = OnceLock::new();
 
@@ -55,6 +84,11 @@ fn main() {
     SimpleLogger::new().init().unwrap();
     log::set_max_level(parse_log_level(&args.log));
 
+    let time_source: Arc<dyn TimeSource> = if args.fixed_time {
+        Arc::new(FixedTime::default())
+    } else {
+        Arc::new(SystemClock)
+    };
     // Initialize block device and filesystem
     let block_mem = if let Some(image) = &args.image {
         println!("Load image {}", image);
@@ -62,11 +96,24 @@ fn main() {
     } else {
         println!("Create disk image with {} blocks", args.block);
         let block_mem = Arc::new(BlockMem::new(args.block));
-        block_mem.mkfs();
+        let now = time_source.now();
+        let mut uuid = [0; 16];
+        uuid[..8].copy_from_slice(&now.sec.to_le_bytes());
+        uuid[8..12].copy_from_slice(&now.nsec.to_le_bytes());
+        let format_options = FormatOptions {
+            block_count: args.block,
+            uuid,
+            time: now,
+            ..FormatOptions::default()
+        };
+        Ext4::format(block_mem.clone(), format_options).expect("Failed to format disk image");
         block_mem
     };
-    // Create filesystem and init if image is newly created
-    let fs = StateExt4FuseFs::new(block_mem.clone(), args.image.is_none());
+    let options = MountOptions {
+        atime: parse_atime_policy(&args.atime),
+        data: parse_data_mode(&args.data),
+    };
+    let fs = StateExt4FuseFs::new(block_mem.clone(), time_source, options);
 
     // Mount fs and enter session loop
     println!("Mount ext4fs to {}", args.mountpoint);
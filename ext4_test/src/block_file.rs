@@ -1,6 +1,8 @@
 use another_ext4::{Block, BlockDevice, BLOCK_SIZE};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct BlockFile(File);
@@ -33,3 +35,49 @@ impl BlockDevice for BlockFile {
         let _r = file.write_all(&block.data);
     }
 }
+
+/// A block file that loses all writes after a journal commit block is
+/// written, as if the machine crashed before checkpointing.
+pub struct CrashBlockFile {
+    file: BlockFile,
+    crashed: Arc<AtomicBool>,
+}
+
+impl CrashBlockFile {
+    pub fn new(path: &str) -> Self {
+        Self {
+            file: BlockFile::new(path),
+            crashed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Lose all later writes, as if the machine crashed now.
+    pub fn crash(&self) {
+        self.crashed.store(true, Ordering::Relaxed);
+    }
+
+    /// Open another file that crashes together with this one.
+    pub fn sharing(&self, path: &str) -> Self {
+        Self {
+            file: BlockFile::new(path),
+            crashed: self.crashed.clone(),
+        }
+    }
+}
+
+impl BlockDevice for CrashBlockFile {
+    fn read_block(&self, block_id: u64) -> Block {
+        self.file.read_block(block_id)
+    }
+
+    fn write_block(&self, block: &Block) {
+        if self.crashed.load(Ordering::Relaxed) {
+            return;
+        }
+        self.file.write_block(block);
+        // Journal magic number and commit block type
+        if block.data[..8] == [0xc0, 0x3b, 0x39, 0x98, 0, 0, 0, 2] {
+            self.crashed.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -1,30 +1,131 @@
-use another_ext4::{Ext4, InodeMode, EXT4_ROOT_INO};
-use block_file::BlockFile;
+use another_ext4::{
+    AccessMode, AclEntry, AclTag, AclType, CheckProblem, Credentials, DataMode, EncryptionPolicy,
+    ErrCode, Ext4, FileType, FormatFeatures, FormatOptions, InodeFlags, InodeMode, MountOptions,
+    PosixAcl, QuotaLimits, QuotaType, RepairAction, SetAttr, TimeSource, Timestamp, BLOCK_SIZE,
+    EXT4_ROOT_INO,
+};
+use block_file::{BlockFile, CrashBlockFile};
 use simple_logger::SimpleLogger;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 
 mod block_file;
 
 const ROOT_INO: u32 = EXT4_ROOT_INO;
 
+/// A clock that advances one second on every query
+struct TickClock(AtomicI64);
+
+impl TimeSource for TickClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from_secs(self.0.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Format an image of `size` bytes
+fn format_image(path: &str, size: u64, features: FormatFeatures) {
+    let file = std::fs::File::create(path).expect("create image failed");
+    file.set_len(size).expect("resize image failed");
+    let options = FormatOptions {
+        block_count: size / BLOCK_SIZE as u64,
+        label: "another_ext4".to_string(),
+        uuid: *b"another_ext4_img",
+        features,
+        time: Timestamp::from_secs(1_700_000_000),
+        ..FormatOptions::default()
+    };
+    Ext4::format(Arc::new(BlockFile::new(path)), options).expect("format failed");
+}
+
+/// Open an image with a clock starting at 1_700_000_000
+fn open_image(path: &str, options: MountOptions) -> Ext4 {
+    let clock = Arc::new(TickClock(AtomicI64::new(1_700_000_000)));
+    Ext4::load(Arc::new(BlockFile::new(path)), clock, options).expect("open ext4 failed")
+}
+
+/// Run a command and return its output
+fn run(cmd: &str, args: &[&str]) -> std::process::Output {
+    std::process::Command::new(cmd)
+        .args(args)
+        .output()
+        .unwrap_or_else(|_| panic!("{} failed", cmd))
+}
+
+/// Check an image with `e2fsck -fn`
+fn fsck(path: &str) {
+    let output = run("e2fsck", &["-fn", path]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+/// Create an image of `size` bytes with `mkfs.ext4` and `args`
+fn mkfs_image(path: &str, size: u64, args: &[&str]) {
+    let _ = std::fs::remove_file(path);
+    let file = std::fs::File::create(path).expect("create image failed");
+    file.set_len(size).expect("resize image failed");
+    let output = run("mkfs.ext4", &[args, &["-q", path]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
 fn make_ext4() {
-    let _ = std::process::Command::new("rm")
-        .args(["-rf", "ext4.img"])
-        .status();
-    let _ = std::process::Command::new("dd")
-        .args(["if=/dev/zero", "of=ext4.img", "bs=1M", "count=512"])
-        .status();
-    let _ = std::process::Command::new("mkfs.ext4")
-        .args(["ext4.img"])
-        .output();
+    let features =
+        FormatOptions::default().features | FormatFeatures::QUOTA | FormatFeatures::PROJECT;
+    format_image("ext4.img", 512 << 20, features);
 }
 
 fn open_ext4() -> Ext4 {
-    let file = BlockFile::new("ext4.img");
     println!("creating ext4");
-    let mut ext4 = Ext4::load(Arc::new(file)).expect("open ext4 failed");
-    ext4.init().expect("init ext4 failed");
-    ext4
+    open_image("ext4.img", MountOptions::default())
+}
+
+fn format_test() {
+    let all =
+        FormatOptions::default().features | FormatFeatures::QUOTA | FormatFeatures::PROJECT;
+    // The last group of the larger image is too small and dropped
+    for (size, features) in [(20 << 20, FormatFeatures::empty()), (129 << 20, all)] {
+        format_image("format.img", size, features);
+        fsck("format.img");
+        let ext4 = open_image("format.img", MountOptions::default());
+        assert!(ext4.check().is_clean());
+        let blocks = ext4.statfs(ROOT_INO).unwrap().blocks;
+        assert_eq!(blocks, (size / BLOCK_SIZE as u64).min(32768));
+        let lost_found = ext4.lookup(ROOT_INO, "lost+found").unwrap();
+        assert_eq!(ext4.getattr(lost_found).unwrap().perm.bits(), 0o700);
+        if features.contains(FormatFeatures::QUOTA) {
+            let quota = ext4.get_quota(QuotaType::Project, 0).unwrap();
+            assert_eq!(quota.inodes, 2);
+        }
+    }
+    // Too small for the metadata of a group
+    let mut options = FormatOptions::default();
+    options.block_count = 16;
+    let file = std::fs::File::create("format.img").unwrap();
+    file.set_len(16 * BLOCK_SIZE as u64).unwrap();
+    let res = Ext4::format(Arc::new(BlockFile::new("format.img")), options);
+    assert_eq!(res.unwrap_err().code(), ErrCode::EINVAL);
+    // Inodes outside group 0 allocate blocks in their own group
+    let file = std::fs::File::create("format.img").unwrap();
+    file.set_len(200 << 20).unwrap();
+    let options = FormatOptions {
+        block_count: (200 << 20) / BLOCK_SIZE as u64,
+        inode_ratio: 1 << 20,
+        ..FormatOptions::default()
+    };
+    Ext4::format(Arc::new(BlockFile::new("format.img")), options).expect("format failed");
+    let ext4 = open_image("format.img", MountOptions::default());
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    // Two groups of inodes
+    let per_group = ext4.statfs(ROOT_INO).unwrap().files as u32 / 2;
+    let mut inode = 0;
+    while inode <= per_group {
+        inode = ext4
+            .create(ROOT_INO, &format!("f{}", inode), file_mode)
+            .expect("create failed");
+    }
+    ext4.write(inode, 0, &[0x7e; 2 * BLOCK_SIZE]).expect("write failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
 }
 
 fn mkdir_test(ext4: &mut Ext4) {
@@ -127,6 +228,1505 @@ fn xattr_test(ext4: &mut Ext4) {
     assert_eq!(names, vec!["user.testtwo"]);
 }
 
+fn time_test(ext4: &mut Ext4) {
+    let file_mode: InodeMode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let file = ext4
+        .generic_create(ROOT_INO, "f3", file_mode)
+        .expect("Create failed");
+    // Post-2038 time with nanoseconds
+    let mtime = Timestamp::new(0x1_2345_6789, 123_456_789);
+    // Pre-1970 time
+    let atime = Timestamp::new(-86400, 999_999_999);
+    ext4.setattr(file, None, None, None, None, Some(atime), Some(mtime), None, None)
+        .expect("setattr failed");
+    let attr = ext4.getattr(file).expect("getattr failed");
+    assert_eq!(attr.mtime, mtime);
+    assert_eq!(attr.atime, atime);
+}
+
+fn timestamp_test(ext4: &mut Ext4) {
+    let file_mode: InodeMode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let dir = ext4
+        .generic_create(ROOT_INO, "d4", InodeMode::DIRECTORY | InodeMode::ALL_RWX)
+        .expect("mkdir failed");
+    let dir_before = ext4.getattr(dir).expect("getattr failed");
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    let file_before = ext4.getattr(file).expect("getattr failed");
+    assert_eq!(file_before.mtime, file_before.crtime);
+    // Creating an entry modifies the parent
+    let dir_after = ext4.getattr(dir).expect("getattr failed");
+    assert!(dir_after.mtime > dir_before.mtime);
+    assert!(dir_after.ctime > dir_before.ctime);
+    // Writing modifies the file
+    ext4.write(file, 0, b"hello").expect("write failed");
+    let file_after = ext4.getattr(file).expect("getattr failed");
+    assert!(file_after.mtime > file_before.mtime);
+    assert!(file_after.ctime > file_before.ctime);
+    // Relatime: atime is older than mtime, so reading updates it
+    let mut buf = [0u8; 5];
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    let file_read = ext4.getattr(file).expect("getattr failed");
+    assert!(file_read.atime > file_after.mtime);
+    // Relatime: atime is newer than mtime, so reading again keeps it
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    assert_eq!(ext4.getattr(file).unwrap().atime, file_read.atime);
+    // Linking changes the inode
+    ext4.link(file, dir, "f2").expect("link failed");
+    assert!(ext4.getattr(file).unwrap().ctime > file_read.ctime);
+}
+
+fn permission_test(ext4: &mut Ext4) {
+    let root = Credentials::root();
+    let alice = Credentials::new(1000, 1000, vec![]);
+    let bob = Credentials::new(1001, 1001, vec![]);
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    // A directory writable only by root
+    let dir = ext4
+        .mkdir_as(&root, ROOT_INO, "d5", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    let err = ext4
+        .create_as(&alice, dir, "f1", file_mode)
+        .expect_err("create should fail");
+    assert_eq!(err.code(), ErrCode::EACCES);
+    ext4.access(dir, &alice, AccessMode::READ | AccessMode::EXEC)
+        .expect("access failed");
+    // A sticky world-writable directory
+    let tmp = ext4
+        .mkdir_as(&root, ROOT_INO, "tmp", InodeMode::from_bits_retain(0o1777))
+        .expect("mkdir failed");
+    let file = ext4
+        .create_as(&alice, tmp, "f1", file_mode)
+        .expect("create failed");
+    assert_eq!(ext4.getattr(file).unwrap().uid, 1000);
+    let err = ext4.unlink_as(&bob, tmp, "f1").expect_err("unlink should fail");
+    assert_eq!(err.code(), ErrCode::EPERM);
+    // Only the owner may chmod
+    let err = ext4
+        .setattr_as(&bob, file, SetAttr { mode: Some(file_mode), ..SetAttr::default() })
+        .expect_err("chmod should fail");
+    assert_eq!(err.code(), ErrCode::EPERM);
+    ext4.access(file, &bob, AccessMode::WRITE)
+        .expect_err("access should fail");
+    ext4.unlink_as(&alice, tmp, "f1").expect("unlink failed");
+}
+
+fn acl_test(ext4: &mut Ext4) {
+    let root = Credentials::root();
+    let alice = Credentials::new(1000, 1000, vec![]);
+    let bob = Credentials::new(1001, 1001, vec![]);
+    // A directory whose default ACL grants bob read and write
+    let dir = ext4
+        .mkdir_as(&root, ROOT_INO, "d6", InodeMode::from_bits_retain(0o777))
+        .expect("mkdir failed");
+    let default = PosixAcl::new(vec![
+        AclEntry::new(AclTag::UserObj, 0o7, 0),
+        AclEntry::new(AclTag::User, 0o6, 1001),
+        AclEntry::new(AclTag::GroupObj, 0o5, 0),
+        AclEntry::new(AclTag::Mask, 0o7, 0),
+        AclEntry::new(AclTag::Other, 0o0, 0),
+    ])
+    .unwrap();
+    ext4.setxattr(dir, "system.posix_acl_default", &default.to_xattr())
+        .expect("setxattr failed");
+    let value = ext4
+        .getxattr(dir, "system.posix_acl_default")
+        .expect("getxattr failed");
+    assert_eq!(PosixAcl::from_xattr(&value).as_ref(), Some(&default));
+    // New file inherits the default ACL
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o660);
+    let file = ext4
+        .create_as(&alice, dir, "f1", file_mode)
+        .expect("create failed");
+    let acl = ext4
+        .get_acl(file, AclType::Access)
+        .unwrap()
+        .expect("no inherited ACL");
+    assert_eq!(ext4.getattr(file).unwrap().perm.bits() & 0o777, 0o660);
+    ext4.access(file, &bob, AccessMode::READ | AccessMode::WRITE)
+        .expect("access failed");
+    // Mask limits named entries after chmod
+    let mode = InodeMode::FILE | InodeMode::from_bits_retain(0o600);
+    ext4.setattr_as(&alice, file, SetAttr { mode: Some(mode), ..SetAttr::default() })
+        .expect("chmod failed");
+    let acl2 = ext4.get_acl(file, AclType::Access).unwrap().unwrap();
+    assert_ne!(acl, acl2);
+    assert_eq!(acl2.mode(), 0o600);
+    ext4.access(file, &bob, AccessMode::READ)
+        .expect_err("access should fail");
+    // New subdirectory inherits the default ACL as its default ACL
+    let sub = ext4
+        .mkdir_as(&alice, dir, "d1", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    assert!(ext4.get_acl(sub, AclType::Default).unwrap().is_some());
+    // A minimal access ACL is folded into the mode bits
+    ext4.set_acl(file, AclType::Access, Some(&PosixAcl::from_mode(0o644)))
+        .expect("set_acl failed");
+    assert!(ext4.get_acl(file, AclType::Access).unwrap().is_none());
+    assert_eq!(ext4.getattr(file).unwrap().perm.bits() & 0o777, 0o644);
+    let err = ext4
+        .set_acl(file, AclType::Default, Some(&default))
+        .expect_err("set default ACL on file should fail");
+    assert_eq!(err.code(), ErrCode::EACCES);
+}
+
+fn acl_image_test() {
+    let bob = Credentials::new(1001, 1001, vec![]);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::from_bits_retain(0o755);
+    // An ACL granting bob read and write, set by debugfs in the inode body
+    mkfs_image("acl.img", 32 << 20, &["-b", "4096"]);
+    let acl = PosixAcl::new(vec![
+        AclEntry::new(AclTag::UserObj, 0o6, 0),
+        AclEntry::new(AclTag::User, 0o6, 1001),
+        AclEntry::new(AclTag::GroupObj, 0o4, 0),
+        AclEntry::new(AclTag::Mask, 0o6, 0),
+        AclEntry::new(AclTag::Other, 0o0, 0),
+    ])
+    .unwrap();
+    std::fs::write("acl.bin", acl.to_xattr()).unwrap();
+    std::fs::write("host.txt", b"host").unwrap();
+    run("debugfs", &["-w", "-R", "write host.txt f", "acl.img"]);
+    let ea_set = "ea_set -f acl.bin f system.posix_acl_access";
+    run("debugfs", &["-w", "-R", ea_set, "acl.img"]);
+
+    let ext4 = open_image("acl.img", MountOptions::default());
+    let file = ext4.lookup(ROOT_INO, "f").expect("lookup failed");
+    assert_eq!(ext4.get_acl(file, AclType::Access).unwrap(), Some(acl.clone()));
+    ext4.access(file, &bob, AccessMode::READ | AccessMode::WRITE)
+        .expect("access failed");
+    let bfree = ext4.statfs(ROOT_INO).unwrap().bfree;
+    // A small ACL is kept in the inode, a large one in the xattr block
+    let dir = ext4.mkdir(ROOT_INO, "d", dir_mode).expect("mkdir failed");
+    ext4.set_acl(dir, AclType::Access, Some(&acl)).expect("set_acl failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 1);
+    let mut entries = vec![AclEntry::new(AclTag::UserObj, 0o7, 0)];
+    entries.extend((2000..2100).map(|uid| AclEntry::new(AclTag::User, 0o5, uid)));
+    entries.extend([
+        AclEntry::new(AclTag::GroupObj, 0o5, 0),
+        AclEntry::new(AclTag::Mask, 0o7, 0),
+        AclEntry::new(AclTag::Other, 0o5, 0),
+    ]);
+    let large = PosixAcl::new(entries).unwrap();
+    ext4.set_acl(dir, AclType::Default, Some(&large)).expect("set_acl failed");
+    ext4.setxattr(dir, "user.note", b"unaligned").expect("setxattr failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 2);
+    assert_eq!(ext4.get_acl(dir, AclType::Access).unwrap(), Some(acl.clone()));
+    assert_eq!(ext4.get_acl(dir, AclType::Default).unwrap(), Some(large));
+    let mut names = ext4.listxattr(dir).unwrap();
+    names.sort();
+    assert_eq!(
+        names,
+        ["system.posix_acl_access", "system.posix_acl_default", "user.note"]
+    );
+    let sub = ext4.mkdir(dir, "sub", dir_mode).expect("mkdir failed");
+    assert!(ext4.get_acl(sub, AclType::Default).unwrap().is_some());
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("acl.img");
+    let stat = run("debugfs", &["-R", "stat d", "acl.img"]);
+    assert!(String::from_utf8_lossy(&stat.stdout).contains("Blockcount: 16"));
+
+    // The xattr block is freed once empty
+    let ext4 = open_image("acl.img", MountOptions::default());
+    ext4.rmdir(dir, "sub").expect("rmdir failed");
+    ext4.set_acl(dir, AclType::Default, None).expect("set_acl failed");
+    ext4.removexattr(dir, "user.note").expect("removexattr failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 1);
+    assert_eq!(ext4.get_acl(dir, AclType::Access).unwrap(), Some(acl));
+    drop(ext4);
+    fsck("acl.img");
+}
+
+fn quota_test(ext4: &mut Ext4) {
+    let root = Credentials::root();
+    let alice = Credentials::new(1000, 1000, vec![]);
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir = ext4
+        .mkdir_as(&root, ROOT_INO, "d7", InodeMode::from_bits_retain(0o777))
+        .expect("mkdir failed");
+    let base = ext4.get_quota(QuotaType::User, 1000).expect("get_quota failed");
+    // Inode hard limit
+    let limits = QuotaLimits {
+        inode_hard: base.inodes + 1,
+        ..Default::default()
+    };
+    ext4.set_quota_limits(QuotaType::User, 1000, limits)
+        .expect("set_quota_limits failed");
+    let file = ext4
+        .create_as(&alice, dir, "f1", file_mode)
+        .expect("create failed");
+    let err = ext4
+        .create_as(&alice, dir, "f2", file_mode)
+        .expect_err("create should fail");
+    assert_eq!(err.code(), ErrCode::EDQUOT);
+    // Space hard limit
+    let quota = ext4.get_quota(QuotaType::User, 1000).unwrap();
+    assert_eq!(quota.inodes, base.inodes + 1);
+    let limits = QuotaLimits {
+        space_hard: quota.space + 2 * BLOCK_SIZE as u64,
+        ..Default::default()
+    };
+    ext4.set_quota_limits(QuotaType::User, 1000, limits)
+        .expect("set_quota_limits failed");
+    let data = vec![0x5au8; 4 * BLOCK_SIZE];
+    let err = ext4.write(file, 0, &data).expect_err("write should fail");
+    assert_eq!(err.code(), ErrCode::EDQUOT);
+    // The failed write allocated nothing
+    assert_eq!(ext4.get_quota(QuotaType::User, 1000).unwrap().space, quota.space);
+    ext4.write(file, 0, &data[..BLOCK_SIZE]).expect("write failed");
+    let quota = ext4.get_quota(QuotaType::User, 1000).unwrap();
+    assert!(quota.space <= limits.space_hard);
+    // Inode soft limit with grace period
+    let limits = QuotaLimits {
+        inode_soft: quota.inodes,
+        ..Default::default()
+    };
+    ext4.set_quota_grace(QuotaType::User, 0, 1)
+        .expect("set_quota_grace failed");
+    ext4.set_quota_limits(QuotaType::User, 1000, limits)
+        .expect("set_quota_limits failed");
+    ext4.create_as(&alice, dir, "f2", file_mode)
+        .expect("create over soft limit failed");
+    let quota = ext4.get_quota(QuotaType::User, 1000).unwrap();
+    assert_ne!(quota.inode_grace_end, 0);
+    let err = ext4
+        .create_as(&alice, dir, "f3", file_mode)
+        .expect_err("create after grace period should fail");
+    assert_eq!(err.code(), ErrCode::EDQUOT);
+    // Changing owner transfers usage
+    ext4.set_quota_limits(QuotaType::User, 1000, QuotaLimits::default())
+        .expect("set_quota_limits failed");
+    let bob_before = ext4.get_quota(QuotaType::User, 1001).unwrap();
+    ext4.setattr_as(&root, file, SetAttr { uid: Some(1001), ..SetAttr::default() })
+        .expect("chown failed");
+    let bob_after = ext4.get_quota(QuotaType::User, 1001).unwrap();
+    assert_eq!(bob_after.inodes, bob_before.inodes + 1);
+    assert_eq!(bob_after.space, bob_before.space + 2 * BLOCK_SIZE as u64);
+    // Unlink releases usage
+    ext4.unlink(dir, "f1").expect("unlink failed");
+    assert_eq!(ext4.get_quota(QuotaType::User, 1001).unwrap(), bob_before);
+}
+
+fn rollback_test(ext4: &mut Ext4) {
+    let root = Credentials::root();
+    let alice = Credentials::new(1000, 1000, vec![]);
+    let dir = ext4
+        .mkdir_as(&root, ROOT_INO, "d9", InodeMode::from_bits_retain(0o777))
+        .expect("mkdir failed");
+    // The inode is allocated before the directory block exceeds the quota
+    let quota = ext4.get_quota(QuotaType::User, 1000).unwrap();
+    let limits = QuotaLimits {
+        space_hard: quota.space,
+        ..Default::default()
+    };
+    ext4.set_quota_limits(QuotaType::User, 1000, limits)
+        .expect("set_quota_limits failed");
+    let quota = ext4.get_quota(QuotaType::User, 1000).unwrap();
+    let statfs = ext4.statfs(ROOT_INO).unwrap();
+    let err = ext4
+        .mkdir_as(&alice, dir, "sub", InodeMode::from_bits_retain(0o755))
+        .expect_err("mkdir should fail");
+    assert_eq!(err.code(), ErrCode::EDQUOT);
+    // Nothing is left of the failed mkdir
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap(), statfs);
+    assert_eq!(ext4.get_quota(QuotaType::User, 1000).unwrap(), quota);
+    assert!(ext4.lookup(dir, "sub").is_err());
+    assert_eq!(ext4.getattr(dir).unwrap().links, 2);
+    ext4.set_quota_limits(QuotaType::User, 1000, QuotaLimits::default())
+        .expect("set_quota_limits failed");
+    ext4.mkdir_as(&alice, dir, "sub", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+}
+
+fn project_test(ext4: &mut Ext4) {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir = ext4
+        .mkdir(ROOT_INO, "d8", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    ext4.set_projid(dir, 42).expect("set_projid failed");
+    ext4.set_flags(dir, InodeFlags::PROJINHERIT)
+        .expect("set_flags failed");
+    // New entries inherit the project
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    let sub = ext4
+        .mkdir(dir, "sub", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    let sub_file = ext4.create(sub, "f2", file_mode).expect("create failed");
+    assert_eq!(ext4.getattr(file).unwrap().projid, 42);
+    assert_eq!(ext4.getattr(sub_file).unwrap().projid, 42);
+    // Space limit of the subtree
+    let quota = ext4.get_quota(QuotaType::Project, 42).unwrap();
+    assert_eq!(quota.inodes, 4);
+    let limits = QuotaLimits {
+        space_hard: quota.space + 2 * BLOCK_SIZE as u64,
+        ..Default::default()
+    };
+    ext4.set_quota_limits(QuotaType::Project, 42, limits)
+        .expect("set_quota_limits failed");
+    let data = vec![0x5au8; 4 * BLOCK_SIZE];
+    let err = ext4.write(sub_file, 0, &data).expect_err("write should fail");
+    assert_eq!(err.code(), ErrCode::EDQUOT);
+    // statfs reports the project limits
+    let stat = ext4.statfs(dir).expect("statfs failed");
+    let quota = ext4.get_quota(QuotaType::Project, 42).unwrap();
+    assert_eq!(stat.blocks, limits.space_hard / BLOCK_SIZE as u64);
+    assert_eq!(stat.bfree, (limits.space_hard - quota.space) / BLOCK_SIZE as u64);
+    assert!(ext4.statfs(ROOT_INO).unwrap().blocks > stat.blocks);
+    // Files cannot be moved into another project
+    ext4.create(ROOT_INO, "f8", file_mode).expect("create failed");
+    let err = ext4
+        .rename(ROOT_INO, "f8", dir, "f8")
+        .expect_err("rename should fail");
+    assert_eq!(err.code(), ErrCode::EXDEV);
+    // Changing the project transfers usage
+    ext4.set_projid(file, 43).expect("set_projid failed");
+    let after = ext4.get_quota(QuotaType::Project, 42).unwrap();
+    assert_eq!(after.inodes, quota.inodes - 1);
+    assert_eq!(ext4.get_quota(QuotaType::Project, 43).unwrap().inodes, 1);
+}
+
+fn flags_test(ext4: &mut Ext4) {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o666);
+    let dir = ext4
+        .mkdir(ROOT_INO, "d9", InodeMode::from_bits_retain(0o777))
+        .expect("mkdir failed");
+    // Inherited flags
+    ext4.set_flags(dir, InodeFlags::NOATIME | InodeFlags::DIRSYNC)
+        .expect("set_flags failed");
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    let flags = ext4.get_flags(file).unwrap();
+    assert!(flags.contains(InodeFlags::NOATIME));
+    assert!(!flags.contains(InodeFlags::DIRSYNC));
+    // No atime update
+    ext4.write(file, 0, b"hello").expect("write failed");
+    let atime = ext4.getattr(file).unwrap().atime;
+    let mut buf = [0u8; 5];
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    assert_eq!(ext4.getattr(file).unwrap().atime, atime);
+    // Only privileged callers can make a file immutable
+    let user = Credentials::new(1000, 1000, vec![]);
+    let err = ext4
+        .set_flags_as(&user, file, InodeFlags::IMMUTABLE)
+        .expect_err("set_flags_as should fail");
+    assert_eq!(err.code(), ErrCode::EPERM);
+    ext4.set_flags_as(&Credentials::root(), file, InodeFlags::IMMUTABLE)
+        .expect("set_flags_as failed");
+    // Immutable file
+    let eperm = |r: Result<(), another_ext4::Ext4Error>| {
+        assert_eq!(r.expect_err("should fail").code(), ErrCode::EPERM)
+    };
+    eperm(ext4.write(file, 5, b"world").map(|_| ()));
+    eperm(ext4.setattr(file, None, None, None, Some(0), None, None, None, None));
+    eperm(ext4.unlink(dir, "f1"));
+    eperm(ext4.rename(dir, "f1", dir, "f2"));
+    eperm(ext4.link(file, dir, "f2"));
+    eperm(ext4.setxattr(file, "user.a", b"a"));
+    // Append-only file
+    ext4.set_flags(file, InodeFlags::APPEND)
+        .expect("set_flags failed");
+    eperm(ext4.write(file, 0, b"world").map(|_| ()));
+    ext4.write(file, 5, b"world").expect("append failed");
+    eperm(ext4.unlink(dir, "f1"));
+    ext4.set_flags(file, InodeFlags::empty())
+        .expect("set_flags failed");
+    ext4.unlink(dir, "f1").expect("unlink failed");
+}
+
+fn make_journal_ext4() {
+    mkfs_image("journal.img", 64 << 20, &["-b", "4096"]);
+}
+
+fn journal_test() {
+    // Log 3 transactions with debugfs without replaying them: write 'A' to
+    // block 5000, write 'B' and 'C' to blocks 5001 and 5002, revoke 5000
+    make_journal_ext4();
+    let data = [[b'A'; BLOCK_SIZE], [b'B'; BLOCK_SIZE], [b'C'; BLOCK_SIZE]];
+    std::fs::write("journal_a.bin", data[0]).unwrap();
+    std::fs::write("journal_bc.bin", [data[1], data[2]].concat()).unwrap();
+    let script = "jo -c\njw -b 5000 journal_a.bin\njc\n\
+                  jo\njw -b 5001,5002 journal_bc.bin\njc\n\
+                  jo\njw -r 5000\njc\n";
+    std::fs::write("journal.cmd", script).unwrap();
+    let _ = run("debugfs", &["-w", "-f", "journal.cmd", "journal.img"]);
+
+    drop(open_image("journal.img", MountOptions::default()));
+    let image = std::fs::read("journal.img").unwrap();
+    let block = |id: usize| &image[id * BLOCK_SIZE..(id + 1) * BLOCK_SIZE];
+    assert_eq!(block(5000), [0; BLOCK_SIZE]);
+    assert_eq!(block(5001), data[1]);
+    assert_eq!(block(5002), data[2]);
+    fsck("journal.img");
+
+    // Crash after committing a transaction, before checkpointing it
+    make_journal_ext4();
+    let file = CrashBlockFile::new("journal.img");
+    let clock = Arc::new(TickClock(AtomicI64::new(1_700_000_000)));
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    let dir_mode: InodeMode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    ext4.mkdir(ROOT_INO, "d10", dir_mode).expect("mkdir failed");
+    drop(ext4);
+    // The superblock has the recovery flag (incompat 0x4) set
+    let image = std::fs::read("journal.img").unwrap();
+    assert_ne!(image[1024 + 0x60] & 0x4, 0);
+    let file = BlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("journal recovery failed");
+    let dir = ext4.lookup(ROOT_INO, "d10").expect("lookup failed");
+    assert_eq!(ext4.getattr(dir).unwrap().ftype, FileType::Directory);
+
+    // Crash while removing a file, its freed blocks are revoked
+    let file_mode: InodeMode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    ext4.setxattr(file, "user.big", &[1; 3000]).expect("setxattr failed");
+    ext4.write(file, 0, &[2; BLOCK_SIZE * 30]).expect("write failed");
+    drop(ext4);
+    let file = CrashBlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    ext4.unlink(dir, "f1").expect("unlink failed");
+    drop(ext4);
+    let file = BlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock, MountOptions::default())
+        .expect("journal recovery failed");
+    assert_eq!(ext4.lookup(dir, "f1").unwrap_err().code(), ErrCode::ENOENT);
+    // Linux enables journal checksums on filesystems with metadata checksums
+    let dumpe2fs = run("dumpe2fs", &["-h", "journal.img"]);
+    let output = String::from_utf8_lossy(&dumpe2fs.stdout);
+    assert!(output.contains("journal_incompat_revoke journal_checksum_v3"));
+}
+
+fn make_external_journal_ext4() {
+    mkfs_image("journal_dev.img", 16 << 20, &["-O", "journal_dev", "-b", "4096"]);
+    mkfs_image("journal.img", 64 << 20, &["-O", "^has_journal", "-b", "4096"]);
+    // Attach the journal device to the filesystem
+    let dumpe2fs = run("dumpe2fs", &["-h", "journal_dev.img"]);
+    let output = String::from_utf8_lossy(&dumpe2fs.stdout);
+    let uuid = output
+        .lines()
+        .find_map(|line| line.strip_prefix("Filesystem UUID:"))
+        .expect("no journal device uuid")
+        .trim();
+    let script = format!(
+        "feature has_journal\nssv journal_inum 0\nssv journal_uuid {}\n",
+        uuid
+    );
+    std::fs::write("journal.cmd", script).unwrap();
+    let _ = run("debugfs", &["-w", "-f", "journal.cmd", "journal.img"]);
+}
+
+fn external_journal_test() {
+    make_external_journal_ext4();
+    let clock = Arc::new(TickClock(AtomicI64::new(1_700_000_000)));
+    // The journal device is required
+    let file = BlockFile::new("journal.img");
+    let err = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default());
+    assert_eq!(err.err().unwrap().code(), ErrCode::ENODEV);
+
+    // Crash after committing a transaction to the journal device
+    let file = CrashBlockFile::new("journal.img");
+    let journal = file.sharing("journal_dev.img");
+    let ext4 = Ext4::load_with_journal(
+        Arc::new(file),
+        Some(Arc::new(journal)),
+        clock.clone(),
+        MountOptions::default(),
+    )
+    .expect("open ext4 failed");
+    let dir_mode: InodeMode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    ext4.mkdir(ROOT_INO, "d10", dir_mode).expect("mkdir failed");
+    drop(ext4);
+    let image = std::fs::read("journal.img").unwrap();
+    assert_ne!(image[1024 + 0x60] & 0x4, 0);
+    let file = BlockFile::new("journal.img");
+    let journal = BlockFile::new("journal_dev.img");
+    let ext4 = Ext4::load_with_journal(
+        Arc::new(file),
+        Some(Arc::new(journal)),
+        clock.clone(),
+        MountOptions::default(),
+    )
+    .expect("journal recovery failed");
+    let dir = ext4.lookup(ROOT_INO, "d10").expect("lookup failed");
+    assert_eq!(ext4.getattr(dir).unwrap().ftype, FileType::Directory);
+    drop(ext4);
+    let image = std::fs::read("journal.img").unwrap();
+    assert_eq!(image[1024 + 0x60] & 0x4, 0);
+
+    // A new journal device has another uuid
+    let _ = run("mke2fs", &["-q", "-F", "-O", "journal_dev", "-b", "4096", "journal_dev.img"]);
+    let file = BlockFile::new("journal.img");
+    let journal = BlockFile::new("journal_dev.img");
+    let err = Ext4::load_with_journal(
+        Arc::new(file),
+        Some(Arc::new(journal)),
+        clock,
+        MountOptions::default(),
+    );
+    assert_eq!(err.err().unwrap().code(), ErrCode::EINVAL);
+}
+
+fn journal_data_test() {
+    let file_mode: InodeMode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let cases = [
+        (DataMode::Ordered, InodeFlags::empty(), 0xa1, false),
+        (DataMode::Journal, InodeFlags::empty(), 0xa2, true),
+        (DataMode::Ordered, InodeFlags::JOURNAL_DATA, 0xa3, true),
+    ];
+    for (data, flags, byte, journaled) in cases {
+        make_journal_ext4();
+        let options = MountOptions {
+            data,
+            ..Default::default()
+        };
+        let ext4 = open_image("journal.img", options.clone());
+        let inode = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+        ext4.set_flags(inode, flags).expect("set_flags failed");
+        drop(ext4);
+        // Crash after committing the write
+        let file = CrashBlockFile::new("journal.img");
+        let clock = Arc::new(TickClock(AtomicI64::new(1_700_000_000)));
+        let ext4 =
+            Ext4::load(Arc::new(file), clock, options.clone()).expect("open ext4 failed");
+        ext4.write(inode, 0, &[byte; BLOCK_SIZE]).expect("write failed");
+        drop(ext4);
+        let ext4 = open_image("journal.img", options);
+        let mut buf = [0; BLOCK_SIZE];
+        ext4.read(inode, 0, &mut buf).expect("read failed");
+        assert_eq!(buf, [byte; BLOCK_SIZE]);
+        drop(ext4);
+        // Journaled data has a copy in the log and one in place
+        let image = std::fs::read("journal.img").unwrap();
+        let copies = image
+            .chunks(BLOCK_SIZE)
+            .filter(|block| block.iter().all(|&b| b == byte))
+            .count();
+        assert_eq!(copies, if journaled { 2 } else { 1 });
+    }
+
+    // Journaled writes and moves larger than the 4 MiB log
+    make_journal_ext4();
+    let options = MountOptions {
+        data: DataMode::Journal,
+        ..Default::default()
+    };
+    let ext4 = open_image("journal.img", options);
+    let data: Vec<u8> = (0..6 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let a = ext4.create(ROOT_INO, "a", file_mode).expect("create failed");
+    let b = ext4.create(ROOT_INO, "b", file_mode).expect("create failed");
+    ext4.write(a, 0, &data).expect("write failed");
+    ext4.write(b, 0, &data).expect("write failed");
+    ext4.write(a, data.len(), &data).expect("write failed");
+    let stats = ext4.defrag(a).expect("defrag failed");
+    assert!(stats.extents_after < stats.extents_before);
+    let mut buf = vec![0; 2 * data.len()];
+    assert_eq!(ext4.read(a, 0, &mut buf).unwrap(), buf.len());
+    assert!(buf[..data.len()] == data && buf[data.len()..] == data);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("journal.img");
+}
+
+fn make_fast_commit_ext4() {
+    mkfs_image("journal.img", 64 << 20, &["-O", "fast_commit", "-b", "4096"]);
+}
+
+fn fast_commit_test() {
+    make_fast_commit_ext4();
+    let clock = Arc::new(TickClock(AtomicI64::new(1_700_000_000)));
+    let file_mode: InodeMode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let mut data = vec![0xf1; BLOCK_SIZE * 3];
+    data.extend_from_slice(&[0xf2; BLOCK_SIZE * 2]);
+    data.resize(BLOCK_SIZE * 5 + 100, 0);
+
+    let file = BlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    let inode = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    drop(ext4);
+
+    // Crash after fast committing 2 writes and a truncate
+    let file = Arc::new(CrashBlockFile::new("journal.img"));
+    let ext4 = Ext4::load(file.clone(), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    ext4.write(inode, 0, &data[..BLOCK_SIZE * 3]).expect("write failed");
+    ext4.write(inode, BLOCK_SIZE * 3, &data[BLOCK_SIZE * 3..BLOCK_SIZE * 5])
+        .expect("write failed");
+    ext4.setattr(
+        inode,
+        None,
+        None,
+        None,
+        Some(data.len() as u64),
+        None,
+        None,
+        None,
+        None,
+    )
+    .expect("setattr failed");
+    file.crash();
+    drop(ext4);
+    // The fast commits are not committed in full
+    let image = std::fs::read("journal.img").unwrap();
+    assert_ne!(image[1024 + 0x60] & 0x4, 0);
+
+    // e2fsck replays the same fast commits
+    std::fs::copy("journal.img", "journal_fc.img").unwrap();
+    let _ = run("e2fsck", &["-fy", "-E", "journal_only", "journal_fc.img"]);
+    let cat = run("debugfs", &["-R", "cat /f1", "journal_fc.img"]);
+    assert_eq!(cat.stdout, data);
+
+    let file = BlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("journal recovery failed");
+    let mut buf = vec![0; data.len()];
+    assert_eq!(ext4.getattr(inode).unwrap().size, data.len() as u64);
+    ext4.read(inode, 0, &mut buf).expect("read failed");
+    assert_eq!(buf, data);
+
+    // Fast committed writes are committed in full when dropped
+    ext4.write(inode, BLOCK_SIZE * 6, &[0xf3; BLOCK_SIZE]).expect("write failed");
+    drop(ext4);
+    let image = std::fs::read("journal.img").unwrap();
+    assert_eq!(image[1024 + 0x60] & 0x4, 0);
+    let file = BlockFile::new("journal.img");
+    let ext4 = Ext4::load(Arc::new(file), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    let mut buf = [0; BLOCK_SIZE];
+    ext4.read(inode, BLOCK_SIZE * 6, &mut buf).expect("read failed");
+    assert_eq!(buf, [0xf3; BLOCK_SIZE]);
+    drop(ext4);
+
+    // With quota, overwrites are still fast committed, and writes mapping
+    // new blocks, which change the usage, are committed in full
+    mkfs_image("journal.img", 64 << 20, &["-O", "fast_commit,quota", "-b", "4096"]);
+    let ext4 = open_image("journal.img", MountOptions::default());
+    let inode = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    ext4.write(inode, 0, &[0xf4; 2 * BLOCK_SIZE]).expect("write failed");
+    drop(ext4);
+    // A full commit would crash the file before the second overwrite
+    let file = Arc::new(CrashBlockFile::new("journal.img"));
+    let ext4 = Ext4::load(file.clone(), clock.clone(), MountOptions::default())
+        .expect("open ext4 failed");
+    ext4.write(inode, 0, &[0xf5; BLOCK_SIZE]).expect("write failed");
+    ext4.write(inode, BLOCK_SIZE, &[0xf6; BLOCK_SIZE]).expect("write failed");
+    file.crash();
+    drop(ext4);
+    let ext4 = open_image("journal.img", MountOptions::default());
+    let mut buf = vec![0; 2 * BLOCK_SIZE];
+    ext4.read(inode, 0, &mut buf).expect("read failed");
+    assert_eq!(buf, [[0xf5; BLOCK_SIZE], [0xf6; BLOCK_SIZE]].concat());
+    drop(ext4);
+    // The full commit of the new block crashes the file before the
+    // overwrite
+    let file = Arc::new(CrashBlockFile::new("journal.img"));
+    let ext4 =
+        Ext4::load(file, clock, MountOptions::default()).expect("open ext4 failed");
+    ext4.write(inode, 2 * BLOCK_SIZE, &[0xf7; BLOCK_SIZE]).expect("write failed");
+    ext4.write(inode, 0, &[0xf8; BLOCK_SIZE]).expect("write failed");
+    drop(ext4);
+    let ext4 = open_image("journal.img", MountOptions::default());
+    let mut buf = vec![0; 3 * BLOCK_SIZE];
+    ext4.read(inode, 0, &mut buf).expect("read failed");
+    let expected = [[0xf5; BLOCK_SIZE], [0xf6; BLOCK_SIZE], [0xf7; BLOCK_SIZE]].concat();
+    assert_eq!(buf, expected);
+    drop(ext4);
+    // The quota usage is up to date
+    fsck("journal.img");
+}
+
+fn make_check_ext4() {
+    mkfs_image("check.img", 64 << 20, &["-b", "4096", "-O", "quota,project"]);
+}
+
+fn check_test() {
+    make_check_ext4();
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    let ext4 = open_image("check.img", MountOptions::default());
+    let dir = ext4
+        .mkdir(ROOT_INO, "d1", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    ext4.generic_create(ROOT_INO, "d1/d2/d3", dir_mode)
+        .expect("mkdir failed");
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, &[0x5a; 3 * BLOCK_SIZE]).expect("write failed");
+    ext4.setxattr(file, "user.a", b"a").expect("setxattr failed");
+    ext4.link(file, ROOT_INO, "f2").expect("link failed");
+    let tmp = ext4.create(dir, "f3", file_mode).expect("create failed");
+    ext4.write(tmp, 0, &[0xa5; BLOCK_SIZE]).expect("write failed");
+    ext4.unlink(dir, "f3").expect("unlink failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    let blocks = ext4.getattr(file).unwrap().blocks;
+    drop(ext4);
+    // Corrupt the link count and the block count, and mark a free block used
+    std::fs::write(
+        "check.cmd",
+        "sif /f2 links_count 5\nsif /f2 blocks 100\nsetb 16000\n",
+    )
+    .unwrap();
+    let _ = run("debugfs", &["-w", "-f", "check.cmd", "check.img"]);
+    let ext4 = open_image("check.img", MountOptions::default());
+    let problems = ext4.check().problems;
+    assert!(problems.contains(&CheckProblem::LinkCount {
+        inode: file,
+        expected: 2,
+        found: 5
+    }));
+    assert!(problems.contains(&CheckProblem::BlockCount {
+        inode: file,
+        expected: blocks,
+        found: 100
+    }));
+    assert!(problems.contains(&CheckProblem::BlockMarkedUsed { block: 16000 }));
+}
+
+fn repair_test() {
+    make_check_ext4();
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let ext4 = open_image("check.img", MountOptions::default());
+    let dir = ext4
+        .mkdir(ROOT_INO, "d1", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    let sub = ext4
+        .mkdir(dir, "d2", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    let file = ext4.create(dir, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, &[0x5a; 2 * BLOCK_SIZE]).expect("write failed");
+    // Too large for the inode body
+    ext4.setxattr(file, "user.big", &[0x3c; 1000]).expect("setxattr failed");
+    let orphan = ext4.create(dir, "f2", file_mode).expect("create failed");
+    let blocks = ext4.getattr(file).unwrap().blocks;
+    drop(ext4);
+    // Corrupt a link count, a block count and a bitmap, and detach a file
+    // and a directory
+    std::fs::write(
+        "check.cmd",
+        "sif /d1/f1 links_count 3\nsif /d1/f1 blocks 100\nsetb 16000\n\
+         unlink /d1/f2\nunlink /d1/d2\n",
+    )
+    .unwrap();
+    let _ = run("debugfs", &["-w", "-f", "check.cmd", "check.img"]);
+    // Corrupt the hash of the xattr entry
+    let stat = run("debugfs", &["-R", "stat /d1/f1", "check.img"]);
+    let stat = String::from_utf8_lossy(&stat.stdout).into_owned();
+    let xattr_block: u64 = stat
+        .split("File ACL: ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|block| block.parse().ok())
+        .expect("no xattr block");
+    let mut image = std::fs::read("check.img").unwrap();
+    // The hash of the first entry, after the 32-byte header
+    image[xattr_block as usize * BLOCK_SIZE + 32 + 12] ^= 0xff;
+    std::fs::write("check.img", image).unwrap();
+    let ext4 = open_image("check.img", MountOptions::default());
+    // Dry run modifies nothing
+    let planned = ext4.repair(true).expect("repair failed").actions;
+    assert!(planned.contains(&RepairAction::LinkCount { inode: file, links: 1 }));
+    assert!(planned.contains(&RepairAction::BlockBitmap { group: 0 }));
+    assert!(planned.contains(&RepairAction::Reconnect { inode: orphan }));
+    assert!(planned.contains(&RepairAction::Reconnect { inode: sub }));
+    assert!(planned.contains(&RepairAction::BlockCount { inode: file, blocks }));
+    assert!(planned.contains(&RepairAction::XattrBlock {
+        inode: file,
+        block: xattr_block
+    }));
+    assert!(!ext4.check().is_clean());
+    // Repair
+    let actions = ext4.repair(false).expect("repair failed").actions;
+    assert!(actions.contains(&RepairAction::Reconnect { inode: orphan }));
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    assert_eq!(ext4.getattr(file).unwrap().links, 1);
+    assert_eq!(ext4.getattr(dir).unwrap().links, 2);
+    let lost_found = ext4.lookup(ROOT_INO, "lost+found").expect("lookup failed");
+    assert_eq!(ext4.lookup(lost_found, &format!("#{}", orphan)).unwrap(), orphan);
+    assert_eq!(ext4.lookup(lost_found, &format!("#{}", sub)).unwrap(), sub);
+    assert_eq!(ext4.lookup(sub, "..").unwrap(), lost_found);
+    assert_eq!(ext4.getxattr(file, "user.big").unwrap(), [0x3c; 1000]);
+    drop(ext4);
+    fsck("check.img");
+
+    // Fixes larger than the 4 MiB log are committed in several
+    // transactions
+    make_check_ext4();
+    let ext4 = open_image("check.img", MountOptions::default());
+    let files: Vec<u32> = (0..1100)
+        .map(|i| {
+            let file = ext4.create(ROOT_INO, &format!("f{}", i), file_mode).unwrap();
+            let value = format!("{:04}", i).repeat(500);
+            ext4.setxattr(file, "user.big", value.as_bytes()).expect("setxattr failed");
+            file
+        })
+        .collect();
+    drop(ext4);
+    // Corrupt the checksums of the xattr blocks
+    let mut image = std::fs::read("check.img").unwrap();
+    for block in image.chunks_mut(BLOCK_SIZE) {
+        if block[..4] == 0xEA02_0000u32.to_le_bytes() {
+            block[0x10] ^= 0xff;
+        }
+    }
+    std::fs::write("check.img", image).unwrap();
+    let ext4 = open_image("check.img", MountOptions::default());
+    let actions = ext4.repair(false).expect("repair failed").actions;
+    assert_eq!(actions.len(), files.len());
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("check.img");
+}
+
+fn resize_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    // Grow the last group and add one, without reserved GDT blocks
+    format_image("format.img", 20 << 20, FormatFeatures::JOURNAL);
+    let ext4 = open_image("format.img", MountOptions::default());
+    let file = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, &[0x5a; 3 * BLOCK_SIZE + 100]).expect("write failed");
+    let free = ext4.statfs(ROOT_INO).unwrap().bfree;
+    let size = 200 << 20;
+    let device = std::fs::OpenOptions::new().write(true).open("format.img").unwrap();
+    device.set_len(size).unwrap();
+    ext4.resize(size / BLOCK_SIZE as u64).expect("resize failed");
+    let stat = ext4.statfs(ROOT_INO).unwrap();
+    assert_eq!(stat.blocks, size / BLOCK_SIZE as u64);
+    assert!(stat.bfree > free + 40000);
+    // Shrinking is refused, the descriptor table cannot grow
+    assert_eq!(ext4.resize(1000).unwrap_err().code(), ErrCode::EINVAL);
+    let res = ext4.resize(100 * 32768);
+    assert_eq!(res.unwrap_err().code(), ErrCode::ENOSPC);
+    let file2 = ext4.create(ROOT_INO, "f2", file_mode).expect("create failed");
+    ext4.write(file2, 0, &[0xa5; 3 * BLOCK_SIZE + 100]).expect("write failed");
+    let mut buf = [0; BLOCK_SIZE];
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    assert_eq!(buf, [0x5a; BLOCK_SIZE]);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+
+    // A formatted image reserves blocks for 1024 times its size
+    let file = std::fs::File::create("format.img").unwrap();
+    file.set_len(16384 * BLOCK_SIZE as u64).unwrap();
+    let options = FormatOptions {
+        block_count: 16384,
+        // Small inode tables for the many new groups
+        inode_ratio: 1 << 20,
+        ..FormatOptions::default()
+    };
+    Ext4::format(Arc::new(BlockFile::new("format.img")), options).expect("format failed");
+    let ext4 = open_image("format.img", MountOptions::default());
+    let file = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, &[0x5a; 3 * BLOCK_SIZE + 100]).expect("write failed");
+    // The bitmaps of 500 groups do not fit in a journal of 1024 blocks
+    let blocks = 500 * 32768;
+    let device = std::fs::OpenOptions::new().write(true).open("format.img").unwrap();
+    device.set_len(blocks * BLOCK_SIZE as u64).unwrap();
+    assert_eq!(ext4.resize(blocks).unwrap_err().code(), ErrCode::ENOSPC);
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, 16384);
+    let blocks = 3_000_000;
+    device.set_len(blocks * BLOCK_SIZE as u64).unwrap();
+    ext4.resize(blocks).expect("resize failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, blocks);
+    ext4.create(ROOT_INO, "f2", file_mode).expect("create failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+
+    // Grow past one block of group descriptors into the reserved blocks
+    make_check_ext4();
+    let ext4 = open_image("check.img", MountOptions::default());
+    let file = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, &[0x5a; 3 * BLOCK_SIZE + 100]).expect("write failed");
+    // The last group is too small for its inode table and left out
+    let blocks = 66 * 32768 + 1000;
+    let device = std::fs::OpenOptions::new().write(true).open("check.img").unwrap();
+    device.set_len(blocks * BLOCK_SIZE as u64).unwrap();
+    ext4.resize(blocks).expect("resize failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, 66 * 32768);
+    let dir = ext4
+        .mkdir(ROOT_INO, "d1", InodeMode::from_bits_retain(0o755))
+        .expect("mkdir failed");
+    ext4.create(dir, "f2", file_mode).expect("create failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("check.img");
+    let _ = std::fs::remove_file("check.img");
+}
+
+fn defrag_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    format_image("format.img", 20 << 20, FormatFeatures::JOURNAL);
+    let ext4 = open_image("format.img", MountOptions::default());
+    // Interleaved appends leave both files in one-block extents
+    let f1 = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    let f2 = ext4.create(ROOT_INO, "f2", file_mode).expect("create failed");
+    let f3 = ext4.create(ROOT_INO, "f3", file_mode).expect("create failed");
+    for i in 0..40 {
+        ext4.write(f1, i * BLOCK_SIZE, &[i as u8; 100]).expect("write failed");
+        ext4.write(f2, i * BLOCK_SIZE, &[0x80 | i as u8; 100]).expect("write failed");
+        if i < 8 {
+            ext4.write(f3, i * BLOCK_SIZE, &[0x40 | i as u8; 100]).expect("write failed");
+        }
+    }
+    let bfree = ext4.statfs(ROOT_INO).unwrap().bfree;
+    let stats = ext4.defrag(f3).expect("defrag failed");
+    assert_eq!(stats.blocks, 8);
+    assert!(stats.extents_before > 1);
+    assert_eq!(stats.extents_after, 1);
+    assert_eq!(ext4.defrag(f3).unwrap().extents_before, 1);
+    assert_eq!(ext4.defrag(ROOT_INO).unwrap_err().code(), ErrCode::EISDIR);
+    // The most fragmented files go first
+    let stats = ext4.defrag_all();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats.iter().map(|s| s.inode).collect::<Vec<_>>(), [f1, f2]);
+    assert!(stats.iter().all(|s| s.extents_before == 40 && s.extents_after == 1));
+    assert!(ext4.defrag_all().is_empty());
+    // Only extent tree blocks are released
+    assert!(ext4.statfs(ROOT_INO).unwrap().bfree >= bfree);
+    let mut buf = [0; 100];
+    for i in 0..40 {
+        ext4.read(f1, i * BLOCK_SIZE, &mut buf).expect("read failed");
+        assert_eq!(buf, [i as u8; 100]);
+        ext4.read(f2, i * BLOCK_SIZE, &mut buf).expect("read failed");
+        assert_eq!(buf, [0x80 | i as u8; 100]);
+    }
+    ext4.write(f1, 40 * BLOCK_SIZE, &[0x5a; 100]).expect("write failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+}
+
+fn fscrypt_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    let secret: Vec<u8> = (0..64).collect();
+    let long_name = "a_file_name_long_enough_to_span_several_cipher_blocks.bin";
+    format_image("format.img", 20 << 20, FormatFeatures::JOURNAL | FormatFeatures::ENCRYPT);
+    let ext4 = open_image("format.img", MountOptions::default());
+    let dir = ext4.mkdir(ROOT_INO, "vault", dir_mode).expect("mkdir failed");
+    let policy = EncryptionPolicy::new([0; 16]);
+    assert_eq!(ext4.set_encryption_policy(dir, &policy).unwrap_err().code(), ErrCode::ENOKEY);
+    let id = ext4.add_encryption_key(&secret).expect("add key failed");
+    let policy = EncryptionPolicy::new(id);
+    ext4.set_encryption_policy(dir, &policy).expect("set policy failed");
+    assert_eq!(ext4.get_encryption_policy(dir).unwrap(), policy);
+    assert_eq!(ext4.get_encryption_policy(ROOT_INO).unwrap_err().code(), ErrCode::ENODATA);
+    // New entries inherit the policy
+    let f1 = ext4.create(dir, "secret.txt", file_mode).expect("create failed");
+    let sub = ext4.mkdir(dir, "nested", dir_mode).expect("mkdir failed");
+    let f2 = ext4.create(sub, long_name, file_mode).expect("create failed");
+    assert_eq!(ext4.get_encryption_policy(f2).unwrap(), policy);
+    ext4.write(f1, 0, b"attack at dawn").expect("write failed");
+    let mut data: Vec<u8> = (0..3 * BLOCK_SIZE + 100).map(|i| (i % 251) as u8).collect();
+    ext4.write(f2, 0, &data).expect("write failed");
+    ext4.write(f2, BLOCK_SIZE + 5, b"patched").expect("write failed");
+    data[BLOCK_SIZE + 5..BLOCK_SIZE + 12].copy_from_slice(b"patched");
+    let mut buf = vec![0; data.len()];
+    ext4.read(f2, 0, &mut buf).expect("read failed");
+    assert_eq!(buf, data);
+    let mut names: Vec<_> = ext4.listdir(dir).unwrap().iter().map(|e| e.name()).collect();
+    names.sort();
+    assert_eq!(names, [".", "..", "nested", "secret.txt"]);
+    assert_eq!(ext4.lookup(sub, long_name).unwrap(), f2);
+    assert_eq!(ext4.setxattr(f1, "encryption.c", &[0; 40]).unwrap_err().code(), ErrCode::EPERM);
+    assert!(ext4.listxattr(f1).unwrap().is_empty());
+    // A file cannot move into another policy
+    let plain = ext4.create(ROOT_INO, "plain", file_mode).expect("create failed");
+    assert_eq!(ext4.link(plain, dir, "plain").unwrap_err().code(), ErrCode::EXDEV);
+    ext4.rename(dir, "secret.txt", dir, "renamed.txt").expect("rename failed");
+    drop(ext4);
+    // Names and contents are encrypted on disk
+    let image = std::fs::read("format.img").unwrap();
+    for plaintext in [&b"renamed.txt"[..], b"nested", b"attack at dawn", b"patched"] {
+        assert!(!image.windows(plaintext.len()).any(|w| w == plaintext));
+    }
+
+    // Without the key, entries go by their no-key names
+    let ext4 = open_image("format.img", MountOptions::default());
+    let entries = ext4.listdir(dir).unwrap();
+    let nokey_name = entries.iter().find(|e| e.inode() == f1).unwrap().name();
+    assert_ne!(nokey_name, "renamed.txt");
+    assert_eq!(ext4.lookup(dir, &nokey_name).unwrap(), f1);
+    assert_eq!(ext4.lookup(dir, "renamed.txt").unwrap_err().code(), ErrCode::ENOENT);
+    assert_eq!(ext4.read(f1, 0, &mut buf).unwrap_err().code(), ErrCode::ENOKEY);
+    assert_eq!(ext4.write(f1, 0, b"x").unwrap_err().code(), ErrCode::ENOKEY);
+    assert_eq!(ext4.create(dir, "new", file_mode).unwrap_err().code(), ErrCode::ENOKEY);
+    ext4.unlink(dir, &nokey_name).expect("unlink failed");
+    // Adding the key back unlocks the files
+    assert_eq!(ext4.add_encryption_key(&secret).unwrap(), id);
+    let names: Vec<_> = ext4.listdir(dir).unwrap().iter().map(|e| e.name()).collect();
+    assert!(names.contains(&"nested".to_string()) && names.len() == 3);
+    assert_eq!(ext4.lookup(sub, long_name).unwrap(), f2);
+    ext4.read(f2, 0, &mut buf).expect("read failed");
+    assert_eq!(buf, data);
+    ext4.remove_encryption_key(&id).expect("remove key failed");
+    assert_eq!(ext4.remove_encryption_key(&id).unwrap_err().code(), ErrCode::ENOKEY);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+}
+
+fn verity_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    let features = FormatFeatures::JOURNAL | FormatFeatures::ENCRYPT | FormatFeatures::VERITY;
+    format_image("format.img", 20 << 20, features);
+    let ext4 = open_image("format.img", MountOptions::default());
+    // Two levels of Merkle tree, each block tagged to find it on disk
+    let data: Vec<u8> = (0..200 * BLOCK_SIZE + 123)
+        .map(|i| match i % BLOCK_SIZE {
+            0..=7 => format!("blk{:05}", i / BLOCK_SIZE).as_bytes()[i % BLOCK_SIZE],
+            _ => (i % 251) as u8,
+        })
+        .collect();
+    let big = ext4.create(ROOT_INO, "big", file_mode).expect("create failed");
+    ext4.write(big, 0, &data).expect("write failed");
+    ext4.defrag(big).expect("defrag failed");
+    let small = ext4.create(ROOT_INO, "small", file_mode).expect("create failed");
+    ext4.write(small, 0, b"integrity").expect("write failed");
+    let empty = ext4.create(ROOT_INO, "empty", file_mode).expect("create failed");
+    let dir = ext4.mkdir(ROOT_INO, "vault", dir_mode).expect("mkdir failed");
+    let id = ext4.add_encryption_key(&[7; 32]).expect("add key failed");
+    ext4.set_encryption_policy(dir, &EncryptionPolicy::new(id)).expect("set policy failed");
+    let secret = ext4.create(dir, "small", file_mode).expect("create failed");
+    ext4.write(secret, 0, b"integrity").expect("write failed");
+    assert_eq!(ext4.measure_verity(big).unwrap_err().code(), ErrCode::ENODATA);
+    for file in [big, small, empty, secret] {
+        ext4.enable_verity(file).expect("enable verity failed");
+        assert!(ext4.get_flags(file).unwrap().contains(InodeFlags::VERITY));
+    }
+    assert_eq!(ext4.enable_verity(big).unwrap_err().code(), ErrCode::EEXIST);
+    assert_eq!(ext4.enable_verity(dir).unwrap_err().code(), ErrCode::EISDIR);
+    // The data is read-only
+    assert_eq!(ext4.write(big, 0, b"x").unwrap_err().code(), ErrCode::EPERM);
+    let err = ext4.setattr(small, None, None, None, Some(0), None, None, None, None);
+    assert_eq!(err.unwrap_err().code(), ErrCode::EPERM);
+    ext4.setattr(small, Some(InodeMode::FILE), None, None, None, None, None, None, None)
+        .expect("setattr failed");
+    // The digests are the same as `fsverity digest`
+    let hex = |digest: [u8; 32]| digest.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    assert_eq!(
+        hex(ext4.measure_verity(small).unwrap()),
+        "fc9eae5391c182746a6b41b73b225c78709218d632291fda7ec70a2b6803d78b"
+    );
+    assert_eq!(ext4.measure_verity(secret).unwrap(), ext4.measure_verity(small).unwrap());
+    assert_eq!(
+        hex(ext4.measure_verity(empty).unwrap()),
+        "3d248ca542a24fc62d1c43b916eae5016878e2533c88238480b26128a1f1af95"
+    );
+    let big_digest = ext4.measure_verity(big).unwrap();
+    assert_eq!(
+        hex(big_digest),
+        "bcc066c8684b4f3e60934b41aebe9f6c1aa09c919bca6887a221c925a76b570e"
+    );
+    let mut buf = vec![0; data.len() + 10];
+    assert_eq!(ext4.read(big, 0, &mut buf).expect("read failed"), data.len());
+    assert_eq!(buf[..data.len()], data);
+    assert_eq!(ext4.read(empty, 0, &mut buf).expect("read failed"), 0);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+
+    // A corrupted block fails to read, the others still read
+    let mut image = std::fs::read("format.img").unwrap();
+    // Stale copies left by the defragmentation are corrupted as well
+    let marks: Vec<usize> = (0..image.len() - 8)
+        .filter(|&pos| &image[pos..pos + 8] == b"blk00150")
+        .collect();
+    for pos in marks {
+        image[pos + 100] ^= 1;
+    }
+    std::fs::write("format.img", &image).unwrap();
+    let ext4 = open_image("format.img", MountOptions::default());
+    assert_eq!(ext4.measure_verity(big).unwrap(), big_digest);
+    let err = ext4.read(big, 150 * BLOCK_SIZE + 10, &mut buf[..10]).unwrap_err();
+    assert_eq!(err.code(), ErrCode::EIO);
+    assert_eq!(ext4.read(big, 0, &mut buf).unwrap_err().code(), ErrCode::EIO);
+    ext4.read(big, 149 * BLOCK_SIZE, &mut buf[..BLOCK_SIZE]).expect("read failed");
+    assert_eq!(buf[..BLOCK_SIZE], data[149 * BLOCK_SIZE..150 * BLOCK_SIZE]);
+    ext4.read(big, 200 * BLOCK_SIZE, &mut buf[..123]).expect("read failed");
+    assert_eq!(buf[..123], data[200 * BLOCK_SIZE..]);
+    // Without the key the encrypted file cannot be measured
+    assert_eq!(ext4.measure_verity(secret).unwrap_err().code(), ErrCode::ENOKEY);
+}
+
+fn casefold_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    format_image("format.img", 20 << 20, FormatFeatures::JOURNAL | FormatFeatures::CASEFOLD);
+    let ext4 = open_image("format.img", MountOptions::default());
+    let dir = ext4.mkdir(ROOT_INO, "ci", dir_mode).expect("mkdir failed");
+    ext4.set_flags(dir, InodeFlags::CASEFOLD).expect("set_flags failed");
+    // Names are matched ignoring case and normalization, and kept as given
+    let file = ext4.create(dir, "Caf\u{e9}.txt", file_mode).expect("create failed");
+    assert_eq!(ext4.lookup(dir, "CAFE\u{301}.TXT").unwrap(), file);
+    assert_eq!(ext4.lookup(dir, "caf\u{c9}.txt").unwrap(), file);
+    let err = ext4.create(dir, "CAF\u{c9}.TXT", file_mode).unwrap_err();
+    assert_eq!(err.code(), ErrCode::EEXIST);
+    assert_eq!(ext4.link(file, dir, "cafe\u{301}.TXT").unwrap_err().code(), ErrCode::EEXIST);
+    ext4.rename(dir, "CAF\u{c9}.TXT", dir, "caf\u{e9}.TXT").expect("rename failed");
+    let names: Vec<_> = ext4.listdir(dir).unwrap().iter().map(|e| e.name()).collect();
+    assert!(names.contains(&"Caf\u{e9}.txt".to_string()));
+    ext4.create(dir, "stra\u{df}e", file_mode).expect("create failed");
+    ext4.unlink(dir, "STRASSE").expect("unlink failed");
+    // Subdirectories inherit the flag, which only changes on empty directories
+    let sub = ext4.mkdir(dir, "Sub", dir_mode).expect("mkdir failed");
+    assert!(ext4.get_flags(sub).unwrap().contains(InodeFlags::CASEFOLD));
+    assert_eq!(ext4.set_flags(dir, InodeFlags::empty()).unwrap_err().code(), ErrCode::ENOTEMPTY);
+    ext4.set_flags(sub, InodeFlags::empty()).expect("set_flags failed");
+    ext4.create(sub, "a", file_mode).expect("create failed");
+    ext4.create(sub, "A", file_mode).expect("create failed");
+    // Fill a directory for e2fsck to index
+    let big = ext4.mkdir(dir, "big", dir_mode).expect("mkdir failed");
+    let name = |i: usize| format!("\u{c9}l\u{e8}ve_num\u{e9}ro_{:04}_{}", i, "x".repeat(i % 40));
+    for i in 0..300 {
+        ext4.create(big, &name(i), file_mode).expect("create failed");
+    }
+    drop(ext4);
+    let tune = run("tune2fs", &["-O", "dir_index", "format.img"]);
+    assert!(tune.status.success());
+    run("e2fsck", &["-fyD", "format.img"]);
+
+    // Lookups in the indexed directory hash the folded names
+    let ext4 = open_image("format.img", MountOptions::default());
+    assert!(ext4.get_flags(big).unwrap().contains(InodeFlags::INDEX));
+    for i in 0..300 {
+        assert!(ext4.lookup(big, &name(i).to_uppercase()).is_ok(), "{}", name(i));
+    }
+    assert_eq!(ext4.lookup(big, "missing").unwrap_err().code(), ErrCode::ENOENT);
+    let err = ext4.create(big, &name(7).to_lowercase(), file_mode).unwrap_err();
+    assert_eq!(err.code(), ErrCode::EEXIST);
+    // New names go to the leaves covering their hashes
+    for i in (0..300).step_by(5) {
+        ext4.unlink(big, &name(i).to_uppercase()).expect("unlink failed");
+    }
+    for i in 300..320 {
+        ext4.create(big, &name(i), file_mode).expect("create failed");
+    }
+    assert!(ext4.get_flags(big).unwrap().contains(InodeFlags::INDEX));
+    drop(ext4);
+    fsck("format.img");
+    // Full leaves are split
+    let ext4 = open_image("format.img", MountOptions::default());
+    for i in 320..400 {
+        ext4.create(big, &name(i), file_mode).expect("create failed");
+    }
+    assert!(ext4.get_flags(big).unwrap().contains(InodeFlags::INDEX));
+    for i in (0..400).filter(|i| i % 5 != 0 || *i >= 300) {
+        assert!(ext4.lookup(big, &name(i).to_lowercase()).is_ok(), "{}", name(i));
+    }
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("format.img");
+}
+
+fn large_dir_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    let name = |i: usize| format!("{:08x}{}", i.wrapping_mul(0x9E37_79B9), "o".repeat(200));
+    // An index built by e2fsck is extended
+    format_image("format.img", 20 << 20, FormatFeatures::JOURNAL);
+    let ext4 = open_image("format.img", MountOptions::default());
+    let dir = ext4.mkdir(ROOT_INO, "objects", dir_mode).expect("mkdir failed");
+    let file = ext4.create(ROOT_INO, "object", file_mode).expect("create failed");
+    for i in 0..100 {
+        ext4.link(file, dir, &name(i)).expect("link failed");
+    }
+    drop(ext4);
+    assert!(run("tune2fs", &["-O", "dir_index", "format.img"]).status.success());
+    run("e2fsck", &["-fyD", "format.img"]);
+    let ext4 = open_image("format.img", MountOptions::default());
+    assert!(ext4.get_flags(dir).unwrap().contains(InodeFlags::INDEX));
+    for i in 100..200 {
+        ext4.link(file, dir, &name(i)).expect("link failed");
+    }
+    for i in 0..200 {
+        assert!(ext4.lookup(dir, &name(i)).is_ok(), "{}", name(i));
+    }
+    drop(ext4);
+    fsck("format.img");
+
+    // A directory is indexed once it outgrows its first block
+    let features = FormatFeatures::JOURNAL | FormatFeatures::LARGE_DIR | FormatFeatures::DIR_INDEX;
+    format_image("format.img", 64 << 20, features);
+    let ext4 = open_image("format.img", MountOptions::default());
+    let dir = ext4.mkdir(ROOT_INO, "objects", dir_mode).expect("mkdir failed");
+    let file = ext4.create(ROOT_INO, "object", file_mode).expect("create failed");
+    for i in 0..18 {
+        ext4.link(file, dir, &name(i)).expect("link failed");
+    }
+    assert!(!ext4.get_flags(dir).unwrap().contains(InodeFlags::INDEX));
+    ext4.link(file, dir, &name(18)).expect("link failed");
+    assert!(ext4.get_flags(dir).unwrap().contains(InodeFlags::INDEX));
+    for i in 0..19 {
+        assert!(ext4.lookup(dir, &name(i)).is_ok(), "{}", name(i));
+    }
+
+    // Leaves split until the root is full, then a level is added
+    for i in 19..8000 {
+        ext4.link(file, dir, &name(i)).expect("link failed");
+    }
+    for i in 0..8000 {
+        assert!(ext4.lookup(dir, &name(i)).is_ok(), "{}", name(i));
+    }
+    assert_eq!(ext4.lookup(dir, "missing").unwrap_err().code(), ErrCode::ENOENT);
+    for i in (0..8000).step_by(3) {
+        ext4.unlink(dir, &name(i)).expect("unlink failed");
+    }
+    for i in (0..8000).filter(|i| i % 3 != 0) {
+        assert!(ext4.lookup(dir, &name(i)).is_ok(), "{}", name(i));
+    }
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    let htree = run("debugfs", &["-R", "htree objects", "format.img"]);
+    assert!(String::from_utf8_lossy(&htree.stdout).contains("Indirect levels: 1"));
+    fsck("format.img");
+    // A corrupted interior node is found
+    let bmap = |lblock: u32| -> usize {
+        let output = run("debugfs", &["-R", &format!("bmap objects {}", lblock), "format.img"]);
+        let block: usize = String::from_utf8_lossy(&output.stdout).trim().parse().unwrap();
+        block * BLOCK_SIZE
+    };
+    let mut image = std::fs::read("format.img").unwrap();
+    // The block of the first entry of the root, after the count and limit
+    let root = bmap(0);
+    let node = bmap(u32::from_le_bytes(image[root + 36..root + 40].try_into().unwrap()));
+    // The hash of the second entry of the node
+    image[node + 16..node + 20].fill(0xff);
+    std::fs::write("format.img", image).unwrap();
+    let ext4 = open_image("format.img", MountOptions::default());
+    let problems = ext4.check().problems;
+    let block = (node / BLOCK_SIZE) as u64;
+    assert_eq!(problems, [CheckProblem::BadDxNode { inode: dir, block }]);
+    drop(ext4);
+
+    // Directories grow past 2 GiB with large_dir, and stay below it without
+    for large_dir in [true, false] {
+        let features = if large_dir {
+            FormatFeatures::JOURNAL | FormatFeatures::LARGE_DIR
+        } else {
+            FormatFeatures::JOURNAL
+        };
+        format_image("format.img", 20 << 20, features);
+        let ext4 = open_image("format.img", MountOptions::default());
+        let dir = ext4.mkdir(ROOT_INO, "flat", dir_mode).expect("mkdir failed");
+        let file = ext4.create(ROOT_INO, "object", file_mode).expect("create failed");
+        // Fill the first block
+        for i in 0..18 {
+            ext4.link(file, dir, &name(i)).expect("link failed");
+        }
+        assert_eq!(ext4.getattr(dir).unwrap().size, BLOCK_SIZE as u64);
+        drop(ext4);
+        let size = format!("sif flat size {:#x}", 0x7FFF_F000u64);
+        assert!(run("debugfs", &["-w", "-R", &size, "format.img"]).status.success());
+        let ext4 = open_image("format.img", MountOptions::default());
+        let res = ext4.link(file, dir, &name(18));
+        if large_dir {
+            res.expect("link failed");
+            assert_eq!(ext4.getattr(dir).unwrap().size, 0x8000_0000);
+        } else {
+            assert_eq!(res.unwrap_err().code(), ErrCode::EFBIG);
+            assert_eq!(ext4.getattr(dir).unwrap().size, 0x7FFF_F000);
+        }
+        assert!(ext4.lookup(dir, &name(0)).is_ok());
+    }
+}
+
+fn bigalloc_test() {
+    let file_mode = InodeMode::FILE | InodeMode::from_bits_retain(0o644);
+    let dir_mode = InodeMode::DIRECTORY | InodeMode::ALL_RWX;
+    // 64 KiB clusters of 16 blocks
+    let cluster = 16 * BLOCK_SIZE as u64;
+    mkfs_image("bigalloc.img", 128 << 20, &["-b", "4096", "-O", "bigalloc,quota", "-C", "65536"]);
+    let host: Vec<u8> = (0..300_000).map(|i| (i % 251) as u8).collect();
+    std::fs::write("host.bin", &host).unwrap();
+    let _ = run("debugfs", &["-w", "-R", "write host.bin host", "bigalloc.img"]);
+    // debugfs does not charge quota
+    let _ = run("e2fsck", &["-fy", "bigalloc.img"]);
+
+    let ext4 = open_image("bigalloc.img", MountOptions::default());
+    let bfree = ext4.statfs(ROOT_INO).unwrap().bfree;
+    // A file written by Linux
+    let file = ext4.lookup(ROOT_INO, "host").expect("lookup failed");
+    let mut buf = vec![0; host.len()];
+    assert_eq!(ext4.read(file, 0, &mut buf).expect("read failed"), host.len());
+    assert_eq!(buf, host);
+
+    // Interleaved files fill their own clusters
+    let a = ext4.create(ROOT_INO, "a", file_mode).expect("create failed");
+    let b = ext4.create(ROOT_INO, "b", file_mode).expect("create failed");
+    ext4.write(a, 0, &[1; 100]).expect("write failed");
+    ext4.write(b, 0, &[2; 100]).expect("write failed");
+    ext4.write(a, BLOCK_SIZE, &[3; 100]).expect("write failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 32);
+    ext4.write(b, 20 * BLOCK_SIZE, &[4; 100]).expect("write failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 48);
+    ext4.read(a, BLOCK_SIZE, &mut buf[..100]).expect("read failed");
+    assert_eq!(buf[..100], [3; 100]);
+    ext4.read(b, 20 * BLOCK_SIZE, &mut buf[..100]).expect("read failed");
+    assert_eq!(buf[..100], [4; 100]);
+
+    // A large media file written in unaligned chunks
+    let media = ext4.create(ROOT_INO, "media.mkv", file_mode).expect("create failed");
+    let data: Vec<u8> = (0..(6 << 20) - 1000).map(|i: u32| (i / 7) as u8).collect();
+    for chunk in (0..data.len()).step_by(100_003) {
+        let end = (chunk + 100_003).min(data.len());
+        ext4.write(media, chunk, &data[chunk..end]).expect("write failed");
+    }
+    let mut buf = vec![0; data.len()];
+    assert_eq!(ext4.read(media, 0, &mut buf).expect("read failed"), data.len());
+    assert_eq!(buf, data);
+    let clusters = (data.len() as u64).div_ceil(cluster);
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree - 48 - clusters * 16);
+    let dir = ext4.mkdir(ROOT_INO, "dir", dir_mode).expect("mkdir failed");
+    for i in 0..10 {
+        let f = ext4.create(dir, &format!("f{}", i), file_mode).expect("create failed");
+        ext4.write(f, 0, &[i as u8; 5000]).expect("write failed");
+    }
+    assert_eq!(ext4.defrag(media).unwrap_err().code(), ErrCode::ENOTSUP);
+    // Usage moves to the new owner in whole clusters
+    let before = ext4.get_quota(QuotaType::User, 1001).unwrap().space;
+    ext4.setattr(b, None, Some(1001), None, None, None, None, None, None)
+        .expect("chown failed");
+    assert_eq!(ext4.get_quota(QuotaType::User, 1001).unwrap().space, before + 2 * cluster);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("bigalloc.img");
+
+    // All clusters are freed again
+    let ext4 = open_image("bigalloc.img", MountOptions::default());
+    for i in 0..10 {
+        ext4.unlink(dir, &format!("f{}", i)).expect("unlink failed");
+    }
+    ext4.rmdir(ROOT_INO, "dir").expect("rmdir failed");
+    for name in ["a", "b", "media.mkv"] {
+        ext4.unlink(ROOT_INO, name).expect("unlink failed");
+    }
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree);
+    ext4.unlink(ROOT_INO, "host").expect("unlink failed");
+    let clusters = (host.len() as u64).div_ceil(cluster);
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().bfree, bfree + clusters * 16);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("bigalloc.img");
+}
+
+fn shrink_test() {
+    // 4 groups of 16 inodes, with a file and a directory in group 2
+    mkfs_image(
+        "shrink.img",
+        512 << 20,
+        &["-b", "4096", "-N", "64", "-J", "size=4,location=0"],
+    );
+    let data: Vec<u8> = (0..60 << 20).map(|i: u32| (i % 251) as u8).collect();
+    std::fs::write("shrink_data.bin", &data).unwrap();
+    std::fs::write("shrink_filler.bin", vec![0x11; 250 << 20]).unwrap();
+    std::fs::write("shrink_small.bin", b"small file").unwrap();
+    let mut cmds = String::from("write shrink_filler.bin filler\nwrite shrink_data.bin data\n");
+    for i in 1..=30 {
+        cmds += &format!("write shrink_small.bin f{}\n", i);
+    }
+    cmds += "mkdir d\ncd d\nwrite shrink_small.bin g1\nwrite shrink_small.bin g2\ncd /\n";
+    cmds += "rm filler\n";
+    for i in 1..=14 {
+        cmds += &format!("rm f{}\n", i);
+    }
+    std::fs::write("shrink.cmd", cmds).unwrap();
+    let _ = run("debugfs", &["-w", "-f", "shrink.cmd", "shrink.img"]);
+    let ext4 = open_image("shrink.img", MountOptions::default());
+    let dir = ext4.lookup(ROOT_INO, "d").expect("lookup failed");
+    assert!(dir > 32);
+    // The data does not fit in 40 MiB, nothing is changed
+    let res = ext4.shrink(10240);
+    assert_eq!(res.unwrap_err().code(), ErrCode::ENOSPC);
+    assert!(ext4.check().is_clean());
+    // Keep group 0 and part of group 1
+    ext4.shrink(51200).expect("shrink failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, 51200);
+    let file = ext4.lookup(ROOT_INO, "data").expect("lookup failed");
+    let mut buf = vec![0; data.len()];
+    assert_eq!(ext4.read(file, 0, &mut buf).unwrap(), data.len());
+    assert!(buf == data);
+    let dir = ext4.lookup(ROOT_INO, "d").expect("lookup failed");
+    assert!(dir <= 32);
+    assert_eq!(ext4.lookup(dir, "..").unwrap(), ROOT_INO);
+    let file = ext4.lookup(dir, "g2").expect("lookup failed");
+    let mut buf = [0; 10];
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    assert_eq!(&buf, b"small file");
+    ext4.create(dir, "g3", InodeMode::FILE | InodeMode::ALL_RWX)
+        .expect("create failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    fsck("shrink.img");
+
+    // The journal in the middle of a default image is moved
+    let device = std::fs::File::create("shrink.img").unwrap();
+    device.set_len(512 << 20).unwrap();
+    let _ = run("mkfs.ext4", &["-q", "shrink.img"]);
+    let ext4 = open_image("shrink.img", MountOptions::default());
+    let file_mode = InodeMode::FILE | InodeMode::ALL_RWX;
+    let file = ext4.create(ROOT_INO, "f1", file_mode).expect("create failed");
+    ext4.write(file, 0, b"small file").expect("write failed");
+    ext4.shrink(40000).expect("shrink failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, 40000);
+    // Later transactions are logged in the new blocks of the journal
+    ext4.create(ROOT_INO, "f2", file_mode).expect("create failed");
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    device.set_len(40000 * BLOCK_SIZE as u64).unwrap();
+    fsck("shrink.img");
+    let ext4 = open_image("shrink.img", MountOptions::default());
+    let file = ext4.lookup(ROOT_INO, "f1").expect("lookup failed");
+    let mut buf = [0; 10];
+    ext4.read(file, 0, &mut buf).expect("read failed");
+    assert_eq!(&buf, b"small file");
+    ext4.lookup(ROOT_INO, "f2").expect("lookup failed");
+    drop(ext4);
+
+    // Shrink the group descriptor table back after growing it
+    make_check_ext4();
+    let ext4 = open_image("check.img", MountOptions::default());
+    let blocks = 66 * 32768;
+    let device = std::fs::OpenOptions::new()
+        .write(true)
+        .open("check.img")
+        .unwrap();
+    device.set_len(blocks * BLOCK_SIZE as u64).unwrap();
+    ext4.resize(blocks).expect("resize failed");
+    ext4.shrink(16384).expect("shrink failed");
+    assert_eq!(ext4.statfs(ROOT_INO).unwrap().blocks, 16384);
+    let report = ext4.check();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    drop(ext4);
+    device.set_len(16384 * BLOCK_SIZE as u64).unwrap();
+    fsck("check.img");
+    for path in [
+        "check.img",
+        "shrink_data.bin",
+        "shrink_filler.bin",
+        "shrink_small.bin",
+    ] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
 fn main() {
     SimpleLogger::new().init().unwrap();
     log::set_max_level(log::LevelFilter::Off);
@@ -146,4 +1746,52 @@ fn main() {
     println!("remove file test done");
     xattr_test(&mut ext4);
     println!("xattr test done");
+    time_test(&mut ext4);
+    println!("time test done");
+    timestamp_test(&mut ext4);
+    println!("timestamp test done");
+    permission_test(&mut ext4);
+    println!("permission test done");
+    acl_test(&mut ext4);
+    println!("acl test done");
+    quota_test(&mut ext4);
+    println!("quota test done");
+    rollback_test(&mut ext4);
+    println!("rollback test done");
+    project_test(&mut ext4);
+    println!("project test done");
+    flags_test(&mut ext4);
+    println!("flags test done");
+    format_test();
+    println!("format test done");
+    acl_image_test();
+    println!("acl image test done");
+    check_test();
+    println!("check test done");
+    repair_test();
+    println!("repair test done");
+    resize_test();
+    println!("resize test done");
+    shrink_test();
+    println!("shrink test done");
+    defrag_test();
+    println!("defrag test done");
+    fscrypt_test();
+    println!("fscrypt test done");
+    casefold_test();
+    println!("casefold test done");
+    verity_test();
+    println!("verity test done");
+    large_dir_test();
+    println!("large dir test done");
+    bigalloc_test();
+    println!("bigalloc test done");
+    journal_test();
+    println!("journal test done");
+    journal_data_test();
+    println!("journal data test done");
+    external_journal_test();
+    println!("external journal test done");
+    fast_commit_test();
+    println!("fast commit test done");
 }
@@ -25,6 +25,8 @@ pub enum ErrCode {
     EFAULT = 14,
     /// File exists.
     EEXIST = 17,
+    /// Cross-device link.
+    EXDEV = 18,
     /// No such device.
     ENODEV = 19,
     /// Not a directory.
@@ -53,6 +55,10 @@ pub enum ErrCode {
     ELINKFAIL = 97,
     /// Inode alloc failed.
     EALLOCFAIL = 98,
+    /// Quota exceeded.
+    EDQUOT = 122,
+    /// Required key not available.
+    ENOKEY = 126,
 }
 
 /// error used in this crate
@@ -0,0 +1,143 @@
+//! POSIX access control lists.
+//!
+//! ACLs are stored as xattrs in the ext4 on-disk format, in the inode body
+//! if they fit, or in the xattr block. The xattr interface (`getxattr`,
+//! `setxattr`, `removexattr`) exchanges them in the VFS format, the same as
+//! the Linux kernel does.
+
+use super::Ext4;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+impl Ext4 {
+    /// Get the ACL of an inode.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    /// * `ty` - the type of the ACL
+    ///
+    /// # Return
+    ///
+    /// `Ok(Some(acl))` if the ACL is set, `Ok(None)` otherwise
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    pub fn get_acl(&self, inode: InodeId, ty: AclType) -> Result<Option<PosixAcl>> {
+        let inode_ref = self.read_inode(inode);
+        if inode_ref.inode.mode().bits() == 0 {
+            return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+        }
+        Ok(self.read_acl(&inode_ref, ty))
+    }
+
+    /// Set or remove the ACL of an inode.
+    ///
+    /// Setting the access ACL also updates the permission bits of the
+    /// inode. An access ACL equivalent to the permission bits is not
+    /// stored.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    /// * `ty` - the type of the ACL
+    /// * `acl` - the new ACL, `None` to remove the ACL
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EPERM` - the inode is immutable or append-only
+    /// * `EACCES` - setting a default ACL on a non-directory
+    /// * `ENOSPC` - neither the inode nor the xattr block has enough space
+    pub fn set_acl(&self, inode: InodeId, ty: AclType, acl: Option<&PosixAcl>) -> Result<()> {
+        self.trans_run(|| {
+            let mut inode_ref = self.read_inode(inode);
+            if inode_ref.inode.mode().bits() == 0 {
+                return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+            }
+            Self::check_flags(&inode_ref, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            let mut acl = acl;
+            match ty {
+                AclType::Access => {
+                    if let Some(a) = acl {
+                        Self::set_perm_bits(&mut inode_ref, a.mode());
+                        if a.equiv_mode().is_some() {
+                            acl = None;
+                        }
+                    }
+                }
+                AclType::Default => {
+                    if !inode_ref.inode.is_dir() {
+                        return_error!(
+                            ErrCode::EACCES,
+                            "Cannot set default ACL on non-directory {}",
+                            inode
+                        );
+                    }
+                }
+            }
+            self.write_acl(&mut inode_ref, ty, acl)?;
+            self.touch_change(&mut inode_ref);
+            self.write_inode_with_csum(&mut inode_ref);
+            Ok(())
+        })
+    }
+
+    /// Read the ACL of an inode from its xattrs. Return `None` if the ACL
+    /// is not set or is corrupted.
+    pub(super) fn read_acl(&self, inode: &InodeRef, ty: AclType) -> Option<PosixAcl> {
+        let value = self.xattr_get(inode, ty.xattr_name())?;
+        let acl = PosixAcl::from_disk(&value);
+        if acl.is_none() {
+            warn!("Corrupted ACL on inode {}", inode.id);
+        }
+        acl
+    }
+
+    /// Update the ACL of an inode in its xattrs. The inode is modified but
+    /// not written.
+    fn write_acl(&self, inode: &mut InodeRef, ty: AclType, acl: Option<&PosixAcl>) -> Result<()> {
+        let value = acl.map(PosixAcl::to_disk);
+        self.xattr_set(inode, ty.xattr_name(), value.as_deref())?;
+        Ok(())
+    }
+
+    /// Update the access ACL of an inode after its permission bits are
+    /// changed. The inode is not written.
+    pub(super) fn acl_chmod(&self, inode: &mut InodeRef) -> Result<()> {
+        if let Some(mut acl) = self.read_acl(inode, AclType::Access) {
+            acl.chmod(inode.inode.mode().bits());
+            self.write_acl(inode, AclType::Access, Some(&acl))?;
+        }
+        Ok(())
+    }
+
+    /// Apply the default ACL of `parent` to a new inode. A new directory
+    /// inherits the default ACL, and the access ACL of the new inode is
+    /// derived from it. The inode is modified but not written.
+    pub(super) fn acl_inherit(&self, parent: &InodeRef, child: &mut InodeRef) -> Result<()> {
+        let default = match self.read_acl(parent, AclType::Default) {
+            Some(acl) => acl,
+            None => return Ok(()),
+        };
+        if child.inode.is_dir() {
+            self.write_acl(child, AclType::Default, Some(&default))?;
+        }
+        let mut access = default;
+        let (perm, not_equiv) = access.create_masq(child.inode.mode().bits());
+        Self::set_perm_bits(child, perm);
+        if not_equiv {
+            self.write_acl(child, AclType::Access, Some(&access))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the rwx permission bits of an inode, keeping the file type
+    /// and the special bits.
+    fn set_perm_bits(inode: &mut InodeRef, perm: u16) {
+        let mode = inode.inode.mode().bits() & !0o777 | perm & 0o777;
+        inode.inode.set_mode(InodeMode::from_bits_retain(mode));
+    }
+}
@@ -1,24 +1,43 @@
 use super::Ext4;
 use crate::constants::*;
 use crate::ext4_defs::*;
-use crate::format_error;
 use crate::prelude::*;
 use crate::return_error;
 
 impl Ext4 {
-    /// Create a new inode, returning the inode and its number
-    pub(super) fn create_inode(&self, mode: InodeMode) -> Result<InodeRef> {
-        // Allocate an inode
-        let is_dir = mode.file_type() == FileType::Directory;
-        let id = self.alloc_inode(is_dir)?;
-
+    /// Create a new inode owned by `uid` and `gid` in directory `parent`,
+    /// returning the inode and its number
+    pub(super) fn create_inode(
+        &self,
+        parent: &InodeRef,
+        mode: InodeMode,
+        uid: u32,
+        gid: u32,
+    ) -> Result<InodeRef> {
         // Initialize the inode
         let mut inode = Inode::default();
         inode.set_mode(mode);
+        inode.set_uid(uid);
+        inode.set_gid(gid);
+        inode.set_flags(InodeFlags::inherited(
+            parent.inode.flags(),
+            mode.file_type(),
+        ));
         inode.extent_init();
+        self.project_inherit(&parent.inode, &mut inode);
+        let now = self.now();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_crtime(now);
+
+        // Allocate an inode
+        let id = self.alloc_inode(&inode)?;
         let mut inode_ref = InodeRef::new(id, inode);
 
-        // Sync the inode to disk
+        // Sync the inode to disk, with no xattrs left by a freed inode
+        let area = self.read_inode_xattr_area(&inode_ref);
+        self.write_inode_xattr_area(&inode_ref, &vec![0; area.len()]);
         self.write_inode_with_csum(&mut inode_ref);
 
         trace!("Alloc inode {} ok", inode_ref.id);
@@ -33,6 +52,11 @@ impl Ext4 {
             InodeMode::from_bits_retain(0o755),
         ));
         inode.extent_init();
+        let now = self.now();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_crtime(now);
 
         let mut root = InodeRef::new(EXT4_ROOT_INO, inode);
         let root_self = root.clone();
@@ -48,13 +72,18 @@ impl Ext4 {
 
     /// Free an allocated inode and all data blocks allocated for it
     pub(super) fn free_inode(&self, inode: &mut InodeRef) -> Result<()> {
-        // Free the data blocks allocated for the inode
+        // Free the data blocks allocated for the inode. With bigalloc,
+        // blocks of several extents may share a cluster freed only once.
+        let ratio = self.read_super_block().cluster_ratio() as PBlockId;
         let pblocks = self.extent_all_data_blocks(&inode);
+        let mut clusters = BTreeSet::new();
         for pblock in pblocks {
             // Deallocate the block
-            self.dealloc_block(inode, pblock)?;
+            if clusters.insert(pblock / ratio) {
+                self.dealloc_block(inode, pblock)?;
+            }
             // Clear the block content
-            self.write_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+            self.write_data_block(&Block::new(pblock, [0; BLOCK_SIZE]));
         }
         // Free extent tree
         let pblocks = self.extent_all_tree_blocks(&inode);
@@ -79,9 +108,8 @@ impl Ext4 {
 
     /// Append a data block for an inode, return a pair of (logical block id, physical block id)
     ///
-    /// Only data blocks allocated by `inode_append_block` will be counted in `inode.block_count`.
-    /// Blocks allocated by calling `alloc_block` directly will not be counted, i.e., blocks
-    /// allocated for the inode's extent tree.
+    /// The block is mapped after the last mapped one, and counted in `inode.block_count` by
+    /// `alloc_block`, like the blocks of the inode's extent tree and xattr block.
     ///
     /// Appending a block does not increase `inode.size`, because `inode.size` records the actual
     /// size of the data content, not the number of blocks allocated for it.
@@ -91,23 +119,149 @@ impl Ext4 {
     /// newly created block.
     pub(super) fn inode_append_block(&self, inode: &mut InodeRef) -> Result<(LBlockId, PBlockId)> {
         // The new logical block id
-        let iblock = inode.inode.fs_block_count() as LBlockId;
-        // Check the extent tree to get the physical block id
-        let fblock = self.extent_query_or_create(inode, iblock, 1)?;
-        // Update block count
-        inode.inode.set_fs_block_count(iblock as u64 + 1);
+        let iblock = self.inode_data_blocks(inode) as LBlockId;
+        let fblock = self.inode_map_block(inode, iblock)?;
         self.write_inode_without_csum(inode);
 
         Ok((iblock, fblock))
     }
 
-    /// Allocate a new physical block for an inode, return the physical block number
+    /// Get the number of data blocks of an inode, mapped from logical block 0.
+    ///
+    /// The data blocks end at the last mapped block. `inode.block_count` also counts the
+    /// extent tree and xattr blocks, and whole clusters with bigalloc.
+    pub(super) fn inode_data_blocks(&self, inode: &InodeRef) -> u64 {
+        self.extent_end_block(inode) as u64
+    }
+
+    /// Map a data block of an inode at logical block `iblock`, which must not be mapped,
+    /// extending the last extent if the new block follows it.
+    ///
+    /// With bigalloc, a block of a logical cluster whose other blocks are mapped is taken from
+    /// their physical cluster at the same offset, and only new clusters are allocated.
+    pub(super) fn inode_map_block(
+        &self,
+        inode: &mut InodeRef,
+        iblock: LBlockId,
+    ) -> Result<PBlockId> {
+        let ratio = self.read_super_block().cluster_ratio();
+        let offset = iblock % ratio;
+        // The physical cluster of the logical cluster, if any of it is mapped
+        let first = iblock - offset;
+        let mut neighbours =
+            (first..first.saturating_add(ratio)).filter(|&lblock| lblock != iblock);
+        let cluster = neighbours.find_map(|lblock| {
+            let pblock = self.extent_query(inode, lblock).ok()?;
+            Some(pblock - (lblock % ratio) as PBlockId)
+        });
+        let fblock = match cluster {
+            Some(cluster) => cluster + offset as PBlockId,
+            None => self.alloc_block(inode)? + offset as PBlockId,
+        };
+        self.extent_map(inode, iblock, fblock)?;
+        Ok(fblock)
+    }
+
+    /// Allocate a new physical block for an inode, return the physical block number.
+    /// With bigalloc, a whole cluster is allocated and its first block returned. The
+    /// block is counted in `inode.block_count`, the inode is not written.
     pub(super) fn alloc_block(&self, inode: &mut InodeRef) -> Result<PBlockId> {
+        let ratio = self.read_super_block().cluster_ratio();
+        let cluster_size = ratio as u64 * BLOCK_SIZE as u64;
+
+        // Charge the owner before allocating
+        self.quota_alloc_space(inode, cluster_size)?;
+
         let mut sb = self.read_super_block();
 
-        // Calc block group id
+        // Start from the block group of the inode
         let inodes_per_group = sb.inodes_per_group();
-        let bgid = ((inode.id - 1) / inodes_per_group) as BlockGroupId;
+        let goal = ((inode.id - 1) / inodes_per_group) as BlockGroupId;
+        for bgid in (goal..sb.block_group_count()).chain(0..goal) {
+            // Load block group descriptor
+            let mut bg = self.read_block_group(bgid);
+            if bg.desc.get_free_blocks_count() == 0 {
+                continue;
+            }
+
+            // Load block bitmap, built if the group is uninitialized
+            let mut bitmap_block = if bg.desc.flags().contains(BlockGroupFlags::BLOCK_UNINIT) {
+                self.resize_init_block_bitmap(&sb, bgid)
+            } else {
+                self.read_block(bg.desc.block_bitmap_block())
+            };
+            let mut bitmap = Bitmap::new(&mut bitmap_block.data, sb.clusters_per_group() as usize);
+
+            // Find the first free cluster
+            let count = sb.clusters_in_group(bgid) as usize;
+            let Some(bit) = bitmap.find_and_set_first_clear_bit(0, count) else {
+                continue;
+            };
+            let fblock = sb.group_first_block(bgid) + (bit * ratio as usize) as PBlockId;
+            // Set block group checksum
+            bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+            self.write_block(&bitmap_block);
+
+            // Update block group counters
+            bg.desc
+                .set_free_blocks_count(bg.desc.get_free_blocks_count() - 1);
+            bg.desc
+                .set_flags(bg.desc.flags() - BlockGroupFlags::BLOCK_UNINIT);
+            self.write_block_group_with_csum(&mut bg);
+
+            // Update superblock counters
+            sb.set_free_blocks_count(sb.free_blocks_count() - ratio as u64);
+            self.write_super_block(&sb);
+
+            // Update inode block count
+            let blocks = inode.inode.fs_block_count() + ratio as u64;
+            inode.inode.set_fs_block_count(blocks);
+
+            trace!("Alloc block {} ok", fblock);
+            return Ok(fblock);
+        }
+        self.quota_free_space(inode, cluster_size);
+        return_error!(ErrCode::ENOSPC, "No free blocks");
+    }
+
+    /// Deallocate a physical block allocated for an inode. With bigalloc, the whole cluster
+    /// of the block is deallocated. The block is no longer counted in `inode.block_count`,
+    /// the inode is not written.
+    pub(super) fn dealloc_block(&self, inode: &mut InodeRef, pblock: PBlockId) -> Result<()> {
+        let ratio = self.read_super_block().cluster_ratio();
+        let cluster = pblock - pblock % ratio as PBlockId;
+
+        // Free the cluster in the block group of the block
+        if !self.mark_block(pblock, false) {
+            return_error!(ErrCode::EINVAL, "Block {} is already free", pblock);
+        }
+
+        // Update inode block count
+        let blocks = inode.inode.fs_block_count().saturating_sub(ratio as u64);
+        inode.inode.set_fs_block_count(blocks);
+
+        // Release from the owner
+        self.quota_free_space(inode, ratio as u64 * BLOCK_SIZE as u64);
+        // Older copies in the journal must not be replayed
+        for block in cluster..cluster + ratio as PBlockId {
+            self.trans_revoke_block(block);
+        }
+
+        trace!("Free block {} ok", pblock);
+        Ok(())
+    }
+
+    /// Mark a physical block as used or free in the block bitmap, without
+    /// charging any owner. Return `false` if it is already marked so. With
+    /// bigalloc, the whole cluster of the block is marked.
+    pub(super) fn mark_block(&self, pblock: PBlockId, used: bool) -> bool {
+        let mut sb = self.read_super_block();
+        let ratio = sb.cluster_ratio();
+
+        // Calc block group id and index in block group
+        let index = pblock - sb.first_data_block() as PBlockId;
+        let bgid = (index / sb.blocks_per_group() as PBlockId) as BlockGroupId;
+        let idx_in_bg = (index % sb.blocks_per_group() as PBlockId) as usize / ratio as usize;
 
         // Load block group descriptor
         let mut bg = self.read_block_group(bgid);
@@ -116,72 +270,80 @@ impl Ext4 {
         let bitmap_block_id = bg.desc.block_bitmap_block();
         let mut bitmap_block = self.read_block(bitmap_block_id);
         let mut bitmap = Bitmap::new(&mut bitmap_block.data, 8 * BLOCK_SIZE);
-
-        // Find the first free block
-        let fblock = bitmap
-            .find_and_set_first_clear_bit(0, 8 * BLOCK_SIZE)
-            .ok_or(format_error!(
-                ErrCode::ENOSPC,
-                "No free blocks in block group {}",
-                bgid
-            ))? as PBlockId;
-        // Set block group checksum
+        if bitmap.is_bit_clear(idx_in_bg) != used {
+            return false;
+        }
+        let free_blocks = bg.desc.get_free_blocks_count();
+        if used {
+            bitmap.set_bit(idx_in_bg);
+            bg.desc.set_free_blocks_count(free_blocks - 1);
+            sb.set_free_blocks_count(sb.free_blocks_count() - ratio as u64);
+        } else {
+            bitmap.clear_bit(idx_in_bg);
+            bg.desc.set_free_blocks_count(free_blocks + 1);
+            sb.set_free_blocks_count(sb.free_blocks_count() + ratio as u64);
+        }
         bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
         self.write_block(&bitmap_block);
-
-        // Update block group counters
-        bg.desc
-            .set_free_blocks_count(bg.desc.get_free_blocks_count() - 1);
         self.write_block_group_with_csum(&mut bg);
-
-        // Update superblock counters
-        sb.set_free_blocks_count(sb.free_blocks_count() - 1);
         self.write_super_block(&sb);
-
-        trace!("Alloc block {} ok", fblock);
-        Ok(fblock)
+        true
     }
 
-    /// Deallocate a physical block allocated for an inode
-    pub(super) fn dealloc_block(&self, inode: &mut InodeRef, pblock: PBlockId) -> Result<()> {
+    /// Mark an inode as used or free in the inode bitmap, without charging
+    /// any owner. Return `false` if it is already marked so.
+    pub(super) fn mark_inode(&self, inode: &Inode, id: InodeId, used: bool) -> bool {
         let mut sb = self.read_super_block();
 
-        // Calc block group id
+        // Calc block group id and index in block group
         let inodes_per_group = sb.inodes_per_group();
-        let bgid = ((inode.id - 1) / inodes_per_group) as BlockGroupId;
+        let bgid = ((id - 1) / inodes_per_group) as BlockGroupId;
+        let idx_in_bg = (id - 1) % inodes_per_group;
 
         // Load block group descriptor
         let mut bg = self.read_block_group(bgid);
 
-        // Load block bitmap
-        let bitmap_block_id = bg.desc.block_bitmap_block();
+        // Load inode bitmap
+        let bitmap_block_id = bg.desc.inode_bitmap_block();
         let mut bitmap_block = self.read_block(bitmap_block_id);
-        let mut bitmap = Bitmap::new(&mut bitmap_block.data, 8 * BLOCK_SIZE);
-
-        // Free the block
-        if bitmap.is_bit_clear(pblock as usize) {
-            return_error!(ErrCode::EINVAL, "Block {} is already free", pblock);
+        let inode_count = sb.inode_count_in_group(bgid);
+        let mut bitmap = Bitmap::new(&mut bitmap_block.data, inode_count as usize);
+        if bitmap.is_bit_clear(idx_in_bg as usize) != used {
+            return false;
         }
-        bitmap.clear_bit(pblock as usize);
-        // Set block group checksum
-        bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+        let dirs = inode.is_dir() as u32;
+        if used {
+            bitmap.set_bit(idx_in_bg as usize);
+            bg.desc
+                .set_free_inodes_count(bg.desc.free_inodes_count() - 1);
+            bg.desc
+                .set_used_dirs_count(bg.desc.used_dirs_count() + dirs);
+            // The inode table is initialized up to the inode
+            if idx_in_bg >= inode_count - bg.desc.itable_unused() {
+                bg.desc.set_itable_unused(inode_count - (idx_in_bg + 1));
+            }
+            sb.set_free_inodes_count(sb.free_inodes_count() - 1);
+        } else {
+            bitmap.clear_bit(idx_in_bg as usize);
+            bg.desc
+                .set_free_inodes_count(bg.desc.free_inodes_count() + 1);
+            bg.desc
+                .set_used_dirs_count(bg.desc.used_dirs_count() - dirs);
+            sb.set_free_inodes_count(sb.free_inodes_count() + 1);
+        }
+        bg.desc.set_inode_bitmap_csum(&sb.uuid(), &bitmap);
         self.write_block(&bitmap_block);
-
-        // Update block group counters
-        bg.desc
-            .set_free_blocks_count(bg.desc.get_free_blocks_count() + 1);
         self.write_block_group_with_csum(&mut bg);
-
-        // Update superblock counters
-        sb.set_free_blocks_count(sb.free_blocks_count() + 1);
         self.write_super_block(&sb);
-
-        trace!("Free block {} ok", pblock);
-        Ok(())
+        true
     }
 
     /// Allocate a new inode, returning the inode number.
-    fn alloc_inode(&self, is_dir: bool) -> Result<InodeId> {
+    fn alloc_inode(&self, inode: &Inode) -> Result<InodeId> {
+        // Charge the owner before allocating
+        self.quota_alloc_inode(inode)?;
+
+        let is_dir = inode.is_dir();
         let mut sb = self.read_super_block();
         let bg_count = sb.block_group_count();
 
@@ -201,14 +363,13 @@ impl Ext4 {
             let mut bitmap = Bitmap::new(&mut bitmap_block.data, inode_count);
 
             // Find a free inode
-            let idx_in_bg =
-                bitmap
-                    .find_and_set_first_clear_bit(0, inode_count)
-                    .ok_or(format_error!(
-                        ErrCode::ENOSPC,
-                        "No free inodes in block group {}",
-                        bgid
-                    ))? as u32;
+            let idx_in_bg = match bitmap.find_and_set_first_clear_bit(0, inode_count) {
+                Some(bit) => bit as u32,
+                None => {
+                    self.quota_free_inode(inode);
+                    return_error!(ErrCode::ENOSPC, "No free inodes in block group {}", bgid);
+                }
+            };
             // Update bitmap in disk
             bg.desc.set_inode_bitmap_csum(&sb.uuid(), &bitmap);
             self.write_block(&bitmap_block);
@@ -237,6 +398,7 @@ impl Ext4 {
             return Ok(inode_id);
         }
         trace!("no free inode");
+        self.quota_free_inode(inode);
         return_error!(ErrCode::ENOSPC, "No free inodes in block group {}", bgid);
     }
 
@@ -276,13 +438,15 @@ impl Ext4 {
         if inode_ref.inode.is_dir() {
             bg.desc.set_used_dirs_count(bg.desc.used_dirs_count() - 1);
         }
-        bg.desc.set_itable_unused(bg.desc.itable_unused() + 1);
         self.write_block_group_with_csum(&mut bg);
 
         // Update superblock counters
         sb.set_free_inodes_count(sb.free_inodes_count() + 1);
         self.write_super_block(&sb);
 
+        // Release from the owner
+        self.quota_free_inode(&inode_ref.inode);
+
         // Clear inode content
         inode_ref.inode = unsafe { mem::zeroed() };
         self.write_inode_without_csum(inode_ref);
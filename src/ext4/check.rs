@@ -0,0 +1,958 @@
+//! Offline consistency check, similar to `e2fsck -fn`.
+//!
+//! The check reads the whole filesystem without modifying it:
+//!
+//! 1. Every inode in use is read, and every block it maps (extent tree,
+//!    block map and xattr block) is claimed and counted against its block
+//!    count. Blocks of superblock copies, group descriptors, bitmaps and
+//!    inode tables are claimed as well. A block may only be claimed once.
+//!    Xattrs in the inode body and the xattr block are parsed.
+//! 2. Directory blocks are parsed. Entries are counted as references to
+//!    the inodes they point to.
+//! 3. Link counts and `..` entries are compared with the references.
+//! 4. Bitmaps and free counts are compared with the claimed blocks and
+//!    the inodes in use.
+//!
+//! Checksums are verified along the way if the filesystem has metadata
+//! checksums.
+
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+
+/// A problem found by `Ext4::check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckProblem {
+    /// The superblock checksum is wrong.
+    SuperBlockChecksum,
+    /// The checksum of a block group descriptor is wrong.
+    GroupDescChecksum { group: BlockGroupId },
+    /// The checksum of a block bitmap is wrong.
+    BlockBitmapChecksum { group: BlockGroupId },
+    /// The checksum of an inode bitmap is wrong.
+    InodeBitmapChecksum { group: BlockGroupId },
+    /// The checksum of an inode is wrong.
+    InodeChecksum { inode: InodeId },
+    /// The checksum of an extent tree block is wrong.
+    ExtentBlockChecksum { inode: InodeId, block: PBlockId },
+    /// The checksum of a directory block is wrong.
+    DirBlockChecksum { inode: InodeId, block: PBlockId },
+    /// The checksum of an xattr block is wrong.
+    XattrBlockChecksum { inode: InodeId, block: PBlockId },
+    /// An xattr block has a bad magic, an entry or value outside the
+    /// block, a misaligned value or a wrong hash.
+    BadXattrBlock { inode: InodeId, block: PBlockId },
+    /// The xattrs in the body of an inode have an entry or value outside
+    /// the inode, a misaligned value or a wrong hash.
+    BadXattrIbody { inode: InodeId },
+    /// An extent tree node has a bad magic, depth or entry count. `block`
+    /// is 0 for the root node in the inode.
+    BadExtentNode { inode: InodeId, block: PBlockId },
+    /// An extent or index starting at `lblock` overlaps the previous one
+    /// or is outside the range of its parent.
+    ExtentOrder { inode: InodeId, lblock: u64 },
+    /// An inode maps a block outside the filesystem.
+    BlockOutOfRange { inode: InodeId, block: PBlockId },
+    /// The block count of an inode, in 512-byte units, differs from the
+    /// data, extent tree and xattr blocks it maps.
+    BlockCount {
+        inode: InodeId,
+        expected: u64,
+        found: u64,
+    },
+    /// A block is already used by metadata or another inode. `inode` is 0
+    /// for filesystem metadata.
+    BlockMultiplyClaimed { inode: InodeId, block: PBlockId },
+    /// A block in use is free in the block bitmap.
+    BlockNotMarked { block: PBlockId },
+    /// A free block is used in the block bitmap.
+    BlockMarkedUsed { block: PBlockId },
+    /// An inode in use is free in the inode bitmap.
+    InodeNotMarked { inode: InodeId },
+    /// A free inode is used in the inode bitmap.
+    InodeMarkedUsed { inode: InodeId },
+    /// Wrong free blocks count in a group descriptor.
+    GroupFreeBlocks {
+        group: BlockGroupId,
+        expected: u64,
+        found: u64,
+    },
+    /// Wrong free inodes count in a group descriptor.
+    GroupFreeInodes {
+        group: BlockGroupId,
+        expected: u32,
+        found: u32,
+    },
+    /// Wrong directories count in a group descriptor.
+    GroupUsedDirs {
+        group: BlockGroupId,
+        expected: u32,
+        found: u32,
+    },
+    /// Wrong free blocks count in the superblock.
+    FreeBlocks { expected: u64, found: u64 },
+    /// Wrong free inodes count in the superblock.
+    FreeInodes { expected: u32, found: u32 },
+    /// A directory entry has a bad `rec_len` or name length, or the
+    /// directory block has a bad tail. The rest of the block is skipped.
+    BadDirEntry {
+        inode: InodeId,
+        block: PBlockId,
+        offset: usize,
+    },
+    /// An index block of a hashed directory has a bad header, count or
+    /// limit, hashes out of order or outside the range of its parent, an
+    /// entry past the directory, or a wrong checksum. The blocks it points
+    /// to are not checked as index blocks.
+    BadDxNode { inode: InodeId, block: PBlockId },
+    /// The first entry of a directory is not `.` pointing to itself.
+    MissingDot { inode: InodeId },
+    /// The `..` entry of a directory is missing or does not point to the
+    /// parent. `found` is 0 if missing.
+    BadDotDot {
+        inode: InodeId,
+        found: InodeId,
+        expected: InodeId,
+    },
+    /// A directory entry points to an inode not in use.
+    DirEntryBadInode {
+        dir: InodeId,
+        name: String,
+        inode: InodeId,
+    },
+    /// The file type of a directory entry differs from the inode's.
+    DirEntryFileType {
+        dir: InodeId,
+        name: String,
+        inode: InodeId,
+    },
+    /// The link count of an inode differs from the references to it.
+    LinkCount {
+        inode: InodeId,
+        expected: u16,
+        found: u16,
+    },
+    /// An inode in use is not referenced by any directory.
+    UnattachedInode { inode: InodeId },
+}
+
+/// The result of `Ext4::check`.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    /// The problems in the order they were found.
+    pub problems: Vec<CheckProblem>,
+}
+
+impl CheckReport {
+    /// Whether no problem is found.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// What the check knows about an inode in use.
+struct InodeState {
+    file_type: FileType,
+    generation: u32,
+    flags: InodeFlags,
+    links: u16,
+    /// Number of directory entries pointing to the inode.
+    refs: u32,
+    /// Whether an entry other than `.` and `..` points to the inode.
+    linked: bool,
+    /// The directory holding an entry of this directory.
+    parent: Option<InodeId>,
+    /// The target of the `..` entry of this directory.
+    dotdot: Option<InodeId>,
+    /// The blocks of this directory, (logical, physical).
+    dir_blocks: Vec<(u64, PBlockId)>,
+}
+
+/// The state of a check.
+pub(super) struct Checker<'a> {
+    fs: &'a Ext4,
+    pub(super) sb: SuperBlock,
+    uuid: [u8; 16],
+    csum: bool,
+    /// One bit for each claimed block.
+    claimed: Vec<u64>,
+    /// The number of clusters claimed by the inode being scanned.
+    clusters: u64,
+    /// The last cluster claimed by the inode being scanned.
+    last_cluster: PBlockId,
+    inodes: BTreeMap<InodeId, InodeState>,
+    pub(super) problems: Vec<CheckProblem>,
+}
+
+impl Ext4 {
+    /// Check the consistency of the filesystem. Nothing is modified.
+    ///
+    /// The check covers block and inode bitmaps against extent trees and
+    /// inodes in use, free counts in group descriptors and the superblock,
+    /// block counts of inodes, xattrs, directory structure, link counts,
+    /// extent tree ordering and all metadata checksums.
+    ///
+    /// # Return
+    ///
+    /// The problems found, empty if the filesystem is consistent
+    pub fn check(&self) -> CheckReport {
+        CheckReport {
+            problems: self.run_check().problems,
+        }
+    }
+
+    /// Run a check, and keep what it found in use.
+    pub(super) fn run_check(&self) -> Checker<'_> {
+        let sb = self.read_super_block();
+        let mut checker = Checker {
+            fs: self,
+            sb,
+            uuid: sb.uuid(),
+            csum: sb.has_metadata_csum(),
+            claimed: vec![0; (sb.block_count() as usize).div_ceil(64)],
+            clusters: 0,
+            last_cluster: 0,
+            inodes: BTreeMap::new(),
+            problems: Vec::new(),
+        };
+        checker.run();
+        checker
+    }
+}
+
+impl<'a> Checker<'a> {
+    fn run(&mut self) {
+        if self.csum && !self.sb.verify_checksum() {
+            self.problems.push(CheckProblem::SuperBlockChecksum);
+        }
+        self.claim_metadata();
+        for bgid in 0..self.sb.block_group_count() {
+            self.scan_inodes(bgid);
+        }
+        let dirs: Vec<InodeId> = self
+            .inodes
+            .iter()
+            .filter(|(_, state)| state.file_type == FileType::Directory)
+            .map(|(&ino, _)| ino)
+            .collect();
+        for &dir in &dirs {
+            self.check_dir(dir);
+        }
+        self.check_links(&dirs);
+        self.check_bitmaps();
+    }
+
+    /// Claim a block for `inode`, 0 for filesystem metadata. Return false
+    /// if the block is out of range or already claimed. Blocks of an inode
+    /// are counted by cluster, even if already claimed.
+    fn claim(&mut self, inode: InodeId, block: PBlockId) -> bool {
+        if block < self.sb.first_data_block() as PBlockId || block >= self.sb.block_count() {
+            self.problems
+                .push(CheckProblem::BlockOutOfRange { inode, block });
+            return false;
+        }
+        let cluster = block / self.sb.cluster_ratio() as PBlockId;
+        if inode != 0 && (self.clusters == 0 || cluster != self.last_cluster) {
+            self.clusters += 1;
+            self.last_cluster = cluster;
+        }
+        let (word, bit) = (block as usize / 64, block % 64);
+        if self.claimed[word] & (1 << bit) != 0 {
+            self.problems
+                .push(CheckProblem::BlockMultiplyClaimed { inode, block });
+            return false;
+        }
+        self.claimed[word] |= 1 << bit;
+        true
+    }
+
+    /// Whether a block is used by the filesystem metadata or an inode.
+    pub(super) fn is_claimed(&self, block: PBlockId) -> bool {
+        self.claimed[block as usize / 64] & (1 << (block % 64)) != 0
+    }
+
+    /// Claim superblock copies, group descriptor tables, bitmaps and inode
+    /// tables. Reserved GDT blocks are claimed through the resize inode
+    /// if there is one.
+    fn claim_metadata(&mut self) {
+        let table_blocks =
+            (self.sb.inodes_per_group() as usize * self.sb.inode_size()).div_ceil(BLOCK_SIZE);
+        for bgid in 0..self.sb.block_group_count() {
+            if self.sb.group_has_super(bgid) {
+                let first = self.sb.group_first_block(bgid);
+                let mut count = 1 + self.sb.group_desc_blocks() as u64;
+                if !self.sb.has_resize_inode() {
+                    count += self.sb.reserved_gdt_blocks() as u64;
+                }
+                for block in first..first + count {
+                    self.claim(0, block);
+                }
+            }
+            let bg = self.fs.read_block_group(bgid);
+            if self.csum && !bg.verify_checksum(&self.uuid) {
+                self.problems
+                    .push(CheckProblem::GroupDescChecksum { group: bgid });
+            }
+            self.claim(0, bg.desc.block_bitmap_block());
+            self.claim(0, bg.desc.inode_bitmap_block());
+            let table = bg.desc.inode_table_first_block();
+            for block in table..table + table_blocks as u64 {
+                self.claim(0, block);
+            }
+        }
+    }
+
+    /// Read the inodes of a block group, and claim the blocks they use.
+    fn scan_inodes(&mut self, bgid: BlockGroupId) {
+        let bg = self.fs.read_block_group(bgid);
+        if bg.desc.flags().contains(BlockGroupFlags::INODE_UNINIT) {
+            return;
+        }
+        let ipg = self.sb.inodes_per_group();
+        let count = if self.sb.has_group_csum() {
+            ipg.saturating_sub(bg.desc.itable_unused())
+        } else {
+            ipg
+        };
+        let inode_size = self.sb.inode_size();
+        let mut block = Block::default();
+        for idx in 0..count as usize {
+            let ino = bgid * ipg + idx as InodeId + 1;
+            if ino > self.sb.inode_count() {
+                break;
+            }
+            let block_id =
+                bg.desc.inode_table_first_block() + (idx * inode_size / BLOCK_SIZE) as u64;
+            if block.id != block_id {
+                block = self.fs.read_block(block_id);
+            }
+            let offset = idx * inode_size % BLOCK_SIZE;
+            let inode: Inode = block.read_offset_as(offset);
+            let in_use = if ino < self.sb.first_inode() {
+                inode.mode().bits() != 0
+            } else {
+                inode.mode().bits() != 0 && inode.link_count() > 0
+            };
+            if !in_use {
+                continue;
+            }
+            let tail = &block.data[offset + size_of::<Inode>()..offset + inode_size];
+            let inode_ref = InodeRef::new(ino, inode);
+            if self.csum && !inode_ref.verify_checksum(&self.uuid, tail) {
+                self.problems
+                    .push(CheckProblem::InodeChecksum { inode: ino });
+            }
+            self.check_inode_blocks(&inode_ref);
+            let start =
+                (offset + 128 + inode_ref.inode.extra_isize() as usize).min(offset + inode_size);
+            let ibody = XattrIbody::new(block.data[start..offset + inode_size].to_vec());
+            if !ibody.verify() {
+                self.problems
+                    .push(CheckProblem::BadXattrIbody { inode: ino });
+            }
+        }
+    }
+
+    /// Claim the blocks of an inode in use, check its block count and
+    /// xattr block, and record its state.
+    fn check_inode_blocks(&mut self, inode_ref: &InodeRef) {
+        let (ino, inode) = (inode_ref.id, &inode_ref.inode);
+        self.clusters = 0;
+        let problems = self.problems.len();
+        let file_type = inode.file_type();
+        let mut dir_blocks = Vec::new();
+        let map = if file_type == FileType::Directory {
+            Some(&mut dir_blocks)
+        } else {
+            None
+        };
+        let has_blocks = match file_type {
+            FileType::RegularFile | FileType::Directory => true,
+            // Fast symlinks keep the target in the block map
+            FileType::SymLink => inode.flags().contains(InodeFlags::EXTENTS) || inode.size() >= 60,
+            _ => ino < self.sb.first_inode(),
+        };
+        if has_blocks && !inode.flags().contains(InodeFlags::INLINE_DATA) {
+            if inode.flags().contains(InodeFlags::EXTENTS) {
+                let mut next = 0;
+                let root = inode.extent_root();
+                self.walk_extents(inode_ref, &root, 0, None, (0, 1 << 32), &mut next, map);
+            } else {
+                self.walk_block_map(inode_ref, map);
+            }
+        }
+        let xattr_block = inode.xattr_block();
+        if xattr_block != 0 && self.claim(ino, xattr_block) {
+            let block = XattrBlock::new(self.fs.read_block(xattr_block));
+            if self.csum && !block.verify_checksum(&self.uuid) {
+                self.problems.push(CheckProblem::XattrBlockChecksum {
+                    inode: ino,
+                    block: xattr_block,
+                });
+            }
+            if !block.verify() {
+                self.problems.push(CheckProblem::BadXattrBlock {
+                    inode: ino,
+                    block: xattr_block,
+                });
+            }
+        }
+        // The blocks under a bad extent node are unknown. Huge files count
+        // filesystem blocks, which this crate never writes.
+        let bad_tree = self.problems[problems..]
+            .iter()
+            .any(|problem| matches!(problem, CheckProblem::BadExtentNode { .. }));
+        let expected =
+            self.clusters * self.sb.cluster_ratio() as u64 * (BLOCK_SIZE / INODE_BLOCK_SIZE) as u64;
+        if !bad_tree
+            && !inode.flags().contains(InodeFlags::HUGE_FILE)
+            && inode.block_count() != expected
+        {
+            self.problems.push(CheckProblem::BlockCount {
+                inode: ino,
+                expected,
+                found: inode.block_count(),
+            });
+        }
+        self.inodes.insert(
+            ino,
+            InodeState {
+                file_type,
+                generation: inode.generation(),
+                flags: inode.flags(),
+                links: inode.link_count(),
+                refs: 0,
+                linked: false,
+                parent: None,
+                dotdot: None,
+                dir_blocks,
+            },
+        );
+    }
+
+    /// Check an extent tree node and claim the blocks it maps. `pblock` is
+    /// 0 for the root node, `depth` is the expected depth of the node,
+    /// `range` is the logical range covered by the parent, and `next` is
+    /// the end of the previous extent.
+    #[allow(clippy::too_many_arguments)]
+    fn walk_extents(
+        &mut self,
+        inode_ref: &InodeRef,
+        node: &ExtentNode,
+        pblock: PBlockId,
+        depth: Option<u16>,
+        range: (u64, u64),
+        next: &mut u64,
+        mut map: Option<&mut Vec<(u64, PBlockId)>>,
+    ) {
+        let ino = inode_ref.id;
+        let header = node.header();
+        let capacity = if pblock == 0 {
+            4
+        } else {
+            (BLOCK_SIZE - size_of::<ExtentHeader>()) / size_of::<Extent>()
+        };
+        if !header.check_magic()
+            || header.entries_count() > header.max_entries_count()
+            || header.max_entries_count() as usize > capacity
+            || depth.map_or(header.depth() > 5, |d| header.depth() != d)
+        {
+            self.problems.push(CheckProblem::BadExtentNode {
+                inode: ino,
+                block: pblock,
+            });
+            return;
+        }
+        if pblock != 0
+            && self.csum
+            && !node.verify_checksum(&self.uuid, ino, inode_ref.inode.generation())
+        {
+            self.problems.push(CheckProblem::ExtentBlockChecksum {
+                inode: ino,
+                block: pblock,
+            });
+        }
+        let count = header.entries_count() as usize;
+        if header.depth() == 0 {
+            for i in 0..count {
+                let extent = node.extent_at(i);
+                let start = extent.start_lblock() as u64;
+                let len = extent.block_count() as u64;
+                if len == 0 || start < range.0.max(*next) || start + len > range.1 {
+                    self.problems.push(CheckProblem::ExtentOrder {
+                        inode: ino,
+                        lblock: start,
+                    });
+                }
+                *next = (*next).max(start + len);
+                for i in 0..len {
+                    let block = extent.start_pblock() + i;
+                    if self.claim(ino, block) && !extent.is_unwritten() {
+                        if let Some(map) = map.as_mut() {
+                            map.push((start + i, block));
+                        }
+                    }
+                }
+            }
+            return;
+        }
+        for i in 0..count {
+            let start = node.extent_index_at(i).start_lblock() as u64;
+            let end = if i + 1 < count {
+                node.extent_index_at(i + 1).start_lblock() as u64
+            } else {
+                range.1
+            };
+            if start < range.0.max(*next) || end > range.1 || start >= end {
+                self.problems.push(CheckProblem::ExtentOrder {
+                    inode: ino,
+                    lblock: start,
+                });
+                continue;
+            }
+            let child = node.extent_index_at(i).leaf();
+            if !self.claim(ino, child) {
+                continue;
+            }
+            let block = self.fs.read_block(child);
+            self.walk_extents(
+                inode_ref,
+                &ExtentNode::from_bytes(&block.data),
+                child,
+                Some(header.depth() - 1),
+                (start, end),
+                next,
+                map.as_deref_mut(),
+            );
+        }
+    }
+
+    /// Claim the blocks of an inode that uses a block map.
+    fn walk_block_map(&mut self, inode_ref: &InodeRef, mut map: Option<&mut Vec<(u64, PBlockId)>>) {
+        let ptrs = inode_ref.inode.block_pointers();
+        for (i, &ptr) in ptrs[..12].iter().enumerate() {
+            if ptr != 0 && self.claim(inode_ref.id, ptr as PBlockId) {
+                if let Some(map) = map.as_mut() {
+                    map.push((i as u64, ptr as PBlockId));
+                }
+            }
+        }
+        let per_block = (BLOCK_SIZE / 4) as u64;
+        let mut lblock = 12;
+        for (level, &ptr) in ptrs[12..].iter().enumerate() {
+            if ptr != 0 {
+                self.walk_indirect(inode_ref.id, ptr as PBlockId, level + 1, lblock, &mut map);
+            }
+            lblock += per_block.pow(level as u32 + 1);
+        }
+    }
+
+    /// Claim an indirect block of `level` and the blocks it points to.
+    /// `lblock` is the first logical block it maps.
+    fn walk_indirect(
+        &mut self,
+        ino: InodeId,
+        pblock: PBlockId,
+        level: usize,
+        lblock: u64,
+        map: &mut Option<&mut Vec<(u64, PBlockId)>>,
+    ) {
+        if !self.claim(ino, pblock) {
+            return;
+        }
+        let block = self.fs.read_block(pblock);
+        let per_block = (BLOCK_SIZE / 4) as u64;
+        let span = per_block.pow(level as u32 - 1);
+        for i in 0..per_block as usize {
+            let ptr = u32::from_le_bytes(block.data[i * 4..i * 4 + 4].try_into().unwrap());
+            if ptr == 0 {
+                continue;
+            }
+            let child_lblock = lblock + i as u64 * span;
+            if level > 1 {
+                self.walk_indirect(ino, ptr as PBlockId, level - 1, child_lblock, map);
+            } else if self.claim(ino, ptr as PBlockId) {
+                if let Some(map) = map.as_mut() {
+                    map.push((child_lblock, ptr as PBlockId));
+                }
+            }
+        }
+    }
+
+    /// Check the blocks of a directory, and count the references of the
+    /// entries.
+    fn check_dir(&mut self, dir: InodeId) {
+        let state = &self.inodes[&dir];
+        let (generation, indexed) = (state.generation, state.flags.contains(InodeFlags::INDEX));
+        let blocks = state.dir_blocks.clone();
+        let nodes = if indexed {
+            self.check_dx_tree(dir, generation, &blocks)
+        } else {
+            BTreeSet::new()
+        };
+        let tail_size = size_of::<DirEntryTail>();
+        let limit = if self.csum {
+            BLOCK_SIZE - tail_size
+        } else {
+            BLOCK_SIZE
+        };
+        for (lblock, pblock) in blocks {
+            // Interior nodes of a hashed directory hold no entries
+            if nodes.contains(&lblock) {
+                continue;
+            }
+            let block = self.fs.read_block(pblock);
+            if self.csum && !(indexed && lblock == 0) {
+                let tail = &block.data[BLOCK_SIZE - tail_size..];
+                if tail[..4] != [0; 4] || tail[4..6] != [12, 0] || tail[6..8] != [0, 0xDE] {
+                    self.problems.push(CheckProblem::BadDirEntry {
+                        inode: dir,
+                        block: pblock,
+                        offset: BLOCK_SIZE - tail_size,
+                    });
+                } else if !DirBlock::new(block).verify_checksum(&self.uuid, dir, generation) {
+                    self.problems.push(CheckProblem::DirBlockChecksum {
+                        inode: dir,
+                        block: pblock,
+                    });
+                }
+            }
+            // ".." in the root of a hashed directory spans the index
+            let limit = if indexed && lblock == 0 {
+                BLOCK_SIZE
+            } else {
+                limit
+            };
+            self.check_dir_entries(dir, lblock, &block, limit);
+        }
+    }
+
+    /// Check the index blocks of a hashed directory, from the root down to
+    /// the leaves: the header of the root, the count and limit, the order
+    /// and range of the hashes, the blocks pointed to and the checksums.
+    ///
+    /// # Return
+    ///
+    /// The logical blocks of the interior nodes
+    fn check_dx_tree(
+        &mut self,
+        dir: InodeId,
+        generation: u32,
+        blocks: &[(u64, PBlockId)],
+    ) -> BTreeSet<u64> {
+        let mapped: BTreeMap<u64, PBlockId> = blocks.iter().copied().collect();
+        let mut nodes = BTreeSet::new();
+        let Some(&root_block) = mapped.get(&0) else {
+            return nodes;
+        };
+        let root = DxBlock::new(self.fs.read_block(root_block));
+        if !root.root_info().is_valid(self.sb.has_large_dir()) {
+            self.problems.push(CheckProblem::BadDxNode {
+                inode: dir,
+                block: root_block,
+            });
+            return nodes;
+        }
+        let levels = root.root_info().indirect_levels();
+        // (index block, depth, least hash, greatest hash)
+        let mut stack = vec![(root, 0, 0, u32::MAX)];
+        while let Some((node, depth, low, high)) = stack.pop() {
+            let root = depth == 0;
+            let entries = node.entries(root).filter(|entries| {
+                entries[1..]
+                    .iter()
+                    .all(|entry| (low..=high).contains(&entry.hash()))
+                    && entries
+                        .windows(2)
+                        .all(|pair| pair[0].hash() <= pair[1].hash())
+                    && entries.iter().all(|entry| {
+                        let lblock = entry.block() as u64;
+                        lblock != 0 && mapped.contains_key(&lblock) && !nodes.contains(&lblock)
+                    })
+            });
+            let csum_ok = !self.csum || node.verify_checksum(root, &self.uuid, dir, generation);
+            let Some(entries) = entries.filter(|_| csum_ok) else {
+                self.problems.push(CheckProblem::BadDxNode {
+                    inode: dir,
+                    block: node.block().id,
+                });
+                continue;
+            };
+            if depth == levels {
+                continue;
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                let lblock = entry.block() as u64;
+                let low = if i == 0 { low } else { entry.hash() };
+                let high = entries.get(i + 1).map_or(high, |next| next.hash());
+                nodes.insert(lblock);
+                let block = self.fs.read_block(mapped[&lblock]);
+                stack.push((DxBlock::new(block), depth + 1, low, high));
+            }
+        }
+        nodes
+    }
+
+    /// Check the entries of a directory block, ending at `limit`.
+    fn check_dir_entries(&mut self, dir: InodeId, lblock: u64, block: &Block, limit: usize) {
+        let data = &block.data;
+        let mut offset = 0;
+        let mut index = 0;
+        while offset < limit {
+            let inode = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+            let rec_len = u16::from_le_bytes([data[offset + 4], data[offset + 5]]) as usize;
+            let name_len = data[offset + 6] as usize;
+            let file_type = data[offset + 7];
+            if rec_len < DirEntry::required_size(name_len)
+                || !rec_len.is_multiple_of(4)
+                || offset + rec_len > limit
+            {
+                self.problems.push(CheckProblem::BadDirEntry {
+                    inode: dir,
+                    block: block.id,
+                    offset,
+                });
+                return;
+            }
+            let name = &data[offset + 8..offset + 8 + name_len];
+            let name = String::from_utf8_lossy(name).into_owned();
+            offset += rec_len;
+            index += 1;
+            if lblock == 0 && index == 1 {
+                if name != "." || inode != dir {
+                    self.problems.push(CheckProblem::MissingDot { inode: dir });
+                }
+                if inode == dir {
+                    self.inodes.get_mut(&dir).unwrap().refs += 1;
+                }
+                continue;
+            }
+            if lblock == 0 && index == 2 && name == ".." {
+                self.inodes.get_mut(&dir).unwrap().dotdot = Some(inode);
+                if let Some(parent) = self.inodes.get_mut(&inode) {
+                    parent.refs += 1;
+                }
+                continue;
+            }
+            if inode == 0 {
+                continue;
+            }
+            let target = match self.inodes.get_mut(&inode) {
+                Some(target) => target,
+                None => {
+                    self.problems
+                        .push(CheckProblem::DirEntryBadInode { dir, name, inode });
+                    continue;
+                }
+            };
+            target.refs += 1;
+            target.linked = true;
+            if target.file_type == FileType::Directory {
+                target.parent.get_or_insert(dir);
+            }
+            if self.sb.has_filetype() && file_type != target.file_type as u8 {
+                self.problems
+                    .push(CheckProblem::DirEntryFileType { dir, name, inode });
+            }
+        }
+    }
+
+    /// Check `..` entries, link counts and connectivity.
+    fn check_links(&mut self, dirs: &[InodeId]) {
+        for &dir in dirs {
+            let state = &self.inodes[&dir];
+            let expected = if dir == EXT4_ROOT_INO {
+                Some(EXT4_ROOT_INO)
+            } else {
+                state.parent
+            };
+            if let Some(expected) = expected {
+                let found = state.dotdot.unwrap_or(0);
+                if found != expected {
+                    self.problems.push(CheckProblem::BadDotDot {
+                        inode: dir,
+                        found,
+                        expected,
+                    });
+                }
+            }
+        }
+        for (&ino, state) in &self.inodes {
+            if self.is_system_inode(ino) {
+                continue;
+            }
+            if !state.linked && ino != EXT4_ROOT_INO {
+                self.problems
+                    .push(CheckProblem::UnattachedInode { inode: ino });
+                continue;
+            }
+            // A directory with too many subdirectories has link count 1
+            let overflow = state.file_type == FileType::Directory
+                && self.sb.has_dir_nlink()
+                && state.links == 1
+                && state.refs >= 65000;
+            if state.links as u32 != state.refs && !overflow {
+                self.problems.push(CheckProblem::LinkCount {
+                    inode: ino,
+                    expected: state.refs.min(u16::MAX as u32) as u16,
+                    found: state.links,
+                });
+            }
+        }
+    }
+
+    /// Inodes used by the filesystem itself, not linked in any directory.
+    fn is_system_inode(&self, ino: InodeId) -> bool {
+        (ino < self.sb.first_inode() && ino != EXT4_ROOT_INO)
+            || ino == self.sb.journal_inode_number()
+            || (self.sb.has_quota()
+                && (ino == self.sb.usr_quota_inum() || ino == self.sb.grp_quota_inum()))
+            || (self.sb.has_project() && ino == self.sb.prj_quota_inum())
+    }
+
+    /// The inodes in use, in order.
+    pub(super) fn inodes(&self) -> impl Iterator<Item = InodeId> + '_ {
+        self.inodes.keys().copied()
+    }
+
+    /// The blocks of a directory, (logical, physical).
+    pub(super) fn dir_blocks(&self, dir: InodeId) -> &[(u64, PBlockId)] {
+        &self.inodes[&dir].dir_blocks
+    }
+
+    /// Whether an inode is in use. Reserved inodes are always in use.
+    pub(super) fn inode_in_use(&self, ino: InodeId) -> bool {
+        ino < self.sb.first_inode() || self.inodes.contains_key(&ino)
+    }
+
+    /// Whether any block of the cluster starting at `first` is claimed.
+    /// Clusters are single blocks without bigalloc.
+    pub(super) fn is_cluster_claimed(&self, first: PBlockId) -> bool {
+        let end = (first + self.sb.cluster_ratio() as PBlockId).min(self.sb.block_count());
+        (first..end).any(|b| self.is_claimed(b))
+    }
+
+    /// The number of free clusters in a block group, which are blocks
+    /// without bigalloc.
+    pub(super) fn group_free_blocks(&self, bgid: BlockGroupId) -> u64 {
+        let first = self.sb.group_first_block(bgid);
+        let ratio = self.sb.cluster_ratio() as PBlockId;
+        let count = self.sb.clusters_in_group(bgid);
+        count
+            - (0..count)
+                .filter(|&c| self.is_cluster_claimed(first + c * ratio))
+                .count() as u64
+    }
+
+    /// The number of free inodes in a block group.
+    pub(super) fn group_free_inodes(&self, bgid: BlockGroupId) -> u32 {
+        let first_ino = bgid * self.sb.inodes_per_group() + 1;
+        let count = self.sb.inode_count_in_group(bgid);
+        count
+            - (first_ino..first_ino + count)
+                .filter(|&ino| self.inode_in_use(ino))
+                .count() as u32
+    }
+
+    /// The number of directories in a block group.
+    pub(super) fn group_dirs(&self, bgid: BlockGroupId) -> u32 {
+        let first_ino = bgid * self.sb.inodes_per_group() + 1;
+        let count = self.sb.inode_count_in_group(bgid);
+        self.inodes
+            .range(first_ino..first_ino + count)
+            .filter(|(_, state)| state.file_type == FileType::Directory)
+            .count() as u32
+    }
+
+    /// Compare bitmaps and free counts with the claimed blocks and the
+    /// inodes in use.
+    fn check_bitmaps(&mut self) {
+        let ipg = self.sb.inodes_per_group();
+        let (mut total_free_blocks, mut total_free_inodes) = (0, 0);
+        let mut problems = Vec::new();
+        for bgid in 0..self.sb.block_group_count() {
+            let bg = self.fs.read_block_group(bgid);
+            let flags = bg.desc.flags();
+
+            let first = self.sb.group_first_block(bgid);
+            if !flags.contains(BlockGroupFlags::BLOCK_UNINIT) {
+                let mut block = self.fs.read_block(bg.desc.block_bitmap_block());
+                let bitmap = Bitmap::new(&mut block.data, self.sb.clusters_per_group() as usize);
+                if self.csum && !bg.desc.verify_block_bitmap_csum(&self.uuid, &bitmap) {
+                    problems.push(CheckProblem::BlockBitmapChecksum { group: bgid });
+                }
+                // One bit per cluster
+                let ratio = self.sb.cluster_ratio() as PBlockId;
+                for i in 0..self.sb.clusters_in_group(bgid) {
+                    let block = first + i * ratio;
+                    let marked = !bitmap.is_bit_clear(i as usize);
+                    let claimed = self.is_cluster_claimed(block);
+                    if claimed && !marked {
+                        problems.push(CheckProblem::BlockNotMarked { block });
+                    } else if !claimed && marked {
+                        problems.push(CheckProblem::BlockMarkedUsed { block });
+                    }
+                }
+            }
+            let free_blocks = self.group_free_blocks(bgid);
+            if bg.desc.get_free_blocks_count() != free_blocks {
+                problems.push(CheckProblem::GroupFreeBlocks {
+                    group: bgid,
+                    expected: free_blocks,
+                    found: bg.desc.get_free_blocks_count(),
+                });
+            }
+            // The superblock counts blocks, not clusters
+            total_free_blocks += free_blocks * self.sb.cluster_ratio() as u64;
+
+            let first_ino = bgid * ipg + 1;
+            if !flags.contains(BlockGroupFlags::INODE_UNINIT) {
+                let mut block = self.fs.read_block(bg.desc.inode_bitmap_block());
+                let bitmap = Bitmap::new(&mut block.data, ipg as usize);
+                if self.csum && !bg.desc.verify_inode_bitmap_csum(&self.uuid, &bitmap) {
+                    problems.push(CheckProblem::InodeBitmapChecksum { group: bgid });
+                }
+                for i in 0..self.sb.inode_count_in_group(bgid) {
+                    let inode = first_ino + i;
+                    let marked = !bitmap.is_bit_clear(i as usize);
+                    if self.inode_in_use(inode) && !marked {
+                        problems.push(CheckProblem::InodeNotMarked { inode });
+                    } else if !self.inode_in_use(inode) && marked {
+                        problems.push(CheckProblem::InodeMarkedUsed { inode });
+                    }
+                }
+            }
+            let free_inodes = self.group_free_inodes(bgid);
+            if bg.desc.free_inodes_count() != free_inodes {
+                problems.push(CheckProblem::GroupFreeInodes {
+                    group: bgid,
+                    expected: free_inodes,
+                    found: bg.desc.free_inodes_count(),
+                });
+            }
+            let dirs = self.group_dirs(bgid);
+            if bg.desc.used_dirs_count() != dirs {
+                problems.push(CheckProblem::GroupUsedDirs {
+                    group: bgid,
+                    expected: dirs,
+                    found: bg.desc.used_dirs_count(),
+                });
+            }
+            total_free_inodes += free_inodes;
+        }
+        if self.sb.free_blocks_count() != total_free_blocks {
+            problems.push(CheckProblem::FreeBlocks {
+                expected: total_free_blocks,
+                found: self.sb.free_blocks_count(),
+            });
+        }
+        if self.sb.free_inodes_count() != total_free_inodes {
+            problems.push(CheckProblem::FreeInodes {
+                expected: total_free_inodes,
+                found: self.sb.free_inodes_count(),
+            });
+        }
+        self.problems.append(&mut problems);
+    }
+}
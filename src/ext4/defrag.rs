@@ -0,0 +1,317 @@
+//! Online defragmentation, modeled on `EXT4_IOC_MOVE_EXT`.
+//!
+//! A fragmented file gets a donor region: the fewest runs of contiguous
+//! free blocks that hold all its data. The data is copied into the donor
+//! region, the extent tree of the file is rebuilt to map it, and the old
+//! data and extent tree blocks are freed, all in one transaction. Holes
+//! and unwritten extents are kept, unwritten blocks are not copied.
+
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+/// Maximum number of blocks of an initialized extent.
+const EXT_INIT_MAX_LEN: u64 = 32768;
+
+/// Maximum number of blocks of an unwritten extent.
+const EXT_UNWRITTEN_MAX_LEN: u64 = 32767;
+
+/// The fragmentation of a file before and after a defragmentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefragStats {
+    /// The inode of the file.
+    pub inode: InodeId,
+    /// Number of mapped data blocks.
+    pub blocks: u64,
+    /// Number of extents before the defragmentation.
+    pub extents_before: usize,
+    /// Number of extents after the defragmentation, the same as before if
+    /// the file is left in place.
+    pub extents_after: usize,
+}
+
+impl DefragStats {
+    /// Number of extents more than the data blocks need.
+    fn excess_extents(&self) -> usize {
+        let ideal = self.blocks.div_ceil(EXT_INIT_MAX_LEN).max(1) as usize;
+        self.extents_before.saturating_sub(ideal)
+    }
+}
+
+impl Ext4 {
+    /// Move the data of a regular file to contiguous blocks. The file is
+    /// left in place if it is not fragmented, or if the free blocks would
+    /// not hold it in fewer extents.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    ///
+    /// # Return
+    ///
+    /// The number of extents of the file before and after.
+    ///
+    /// # Error
+    ///
+    /// * `EISDIR` - `inode` is not a regular file
+    /// * `EPERM` - the file is immutable or append-only
+    /// * `ENOTSUP` - the file uses a block map or inline data, or the
+    ///   filesystem uses bigalloc
+    /// * `ENOSPC` - there are not enough free blocks for a donor region
+    pub fn defrag(&self, inode: InodeId) -> Result<DefragStats> {
+        self.trans_run(|| {
+            let mut file = self.read_inode(inode);
+            if !file.inode.is_file() {
+                return_error!(ErrCode::EISDIR, "Inode {} is not a file", inode);
+            }
+            Self::check_flags(&file, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            if !file.inode.flags().contains(InodeFlags::EXTENTS)
+                || file.inode.flags().contains(InodeFlags::INLINE_DATA)
+            {
+                return_error!(
+                    ErrCode::ENOTSUP,
+                    "Inode {} does not store data in extents",
+                    inode
+                );
+            }
+            if self.read_super_block().has_bigalloc() {
+                return_error!(ErrCode::ENOTSUP, "Cannot defragment with bigalloc");
+            }
+            let extents = self.extent_all_extents(&file);
+            let mut stats = Self::defrag_stats(inode, &extents);
+            if stats.excess_extents() == 0 {
+                return Ok(stats);
+            }
+
+            // Allocate the donor region
+            let mut donor = Vec::new();
+            let mut left = stats.blocks;
+            while left > 0 {
+                let Some((start, len)) = self.defrag_find_run(left) else {
+                    return_error!(
+                        ErrCode::ENOSPC,
+                        "No free blocks to defragment inode {}",
+                        inode
+                    );
+                };
+                for pblock in start..start + len {
+                    self.mark_block(pblock, true);
+                }
+                donor.push((start, len));
+                left -= len;
+            }
+            let (new_extents, moves) = Self::defrag_map(&extents, &donor);
+            if new_extents.len() >= extents.len() {
+                for &(start, len) in donor.iter() {
+                    for pblock in start..start + len {
+                        self.mark_block(pblock, false);
+                    }
+                }
+                return Ok(stats);
+            }
+
+            // Copy the data in place, even if journaled, since nothing
+            // references the donor blocks before the transaction commits
+            for &(from, to, len) in moves.iter() {
+                for i in 0..len {
+                    let block = self.read_block(from + i);
+                    self.write_data_block(&Block::new(to + i, block.data));
+                }
+            }
+
+            // Swap the extent mappings
+            for pblock in self.extent_all_tree_blocks(&file) {
+                self.mark_block(pblock, false);
+                self.quota_free_space(&file, BLOCK_SIZE as u64);
+                self.trans_revoke_block(pblock);
+                let blocks = file.inode.fs_block_count() - 1;
+                file.inode.set_fs_block_count(blocks);
+            }
+            file.inode.extent_init();
+            for extent in new_extents.iter() {
+                self.extent_insert(&mut file, extent)?;
+            }
+            self.write_inode_with_csum(&mut file);
+
+            // Free the old data blocks
+            let journal_data = self.journal_data(&file);
+            for extent in extents.iter() {
+                let start = extent.start_pblock();
+                for pblock in start..start + extent.block_count() as PBlockId {
+                    self.mark_block(pblock, false);
+                    if journal_data {
+                        self.trans_revoke_block(pblock);
+                    }
+                }
+            }
+            stats.extents_after = new_extents.len();
+            Ok(stats)
+        })
+    }
+
+    /// Defragment all regular files, the most fragmented first. Files
+    /// that cannot be defragmented, e.g. for lack of free blocks, are
+    /// skipped.
+    ///
+    /// # Return
+    ///
+    /// The stats of the fragmented files, in the order they were
+    /// processed.
+    pub fn defrag_all(&self) -> Vec<DefragStats> {
+        let mut ranked = self.defrag_rank();
+        ranked.retain(|stats| stats.excess_extents() > 0);
+        ranked
+            .iter()
+            .map(|stats| self.defrag(stats.inode).unwrap_or(*stats))
+            .collect()
+    }
+
+    /// The stats of all regular files with extents, ranked by the number
+    /// of excess extents, then by size, most fragmented first.
+    fn defrag_rank(&self) -> Vec<DefragStats> {
+        let sb = self.read_super_block();
+        let ipg = sb.inodes_per_group();
+        let mut ranked = Vec::new();
+        for bgid in 0..sb.block_group_count() {
+            let bg = self.read_block_group(bgid);
+            if bg.desc.flags().contains(BlockGroupFlags::INODE_UNINIT) {
+                continue;
+            }
+            let mut bitmap_block = self.read_block(bg.desc.inode_bitmap_block());
+            let bitmap = Bitmap::new(&mut bitmap_block.data, ipg as usize);
+            for idx in 0..ipg {
+                let ino = bgid * ipg + idx + 1;
+                if ino < sb.first_inode() || bitmap.is_bit_clear(idx as usize) {
+                    continue;
+                }
+                let file = self.read_inode(ino);
+                let flags = file.inode.flags();
+                if !file.inode.is_file()
+                    || file.inode.link_count() == 0
+                    || !flags.contains(InodeFlags::EXTENTS)
+                    || flags.contains(InodeFlags::INLINE_DATA)
+                {
+                    continue;
+                }
+                ranked.push(Self::defrag_stats(ino, &self.extent_all_extents(&file)));
+            }
+        }
+        ranked.sort_by(|a, b| {
+            b.excess_extents()
+                .cmp(&a.excess_extents())
+                .then(b.blocks.cmp(&a.blocks))
+                .then(a.inode.cmp(&b.inode))
+        });
+        ranked
+    }
+
+    /// The stats of a file before a defragmentation.
+    fn defrag_stats(inode: InodeId, extents: &[Extent]) -> DefragStats {
+        DefragStats {
+            inode,
+            blocks: extents.iter().map(|ex| ex.block_count() as u64).sum(),
+            extents_before: extents.len(),
+            extents_after: extents.len(),
+        }
+    }
+
+    /// Find free blocks for a donor region of `len` blocks. Return the
+    /// first run of `len` free blocks, or else the longest run.
+    fn defrag_find_run(&self, len: u64) -> Option<(PBlockId, u64)> {
+        let sb = self.read_super_block();
+        let mut best: Option<(PBlockId, u64)> = None;
+        for bgid in 0..sb.block_group_count() {
+            let bg = self.read_block_group(bgid);
+            if bg.desc.get_free_blocks_count() == 0
+                || bg.desc.flags().contains(BlockGroupFlags::BLOCK_UNINIT)
+            {
+                continue;
+            }
+            let first = sb.group_first_block(bgid);
+            let count = sb.blocks_in_group(bgid);
+            let mut bitmap_block = self.read_block(bg.desc.block_bitmap_block());
+            let bitmap = Bitmap::new(&mut bitmap_block.data, 8 * BLOCK_SIZE);
+            let mut start = 0;
+            for bit in 0..=count {
+                if bit < count && bitmap.is_bit_clear(bit as usize) {
+                    if bit + 1 - start == len {
+                        return Some((first + start, len));
+                    }
+                    continue;
+                }
+                if bit > start && best.is_none_or(|(_, l)| bit - start > l) {
+                    best = Some((first + start, bit - start));
+                }
+                start = bit + 1;
+            }
+        }
+        best
+    }
+
+    /// Map the `extents` of a file to the `donor` runs in logical order.
+    /// Return the new extents, and the written blocks to copy as the old
+    /// start, the new start and the length.
+    fn defrag_map(
+        extents: &[Extent],
+        donor: &[(PBlockId, u64)],
+    ) -> (Vec<Extent>, Vec<(PBlockId, PBlockId, u64)>) {
+        let mut new_extents: Vec<Extent> = Vec::new();
+        let mut moves = Vec::new();
+        let mut runs = donor.iter().copied();
+        let (mut to, mut run_left) = (0, 0);
+        for extent in extents.iter() {
+            let unwritten = extent.is_unwritten();
+            let max_len = if unwritten {
+                EXT_UNWRITTEN_MAX_LEN
+            } else {
+                EXT_INIT_MAX_LEN
+            };
+            let mut lblock = extent.start_lblock();
+            let mut from = extent.start_pblock();
+            let mut left = extent.block_count() as u64;
+            while left > 0 {
+                if run_left == 0 {
+                    (to, run_left) = runs.next().expect("Donor region too small");
+                }
+                let len = left.min(run_left);
+                if !unwritten {
+                    moves.push((from, to, len));
+                }
+                // Extend the last extent if it is contiguous
+                let mut len_left = len;
+                if let Some(last) = new_extents.last_mut() {
+                    let last_len = last.block_count() as u64;
+                    if last.is_unwritten() == unwritten
+                        && last.start_lblock() + last_len as LBlockId == lblock
+                        && last.start_pblock() + last_len == to
+                    {
+                        let add = len.min(max_len - last_len);
+                        last.set_block_count((last_len + add) as LBlockId);
+                        if unwritten {
+                            last.mark_unwritten();
+                        }
+                        len_left -= add;
+                    }
+                }
+                while len_left > 0 {
+                    let add = len_left.min(max_len);
+                    let offset = len - len_left;
+                    let mut new = Extent::new(lblock + offset as LBlockId, to + offset, add as u16);
+                    if unwritten {
+                        new.mark_unwritten();
+                    }
+                    new_extents.push(new);
+                    len_left -= add;
+                }
+                lblock += len as LBlockId;
+                from += len;
+                to += len;
+                run_left -= len;
+                left -= len;
+            }
+        }
+        (new_extents, moves)
+    }
+}
@@ -4,23 +4,48 @@ use crate::ext4_defs::*;
 use crate::prelude::*;
 use crate::return_error;
 
+/// An index block on the path from the root of a hashed directory to a
+/// leaf.
+struct DxFrame {
+    /// Logical block of the index block.
+    lblock: LBlockId,
+    node: DxBlock,
+    entries: Vec<DxEntry>,
+    /// The entry followed down the path.
+    at: usize,
+}
+
+/// The path from the root of a hashed directory to the leaf that covers
+/// a hash.
+struct DxPath {
+    hash: u32,
+    /// Hash version of the directory.
+    version: u8,
+    /// Index blocks from the root.
+    frames: Vec<DxFrame>,
+}
+
 impl Ext4 {
     /// Find a directory entry that matches a given name under a parent directory
     pub(super) fn dir_find_entry(&self, dir: &InodeRef, name: &str) -> Result<InodeId> {
         trace!("Dir find entry: dir {}, name {}", dir.id, name);
-        let total_blocks = dir.inode.fs_block_count() as u32;
-        let mut iblock: LBlockId = 0;
-        while iblock < total_blocks {
+        let disk_name = self.fscrypt_disk_name(dir, name, false)?;
+        let casefold = self.dir_casefold(dir)?;
+        // A hashed directory only needs its leaves that cover the name
+        let iblocks = match self.dir_dx_leaves(dir, &disk_name, casefold) {
+            Some(leaves) => leaves,
+            None => (0..self.inode_data_blocks(dir) as LBlockId).collect(),
+        };
+        for iblock in iblocks {
             // Get the fs block id
             let fblock = self.extent_query(dir, iblock)?;
             // Load block from disk
             let dir_block = DirBlock::new(self.read_block(fblock));
             // Find the entry in block
-            let res = dir_block.get(name);
+            let res = dir_block.get(&disk_name, casefold);
             if let Some(r) = res {
                 return Ok(r);
             }
-            iblock += 1;
         }
         return_error!(
             ErrCode::ENOENT,
@@ -43,7 +68,22 @@ impl Ext4 {
             child.id,
             name
         );
-        let total_blocks = dir.inode.fs_block_count() as u32;
+        let disk_name = self.fscrypt_disk_name(dir, name, true)?;
+        let casefold = self.dir_casefold(dir)?;
+        if casefold && self.dir_find_entry(dir, name).is_ok() {
+            return_error!(
+                ErrCode::EEXIST,
+                "Directory entry exists ignoring case: dir {}, name {}",
+                dir.id,
+                name
+            );
+        }
+        if self.dir_dx_add_entry(dir, child, &disk_name, casefold)? {
+            self.touch_modify(dir);
+            self.write_inode_with_csum(dir);
+            return Ok(());
+        }
+        let total_blocks = self.inode_data_blocks(dir) as u32;
         let mut iblock: LBlockId = 0;
         // Try finding a block with enough space
         while iblock < total_blocks {
@@ -52,7 +92,7 @@ impl Ext4 {
             // Load the parent block from disk
             let mut dir_block = DirBlock::new(self.read_block(fblock));
             // Try inserting the entry to parent block
-            if dir_block.insert(name, child.id, child.inode.file_type()) {
+            if dir_block.insert(&disk_name, child.id, child.inode.file_type()) {
                 // Update checksum
                 dir_block.set_checksum(
                     &self.read_super_block().uuid(),
@@ -61,21 +101,29 @@ impl Ext4 {
                 );
                 // Write the block back to disk
                 self.write_block(&dir_block.block());
+                // Update directory timestamps
+                self.touch_modify(dir);
+                self.write_inode_with_csum(dir);
                 return Ok(());
             }
             // Current block has no enough space
             iblock += 1;
         }
+        // A directory that outgrows its first block is indexed
+        if self.dir_dx_make_index(dir)?
+            && self.dir_dx_add_entry(dir, child, &disk_name, casefold)?
+        {
+            self.touch_modify(dir);
+            self.write_inode_with_csum(dir);
+            return Ok(());
+        }
         // No free block found - needed to allocate a new data block
-        // Append a new data block
-        let (_, fblock) = self.inode_append_block(dir)?;
-        // Update inode size
-        dir.inode.set_size(dir.inode.size() + BLOCK_SIZE as u64);
-        // Load new block
-        let mut new_dir_block = DirBlock::new(self.read_block(fblock));
+        let (_, fblock) = self.dir_append_block(dir)?;
+        // The new block may hold stale data of a freed block
+        let mut new_dir_block = DirBlock::new(Block::new(fblock, [0; BLOCK_SIZE]));
         // Write the entry to block
         new_dir_block.init();
-        new_dir_block.insert(name, child.id, child.inode.file_type());
+        new_dir_block.insert(&disk_name, child.id, child.inode.file_type());
         new_dir_block.set_checksum(
             &self.read_super_block().uuid(),
             dir.id,
@@ -83,14 +131,34 @@ impl Ext4 {
         );
         // Write the block back to disk
         self.write_block(&new_dir_block.block());
+        // Update directory timestamps
+        self.touch_modify(dir);
+        self.write_inode_with_csum(dir);
 
         Ok(())
     }
 
     /// Remove a entry from a directory
-    pub(super) fn dir_remove_entry(&self, dir: &InodeRef, name: &str) -> Result<()> {
-        trace!("Dir remove entry: dir {}, name {}", dir.id, name);
-        let total_blocks = dir.inode.fs_block_count() as u32;
+    pub(super) fn dir_remove_entry(&self, dir: &mut InodeRef, name: &str) -> Result<()> {
+        let disk_name = self.fscrypt_disk_name(dir, name, false)?;
+        let casefold = self.dir_casefold(dir)?;
+        self.dir_remove_disk_entry(dir, &disk_name, casefold)
+    }
+
+    /// Remove a entry from a directory by the name stored in its blocks,
+    /// ignoring case if `casefold`
+    pub(super) fn dir_remove_disk_entry(
+        &self,
+        dir: &mut InodeRef,
+        name: &[u8],
+        casefold: bool,
+    ) -> Result<()> {
+        trace!(
+            "Dir remove entry: dir {}, name {}",
+            dir.id,
+            String::from_utf8_lossy(name)
+        );
+        let total_blocks = self.inode_data_blocks(dir) as u32;
         // Check each block
         let mut iblock: LBlockId = 0;
         while iblock < total_blocks {
@@ -99,7 +167,7 @@ impl Ext4 {
             // Load the block from disk
             let mut dir_block = DirBlock::new(self.read_block(fblock));
             // Try removing the entry
-            if dir_block.remove(name) {
+            if dir_block.remove(name, casefold) {
                 // Update checksum
                 dir_block.set_checksum(
                     &self.read_super_block().uuid(),
@@ -108,6 +176,9 @@ impl Ext4 {
                 );
                 // Write the block back to disk
                 self.write_block(&dir_block.block());
+                // Update directory timestamps
+                self.touch_modify(dir);
+                self.write_inode_with_csum(dir);
                 return Ok(());
             }
             // Current block has no enough space
@@ -118,13 +189,13 @@ impl Ext4 {
             ErrCode::ENOENT,
             "Directory entry not found: dir {}, name {}",
             dir.id,
-            name
+            String::from_utf8_lossy(name)
         );
     }
 
     /// Get all entries under a directory
     pub(super) fn dir_list_entries(&self, dir: &InodeRef) -> Vec<DirEntry> {
-        let total_blocks = dir.inode.fs_block_count() as u32;
+        let total_blocks = self.inode_data_blocks(dir) as u32;
         let mut entries: Vec<DirEntry> = Vec::new();
         let mut iblock: LBlockId = 0;
         while iblock < total_blocks {
@@ -138,4 +209,408 @@ impl Ext4 {
         }
         entries
     }
+
+    /// Whether names in a directory are compared ignoring case.
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - the directory is both case-insensitive and encrypted
+    pub(super) fn dir_casefold(&self, dir: &InodeRef) -> Result<bool> {
+        let flags = dir.inode.flags();
+        if !flags.contains(InodeFlags::CASEFOLD) || !self.read_super_block().has_casefold() {
+            return Ok(false);
+        }
+        if flags.contains(InodeFlags::ENCRYPT) {
+            return_error!(
+                ErrCode::ENOTSUP,
+                "Encrypted case-insensitive directory {} is not supported",
+                dir.id
+            );
+        }
+        Ok(true)
+    }
+
+    /// Whether `name` is looked up and added through the index of a
+    /// hashed directory. "." and ".." are always in the root.
+    fn dir_is_dx(&self, dir: &InodeRef, name: &[u8]) -> bool {
+        self.read_super_block().has_dir_index()
+            && dir.inode.flags().contains(InodeFlags::INDEX)
+            && name != b"."
+            && name != b".."
+    }
+
+    /// Walk down the index of a hashed directory to the leaf that covers
+    /// the hash of `name`.
+    ///
+    /// Return `None` if the index is not valid.
+    fn dir_dx_probe(&self, dir: &InodeRef, name: &[u8], casefold: bool) -> Option<DxPath> {
+        let sb = self.read_super_block();
+        let read_node = |lblock: LBlockId| {
+            let fblock = self.extent_query(dir, lblock).ok()?;
+            Some(DxBlock::new(self.read_block(fblock)))
+        };
+        let root = read_node(0)?;
+        let info = root.root_info();
+        if !info.is_valid(sb.has_large_dir()) {
+            return None;
+        }
+        let version = info.hash_version(sb.has_unsigned_hash());
+        let hash = dx_hash(name, casefold, version, &sb.hash_seed())?;
+        let mut frames = Vec::new();
+        let (mut lblock, mut node) = (0, root);
+        loop {
+            let entries = node.entries(lblock == 0)?;
+            let at = DxEntry::search(&entries, hash);
+            let next = entries[at].block();
+            frames.push(DxFrame {
+                lblock,
+                node,
+                entries,
+                at,
+            });
+            if frames.len() > info.indirect_levels() as usize {
+                break;
+            }
+            lblock = next;
+            node = read_node(lblock)?;
+        }
+        Some(DxPath {
+            hash,
+            version,
+            frames,
+        })
+    }
+
+    /// Find the leaves of a hashed directory that may hold `name`: the
+    /// leaf that covers the hash of `name`, then the leaves that continue
+    /// it when names with the same hash span several leaves.
+    ///
+    /// Return `None` if the directory is not hashed or its index is not
+    /// valid, and is to be searched linearly.
+    fn dir_dx_leaves(&self, dir: &InodeRef, name: &[u8], casefold: bool) -> Option<Vec<LBlockId>> {
+        if !self.dir_is_dx(dir, name) {
+            return None;
+        }
+        let read_node = |lblock: LBlockId| {
+            let fblock = self.extent_query(dir, lblock).ok()?;
+            Some(DxBlock::new(self.read_block(fblock)))
+        };
+        let DxPath { hash, frames, .. } = self.dir_dx_probe(dir, name, casefold)?;
+        let levels = frames.len() - 1;
+        // The path of index entries from the root
+        let mut path: Vec<(usize, Vec<DxEntry>)> = frames
+            .into_iter()
+            .map(|frame| (frame.at, frame.entries))
+            .collect();
+        let mut leaves = Vec::new();
+        loop {
+            let (at, entries) = path.last().unwrap();
+            leaves.push(entries[*at].block());
+            // Move to the next leaf, going up as far as needed
+            let Some(level) = path
+                .iter()
+                .rposition(|(at, entries)| at + 1 < entries.len())
+            else {
+                break;
+            };
+            path.truncate(level + 1);
+            let (at, entries) = path.last_mut().unwrap();
+            *at += 1;
+            // The next leaf continues this one if it starts with the hash
+            if entries[*at].hash() & !1 != hash {
+                break;
+            }
+            while path.len() <= levels {
+                let (at, entries) = path.last().unwrap();
+                let entries = read_node(entries[*at].block())?.entries(false)?;
+                path.push((0, entries));
+            }
+        }
+        Some(leaves)
+    }
+
+    /// Add an entry to the leaf of a hashed directory that covers the hash
+    /// of `name`. A full leaf is split in two by hash, full interior nodes
+    /// are split on the way up, and a level is added below a full root.
+    ///
+    /// # Return
+    ///
+    /// `false` if the directory is not hashed and the entry is to be added
+    /// linearly. A directory with an invalid index is turned into a linear
+    /// one first.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - the index has no room for more leaves, or no space
+    ///   left on device
+    /// * `EFBIG` - the directory is too large
+    fn dir_dx_add_entry(
+        &self,
+        dir: &mut InodeRef,
+        child: &InodeRef,
+        name: &[u8],
+        casefold: bool,
+    ) -> Result<bool> {
+        if !self.dir_is_dx(dir, name) {
+            return Ok(false);
+        }
+        loop {
+            let Some(mut path) = self.dir_dx_probe(dir, name, casefold) else {
+                // The index is not valid, fall back to a linear directory
+                self.dir_dx_drop_index(dir)?;
+                return Ok(false);
+            };
+            let leaf = path.frames.last().unwrap();
+            let fblock = self.extent_query(dir, leaf.entries[leaf.at].block())?;
+            let mut dir_block = DirBlock::new(self.read_block(fblock));
+            if dir_block.insert(name, child.id, child.inode.file_type()) {
+                dir_block.set_checksum(
+                    &self.read_super_block().uuid(),
+                    dir.id,
+                    dir.inode.generation(),
+                );
+                self.write_block(dir_block.block());
+                return Ok(true);
+            }
+            // Split the lowest index block with room for another entry
+            let levels = path.frames.len();
+            let room = path
+                .frames
+                .iter()
+                .rposition(|frame| frame.entries.len() < frame.node.limit(frame.lblock == 0));
+            match room {
+                Some(level) if level + 1 == levels => {
+                    let parent = &mut path.frames[level];
+                    self.dir_dx_split_leaf(dir, parent, &dir_block, path.version, casefold)?
+                }
+                Some(level) => {
+                    let (upper, lower) = path.frames.split_at_mut(level + 1);
+                    self.dir_dx_split_node(dir, &mut upper[level], &mut lower[0])?
+                }
+                None => self.dir_dx_add_level(dir, &mut path.frames[0])?,
+            }
+        }
+    }
+
+    /// Turn a linear directory of one full block into a hashed one, as
+    /// `make_indexed_dir` of Linux does. The entries but "." and ".." move
+    /// to a new leaf, and the first block becomes the root of the index.
+    /// The inode is modified but not written.
+    ///
+    /// # Return
+    ///
+    /// `false` if the filesystem has no `dir_index` feature, or the
+    /// directory is already hashed or has more than one block.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - no space left on device
+    fn dir_dx_make_index(&self, dir: &mut InodeRef) -> Result<bool> {
+        let sb = self.read_super_block();
+        if !sb.has_dir_index()
+            || dir.inode.flags().contains(InodeFlags::INDEX)
+            || self.inode_data_blocks(dir) != 1
+        {
+            return Ok(false);
+        }
+        let fblock = self.extent_query(dir, 0)?;
+        let mut entries = Vec::new();
+        DirBlock::new(self.read_block(fblock)).list(&mut entries);
+        let parent = entries
+            .iter()
+            .find(|de| de.name_bytes() == b"..")
+            .map_or(dir.id, |de| de.inode());
+
+        let (lblock, leaf_fblock) = self.dir_append_block(dir)?;
+        let mut leaf = DirBlock::new(Block::new(leaf_fblock, [0; BLOCK_SIZE]));
+        leaf.init();
+        for de in entries.iter() {
+            if de.name_bytes() != b"." && de.name_bytes() != b".." {
+                leaf.insert(de.name_bytes(), de.inode(), de.file_type());
+            }
+        }
+        leaf.set_checksum(&sb.uuid(), dir.id, dir.inode.generation());
+        self.write_block(leaf.block());
+
+        let mut root = DxBlock::new_root(fblock, dir.id, parent, sb.default_hash_version());
+        self.dir_dx_write_node(dir, &mut root, true, &[DxEntry::new(0, lblock)]);
+        dir.inode.set_flags(dir.inode.flags() | InodeFlags::INDEX);
+        Ok(true)
+    }
+
+    /// Split a full leaf of a hashed directory, moving the entries with the
+    /// greater hashes to a new leaf indexed after it in `parent`.
+    fn dir_dx_split_leaf(
+        &self,
+        dir: &mut InodeRef,
+        parent: &mut DxFrame,
+        leaf: &DirBlock,
+        version: u8,
+        casefold: bool,
+    ) -> Result<()> {
+        let seed = self.read_super_block().hash_seed();
+        let mut entries = Vec::new();
+        leaf.list(&mut entries);
+        let mut entries: Vec<(u32, DirEntry)> = entries
+            .into_iter()
+            .map(|de| {
+                let hash = dx_hash(de.name_bytes(), casefold, version, &seed).unwrap_or(0);
+                (hash, de)
+            })
+            .collect();
+        entries.sort_by_key(|(hash, _)| *hash);
+        // Move about half of the entries by size, from the greatest hash
+        let mut split = entries.len();
+        let mut size = 0;
+        while split > 1 {
+            let entry_size = entries[split - 1].1.used_size();
+            if size + entry_size / 2 > BLOCK_SIZE / 2 {
+                break;
+            }
+            size += entry_size;
+            split -= 1;
+        }
+        let upper = entries.split_off(split);
+        // Names with the same hash continue in the new leaf
+        let mut hash = upper[0].0;
+        if entries.last().is_some_and(|(last, _)| *last == hash) {
+            hash |= 1;
+        }
+
+        let (lblock, fblock) = self.dir_append_block(dir)?;
+        let uuid = self.read_super_block().uuid();
+        for (fblock, entries) in [(leaf.block().id, entries), (fblock, upper)] {
+            let mut dir_block = DirBlock::new(Block::new(fblock, [0; BLOCK_SIZE]));
+            dir_block.init();
+            for (_, de) in entries {
+                dir_block.insert(de.name_bytes(), de.inode(), de.file_type());
+            }
+            dir_block.set_checksum(&uuid, dir.id, dir.inode.generation());
+            self.write_block(dir_block.block());
+        }
+        parent
+            .entries
+            .insert(parent.at + 1, DxEntry::new(hash, lblock));
+        self.dir_dx_write_node(dir, &mut parent.node, parent.lblock == 0, &parent.entries);
+        Ok(())
+    }
+
+    /// Split a full interior node of a hashed directory, moving the upper
+    /// half of its entries to a new node indexed after it in `parent`.
+    fn dir_dx_split_node(
+        &self,
+        dir: &mut InodeRef,
+        parent: &mut DxFrame,
+        node: &mut DxFrame,
+    ) -> Result<()> {
+        let (lblock, fblock) = self.dir_append_block(dir)?;
+        let upper = node.entries.split_off(node.entries.len() / 2);
+        let mut new_node = DxBlock::new_node(fblock);
+        self.dir_dx_write_node(dir, &mut new_node, false, &upper);
+        self.dir_dx_write_node(dir, &mut node.node, false, &node.entries);
+        parent
+            .entries
+            .insert(parent.at + 1, DxEntry::new(upper[0].hash(), lblock));
+        self.dir_dx_write_node(dir, &mut parent.node, parent.lblock == 0, &parent.entries);
+        Ok(())
+    }
+
+    /// Add a level of interior nodes below the full root of a hashed
+    /// directory, moving the entries of the root to a new node.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - the index has the most levels allowed
+    fn dir_dx_add_level(&self, dir: &mut InodeRef, root: &mut DxFrame) -> Result<()> {
+        let mut info = root.node.root_info();
+        let large_dir = self.read_super_block().has_large_dir();
+        if info.indirect_levels() >= DxRootInfo::max_indirect_levels(large_dir) {
+            return_error!(ErrCode::ENOSPC, "Index of directory {} is full", dir.id);
+        }
+        let (lblock, fblock) = self.dir_append_block(dir)?;
+        let mut node = DxBlock::new_node(fblock);
+        self.dir_dx_write_node(dir, &mut node, false, &root.entries);
+        info.set_indirect_levels(info.indirect_levels() + 1);
+        root.node.set_root_info(&info);
+        self.dir_dx_write_node(dir, &mut root.node, true, &[DxEntry::new(0, lblock)]);
+        Ok(())
+    }
+
+    /// Write an index block of a hashed directory with `entries`.
+    fn dir_dx_write_node(
+        &self,
+        dir: &InodeRef,
+        node: &mut DxBlock,
+        root: bool,
+        entries: &[DxEntry],
+    ) {
+        node.set_entries(root, entries);
+        node.set_checksum(
+            root,
+            &self.read_super_block().uuid(),
+            dir.id,
+            dir.inode.generation(),
+        );
+        self.write_block(node.block());
+    }
+
+    /// Append a block to a directory and grow its size. Without the
+    /// `large_dir` feature, a directory stays below 2 GiB and the high
+    /// 32 bits of its size are not used.
+    ///
+    /// # Error
+    ///
+    /// * `EFBIG` - the directory is too large
+    /// * `ENOSPC` - no space left on device
+    fn dir_append_block(&self, dir: &mut InodeRef) -> Result<(LBlockId, PBlockId)> {
+        let mut size = dir.inode.size();
+        if !self.read_super_block().has_large_dir() {
+            size &= u32::MAX as u64;
+            if size + BLOCK_SIZE as u64 > i32::MAX as u64 {
+                return_error!(ErrCode::EFBIG, "Directory {} is too large", dir.id);
+            }
+        }
+        let (iblock, fblock) = self.inode_append_block(dir)?;
+        dir.inode.set_size(size + BLOCK_SIZE as u64);
+        Ok((iblock, fblock))
+    }
+
+    /// Turn a hashed directory into a linear one. The root and interior
+    /// nodes of the index become normal directory blocks, with "." and
+    /// ".." kept in the root.
+    fn dir_dx_drop_index(&self, dir: &mut InodeRef) -> Result<()> {
+        let root = self.read_block(self.extent_query(dir, 0)?);
+        let parent = root.read_offset_as::<DirEntry>(12).inode();
+        let levels = DxBlock::new(root).root_info().indirect_levels();
+        // Collect the interior nodes level by level
+        let mut nodes = vec![0];
+        let mut level = vec![0];
+        for depth in 0..levels {
+            let mut next = Vec::new();
+            for &lblock in level.iter() {
+                let node = DxBlock::new(self.read_block(self.extent_query(dir, lblock)?));
+                let Some(entries) = node.entries(depth == 0) else {
+                    return_error!(ErrCode::EIO, "Invalid index of directory {}", dir.id);
+                };
+                next.extend(entries.iter().map(|entry| entry.block()));
+            }
+            nodes.extend(next.iter().copied());
+            level = next;
+        }
+        let uuid = self.read_super_block().uuid();
+        for lblock in nodes {
+            let fblock = self.extent_query(dir, lblock)?;
+            let mut dir_block = DirBlock::new(Block::new(fblock, [0; BLOCK_SIZE]));
+            dir_block.init();
+            if lblock == 0 {
+                dir_block.insert(b".", dir.id, FileType::Directory);
+                dir_block.insert(b"..", parent, FileType::Directory);
+            }
+            dir_block.set_checksum(&uuid, dir.id, dir.inode.generation());
+            self.write_block(dir_block.block());
+        }
+        dir.inode.set_flags(dir.inode.flags() - InodeFlags::INDEX);
+        self.write_inode_with_csum(dir);
+        Ok(())
+    }
 }
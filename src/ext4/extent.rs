@@ -1,9 +1,8 @@
 use super::Ext4;
-use crate::constants::*;
 use crate::ext4_defs::*;
 use crate::format_error;
 use crate::prelude::*;
-use core::cmp::min;
+use crate::return_error;
 
 #[derive(Debug)]
 struct ExtentSearchStep {
@@ -52,44 +51,161 @@ impl Ext4 {
         }
     }
 
-    /// Given a logic block id, find the corresponding fs block id.
-    /// Create a new extent if not found.
-    pub(super) fn extent_query_or_create(
+    /// Insert an extent into the extent tree. The logic blocks it covers
+    /// must not be mapped.
+    pub(super) fn extent_insert(&self, inode_ref: &mut InodeRef, extent: &Extent) -> Result<()> {
+        let path = self.find_extent(inode_ref, extent.start_lblock());
+        self.insert_extent(inode_ref, &path, extent)
+    }
+
+    /// Map a logic block to a physical block. The logic block must not be
+    /// mapped. Extend the previous extent if possible, otherwise insert a
+    /// new extent.
+    pub(super) fn extent_map(
         &self,
         inode_ref: &mut InodeRef,
         iblock: LBlockId,
-        block_count: u32,
-    ) -> Result<PBlockId> {
+        pblock: PBlockId,
+    ) -> Result<()> {
         let path = self.find_extent(inode_ref, iblock);
-        // Leaf is the last element of the path
         let leaf = path.last().unwrap();
-        // Note: block data must be defined here to keep it alive
-        let mut block_data: Block;
-        let ex_node = if leaf.pblock != 0 {
+        let pos = match leaf.index {
+            Ok(_) => {
+                return_error!(
+                    ErrCode::EEXIST,
+                    "extent_map: inode {} iblock {} is already mapped",
+                    inode_ref.id,
+                    iblock
+                );
+            }
+            Err(pos) => pos,
+        };
+        let new_ext = Extent::new(iblock, pblock, 1);
+        if pos > 0 {
+            let mut block_data = Block::default();
+            if leaf.pblock != 0 {
+                block_data = self.read_block(leaf.pblock);
+            }
+            let appended = {
+                let mut ex_node = if leaf.pblock != 0 {
+                    ExtentNodeMut::from_bytes(&mut block_data.data)
+                } else {
+                    inode_ref.inode.extent_root_mut()
+                };
+                let prev = ex_node.extent_mut_at(pos - 1);
+                let appended = !prev.is_unwritten() && Extent::can_append(prev, &new_ext);
+                if appended {
+                    prev.set_block_count(prev.block_count() + 1);
+                }
+                appended
+            };
+            if appended {
+                if leaf.pblock != 0 {
+                    self.write_extent_block(inode_ref, &mut block_data);
+                } else {
+                    self.write_inode_without_csum(inode_ref);
+                }
+                return Ok(());
+            }
+        }
+        self.insert_extent(inode_ref, &path, &new_ext)
+    }
+
+    /// Unmap a logic block, return the physical block it was mapped to,
+    /// or `None` if not mapped. The physical block is not freed.
+    pub(super) fn extent_unmap(
+        &self,
+        inode_ref: &mut InodeRef,
+        iblock: LBlockId,
+    ) -> Result<Option<PBlockId>> {
+        let path = self.find_extent(inode_ref, iblock);
+        let leaf = path.last().unwrap();
+        let Ok(index) = leaf.index else {
+            return Ok(None);
+        };
+        let mut block_data = Block::default();
+        if leaf.pblock != 0 {
             block_data = self.read_block(leaf.pblock);
+        }
+        let mut ex_node = if leaf.pblock != 0 {
             ExtentNodeMut::from_bytes(&mut block_data.data)
         } else {
-            // Root node
             inode_ref.inode.extent_root_mut()
         };
-        match leaf.index {
-            Ok(index) => {
-                // Found, return the corresponding fs block id
-                let ex = ex_node.extent_at(index);
-                Ok(ex.start_pblock() + (iblock - ex.start_lblock()) as PBlockId)
-            }
-            Err(_) => {
-                // Not found, create a new extent
-                let block_count = min(block_count, MAX_BLOCKS - iblock);
-                // Allocate physical block
-                let fblock = self.alloc_block(inode_ref)?;
-                // Create a new extent
-                let new_ext = Extent::new(iblock, fblock, block_count as u16);
-                // Insert the new extent
-                self.insert_extent(inode_ref, &path, &new_ext)?;
-                Ok(fblock)
+        let ex = *ex_node.extent_at(index);
+        let offset = iblock - ex.start_lblock();
+        let pblock = ex.start_pblock() + offset as PBlockId;
+        let count = ex.block_count();
+        // The part of the extent after `iblock`, if any
+        let mut right = None;
+        if count == 1 {
+            ex_node.remove_extent(index);
+        } else if offset == 0 {
+            let ex = ex_node.extent_mut_at(index);
+            ex.set_start_lblock(iblock + 1);
+            ex.set_start_pblock(pblock + 1);
+            ex.set_block_count(count - 1);
+        } else {
+            ex_node.extent_mut_at(index).set_block_count(offset);
+            if offset + 1 < count {
+                right = Some(Extent::new(
+                    iblock + 1,
+                    pblock + 1,
+                    (count - offset - 1) as u16,
+                ));
             }
         }
+        if leaf.pblock != 0 {
+            self.write_extent_block(inode_ref, &mut block_data);
+        } else {
+            self.write_inode_without_csum(inode_ref);
+        }
+        if let Some(right) = right {
+            // Split in the middle, insert the right part as a new extent
+            let path = self.find_extent(inode_ref, iblock + 1);
+            self.insert_extent(inode_ref, &path, &right)?;
+        }
+        Ok(Some(pblock))
+    }
+
+    /// Get the logical block after the last mapped one, 0 if none.
+    pub(super) fn extent_end_block(&self, inode_ref: &InodeRef) -> LBlockId {
+        let path = self.find_extent(inode_ref, LBlockId::MAX);
+        let leaf = path.last().unwrap();
+        let pos = match leaf.index {
+            Ok(index) => index + 1,
+            Err(pos) => pos,
+        };
+        if pos == 0 {
+            return 0;
+        }
+        let block_data: Block;
+        let ex_node = if leaf.pblock != 0 {
+            block_data = self.read_block(leaf.pblock);
+            ExtentNode::from_bytes(&block_data.data)
+        } else {
+            inode_ref.inode.extent_root()
+        };
+        let ex = ex_node.extent_at(pos - 1);
+        ex.start_lblock() + ex.block_count() as LBlockId
+    }
+
+    /// Write an extent tree block of `inode_ref` with checksum
+    fn write_extent_block(&self, inode_ref: &InodeRef, block: &mut Block) {
+        ExtentNodeMut::from_bytes(&mut block.data).set_checksum(
+            &self.read_super_block().uuid(),
+            inode_ref.id,
+            inode_ref.inode.generation(),
+        );
+        self.write_block(block);
+    }
+
+    /// Get all extents recorded in the leaves of the extent tree
+    pub(super) fn extent_all_extents(&self, inode_ref: &InodeRef) -> Vec<Extent> {
+        let mut extents = Vec::new();
+        let ex_node = inode_ref.inode.extent_root();
+        self.get_all_extents_recursive(&ex_node, &mut extents);
+        extents
     }
 
     /// Get all data blocks recorded in the extent tree
@@ -128,6 +244,23 @@ impl Ext4 {
         }
     }
 
+    fn get_all_extents_recursive(&self, ex_node: &ExtentNode, extents: &mut Vec<Extent>) {
+        if ex_node.header().depth() == 0 {
+            // Leaf
+            for i in 0..ex_node.header().entries_count() as usize {
+                extents.push(*ex_node.extent_at(i));
+            }
+        } else {
+            // Non-leaf
+            for i in 0..ex_node.header().entries_count() as usize {
+                let ex_idx = ex_node.extent_index_at(i);
+                let child_block = self.read_block(ex_idx.leaf());
+                let child_node = ExtentNode::from_bytes(&child_block.data);
+                self.get_all_extents_recursive(&child_node, extents);
+            }
+        }
+    }
+
     fn get_all_nodes_recursive(&self, ex_node: &ExtentNode, pblocks: &mut Vec<PBlockId>) {
         if ex_node.header().depth() != 0 {
             // Non-leaf
@@ -195,7 +328,7 @@ impl Ext4 {
         let mut leaf_node = ExtentNodeMut::from_bytes(&mut leaf_block.data);
         // Insert the extent
         let res = leaf_node.insert_extent(new_ext, leaf.index.unwrap_err());
-        self.write_block(&leaf_block);
+        self.write_extent_block(inode_ref, &mut leaf_block);
         // Handle split
         if let Err(mut split) = res {
             // Handle split until root
@@ -262,12 +395,12 @@ impl Ext4 {
             let mut parent_node = ExtentNodeMut::from_bytes(&mut parent_block.data);
             parent_depth = parent_node.header().depth();
             res = parent_node.insert_extent_index(&extent_index, child_pos + 1);
-            self.write_block(&parent_block);
+            self.write_extent_block(inode_ref, &mut parent_block);
         }
 
         // Right node is the child of parent, so its depth is 1 less than parent
         right_node.header_mut().set_depth(parent_depth - 1);
-        self.write_block(&right_block);
+        self.write_extent_block(inode_ref, &mut right_block);
 
         res
     }
@@ -313,8 +446,8 @@ impl Ext4 {
         *root.extent_index_mut_at(1) = ExtentIndex::new(right.extent_at(0).start_lblock(), r_bid);
 
         // Sync to disk
-        self.write_block(&l_block);
-        self.write_block(&r_block);
+        self.write_extent_block(inode_ref, &mut l_block);
+        self.write_extent_block(inode_ref, &mut r_block);
         self.write_inode_without_csum(inode_ref);
 
         Ok(())
@@ -0,0 +1,361 @@
+//! Fast commits (Linux `fast_commit` feature).
+//!
+//! A fast commit logs the changes of an operation as a few records in the
+//! fast commit area of the journal, instead of copies of every metadata
+//! block it touched. `write` and `setattr` are fast committed when they
+//! only change one inode and map new blocks to it. Their metadata blocks
+//! stay in the running transaction, which is committed in full by the
+//! next operation that cannot be fast committed, or when the filesystem
+//! is dropped.
+//!
+//! Quota records are not logged by fast commits. With the `quota`
+//! feature, operations that change the usage of an owner, e.g. writes
+//! mapping new blocks, are committed in full, and the others, e.g.
+//! overwrites, are still fast committed. Fast commits are disabled with
+//! `bigalloc`.
+//!
+//! Fast commits belong to the transaction following the last committed
+//! one. Recovery replays the valid fast commits of that transaction after
+//! the log, by changing the filesystem as the records describe:
+//!
+//! - `AddRange` and `DelRange` map and unmap blocks of an inode.
+//! - `Create`, `Link` and `Unlink` add and remove directory entries.
+//! - `Inode` overwrites an inode, except its extent tree.
+//!
+//! Blocks mapped by the fast commits are marked as used before the replay,
+//! so that the replay does not allocate them to extent tree nodes.
+
+use super::journal::Journal;
+use super::{DataMode, Ext4};
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use core::cmp::{max, min};
+
+impl Ext4 {
+    /// Fast commit the running operation, which changed `inode` and mapped
+    /// its blocks from `from`, after the `fc_blocks` blocks of earlier fast
+    /// commits. Return the number of blocks used by the fast commits, or
+    /// `None` if the transaction must be committed in full instead.
+    pub(super) fn fast_commit(
+        &self,
+        journal: &Journal,
+        inode: InodeId,
+        from: LBlockId,
+        fc_blocks: u32,
+    ) -> Result<Option<u32>> {
+        let sb = self.read_super_block();
+        let mut jsb = self.journal_read_super_block(journal)?;
+        // Clusters shared by several blocks are not logged, replay would
+        // allocate them again
+        if !sb.has_fast_commit() || !jsb.has_fast_commit() || sb.has_bigalloc() {
+            return Ok(None);
+        }
+        // The transaction must still fit in the log when committed in full
+        if self.trans_pending_blocks() * 2 > (jsb.log_end() - jsb.first()) as usize {
+            return Ok(None);
+        }
+        let tid = jsb.sequence();
+        let inode = self.read_inode(inode);
+        let mut fc = FastCommitBuilder::new();
+        if fc_blocks == 0 {
+            fc.push(&FastCommitTag::Head { features: 0, tid });
+        }
+        // Log the new blocks as runs of contiguous blocks
+        let end = self.inode_data_blocks(&inode) as LBlockId;
+        let mut iblock = from;
+        while iblock < end {
+            let pblock = self.extent_query(&inode, iblock)?;
+            let mut len = 1;
+            // An initialized extent holds at most 32768 blocks
+            while iblock + len < end
+                && len < 32768
+                && self.extent_query(&inode, iblock + len)? == pblock + len as PBlockId
+            {
+                len += 1;
+            }
+            fc.push(&FastCommitTag::AddRange {
+                inode: inode.id,
+                extent: Extent::new(iblock, pblock, len as u16),
+            });
+            iblock += len;
+        }
+        fc.push(&FastCommitTag::Inode {
+            inode: inode.id,
+            raw: inode.inode.to_bytes().to_vec(),
+        });
+        let blocks = fc.finish(tid);
+        let area = jsb.fast_commit_area();
+        let first = area.start + fc_blocks;
+        if first + blocks.len() as u32 > area.end {
+            return Ok(None);
+        }
+
+        if self.options.data == DataMode::Ordered {
+            // Data written in place reaches the disk before the fast commit
+            self.flush_all();
+        }
+        if jsb.start() == 0 {
+            // The journal is replayed from an empty log
+            jsb.set_start(jsb.first());
+            self.journal_write_super_block(journal, &jsb)?;
+        }
+        self.journal_set_needs_recovery(true);
+        self.flush_all();
+        for (i, data) in blocks.iter().enumerate() {
+            self.journal_write_block(journal, first + i as u32, &Block::new(0, *data))?;
+        }
+        self.flush_all();
+        Ok(Some(fc_blocks + blocks.len() as u32))
+    }
+
+    /// Replay the fast commits of transaction `tid`. Return whether any
+    /// fast commit is replayed.
+    pub(super) fn fast_commit_recover(
+        &self,
+        journal: &Journal,
+        jsb: &JournalSuperBlock,
+        tid: u32,
+    ) -> Result<bool> {
+        let mut scanner = FastCommitScanner::new(tid);
+        for jblock in jsb.fast_commit_area() {
+            let block = self.journal_read_block(journal, jblock)?;
+            if !scanner.scan_block(&block.data) {
+                break;
+            }
+        }
+        let tags = scanner.into_tags();
+        if tags.is_empty() {
+            return Ok(false);
+        }
+        info!(
+            "Journal recovery: replaying {} fast commit records of transaction {}",
+            tags.len(),
+            tid
+        );
+        self.fast_commit_replay(&tags)?;
+        Ok(true)
+    }
+
+    /// Apply fast commit records to the filesystem.
+    fn fast_commit_replay(&self, tags: &[FastCommitTag]) -> Result<()> {
+        // Blocks mapped by the fast commits, and the inodes they belong to
+        let mut added = BTreeSet::new();
+        let mut inodes = BTreeSet::new();
+        for tag in tags {
+            if let FastCommitTag::AddRange { inode, extent } = tag {
+                if extent.is_unwritten() {
+                    continue;
+                }
+                for i in 0..extent.block_count() {
+                    let pblock = extent.start_pblock() + i as PBlockId;
+                    added.insert(pblock);
+                    self.mark_block(pblock, true);
+                }
+                inodes.insert(*inode);
+            }
+        }
+        for tag in tags {
+            trace!("Fast commit replay: {:?}", tag);
+            match tag {
+                FastCommitTag::AddRange { inode, extent } => {
+                    self.fc_replay_add_range(*inode, extent, &added)?
+                }
+                FastCommitTag::DelRange { inode, lblock, len } => {
+                    self.fc_replay_del_range(*inode, *lblock, *len, &added)?
+                }
+                FastCommitTag::Create(dentry) => self.fc_replay_create(dentry)?,
+                FastCommitTag::Link(dentry) => self.fc_replay_link(dentry)?,
+                FastCommitTag::Unlink(dentry) => self.fc_replay_unlink(dentry, &added)?,
+                FastCommitTag::Inode { inode, raw } => self.fc_replay_inode(*inode, raw),
+                _ => {}
+            }
+        }
+        // Free the blocks which are unmapped again by later records
+        for inode in inodes {
+            let inode = self.read_inode(inode);
+            for pblock in self.extent_all_data_blocks(&inode) {
+                added.remove(&pblock);
+            }
+        }
+        for pblock in added {
+            self.mark_block(pblock, false);
+        }
+        Ok(())
+    }
+
+    /// Map the blocks of `extent` to `inode`, replacing older mappings.
+    fn fc_replay_add_range(
+        &self,
+        inode: InodeId,
+        extent: &Extent,
+        added: &BTreeSet<PBlockId>,
+    ) -> Result<()> {
+        if extent.is_unwritten() {
+            warn!("Fast commit replay: unwritten extents are not supported");
+            return Ok(());
+        }
+        let mut inode = self.read_inode(inode);
+        if !inode.inode.extent_root().header().check_magic() {
+            // A new inode
+            inode.inode.extent_init();
+        }
+        for i in 0..extent.block_count() {
+            let iblock = extent.start_lblock() + i;
+            let pblock = extent.start_pblock() + i as PBlockId;
+            match self.extent_query(&inode, iblock) {
+                Ok(old) if old == pblock => continue,
+                Ok(_) => self.fc_unmap_block(&mut inode, iblock, added)?,
+                Err(_) => {}
+            }
+            self.extent_map(&mut inode, iblock, pblock)?;
+        }
+        self.write_inode_with_csum(&mut inode);
+        Ok(())
+    }
+
+    /// Unmap the blocks of `inode` in `len` blocks from `lblock`.
+    fn fc_replay_del_range(
+        &self,
+        inode: InodeId,
+        lblock: LBlockId,
+        len: u32,
+        added: &BTreeSet<PBlockId>,
+    ) -> Result<()> {
+        let mut inode = self.read_inode(inode);
+        if !inode.inode.extent_root().header().check_magic() {
+            return Ok(());
+        }
+        let end = lblock as u64 + len as u64;
+        for ex in self.extent_all_extents(&inode) {
+            if ex.is_unwritten() {
+                continue;
+            }
+            let start = max(ex.start_lblock() as u64, lblock as u64);
+            let ex_end = min(ex.start_lblock() as u64 + ex.block_count() as u64, end);
+            for iblock in start..ex_end {
+                self.fc_unmap_block(&mut inode, iblock as LBlockId, added)?;
+            }
+        }
+        self.write_inode_with_csum(&mut inode);
+        Ok(())
+    }
+
+    /// Add the entry of a new inode to a directory.
+    fn fc_replay_create(&self, dentry: &FastCommitDentry) -> Result<()> {
+        let mut parent = self.read_inode(dentry.parent);
+        if self.dir_find_entry(&parent, &dentry.name).is_ok() {
+            return Ok(());
+        }
+        let mut child = self.read_inode(dentry.inode);
+        if child.inode.is_dir() {
+            // Directory blocks are only written by full commits, rebuild
+            // the first block of the new directory
+            let pblock = match self.extent_query(&child, 0) {
+                Ok(pblock) => pblock,
+                Err(_) => {
+                    child.inode.set_fs_block_count(0);
+                    self.inode_append_block(&mut child)?.1
+                }
+            };
+            let mut dir_block = DirBlock::new(Block::new(pblock, [0; BLOCK_SIZE]));
+            dir_block.init();
+            dir_block.insert(b".", child.id, FileType::Directory);
+            dir_block.insert(b"..", parent.id, FileType::Directory);
+            dir_block.set_checksum(
+                &self.read_super_block().uuid(),
+                child.id,
+                child.inode.generation(),
+            );
+            self.write_block(dir_block.block());
+            self.write_inode_with_csum(&mut child);
+        }
+        // The link count is replayed by the inode record
+        self.dir_add_entry(&mut parent, &child, &dentry.name)
+    }
+
+    /// Add the entry of an existing inode to a directory.
+    fn fc_replay_link(&self, dentry: &FastCommitDentry) -> Result<()> {
+        let mut parent = self.read_inode(dentry.parent);
+        if self.dir_find_entry(&parent, &dentry.name).is_ok() {
+            return Ok(());
+        }
+        let child = self.read_inode(dentry.inode);
+        // The link count is replayed by the inode record
+        self.dir_add_entry(&mut parent, &child, &dentry.name)
+    }
+
+    /// Remove an entry from a directory, freeing the inode on its last
+    /// link.
+    fn fc_replay_unlink(
+        &self,
+        dentry: &FastCommitDentry,
+        added: &BTreeSet<PBlockId>,
+    ) -> Result<()> {
+        let mut parent = self.read_inode(dentry.parent);
+        if self.dir_find_entry(&parent, &dentry.name).ok() != Some(dentry.inode) {
+            return Ok(());
+        }
+        let mut child = self.read_inode(dentry.inode);
+        self.unlink_inode(&mut parent, &mut child, &dentry.name, false)?;
+        let links = child.inode.link_count();
+        if links == 0 || child.inode.is_dir() && links <= 1 {
+            // Blocks mapped again by the fast commits keep their data
+            for ex in self.extent_all_extents(&child) {
+                for i in 0..ex.block_count() {
+                    let pblock = ex.start_pblock() + i as PBlockId;
+                    if !ex.is_unwritten() && !added.contains(&pblock) {
+                        self.mark_block(pblock, false);
+                    }
+                }
+            }
+            for pblock in self.extent_all_tree_blocks(&child) {
+                self.mark_block(pblock, false);
+            }
+            if child.inode.xattr_block() != 0 {
+                self.mark_block(child.inode.xattr_block(), false);
+            }
+            self.mark_inode(&child.inode, child.id, false);
+            child.inode = unsafe { mem::zeroed() };
+            self.write_inode_without_csum(&child);
+        }
+        Ok(())
+    }
+
+    /// Overwrite an inode with the logged one, keeping its extent tree.
+    fn fc_replay_inode(&self, id: InodeId, raw: &[u8]) {
+        let mut inode = self.read_inode(id);
+        let mut bytes = inode.inode.to_bytes().to_vec();
+        let len = min(raw.len(), bytes.len());
+        bytes[..len].copy_from_slice(&raw[..len]);
+        let mut new = Inode::from_bytes(&bytes);
+        if new.flags().contains(InodeFlags::EXTENTS) {
+            // Blocks are replayed by the range records
+            new.copy_block_map(&inode.inode);
+            if !new.extent_root().header().check_magic() {
+                new.extent_init();
+            }
+        }
+        inode.inode = new;
+        if inode.inode.link_count() > 0 {
+            self.mark_inode(&inode.inode, id, true);
+        }
+        self.write_inode_with_csum(&mut inode);
+    }
+
+    /// Unmap a block of an inode, and free it unless it is mapped by the
+    /// fast commits.
+    fn fc_unmap_block(
+        &self,
+        inode: &mut InodeRef,
+        iblock: LBlockId,
+        added: &BTreeSet<PBlockId>,
+    ) -> Result<()> {
+        if let Some(pblock) = self.extent_unmap(inode, iblock)? {
+            if !added.contains(&pblock) {
+                self.mark_block(pblock, false);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Inode flags, the same as `FS_IOC_GETFLAGS` and `FS_IOC_SETFLAGS`.
+//!
+//! Flags enforced by the filesystem:
+//!
+//! * `IMMUTABLE` - the inode cannot be written, have its attributes
+//!   changed, or be linked, unlinked or renamed. No entries can be added
+//!   to or removed from an immutable directory.
+//! * `APPEND` - data can only be appended to the file. The file cannot be
+//!   unlinked or renamed, and no entries can be removed from an append-only
+//!   directory.
+//! * `NOATIME` - access time is not updated.
+//! * `SYNC` - all changes to the inode are flushed immediately.
+//! * `DIRSYNC` - changes to the directory are flushed immediately.
+//! * `JOURNAL_DATA` - file data is journaled whatever the `DataMode`.
+//! * `CASEFOLD` - names in the directory are compared ignoring case. It
+//!   can only be changed on an empty directory.
+//! * `VERITY` - the data of the file cannot be written or resized, and is
+//!   verified when read. It is set by `enable_verity` only.
+
+use super::Ext4;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+impl Ext4 {
+    /// Get the flags of an inode.
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    pub fn get_flags(&self, inode: InodeId) -> Result<InodeFlags> {
+        let inode_ref = self.read_inode(inode);
+        if inode_ref.inode.mode().bits() == 0 {
+            return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+        }
+        Ok(inode_ref.inode.flags())
+    }
+
+    /// Set the flags of an inode. Only `InodeFlags::USER_MODIFIABLE`
+    /// flags are changed, and flags that do not apply to the file type
+    /// are ignored.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    /// * `flags` - the new flags
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EPERM` - the inode is immutable and flags other than
+    ///   `IMMUTABLE` are changed
+    /// * `ENOTSUP` - `CASEFOLD` is changed but the filesystem does not have
+    ///   the `casefold` feature, or the directory is encrypted
+    /// * `ENOTEMPTY` - `CASEFOLD` is changed on a non-empty directory
+    pub fn set_flags(&self, inode: InodeId, flags: InodeFlags) -> Result<()> {
+        self.trans_run(|| {
+            let mut inode_ref = self.read_inode(inode);
+            if inode_ref.inode.mode().bits() == 0 {
+                return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+            }
+            let old = inode_ref.inode.flags();
+            let flags = flags.mask(inode_ref.inode.file_type()) & InodeFlags::USER_MODIFIABLE;
+            let new = (old - InodeFlags::USER_MODIFIABLE) | flags;
+            // An immutable inode can only be made mutable
+            if old.contains(InodeFlags::IMMUTABLE)
+                && new.contains(InodeFlags::IMMUTABLE)
+                && old != new
+            {
+                return_error!(ErrCode::EPERM, "Inode {} is immutable", inode);
+            }
+            if (old ^ new).contains(InodeFlags::CASEFOLD) {
+                if !self.read_super_block().has_casefold() {
+                    return_error!(ErrCode::ENOTSUP, "Filesystem does not support casefold");
+                }
+                if old.contains(InodeFlags::ENCRYPT) {
+                    return_error!(
+                        ErrCode::ENOTSUP,
+                        "Encrypted directory {} cannot be case-insensitive",
+                        inode
+                    );
+                }
+                if self.dir_list_entries(&inode_ref).len() > 2 {
+                    return_error!(ErrCode::ENOTEMPTY, "Directory {} is not empty", inode);
+                }
+            }
+            inode_ref.inode.set_flags(new);
+            self.touch_change(&mut inode_ref);
+            self.write_inode_with_csum(&mut inode_ref);
+            self.sync_if(&inode_ref, InodeFlags::SYNC);
+            Ok(())
+        })
+    }
+
+    /// Check that an inode has none of the flags in `deny`.
+    ///
+    /// # Error
+    ///
+    /// * `EPERM` - the inode has a flag in `deny`
+    pub(super) fn check_flags(inode: &InodeRef, deny: InodeFlags) -> Result<()> {
+        let flags = inode.inode.flags() & deny;
+        if !flags.is_empty() {
+            return_error!(
+                ErrCode::EPERM,
+                "Operation not permitted on inode {} with flags {:?}",
+                inode.id,
+                flags
+            );
+        }
+        Ok(())
+    }
+
+    /// Check that `child` may be removed from directory `parent`.
+    ///
+    /// # Error
+    ///
+    /// * `EPERM` - `parent` or `child` is immutable or append-only
+    pub(super) fn check_flags_unlink(parent: &InodeRef, child: &InodeRef) -> Result<()> {
+        Self::check_flags(parent, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+        Self::check_flags(child, InodeFlags::IMMUTABLE | InodeFlags::APPEND)
+    }
+
+    /// Flush all dirty blocks if the inode has any of `flags`.
+    pub(super) fn sync_if(&self, inode: &InodeRef, flags: InodeFlags) {
+        if inode.inode.flags().intersects(flags) {
+            self.flush_all();
+        }
+    }
+}
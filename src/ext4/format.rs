@@ -0,0 +1,462 @@
+//! Creating a new filesystem on a block device.
+//!
+//! Block groups are laid out without flexible block groups:
+//!
+//! | Super Block | Group Descriptors | Reserved GDT Blocks | Block Bitmap |
+//! | Inode Bitmap | Inode Table | Data Blocks |
+//!
+//! The superblock and the descriptor table are only stored in groups 0, 1
+//! and powers of 3, 5 and 7 (sparse superblocks). Every group is fully
+//! initialized. The root directory, lost+found, the journal, the resize
+//! inode and the quota files are then created by a mounted filesystem.
+//!
+//! With the resize inode, blocks are reserved after the descriptor table
+//! so that it can grow with the filesystem up to 1024 times its size, as
+//! `mke2fs` does.
+
+use super::resize::{ADDRS_PER_BLOCK, RESIZE_DIND_BLOCK, RESIZE_INO};
+use super::Ext4;
+use super::{FormatFeatures, FormatOptions, MountOptions};
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+use core::ops::Range;
+
+/// Number of blocks in a block group.
+const BLOCKS_PER_GROUP: u64 = (BLOCK_SIZE * 8) as u64;
+
+/// Number of inodes in an inode table block.
+const INODES_PER_BLOCK: u64 = (BLOCK_SIZE / SB_GOOD_INODE_SIZE) as u64;
+
+/// Number of group descriptors in a block.
+const DESCS_PER_BLOCK: u64 = (BLOCK_SIZE / SB_GOOD_DESC_SIZE) as u64;
+
+/// A last block group with less data blocks is dropped.
+pub(super) const MIN_GROUP_DATA_BLOCKS: u64 = 50;
+
+/// The journal inode.
+const JOURNAL_INO: InodeId = 8;
+
+/// The user quota inode.
+const USR_QUOTA_INO: InodeId = 3;
+
+/// The group quota inode.
+const GRP_QUOTA_INO: InodeId = 4;
+
+/// The project quota inode, the first inode after lost+found.
+const PRJ_QUOTA_INO: InodeId = 12;
+
+impl Ext4 {
+    /// Format `device` with a new, empty filesystem. The filesystem has a
+    /// root directory and a lost+found directory, and can then be opened
+    /// with `Ext4::load`.
+    ///
+    /// # Params
+    ///
+    /// * `device` - the block device to format, at least
+    ///   `options.block_count` blocks large
+    /// * `options` - the size and features of the filesystem
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - an option is invalid, or the filesystem is too small
+    /// * `ENOSPC` - no block group is large enough for the journal
+    pub fn format(device: Arc<dyn BlockDevice>, options: FormatOptions) -> Result<()> {
+        if options.label.len() > 16 {
+            return_error!(ErrCode::EINVAL, "Label {} is too long", options.label);
+        }
+        if options.reserved_percent > 50 {
+            return_error!(
+                ErrCode::EINVAL,
+                "Invalid reserved percentage {}",
+                options.reserved_percent
+            );
+        }
+        if options.inode_ratio < 1024 {
+            return_error!(
+                ErrCode::EINVAL,
+                "Invalid inode ratio {}",
+                options.inode_ratio
+            );
+        }
+        let (block_count, inodes_per_group, reserved_gdt) = Self::format_geometry(&options)?;
+        let reserved = block_count * options.reserved_percent as u64 / 100;
+        let time = options.time.sec as u32;
+        let mut sb = SuperBlock::new(block_count, inodes_per_group, reserved, options.uuid, time);
+        sb.set_volume_name(&options.label);
+        if reserved_gdt > 0 {
+            sb.set_resize_inode(reserved_gdt);
+        }
+        if options.features.contains(FormatFeatures::DIR_INDEX) {
+            sb.set_dir_index();
+        }
+        if options.features.contains(FormatFeatures::PROJECT) {
+            sb.set_project();
+        }
+        if options.features.contains(FormatFeatures::ENCRYPT) {
+            sb.set_encrypt();
+        }
+        if options.features.contains(FormatFeatures::CASEFOLD) {
+            sb.set_casefold();
+        }
+        if options.features.contains(FormatFeatures::VERITY) {
+            sb.set_verity();
+        }
+        if options.features.contains(FormatFeatures::LARGE_DIR) {
+            sb.set_large_dir();
+        }
+        let journal = if options.features.contains(FormatFeatures::JOURNAL) {
+            Some(Self::format_journal_blocks(&sb)?)
+        } else {
+            None
+        };
+        Self::format_groups(device.as_ref(), &mut sb, journal.clone());
+        // The resize inode block follows the inode table of group 0
+        let mut resize_dind = Self::format_group_inode_table(&sb, 0).end;
+        if let Some(journal) = journal.as_ref().filter(|j| j.start == resize_dind) {
+            resize_dind = journal.end;
+        }
+
+        // Create the files with a mounted filesystem
+        let ext4 = Self::load(
+            device,
+            Arc::new(FixedTime(options.time)),
+            MountOptions::default(),
+        )?;
+        ext4.trans_run(|| {
+            if let Some(journal) = journal {
+                ext4.format_journal(journal);
+            }
+            if sb.has_resize_inode() {
+                ext4.format_resize_inode(resize_dind);
+            }
+            if options.features.contains(FormatFeatures::QUOTA) {
+                ext4.format_quota(options.features.contains(FormatFeatures::PROJECT))?;
+            }
+            let root = ext4.create_root_inode()?;
+            ext4.mark_inode(&root.inode, root.id, true);
+            ext4.quota_alloc_inode(&root.inode)?;
+            ext4.mkdir(root.id, "lost+found", InodeMode::from_bits_retain(0o700))?;
+            ext4.write_backup_super();
+            Ok(())
+        })?;
+        ext4.flush_all();
+        Ok(())
+    }
+
+    /// Compute the number of blocks, of inodes per group and of reserved
+    /// GDT blocks. A last group too small for its metadata is dropped.
+    fn format_geometry(options: &FormatOptions) -> Result<(u64, u32, u32)> {
+        let mut block_count = options.block_count;
+        loop {
+            let groups = block_count.div_ceil(BLOCKS_PER_GROUP);
+            if groups == 0 {
+                return_error!(ErrCode::EINVAL, "Filesystem of 0 blocks");
+            }
+            // Fill whole inode table blocks, the inode bitmap is one block
+            let inodes = block_count * BLOCK_SIZE as u64 / options.inode_ratio as u64;
+            let max_inodes = (u32::MAX as u64 / groups / INODES_PER_BLOCK * INODES_PER_BLOCK)
+                .min(BLOCKS_PER_GROUP);
+            let inodes_per_group = inodes
+                .div_ceil(groups)
+                .next_multiple_of(INODES_PER_BLOCK)
+                .clamp(INODES_PER_BLOCK, max_inodes);
+            // Check the data area of the last group
+            let last = groups - 1;
+            let mut sb = SuperBlock::new(block_count, inodes_per_group as u32, 0, [0; 16], 0);
+            let reserved_gdt = if options.features.contains(FormatFeatures::RESIZE_INODE) {
+                Self::format_reserved_gdt_blocks(&sb)
+            } else {
+                0
+            };
+            sb.set_reserved_gdt_blocks(reserved_gdt);
+            let overhead = Self::format_group_inode_table(&sb, last as BlockGroupId).end
+                - sb.group_first_block(last as BlockGroupId);
+            if block_count - last * BLOCKS_PER_GROUP >= overhead + MIN_GROUP_DATA_BLOCKS {
+                return Ok((block_count, inodes_per_group as u32, reserved_gdt));
+            }
+            if last == 0 {
+                return_error!(
+                    ErrCode::EINVAL,
+                    "Filesystem of {} blocks is too small",
+                    block_count
+                );
+            }
+            block_count = last * BLOCKS_PER_GROUP;
+        }
+    }
+
+    /// Number of blocks to reserve after the group descriptor table of
+    /// `sb` for it to grow to 1024 times the size of the filesystem, or to
+    /// 2^32 blocks. Block numbers of the resize inode are 32-bit, so a
+    /// larger filesystem reserves none.
+    fn format_reserved_gdt_blocks(sb: &SuperBlock) -> u32 {
+        if sb.block_count() > u32::MAX as u64 {
+            return 0;
+        }
+        let max_blocks = (sb.block_count() * 1024).min(u32::MAX as u64);
+        let max_gdt = max_blocks
+            .div_ceil(BLOCKS_PER_GROUP)
+            .div_ceil(DESCS_PER_BLOCK) as u32;
+        max_gdt
+            .saturating_sub(sb.group_desc_blocks())
+            .min(ADDRS_PER_BLOCK as u32)
+    }
+
+    /// Choose the blocks of the journal, at the start of the data area of
+    /// the middle group, or of group 0 if it is too small. The size
+    /// follows `mke2fs`.
+    fn format_journal_blocks(sb: &SuperBlock) -> Result<Range<PBlockId>> {
+        let len = match sb.block_count() {
+            0..=32767 => 1024,
+            32768..=262143 => 4096,
+            262144..=524287 => 8192,
+            _ => 16384,
+        };
+        let groups = sb.block_group_count();
+        for bgid in [groups / 2, 0] {
+            let start = Self::format_group_inode_table(sb, bgid).end;
+            let end = sb.group_first_block(bgid) + sb.blocks_in_group(bgid);
+            if end - start >= len + MIN_GROUP_DATA_BLOCKS {
+                return Ok(start..start + len);
+            }
+        }
+        return_error!(ErrCode::ENOSPC, "No block group fits a journal of {}", len);
+    }
+
+    /// Write the bitmaps, inode tables, group descriptors and superblock
+    /// of every block group. Reserved inodes except the root and the
+    /// `journal` blocks are marked used.
+    fn format_groups(
+        device: &dyn BlockDevice,
+        sb: &mut SuperBlock,
+        journal: Option<Range<PBlockId>>,
+    ) {
+        let mut descs = Vec::new();
+        let mut free_blocks = 0;
+        let mut free_inodes = 0;
+        for bgid in 0..sb.block_group_count() {
+            let (bg, bitmaps) = Self::format_group(sb, bgid, journal.as_ref());
+            for block in bitmaps.iter() {
+                device.write_block(block);
+            }
+            for pblock in Self::format_group_inode_table(sb, bgid) {
+                device.write_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+            }
+            free_blocks += bg.desc.get_free_blocks_count();
+            free_inodes += bg.desc.free_inodes_count();
+            descs.push(bg.desc);
+        }
+        sb.set_free_blocks_count(free_blocks);
+        sb.set_free_inodes_count(free_inodes);
+
+        // The primary superblock and group descriptor table
+        let mut block = Block::new(0, [0; BLOCK_SIZE]);
+        sb.set_checksum();
+        block.write_offset_as(BASE_OFFSET, sb);
+        device.write_block(&block);
+        for (i, descs) in descs.chunks(DESCS_PER_BLOCK as usize).enumerate() {
+            let mut block = Block::new(1 + i as PBlockId, [0; BLOCK_SIZE]);
+            for (j, desc) in descs.iter().enumerate() {
+                block.write_offset_as(j * SB_GOOD_DESC_SIZE, desc);
+            }
+            device.write_block(&block);
+        }
+    }
+
+    /// Lay out block group `bgid` of `sb`, with its bitmaps and inode
+    /// table after the superblock backup. Return the descriptor and the
+    /// block and inode bitmaps. In group 0, reserved inodes except the
+    /// root are marked used. `journal` blocks in the group are marked
+    /// used. The inode table is not written.
+    pub(super) fn format_group(
+        sb: &SuperBlock,
+        bgid: BlockGroupId,
+        journal: Option<&Range<PBlockId>>,
+    ) -> (BlockGroupRef, [Block; 2]) {
+        let uuid = sb.uuid();
+        let inodes_per_group = sb.inodes_per_group() as usize;
+        let first = sb.group_first_block(bgid);
+        let blocks = sb.blocks_in_group(bgid) as usize;
+        let inode_table = Self::format_group_inode_table(sb, bgid);
+        let block_bitmap = inode_table.start - 2;
+        let mut desc = BlockGroupDesc::new(block_bitmap, block_bitmap + 1, inode_table.start);
+
+        // Block bitmap, blocks beyond the group are padded as used
+        let mut block_bitmap = Block::new(block_bitmap, [0; BLOCK_SIZE]);
+        let mut bitmap = Bitmap::new(&mut block_bitmap.data, 8 * BLOCK_SIZE);
+        let mut used = (inode_table.end - first) as usize;
+        for bit in 0..used {
+            bitmap.set_bit(bit);
+        }
+        if let Some(journal) = journal {
+            if (first..first + blocks as PBlockId).contains(&journal.start) {
+                for pblock in journal.clone() {
+                    bitmap.set_bit((pblock - first) as usize);
+                }
+                used += journal.clone().count();
+            }
+        }
+        for bit in blocks..8 * BLOCK_SIZE {
+            bitmap.set_bit(bit);
+        }
+        desc.set_block_bitmap_csum(&uuid, &bitmap);
+        desc.set_free_blocks_count((blocks - used) as u64);
+
+        // Inode bitmap, padded as used after the inodes of the group
+        let mut inode_bitmap = Block::new(block_bitmap.id + 1, [0; BLOCK_SIZE]);
+        inode_bitmap.data[inodes_per_group / 8..].fill(0xFF);
+        let mut bitmap = Bitmap::new(&mut inode_bitmap.data, inodes_per_group);
+        let mut used = 0;
+        let reserved = if bgid == 0 { sb.first_inode() - 1 } else { 0 };
+        for id in 1..=reserved {
+            if id != EXT4_ROOT_INO {
+                bitmap.set_bit(id as usize - 1);
+                used += 1;
+            }
+        }
+        desc.set_inode_bitmap_csum(&uuid, &bitmap);
+        desc.set_free_inodes_count(inodes_per_group as u32 - used);
+        desc.set_itable_unused(inodes_per_group as u32 - reserved);
+        desc.set_flags(BlockGroupFlags::ITABLE_ZEROED);
+
+        let mut bg = BlockGroupRef::new(bgid, desc);
+        bg.set_checksum(&uuid);
+        (bg, [block_bitmap, inode_bitmap])
+    }
+
+    /// The inode table blocks of a block group laid out by `format_group`,
+    /// followed by the data blocks.
+    pub(super) fn format_group_inode_table(sb: &SuperBlock, bgid: BlockGroupId) -> Range<PBlockId> {
+        let mut start = sb.group_first_block(bgid) + 2;
+        if sb.group_has_super(bgid) {
+            start += 1 + (sb.group_desc_blocks() + sb.reserved_gdt_blocks()) as PBlockId;
+        }
+        start..start + sb.inodes_per_group() as PBlockId / INODES_PER_BLOCK
+    }
+
+    /// Create the journal inode on `blocks`, which are already marked
+    /// used, with an empty log.
+    fn format_journal(&self, blocks: Range<PBlockId>) {
+        let mut sb = self.read_super_block();
+        let len = (blocks.end - blocks.start) as u32;
+        for pblock in blocks.clone() {
+            self.write_data_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+        }
+        let mut block = Block::new(blocks.start, [0; BLOCK_SIZE]);
+        let mut jsb = JournalSuperBlock::new(len, sb.uuid());
+        jsb.set_checksum();
+        block.write_offset_as(0, &jsb);
+        self.write_block(&block);
+
+        let mut inode = Inode::default();
+        inode.set_mode(InodeMode::FILE | InodeMode::USER_READ | InodeMode::USER_WRITE);
+        inode.extent_init();
+        let extent = Extent::new(0, blocks.start, len as u16);
+        let _ = inode.extent_root_mut().insert_extent(&extent, 0);
+        inode.set_size(len as u64 * BLOCK_SIZE as u64);
+        inode.set_fs_block_count(len as u64);
+        inode.set_link_count(1);
+        let now = self.now();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_crtime(now);
+        let mut inode = InodeRef::new(JOURNAL_INO, inode);
+        self.write_inode_with_csum(&mut inode);
+
+        let size = inode.inode.size();
+        sb.set_journal_inode(JOURNAL_INO, inode.inode.block_pointers(), size);
+        self.write_super_block(&sb);
+    }
+
+    /// Create the resize inode with its double indirect block at `dind`,
+    /// which is free. The inode lists the reserved GDT blocks in `dind`,
+    /// and each reserved block lists its backups in the groups with a
+    /// superblock.
+    fn format_resize_inode(&self, dind: PBlockId) {
+        let sb = self.read_super_block();
+        self.mark_block(dind, true);
+        let backup_groups: Vec<BlockGroupId> = (1..sb.block_group_count())
+            .filter(|&bgid| sb.group_has_super(bgid))
+            .take(ADDRS_PER_BLOCK)
+            .collect();
+        let mut dind_block = Block::new(dind, [0; BLOCK_SIZE]);
+        let mut blocks = 1;
+        for gdt in sb.group_desc_blocks()..sb.group_desc_blocks() + sb.reserved_gdt_blocks() {
+            let pblock = sb.first_data_block() as PBlockId + 1 + gdt as PBlockId;
+            let offset = gdt as usize % ADDRS_PER_BLOCK * 4;
+            dind_block.write_offset(offset, &(pblock as u32).to_le_bytes());
+            let mut block = Block::new(pblock, [0; BLOCK_SIZE]);
+            for (i, &bgid) in backup_groups.iter().enumerate() {
+                let backup =
+                    pblock + sb.group_first_block(bgid) - sb.first_data_block() as PBlockId;
+                block.write_offset(i * 4, &(backup as u32).to_le_bytes());
+            }
+            self.write_block(&block);
+            blocks += 1 + backup_groups.len() as u64;
+        }
+        self.write_block(&dind_block);
+
+        let mut inode = Inode::default();
+        inode.set_mode(InodeMode::FILE | InodeMode::USER_READ | InodeMode::USER_WRITE);
+        let mut ptrs = [0; 15];
+        ptrs[RESIZE_DIND_BLOCK] = dind as u32;
+        inode.set_block_pointers(&ptrs);
+        // The size covers the double indirect block, as with mke2fs
+        let addrs = ADDRS_PER_BLOCK as u64;
+        inode.set_size((addrs * addrs + addrs + 12) * BLOCK_SIZE as u64);
+        inode.set_fs_block_count(blocks);
+        inode.set_link_count(1);
+        let now = self.now();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_crtime(now);
+        let mut inode = InodeRef::new(RESIZE_INO, inode);
+        self.write_inode_with_csum(&mut inode);
+    }
+
+    /// Enable quota and create the user and group quota files, and the
+    /// project quota file if `project` is set.
+    fn format_quota(&self, project: bool) -> Result<()> {
+        let mut sb = self.read_super_block();
+        let prj = if project { PRJ_QUOTA_INO } else { 0 };
+        sb.set_quota_inums(USR_QUOTA_INO, GRP_QUOTA_INO, prj);
+        self.write_super_block(&sb);
+        self.quota_create_file(QuotaType::User, USR_QUOTA_INO)?;
+        self.quota_create_file(QuotaType::Group, GRP_QUOTA_INO)?;
+        if project {
+            // Not a reserved inode, mark it before lost+found is created
+            let inode = Inode::default();
+            self.mark_inode(&inode, PRJ_QUOTA_INO, true);
+            self.quota_create_file(QuotaType::Project, PRJ_QUOTA_INO)?;
+        }
+        Ok(())
+    }
+
+    /// Copy the superblock and the group descriptor table to the block
+    /// groups that keep a backup of them.
+    pub(super) fn write_backup_super(&self) {
+        let sb = self.read_super_block();
+        let gdt: Vec<Block> = (0..sb.group_desc_blocks() as PBlockId)
+            .map(|i| self.read_block(sb.first_data_block() as PBlockId + 1 + i))
+            .collect();
+        for bgid in 1..sb.block_group_count() {
+            if !sb.group_has_super(bgid) {
+                continue;
+            }
+            let first = sb.group_first_block(bgid);
+            let mut backup = sb;
+            backup.set_block_group_index(bgid);
+            backup.set_checksum();
+            let mut block = Block::new(first, [0; BLOCK_SIZE]);
+            block.write_offset_as(0, &backup);
+            self.write_block(&block);
+            for (i, gdt_block) in gdt.iter().enumerate() {
+                self.write_block(&Block::new(first + 1 + i as PBlockId, gdt_block.data));
+            }
+        }
+    }
+}
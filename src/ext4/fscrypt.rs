@@ -0,0 +1,382 @@
+//! Encrypted directories, compatible with fscrypt policy v2.
+//!
+//! Master keys are provided with `add_encryption_key` and kept in memory
+//! until removed. An empty directory is given a policy, and the files and
+//! directories created in it inherit the policy with a nonce of their own.
+//!
+//! A directory whose master key is added is unlocked: names are encrypted
+//! in its blocks and decrypted when listed, and the contents of its files
+//! are encrypted on write and decrypted on read. A locked directory lists
+//! its entries by their no-key names, which can be looked up and removed,
+//! but nothing can be added to it and its files cannot be read or written.
+
+use super::Ext4;
+use crate::ext4_defs::*;
+use crate::format_error;
+use crate::prelude::*;
+use crate::return_error;
+
+/// The master keys added to the filesystem.
+#[derive(Default)]
+pub(super) struct Keyring {
+    /// The keys by their identifiers.
+    keys: BTreeMap<[u8; FSCRYPT_KEY_IDENTIFIER_SIZE], MasterKey>,
+    /// Number of nonces derived, to make each seed unique.
+    nonces: u64,
+}
+
+impl Ext4 {
+    /// Add a master key, unlocking the directories and files encrypted
+    /// with it.
+    ///
+    /// # Params
+    ///
+    /// * `key` - the secret of the master key
+    ///
+    /// # Return
+    ///
+    /// `Ok(identifier)` - the identifier of the key, to set in a policy
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the key is not 16 to 64 bytes long
+    pub fn add_encryption_key(&self, key: &[u8]) -> Result<[u8; FSCRYPT_KEY_IDENTIFIER_SIZE]> {
+        if !(FSCRYPT_MIN_KEY_SIZE..=FSCRYPT_MAX_KEY_SIZE).contains(&key.len()) {
+            return_error!(ErrCode::EINVAL, "Invalid key size {}", key.len());
+        }
+        let key = MasterKey::new(key);
+        let identifier = key.identifier();
+        self.keyring.lock().keys.insert(identifier, key);
+        Ok(identifier)
+    }
+
+    /// Remove a master key, locking the directories and files encrypted
+    /// with it.
+    ///
+    /// # Params
+    ///
+    /// * `identifier` - the identifier of the key
+    ///
+    /// # Error
+    ///
+    /// * `ENOKEY` - the key is not added
+    pub fn remove_encryption_key(
+        &self,
+        identifier: &[u8; FSCRYPT_KEY_IDENTIFIER_SIZE],
+    ) -> Result<()> {
+        if self.keyring.lock().keys.remove(identifier).is_none() {
+            return_error!(ErrCode::ENOKEY, "Key {:02x?} is not added", identifier);
+        }
+        Ok(())
+    }
+
+    /// Set the encryption policy of an empty directory. Setting the policy
+    /// the directory already has does nothing.
+    ///
+    /// # Params
+    ///
+    /// * `dir` - the inode of the directory
+    /// * `policy` - the policy, whose master key must be added
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - the filesystem does not have the `encrypt` feature
+    /// * `ENOTDIR` - `dir` is not a directory
+    /// * `EINVAL` - the modes or flags of the policy are not supported
+    /// * `ENOTSUP` - `dir` is case-insensitive
+    /// * `EEXIST` - `dir` has a different policy
+    /// * `EPERM` - `dir` is immutable or append-only
+    /// * `ENOTEMPTY` - `dir` is not empty
+    /// * `ENOKEY` - the master key of the policy is not added
+    /// * `ENOSPC` - the inode has no space left for the encryption context
+    pub fn set_encryption_policy(&self, dir: InodeId, policy: &EncryptionPolicy) -> Result<()> {
+        self.trans_run(|| {
+            if !self.read_super_block().has_encrypt() {
+                return_error!(ErrCode::ENOTSUP, "Filesystem does not support encryption");
+            }
+            let mut dir = self.read_inode(dir);
+            if !dir.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", dir.id);
+            }
+            if dir.inode.flags().contains(InodeFlags::CASEFOLD) {
+                return_error!(
+                    ErrCode::ENOTSUP,
+                    "Case-insensitive directory {} cannot be encrypted",
+                    dir.id
+                );
+            }
+            if !policy.is_supported() {
+                return_error!(
+                    ErrCode::EINVAL,
+                    "Unsupported encryption policy {:?}",
+                    policy
+                );
+            }
+            if let Some(context) = self.fscrypt_context(&dir)? {
+                if context.policy() == *policy {
+                    return Ok(());
+                }
+                return_error!(ErrCode::EEXIST, "Inode {} has another policy", dir.id);
+            }
+            Self::check_flags(&dir, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            if self.dir_list_entries(&dir).len() > 2 {
+                return_error!(ErrCode::ENOTEMPTY, "Directory {} is not empty", dir.id);
+            }
+            self.fscrypt_set_policy(&mut dir, policy)?;
+            self.touch_change(&mut dir);
+            self.write_inode_with_csum(&mut dir);
+            Ok(())
+        })
+    }
+
+    /// Get the encryption policy of a directory or file.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the directory or file
+    ///
+    /// # Error
+    ///
+    /// * `ENODATA` - `inode` is not encrypted
+    /// * `EIO` - the encryption context is missing or corrupted
+    pub fn get_encryption_policy(&self, inode: InodeId) -> Result<EncryptionPolicy> {
+        let inode_ref = self.read_inode(inode);
+        match self.fscrypt_context(&inode_ref)? {
+            Some(context) => Ok(context.policy()),
+            None => Err(format_error!(
+                ErrCode::ENODATA,
+                "Inode {} is not encrypted",
+                inode
+            )),
+        }
+    }
+
+    /// Read the encryption context of an inode, `None` if the inode is
+    /// not encrypted.
+    ///
+    /// # Error
+    ///
+    /// * `EIO` - the inode is encrypted but the context is missing
+    /// * `ENOTSUP` - the context is not a v2 context
+    pub(super) fn fscrypt_context(&self, inode: &InodeRef) -> Result<Option<EncryptionContext>> {
+        if !inode.inode.flags().contains(InodeFlags::ENCRYPT) {
+            return Ok(None);
+        }
+        let ibody = XattrIbody::new(self.read_inode_xattr_area(inode));
+        let mut value = ibody.get(XATTR_NAME_ENCRYPTION_CONTEXT).map(<[u8]>::to_vec);
+        let xattr_block_id = inode.inode.xattr_block();
+        if value.is_none() && xattr_block_id != 0 {
+            let xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
+            value = xattr_block
+                .get(XATTR_NAME_ENCRYPTION_CONTEXT)
+                .map(<[u8]>::to_vec);
+        }
+        let Some(value) = value else {
+            return_error!(ErrCode::EIO, "Inode {} has no encryption context", inode.id);
+        };
+        match EncryptionContext::from_xattr(&value) {
+            Some(context) => Ok(Some(context)),
+            None => Err(format_error!(
+                ErrCode::ENOTSUP,
+                "Unsupported encryption context of inode {}",
+                inode.id
+            )),
+        }
+    }
+
+    /// Get the contents key of a file, `None` if it is not encrypted.
+    ///
+    /// # Error
+    ///
+    /// * `ENOKEY` - the master key of the file is not added
+    /// * `ENOTSUP` - the policy of the file is not supported
+    pub(super) fn fscrypt_contents_key(&self, file: &InodeRef) -> Result<Option<ContentsKey>> {
+        let Some(context) = self.fscrypt_context(file)? else {
+            return Ok(None);
+        };
+        self.fscrypt_with_key(file, &context, |key| key.contents_key(&context))
+            .map(Some)
+    }
+
+    /// The name under which an entry named `name` by the user is stored
+    /// in `dir`. In an unlocked directory it is the encrypted name, and
+    /// in a locked one the name of the entry whose no-key name is `name`.
+    ///
+    /// # Params
+    ///
+    /// * `dir` - the directory
+    /// * `name` - the name given by the user
+    /// * `add` - whether the entry is to be added
+    ///
+    /// # Error
+    ///
+    /// * `ENOKEY` - `dir` is locked and `add` is set
+    /// * `ENOENT` - `dir` is locked and no entry has the no-key name
+    pub(super) fn fscrypt_disk_name(
+        &self,
+        dir: &InodeRef,
+        name: &str,
+        add: bool,
+    ) -> Result<Vec<u8>> {
+        if name == "." || name == ".." {
+            return Ok(name.as_bytes().to_vec());
+        }
+        let Some(context) = self.fscrypt_context(dir)? else {
+            return Ok(name.as_bytes().to_vec());
+        };
+        match self.fscrypt_with_key(dir, &context, |key| key.filenames_key(&context)) {
+            Ok(key) => Ok(key.encrypt(name.as_bytes())),
+            Err(err) if err.code() == ErrCode::ENOKEY && !add => self
+                .dir_list_entries(dir)
+                .into_iter()
+                .map(|entry| entry.name_bytes().to_vec())
+                .find(|disk_name| nokey_name_matches(name, disk_name))
+                .ok_or(format_error!(
+                    ErrCode::ENOENT,
+                    "Directory entry not found: dir {}, name {}",
+                    dir.id,
+                    name
+                )),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Give the entries listed from `dir` the names seen by the user:
+    /// the decrypted names if `dir` is unlocked, the no-key names if not.
+    pub(super) fn fscrypt_user_entries(
+        &self,
+        dir: &InodeRef,
+        entries: &mut [DirEntry],
+    ) -> Result<()> {
+        let Some(context) = self.fscrypt_context(dir)? else {
+            return Ok(());
+        };
+        let key = match self.fscrypt_with_key(dir, &context, |key| key.filenames_key(&context)) {
+            Ok(key) => Some(key),
+            Err(err) if err.code() == ErrCode::ENOKEY => None,
+            Err(err) => return Err(err),
+        };
+        for entry in entries.iter_mut() {
+            let disk_name = entry.name_bytes();
+            if disk_name == b"." || disk_name == b".." {
+                continue;
+            }
+            let name = key
+                .as_ref()
+                .and_then(|key| key.decrypt(disk_name))
+                .unwrap_or_else(|| nokey_name(disk_name).into_bytes());
+            entry.set_name(&name);
+        }
+        Ok(())
+    }
+
+    /// Apply the policy of `parent` to a new inode. Regular files,
+    /// directories and symlinks are encrypted. The inode is modified
+    /// but not written.
+    ///
+    /// # Error
+    ///
+    /// * `ENOKEY` - `parent` is locked
+    pub(super) fn fscrypt_inherit(&self, parent: &InodeRef, child: &mut InodeRef) -> Result<()> {
+        let Some(context) = self.fscrypt_context(parent)? else {
+            return Ok(());
+        };
+        if !matches!(
+            child.inode.file_type(),
+            FileType::RegularFile | FileType::Directory | FileType::SymLink
+        ) {
+            return Ok(());
+        }
+        self.fscrypt_set_policy(child, &context.policy())
+    }
+
+    /// Check that `child` may be linked into `new_parent`: an entry of an
+    /// encrypted directory must have the policy of the directory.
+    ///
+    /// # Error
+    ///
+    /// * `EXDEV` - the policies differ
+    pub(super) fn fscrypt_check_move(&self, new_parent: &InodeRef, child: &InodeRef) -> Result<()> {
+        let Some(context) = self.fscrypt_context(new_parent)? else {
+            return Ok(());
+        };
+        let child_policy = self.fscrypt_context(child)?.map(|c| c.policy());
+        if child_policy != Some(context.policy()) {
+            return_error!(
+                ErrCode::EXDEV,
+                "Inode {} is outside the encryption policy of inode {}",
+                child.id,
+                new_parent.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Run `f` with the master key of an encryption context.
+    fn fscrypt_with_key<T>(
+        &self,
+        inode: &InodeRef,
+        context: &EncryptionContext,
+        f: impl FnOnce(&MasterKey) -> T,
+    ) -> Result<T> {
+        let policy = context.policy();
+        if !policy.is_supported() || !context.has_block_data_units() {
+            return_error!(
+                ErrCode::ENOTSUP,
+                "Unsupported encryption policy of inode {}",
+                inode.id
+            );
+        }
+        match self.keyring.lock().keys.get(&policy.master_key_identifier) {
+            Some(key) => Ok(f(key)),
+            None => Err(format_error!(
+                ErrCode::ENOKEY,
+                "Key of inode {} is not added",
+                inode.id
+            )),
+        }
+    }
+
+    /// Give an inode a new encryption context with `policy` and set its
+    /// `ENCRYPT` flag. The inode is modified but not written.
+    fn fscrypt_set_policy(&self, inode: &mut InodeRef, policy: &EncryptionPolicy) -> Result<()> {
+        // Without a source of randomness, the nonce is derived from a seed
+        // that is unique to the inode and to the moment
+        let now = self.now();
+        let mut seed = Vec::new();
+        seed.extend_from_slice(&self.read_super_block().uuid());
+        seed.extend_from_slice(&inode.id.to_le_bytes());
+        seed.extend_from_slice(&inode.inode.generation().to_le_bytes());
+        seed.extend_from_slice(&now.sec.to_le_bytes());
+        seed.extend_from_slice(&now.nsec.to_le_bytes());
+        let nonce = {
+            let mut keyring = self.keyring.lock();
+            keyring.nonces += 1;
+            seed.extend_from_slice(&keyring.nonces.to_le_bytes());
+            let Some(key) = keyring.keys.get(&policy.master_key_identifier) else {
+                return_error!(
+                    ErrCode::ENOKEY,
+                    "Key {:02x?} is not added",
+                    policy.master_key_identifier
+                );
+            };
+            key.nonce(&seed)
+        };
+        let context = EncryptionContext::new(policy, nonce);
+
+        // The context is kept in the inode, like the kernel does
+        let mut ibody = XattrIbody::new(self.read_inode_xattr_area(inode));
+        if !ibody.set(XATTR_NAME_ENCRYPTION_CONTEXT, context.to_bytes()) {
+            return_error!(
+                ErrCode::ENOSPC,
+                "Inode {} does not have space for the encryption context",
+                inode.id
+            );
+        }
+        self.write_inode_xattr_area(inode, ibody.area());
+        inode
+            .inode
+            .set_flags(inode.inode.flags() | InodeFlags::ENCRYPT);
+        Ok(())
+    }
+}
@@ -1,11 +1,830 @@
-use super::Ext4;
+//! Journal (JBD2) transactions and recovery.
+//!
+//! Every operation that modifies metadata opens a handle with
+//! `trans_start`. Metadata blocks written while a handle is open are kept
+//! in the running transaction instead of being written in place, and
+//! reads see the transaction's copies. When the last handle is dropped,
+//! the transaction is committed:
+//!
+//! 1. Revoke blocks record the metadata blocks freed by the transaction,
+//!    so that older copies in the log are not replayed over their new
+//!    contents. The blocks are written to the log, after descriptor blocks
+//!    giving their home locations.
+//! 2. `jsb.start` is set and the filesystem is marked as needing
+//!    recovery.
+//! 3. The commit block is written. From now on, the transaction is
+//!    replayed after a crash.
+//! 4. The blocks are written to their home locations (checkpoint).
+//! 5. The log is emptied and the recovery flag is cleared.
+//!
+//! File data is handled according to the `DataMode` mount option. In
+//! ordered mode, data is written in place and flushed before the log. In
+//! journal mode, or for inodes with the `JOURNAL_DATA` flag, data blocks
+//! are written to the transaction like metadata. Writes larger than the
+//! log are split into several transactions, and blocks newly mapped by
+//! defragmentation or fs-verity are written in place, since nothing
+//! references them before the transaction is committed.
+//!
+//! If the journal uses checksums (version 3 is enabled on filesystems with
+//! metadata checksums, as Linux does), descriptor, revoke and commit
+//! blocks are checksummed, and so is every logged block in its tag.
+//!
+//! If the filesystem was not unmounted cleanly, the journal may contain
+//! committed transactions whose blocks have not been written to their home
+//! locations. Recovery scans the log from `jsb.start`, collects every
+//! committed transaction and the blocks revoked by them, then writes the
+//! logged blocks home, skipping blocks revoked by the same or a later
+//! transaction. The log ends at the first block with a bad checksum, and
+//! logged blocks with a bad checksum are not replayed.
+//!
+//! Operations are all-or-nothing. Before a handle writes or revokes a
+//! metadata block for the first time, the block is saved: its contents on
+//! the device if there is no journal, or its copy in the transaction. If
+//! the operation fails, the blocks saved by its handle are restored. Data
+//! blocks are written in place after everything that can fail. An
+//! operation also fails if the transaction no longer fits in the log, so
+//! that every transaction can be committed.
+//!
+//! With the `fast_commit` feature, some operations are logged as fast
+//! commits instead, see the `fast_commit` module. Their metadata blocks
+//! are kept in the transaction until it is committed in full.
+//!
+//! The journal is either stored in an inode of the filesystem, or on an
+//! external journal device given at mount time. An external device holds
+//! an ext4 superblock marking it as a journal, whose uuid must match the
+//! `journal_uuid` of the filesystem, and the journal superblock in the
+//! following block. Log blocks are numbered by device block.
+
+use super::{DataMode, Ext4};
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+use crate::sync::SpinMutex;
+
+/// The running transaction.
+#[derive(Default)]
+pub(super) struct Transaction {
+    /// Number of open handles.
+    handles: usize,
+    /// Blocks saved by each open handle, restored if its operation fails.
+    undo: Vec<BTreeMap<PBlockId, SavedBlock>>,
+    /// Metadata blocks written by the transaction, by home location.
+    blocks: BTreeMap<PBlockId, Block>,
+    /// Metadata blocks freed by the transaction.
+    revoked: BTreeSet<PBlockId>,
+    /// Whether the running operation wrote metadata.
+    dirty: bool,
+    /// The inode changed by the running operation and the first block it
+    /// mapped, if the operation can be fast committed.
+    fast_commit: Option<(InodeId, LBlockId)>,
+    /// Whether the running operation changed blocks that fast commits do
+    /// not log, so that it is committed in full.
+    fc_ineligible: bool,
+    /// Number of blocks used by the fast commits of the transaction.
+    fc_blocks: u32,
+}
+
+/// A metadata block as it was before an operation changed it.
+enum SavedBlock {
+    /// The contents on the device, if there is no journal.
+    Device(Block),
+    /// The copy in the running transaction, and whether it was revoked.
+    Transaction(Option<Block>, bool),
+}
+
+/// A handle on the running transaction, the transaction is committed when
+/// the last handle is dropped.
+pub(super) struct JournalHandle<'a> {
+    ext4: &'a Ext4,
+}
+
+impl Drop for JournalHandle<'_> {
+    fn drop(&mut self) {
+        self.ext4.trans_stop();
+    }
+}
+
+/// Where the journal is stored.
+pub(super) enum Journal {
+    /// In an inode of the filesystem, reloaded when its blocks move.
+    Inode(SpinMutex<InodeRef>),
+    /// On an external journal device.
+    Device(Arc<dyn BlockDevice>),
+}
+
+impl Journal {
+    /// The journal block holding the journal superblock.
+    fn super_block_id(&self) -> u32 {
+        match self {
+            Self::Inode(_) => 0,
+            // Follows the block holding the ext4 superblock
+            Self::Device(_) => ((BASE_OFFSET + BLOCK_SIZE) / BLOCK_SIZE) as u32,
+        }
+    }
+}
+
+/// A committed transaction found in the log.
+struct JournalTransaction {
+    /// Transaction sequence number.
+    sequence: u32,
+    /// Logged blocks, as the journal block holding the data and the tag
+    /// describing it.
+    blocks: Vec<(u32, JournalBlockTag)>,
+}
 
 impl Ext4 {
-    /// start transaction
-    #[allow(unused)]
-    pub(super) fn trans_start(&self) {}
+    /// Find the journal of the filesystem, in its inode or on the external
+    /// `journal_device`. Return `None` if the filesystem has no journal.
+    ///
+    /// # Error
+    ///
+    /// * `ENODEV` - the journal is external but no journal device is given
+    /// * `EINVAL` - the journal device is not the journal of the filesystem
+    pub(super) fn journal_open(
+        &self,
+        journal_device: Option<Arc<dyn BlockDevice>>,
+    ) -> Result<Option<Journal>> {
+        let sb = self.read_super_block();
+        if !sb.has_journal() {
+            if journal_device.is_some() {
+                return_error!(ErrCode::EINVAL, "Filesystem has no journal");
+            }
+            return Ok(None);
+        }
+        match (sb.journal_inode_number(), journal_device) {
+            (0, None) => {
+                return_error!(ErrCode::ENODEV, "External journal device is required");
+            }
+            (0, Some(device)) => {
+                let block = device.read_block(0);
+                let jdev_sb: SuperBlock = block.read_offset_as(BASE_OFFSET);
+                if !jdev_sb.check_magic() || !jdev_sb.is_journal_dev() {
+                    return_error!(ErrCode::EINVAL, "Not an external journal device");
+                }
+                if jdev_sb.uuid() != sb.journal_uuid() {
+                    return_error!(
+                        ErrCode::EINVAL,
+                        "Journal device uuid does not match the filesystem"
+                    );
+                }
+                Ok(Some(Journal::Device(device)))
+            }
+            (inode, None) => Ok(Some(Journal::Inode(SpinMutex::new(self.read_inode(inode))))),
+            (_, Some(_)) => {
+                return_error!(ErrCode::EINVAL, "Filesystem has an internal journal");
+            }
+        }
+    }
+
+    /// Prepare the journal for transactions.
+    pub(super) fn journal_load(&self, journal: &Journal) -> Result<()> {
+        let sb = self.read_super_block();
+        let old = self.journal_read_super_block(journal)?;
+        let mut jsb = old;
+        jsb.set_feature_incompat(JournalSuperBlock::FEATURE_INCOMPAT_REVOKE);
+        // Tags must hold every block number of the filesystem
+        if sb.block_count() > u32::MAX as u64 {
+            jsb.set_feature_incompat(JournalSuperBlock::FEATURE_INCOMPAT_64BIT);
+        }
+        if sb.has_metadata_csum() {
+            jsb.set_csum_v3();
+        }
+        if sb.has_fast_commit() {
+            jsb.set_feature_incompat(JournalSuperBlock::FEATURE_INCOMPAT_FAST_COMMIT);
+        }
+        if jsb.to_bytes() != old.to_bytes() {
+            self.journal_write_super_block(journal, &jsb)?;
+        }
+        Ok(())
+    }
+
+    /// Run an operation in a handle on the running transaction. If it
+    /// fails, the metadata blocks it changed are restored, so that it has
+    /// no effect.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - the transaction with the operation is larger than the
+    ///   log, as well as the errors of `op`
+    pub(super) fn trans_run<T>(&self, op: impl FnOnce() -> Result<T>) -> Result<T> {
+        let _handle = self.trans_start();
+        let res = op().and_then(|res| self.trans_check_len().map(|_| res));
+        if res.is_err() {
+            self.trans_rollback();
+        }
+        res
+    }
+
+    /// Check that the running transaction fits in the log when the last
+    /// handle on it is closed.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - the transaction is larger than the log
+    fn trans_check_len(&self) -> Result<()> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+        let (blocks, revoked) = {
+            let trans = self.transaction.lock();
+            if trans.handles > 1 || !trans.dirty {
+                return Ok(());
+            }
+            (trans.blocks.len(), trans.revoked.len())
+        };
+        let jsb = self.journal_read_super_block(journal)?;
+        let len = jsb.transaction_len(blocks, revoked);
+        if len > (jsb.log_end() - jsb.first()) as usize {
+            return_error!(
+                ErrCode::ENOSPC,
+                "Transaction of {} blocks is larger than the journal",
+                len
+            );
+        }
+        Ok(())
+    }
+
+    /// Open a handle on the running transaction, starting a new one if no
+    /// transaction is running. Metadata blocks are written to the
+    /// transaction until the last handle is dropped.
+    fn trans_start(&self) -> JournalHandle<'_> {
+        let mut trans = self.transaction.lock();
+        if trans.handles == 0 {
+            // A new operation
+            trans.dirty = false;
+            trans.fast_commit = None;
+            trans.fc_ineligible = false;
+        }
+        trans.handles += 1;
+        trans.undo.push(BTreeMap::new());
+        JournalHandle { ext4: self }
+    }
+
+    /// Close a handle on the running transaction. If it was the last one,
+    /// fast commit the operation if possible, otherwise commit the
+    /// transaction.
+    fn trans_stop(&self) {
+        let (fast_commit, fc_blocks) = {
+            let mut trans = self.transaction.lock();
+            trans.handles -= 1;
+            // The blocks saved by an operation called by another one are
+            // restored if the caller fails
+            let saved = trans.undo.pop().unwrap();
+            if let Some(undo) = trans.undo.last_mut() {
+                for (block_id, block) in saved {
+                    undo.entry(block_id).or_insert(block);
+                }
+            }
+            if trans.handles > 0 || !trans.dirty {
+                return;
+            }
+            let fast_commit = trans.fast_commit.take();
+            (
+                fast_commit.filter(|_| !trans.fc_ineligible),
+                trans.fc_blocks,
+            )
+        };
+        let journal = self
+            .journal
+            .as_ref()
+            .expect("No journal for the transaction");
+        if let Some((inode, from)) = fast_commit {
+            match self.fast_commit(journal, inode, from, fc_blocks) {
+                Ok(Some(fc_blocks)) => {
+                    self.transaction.lock().fc_blocks = fc_blocks;
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Fast commit failed: {:?}, committing the transaction", e),
+            }
+        }
+        self.trans_commit();
+    }
+
+    /// Restore the metadata blocks saved by the innermost handle.
+    fn trans_rollback(&self) {
+        let mut trans = self.transaction.lock();
+        let saved = mem::take(trans.undo.last_mut().unwrap());
+        for (block_id, block) in saved {
+            match block {
+                SavedBlock::Device(block) => self.device_write_block(&block),
+                SavedBlock::Transaction(copy, revoked) => {
+                    match copy {
+                        Some(copy) => trans.blocks.insert(block_id, copy),
+                        None => trans.blocks.remove(&block_id),
+                    };
+                    if revoked {
+                        trans.revoked.insert(block_id);
+                    } else {
+                        trans.revoked.remove(&block_id);
+                    }
+                }
+            }
+        }
+        if trans.handles == 1 {
+            // The operation has no effect
+            trans.dirty = false;
+            trans.fast_commit = None;
+            trans.fc_ineligible = false;
+        }
+    }
+
+    /// Save a metadata block before the running operation writes or
+    /// revokes it for the first time, so that it can be restored.
+    pub(super) fn trans_save_block(&self, block_id: PBlockId) {
+        {
+            let mut trans = self.transaction.lock();
+            let trans = &mut *trans;
+            let Some(undo) = trans.undo.last_mut() else {
+                return;
+            };
+            if undo.contains_key(&block_id) {
+                return;
+            }
+            if self.journal.is_some() {
+                let copy = trans.blocks.get(&block_id).cloned();
+                let revoked = trans.revoked.contains(&block_id);
+                undo.insert(block_id, SavedBlock::Transaction(copy, revoked));
+                return;
+            }
+        }
+        // Metadata is written in place
+        let block = self.device_read_block(block_id);
+        if let Some(undo) = self.transaction.lock().undo.last_mut() {
+            undo.entry(block_id).or_insert(SavedBlock::Device(block));
+        }
+    }
+
+    /// Record that the running operation only changed `inode` and mapped
+    /// its blocks from `from`, so that it can be fast committed.
+    pub(super) fn trans_fast_commit(&self, inode: InodeId, from: LBlockId) {
+        let mut trans = self.transaction.lock();
+        // Operations called by others are committed with them
+        if self.journal.is_some() && trans.handles == 1 {
+            trans.fast_commit = Some((inode, from));
+        }
+    }
+
+    /// Record that the running operation changed blocks that fast commits
+    /// do not log, e.g. quota records, so that it is committed in full.
+    pub(super) fn trans_fc_ineligible(&self) {
+        self.transaction.lock().fc_ineligible = true;
+    }
+
+    /// Number of blocks written or freed by the running transaction.
+    pub(super) fn trans_pending_blocks(&self) -> usize {
+        let trans = self.transaction.lock();
+        trans.blocks.len() + trans.revoked.len()
+    }
+
+    /// Number of blocks an operation split over several transactions
+    /// writes in each one: half of the log, leaving the rest for the
+    /// blocks changed with them. Unlimited without a journal.
+    pub(super) fn trans_chunk_blocks(&self) -> Result<usize> {
+        let Some(journal) = &self.journal else {
+            return Ok(usize::MAX);
+        };
+        let jsb = self.journal_read_super_block(journal)?;
+        Ok(((jsb.log_end() - jsb.first()) as usize / 2).max(1))
+    }
+
+    /// Commit the running transaction, including the operations that were
+    /// fast committed. Operations keep the transaction small enough for
+    /// the log. If the log cannot be written, the transaction keeps
+    /// running and is committed again with the next operation.
+    pub(super) fn trans_commit(&self) {
+        let (blocks, revoked) = {
+            let mut trans = self.transaction.lock();
+            trans.fc_blocks = 0;
+            (mem::take(&mut trans.blocks), mem::take(&mut trans.revoked))
+        };
+        if blocks.is_empty() && revoked.is_empty() {
+            return;
+        }
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        if let Err(e) = self.journal_commit(journal, &blocks, &revoked) {
+            // Blocks are never written in place outside of the log
+            warn!(
+                "Journal commit failed: {:?}, retrying with the next commit",
+                e
+            );
+            let mut trans = self.transaction.lock();
+            for (block_id, block) in blocks {
+                trans.blocks.entry(block_id).or_insert(block);
+            }
+            for block_id in revoked {
+                if !trans.blocks.contains_key(&block_id) {
+                    trans.revoked.insert(block_id);
+                }
+            }
+        }
+    }
+
+    /// Whether the data of `inode` is journaled.
+    pub(super) fn journal_data(&self, inode: &InodeRef) -> bool {
+        self.journal.is_some()
+            && (self.options.data == DataMode::Journal
+                || inode.inode.flags().contains(InodeFlags::JOURNAL_DATA))
+    }
+
+    /// Get the copy of a block in the running transaction.
+    pub(super) fn trans_read_block(&self, block_id: PBlockId) -> Option<Block> {
+        self.transaction.lock().blocks.get(&block_id).cloned()
+    }
+
+    /// Write a block to the running transaction. Return `false` if no
+    /// transaction is running, then the block must be written in place.
+    pub(super) fn trans_write_block(&self, block: &Block) -> bool {
+        if self.journal.is_none() {
+            return false;
+        }
+        let mut trans = self.transaction.lock();
+        if trans.handles == 0 {
+            // Keep the copy of a fast committed operation up to date
+            if let Some(copy) = trans.blocks.get_mut(&block.id) {
+                *copy = *block;
+                return true;
+            }
+            return false;
+        }
+        // The block is in use again
+        trans.revoked.remove(&block.id);
+        trans.blocks.insert(block.id, *block);
+        trans.dirty = true;
+        true
+    }
+
+    /// Record that a metadata block is freed by the running transaction.
+    pub(super) fn trans_revoke_block(&self, block_id: PBlockId) {
+        if self.journal.is_none() {
+            return;
+        }
+        self.trans_save_block(block_id);
+        let mut trans = self.transaction.lock();
+        if trans.handles > 0 {
+            trans.blocks.remove(&block_id);
+            trans.revoked.insert(block_id);
+            trans.dirty = true;
+        }
+    }
+
+    /// Remove a block from the running transaction, as it is about to be
+    /// written in place.
+    pub(super) fn trans_forget_block(&self, block_id: PBlockId) {
+        self.transaction.lock().blocks.remove(&block_id);
+    }
+
+    /// Commit a transaction to the journal and checkpoint it.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - the transaction is larger than the journal
+    fn journal_commit(
+        &self,
+        journal: &Journal,
+        blocks: &BTreeMap<PBlockId, Block>,
+        revoked: &BTreeSet<PBlockId>,
+    ) -> Result<()> {
+        if self.options.data != DataMode::Writeback {
+            // Data written in place reaches the disk before the metadata
+            // referencing it is committed
+            self.flush_all();
+        }
+        let mut jsb = self.journal_read_super_block(journal)?;
+        let sequence = jsb.sequence();
+
+        // Build the log: revoke blocks, descriptor blocks each followed by
+        // the blocks they describe, then the commit block
+        let revoked: Vec<PBlockId> = revoked.iter().copied().collect();
+        let mut log: Vec<Block> = build_revoke_blocks(&revoked, sequence, &jsb)
+            .into_iter()
+            .map(|data| Block::new(0, data))
+            .collect();
+        let tail_size = if jsb.has_block_tail() { 4 } else { 0 };
+        let mut blocks_iter = blocks.values().peekable();
+        while blocks_iter.peek().is_some() {
+            let desc_pos = log.len();
+            log.push(Block::default());
+            let mut tags = Vec::new();
+            // The first tag is followed by the journal uuid
+            let mut desc_size = size_of::<JournalHeader>() + 16;
+            while desc_size + jsb.tag_size() + tail_size <= BLOCK_SIZE {
+                let Some(block) = blocks_iter.next() else {
+                    break;
+                };
+                let mut tag = JournalBlockTag {
+                    block: block.id,
+                    flags: 0,
+                    checksum: 0,
+                };
+                let mut data = *block;
+                // Logged blocks must not look like journal metadata blocks
+                if data.data[..4] == JBD2_MAGIC.to_be_bytes() {
+                    data.data[..4].fill(0);
+                    tag.flags |= JournalBlockTag::FLAG_ESCAPE;
+                }
+                tag.checksum = jsb.data_checksum(sequence, &data.data);
+                tags.push(tag);
+                log.push(data);
+                desc_size += jsb.tag_size();
+            }
+            log[desc_pos] = Block::new(0, Self::journal_descriptor(&jsb, sequence, &mut tags));
+        }
+        let mut commit = Block::default();
+        commit.write_offset_as(0, &JournalCommitBlock::new(sequence, self.now()));
+        jsb.set_commit_checksum(&mut commit.data);
+        log.push(commit);
+        if log.len() > (jsb.log_end() - jsb.first()) as usize {
+            return_error!(
+                ErrCode::ENOSPC,
+                "Transaction of {} blocks is larger than the journal",
+                log.len()
+            );
+        }
+
+        // Write the log, then point the journal superblock to it, and
+        // write the commit block last
+        let (commit, log) = log.split_last().unwrap();
+        let first = jsb.first();
+        for (i, block) in log.iter().enumerate() {
+            self.journal_write_block(journal, first + i as u32, block)?;
+        }
+        jsb.set_start(first);
+        self.journal_write_super_block(journal, &jsb)?;
+        self.journal_set_needs_recovery(true);
+        self.flush_all();
+        self.journal_write_block(journal, first + log.len() as u32, commit)?;
+        self.flush_all();
+
+        // Checkpoint the blocks to their home locations, then empty the log
+        for block in blocks.values() {
+            self.write_block(block);
+        }
+        self.flush_all();
+        jsb.set_start(0);
+        jsb.set_sequence(sequence.wrapping_add(1));
+        self.journal_write_super_block(journal, &jsb)?;
+        self.journal_set_needs_recovery(false);
+        self.flush_all();
+        Ok(())
+    }
+
+    /// Build a descriptor block of transaction `sequence` from its tags.
+    fn journal_descriptor(
+        jsb: &JournalSuperBlock,
+        sequence: u32,
+        tags: &mut [JournalBlockTag],
+    ) -> [u8; BLOCK_SIZE] {
+        let mut desc = [0; BLOCK_SIZE];
+        let header = JournalHeader::new(JournalBlockType::Descriptor, sequence);
+        desc[..size_of::<JournalHeader>()].copy_from_slice(header.to_bytes());
+        tags.last_mut().unwrap().flags |= JournalBlockTag::FLAG_LAST_TAG;
+        let mut offset = size_of::<JournalHeader>();
+        for (i, tag) in tags.iter_mut().enumerate() {
+            if i > 0 {
+                tag.flags |= JournalBlockTag::FLAG_SAME_UUID;
+            }
+            tag.write(&mut desc[offset..offset + jsb.tag_size()], jsb);
+            offset += jsb.tag_size();
+            if i == 0 {
+                desc[offset..offset + 16].copy_from_slice(&jsb.uuid());
+                offset += 16;
+            }
+        }
+        jsb.set_tail_checksum(&mut desc);
+        desc
+    }
+
+    /// Set or clear the recovery flag of the filesystem on disk, not in
+    /// the copy of the running transaction.
+    pub(super) fn journal_set_needs_recovery(&self, recover: bool) {
+        let mut block = self.device_read_block(0);
+        let mut sb: SuperBlock = block.read_offset_as(BASE_OFFSET);
+        sb.set_needs_recovery(recover);
+        sb.set_checksum();
+        block.write_offset_as(BASE_OFFSET, &sb);
+        self.device_write_block(&block);
+    }
+
+    /// Replay the journal if the filesystem needs recovery, then mark the
+    /// journal as clean.
+    ///
+    /// # Error
+    ///
+    /// * `EIO` - the journal superblock is corrupted
+    pub(super) fn journal_recover(&self, journal: &Journal) -> Result<()> {
+        if !self.read_super_block().needs_recovery() {
+            return Ok(());
+        }
+        let mut jsb = self.journal_read_super_block(journal)?;
+        if jsb.start() != 0 {
+            let (transactions, mut next_sequence) = self.journal_scan(journal, &jsb)?;
+            info!(
+                "Journal recovery: replaying {} transactions from sequence {}",
+                transactions.len(),
+                jsb.sequence()
+            );
+            self.journal_replay(journal, &jsb, &transactions)?;
+            // Fast commits belong to the transaction after the last one
+            if jsb.has_fast_commit() && self.fast_commit_recover(journal, &jsb, next_sequence)? {
+                next_sequence = next_sequence.wrapping_add(1);
+            }
+            // The log is empty, the next transaction follows the last one
+            jsb.set_sequence(next_sequence);
+            jsb.set_start(0);
+            self.journal_write_super_block(journal, &jsb)?;
+        }
+        // The superblock itself may have been replayed
+        self.journal_set_needs_recovery(false);
+        Ok(())
+    }
+
+    /// Scan the log for committed transactions. Return the transactions
+    /// in order with the revoked blocks removed, and the sequence number
+    /// following the last committed transaction.
+    fn journal_scan(
+        &self,
+        journal: &Journal,
+        jsb: &JournalSuperBlock,
+    ) -> Result<(Vec<JournalTransaction>, u32)> {
+        let mut transactions = Vec::new();
+        // Block -> sequence of the latest committed transaction revoking it
+        let mut revoked = BTreeMap::new();
+        let mut sequence = jsb.sequence();
+        let mut cur = JournalTransaction {
+            sequence,
+            blocks: Vec::new(),
+        };
+        let mut cur_revoked = Vec::new();
+        let mut jblock = jsb.start();
+        // The log cannot be longer than the journal
+        for _ in 0..jsb.max_len() {
+            let block = self.journal_read_block(journal, jblock)?;
+            let header: JournalHeader = block.read_offset_as(0);
+            if header.sequence() != sequence {
+                break;
+            }
+            let valid = match header.block_type() {
+                Some(JournalBlockType::Descriptor | JournalBlockType::Revoke) => {
+                    jsb.verify_tail_checksum(&block.data)
+                }
+                Some(JournalBlockType::Commit) => jsb.verify_commit_checksum(&block.data),
+                _ => true,
+            };
+            if !valid {
+                warn!("Journal recovery: bad checksum in journal block {}", jblock);
+                break;
+            }
+            match header.block_type() {
+                Some(JournalBlockType::Descriptor) => {
+                    for tag in JournalBlockTag::parse_all(&block.data, jsb) {
+                        jblock = Self::journal_next_block(jsb, jblock);
+                        cur.blocks.push((jblock, tag));
+                    }
+                }
+                Some(JournalBlockType::Revoke) => {
+                    cur_revoked.extend(parse_revoke_records(&block.data, jsb));
+                }
+                Some(JournalBlockType::Commit) => {
+                    for block in cur_revoked.drain(..) {
+                        revoked.insert(block, sequence);
+                    }
+                    sequence = sequence.wrapping_add(1);
+                    let next = JournalTransaction {
+                        sequence,
+                        blocks: Vec::new(),
+                    };
+                    transactions.push(mem::replace(&mut cur, next));
+                }
+                _ => break,
+            }
+            jblock = Self::journal_next_block(jsb, jblock);
+        }
+        // A block is not replayed if revoked by the same or a later
+        // transaction
+        for trans in transactions.iter_mut() {
+            trans
+                .blocks
+                .retain(|(_, tag)| match revoked.get(&tag.block) {
+                    Some(&seq) => (seq.wrapping_sub(trans.sequence) as i32) < 0,
+                    None => true,
+                });
+        }
+        Ok((transactions, sequence))
+    }
+
+    /// Write the blocks logged by `transactions` to their home locations.
+    fn journal_replay(
+        &self,
+        journal: &Journal,
+        jsb: &JournalSuperBlock,
+        transactions: &[JournalTransaction],
+    ) -> Result<()> {
+        for trans in transactions {
+            for (jblock, tag) in &trans.blocks {
+                let mut block = self.journal_read_block(journal, *jblock)?;
+                if jsb.data_checksum(trans.sequence, &block.data) != tag.checksum {
+                    warn!(
+                        "Journal recovery: bad checksum of block {}, not replayed",
+                        tag.block
+                    );
+                    continue;
+                }
+                block.id = tag.block;
+                if tag.flags & JournalBlockTag::FLAG_ESCAPE != 0 {
+                    block.write_offset(0, &JBD2_MAGIC.to_be_bytes());
+                }
+                trace!(
+                    "Journal recovery: transaction {} block {} -> {}",
+                    trans.sequence,
+                    jblock,
+                    tag.block
+                );
+                self.write_block(&block);
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow the journal inode to the blocks it was moved to, and carry
+    /// the journal superblock over from the old blocks, which are still
+    /// readable. The running transaction must be empty.
+    pub(super) fn journal_reload_inode(&self) -> Result<()> {
+        let Some(journal @ Journal::Inode(inode)) = &self.journal else {
+            return Ok(());
+        };
+        let jsb = self.journal_read_super_block(journal)?;
+        let id = inode.lock().id;
+        *inode.lock() = self.read_inode(id);
+        self.journal_write_super_block(journal, &jsb)?;
+        self.flush_all();
+        Ok(())
+    }
+
+    /// Read and validate the journal superblock.
+    pub(super) fn journal_read_super_block(&self, journal: &Journal) -> Result<JournalSuperBlock> {
+        let block = self.journal_read_block(journal, journal.super_block_id())?;
+        let jsb: JournalSuperBlock = block.read_offset_as(0);
+        if !jsb.is_valid() {
+            return_error!(ErrCode::EIO, "Invalid journal superblock");
+        }
+        Ok(jsb)
+    }
+
+    /// Write the journal superblock with checksum.
+    pub(super) fn journal_write_super_block(
+        &self,
+        journal: &Journal,
+        jsb: &JournalSuperBlock,
+    ) -> Result<()> {
+        let mut jsb = *jsb;
+        jsb.set_checksum();
+        let mut block = self.journal_read_block(journal, journal.super_block_id())?;
+        block.write_offset_as(0, &jsb);
+        self.journal_write_block(journal, journal.super_block_id(), &block)
+    }
+
+    /// Read a block of the journal.
+    pub(super) fn journal_read_block(&self, journal: &Journal, jblock: u32) -> Result<Block> {
+        match journal {
+            Journal::Inode(inode) => {
+                let inode = inode.lock().clone();
+                let pblock = self.extent_query(&inode, jblock)?;
+                Ok(self.read_block(pblock))
+            }
+            Journal::Device(device) => Ok(device.read_block(jblock as PBlockId)),
+        }
+    }
+
+    /// Write a block of the journal.
+    pub(super) fn journal_write_block(
+        &self,
+        journal: &Journal,
+        jblock: u32,
+        block: &Block,
+    ) -> Result<()> {
+        match journal {
+            Journal::Inode(inode) => {
+                let inode = inode.lock().clone();
+                let pblock = self.extent_query(&inode, jblock)?;
+                self.write_block(&Block::new(pblock, block.data));
+            }
+            Journal::Device(device) => {
+                device.write_block(&Block::new(jblock as PBlockId, block.data))
+            }
+        }
+        Ok(())
+    }
 
-    /// stop transaction
-    #[allow(unused)]
-    pub(super) fn trans_abort(&self) {}
+    /// The block following `jblock` in the circular log.
+    fn journal_next_block(jsb: &JournalSuperBlock, jblock: u32) -> u32 {
+        if jblock + 1 >= jsb.log_end() {
+            jsb.first()
+        } else {
+            jblock + 1
+        }
+    }
 }
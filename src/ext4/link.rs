@@ -22,6 +22,7 @@ impl Ext4 {
         }
         // Link parent/child
         child.inode.set_link_count(child_link_count + 1);
+        self.touch_change(child);
         self.write_inode_with_csum(child);
         Ok(())
     }
@@ -43,7 +44,7 @@ impl Ext4 {
         if child.inode.is_dir() {
             // Child is a directory
             // Unlink "child/.."
-            self.dir_remove_entry(&child, "..")?;
+            self.dir_remove_entry(child, "..")?;
             parent.inode.set_link_count(parent.inode.link_count() - 1);
             self.write_inode_with_csum(parent);
         }
@@ -52,6 +53,7 @@ impl Ext4 {
             return self.free_inode(child);
         }
         child.inode.set_link_count(child_link_cnt - 1);
+        self.touch_change(child);
         self.write_inode_with_csum(child);
         Ok(())
     }
@@ -43,10 +43,12 @@ impl Ext4 {
             links: inode.inode.link_count(),
             uid: inode.inode.uid(),
             gid: inode.inode.gid(),
+            projid: inode.inode.projid(),
         })
     }
 
-    /// Set file attributes.
+    /// Set file attributes. ctime is set to the current time unless
+    /// given explicitly, and changing size also updates mtime.
     ///
     /// # Params
     ///
@@ -55,14 +57,17 @@ impl Ext4 {
     /// * `uid` - 32-bit user id
     /// * `gid` - 32-bit group id
     /// * `size` - 64-bit file size
-    /// * `atime` - 32-bit access time in seconds
-    /// * `mtime` - 32-bit modify time in seconds
-    /// * `ctime` - 32-bit change time in seconds
-    /// * `crtime` - 32-bit create time in seconds
+    /// * `atime` - access time
+    /// * `mtime` - modify time
+    /// * `ctime` - change time
+    /// * `crtime` - create time
     ///
     /// # Error
     ///
-    /// `EINVAL` if the inode is invalid (mode == 0).
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EPERM` - the inode is immutable or append-only, or `size` is
+    ///   changed on a file with fs-verity
+    /// * `EDQUOT` - quota of the new owner exceeded
     pub fn setattr(
         &self,
         id: InodeId,
@@ -70,46 +75,64 @@ impl Ext4 {
         uid: Option<u32>,
         gid: Option<u32>,
         size: Option<u64>,
-        atime: Option<u32>,
-        mtime: Option<u32>,
-        ctime: Option<u32>,
-        crtime: Option<u32>,
+        atime: Option<Timestamp>,
+        mtime: Option<Timestamp>,
+        ctime: Option<Timestamp>,
+        crtime: Option<Timestamp>,
     ) -> Result<()> {
-        let mut inode = self.read_inode(id);
-        if inode.inode.mode().bits() == 0 {
-            return_error!(ErrCode::EINVAL, "Invalid inode {}", id);
-        }
-        if let Some(mode) = mode {
-            inode.inode.set_mode(mode);
-        }
-        if let Some(uid) = uid {
-            inode.inode.set_uid(uid);
-        }
-        if let Some(gid) = gid {
-            inode.inode.set_gid(gid);
-        }
-        if let Some(size) = size {
-            // If size increases, allocate new blocks if needed.
-            let required_blocks = (size as usize + INODE_BLOCK_SIZE - 1) / INODE_BLOCK_SIZE;
-            for _ in inode.inode.block_count()..required_blocks as u64 {
-                self.inode_append_block(&mut inode)?;
+        self.trans_run(|| {
+            let mut inode = self.read_inode(id);
+            if inode.inode.mode().bits() == 0 {
+                return_error!(ErrCode::EINVAL, "Invalid inode {}", id);
             }
-            inode.inode.set_size(size);
-        }
-        if let Some(atime) = atime {
-            inode.inode.set_atime(atime);
-        }
-        if let Some(mtime) = mtime {
-            inode.inode.set_mtime(mtime);
-        }
-        if let Some(ctime) = ctime {
-            inode.inode.set_ctime(ctime);
-        }
-        if let Some(crtime) = crtime {
-            inode.inode.set_crtime(crtime);
-        }
-        self.write_inode_with_csum(&mut inode);
-        Ok(())
+            Self::check_flags(&inode, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            if size.is_some() {
+                Self::check_flags(&inode, InodeFlags::VERITY)?;
+            }
+            let first_new_block = self.inode_data_blocks(&inode) as LBlockId;
+            if uid.is_some() || gid.is_some() {
+                // Move quota usage to the new owner
+                let mut new = inode.inode.clone();
+                new.set_uid(uid.unwrap_or(inode.inode.uid()));
+                new.set_gid(gid.unwrap_or(inode.inode.gid()));
+                self.quota_transfer(&inode, &new)?;
+                inode.inode = new;
+            }
+            if let Some(mode) = mode {
+                inode.inode.set_mode(mode);
+                // Keep the access ACL consistent with the new mode
+                self.acl_chmod(&mut inode)?;
+            }
+            if let Some(size) = size {
+                // If size increases, allocate new blocks if needed.
+                let required_blocks = size.div_ceil(BLOCK_SIZE as u64);
+                for _ in first_new_block as u64..required_blocks {
+                    self.inode_append_block(&mut inode)?;
+                }
+                inode.inode.set_size(size);
+                inode.inode.set_mtime(self.now());
+            }
+            inode.inode.set_ctime(self.now());
+            if let Some(atime) = atime {
+                inode.inode.set_atime(atime);
+            }
+            if let Some(mtime) = mtime {
+                inode.inode.set_mtime(mtime);
+            }
+            if let Some(ctime) = ctime {
+                inode.inode.set_ctime(ctime);
+            }
+            if let Some(crtime) = crtime {
+                inode.inode.set_crtime(crtime);
+            }
+            self.write_inode_with_csum(&mut inode);
+            // Quota and ACL changes are not logged by fast commits
+            if mode.is_none() && uid.is_none() && gid.is_none() {
+                self.trans_fast_commit(id, first_new_block);
+            }
+            self.sync_if(&inode, InodeFlags::SYNC);
+            Ok(())
+        })
     }
 
     /// Create a file. This function will not check the existence of
@@ -129,18 +152,42 @@ impl Ext4 {
     /// # Error
     ///
     /// * `ENOTDIR` - `parent` is not a directory
+    /// * `EPERM` - `parent` is immutable
     /// * `ENOSPC` - No space left on device
+    /// * `EFBIG` - `parent` is too large for another entry
+    /// * `EDQUOT` - Quota exceeded
+    /// * `ENOKEY` - `parent` is encrypted and its key is not added
+    /// * `EEXIST` - `parent` is case-insensitive and has `name` in another
+    ///   case
     pub fn create(&self, parent: InodeId, name: &str, mode: InodeMode) -> Result<InodeId> {
-        let mut parent = self.read_inode(parent);
-        // Can only create a file in a directory
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        // Create child inode and link it to parent directory
-        let mut child = self.create_inode(mode)?;
-        self.link_inode(&mut parent, &mut child, name)?;
-        // Create file handler
-        Ok(child.id)
+        self.create_with_owner(parent, name, mode, 0, 0)
+    }
+
+    /// Create a file owned by `uid` and `gid`. See `create`.
+    pub(super) fn create_with_owner(
+        &self,
+        parent: InodeId,
+        name: &str,
+        mode: InodeMode,
+        uid: u32,
+        gid: u32,
+    ) -> Result<InodeId> {
+        self.trans_run(|| {
+            let mut parent = self.read_inode(parent);
+            // Can only create a file in a directory
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            Self::check_flags(&parent, InodeFlags::IMMUTABLE)?;
+            // Create child inode and link it to parent directory
+            let mut child = self.create_inode(&parent, mode, uid, gid)?;
+            self.acl_inherit(&parent, &mut child)?;
+            self.fscrypt_inherit(&parent, &mut child)?;
+            self.link_inode(&mut parent, &mut child, name)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            // Create file handler
+            Ok(child.id)
+        })
     }
 
     /// Read data from a file. This function will read exactly `buf.len()`
@@ -159,52 +206,82 @@ impl Ext4 {
     /// # Error
     ///
     /// * `EISDIR` - `file` is not a regular file
+    /// * `ENOKEY` - `file` is encrypted and its key is not added
+    /// * `EIO` - `file` has fs-verity and a block does not match its hash
     pub fn read(&self, file: InodeId, offset: usize, buf: &mut [u8]) -> Result<usize> {
-        // Get the inode of the file
-        let mut file = self.read_inode(file);
-        if !file.inode.is_file() {
-            return_error!(ErrCode::EISDIR, "Inode {} is not a file", file.id);
-        }
+        self.trans_run(|| {
+            // Get the inode of the file
+            let mut file = self.read_inode(file);
+            if !file.inode.is_file() {
+                return_error!(ErrCode::EISDIR, "Inode {} is not a file", file.id);
+            }
+            let contents_key = self.fscrypt_contents_key(&file)?;
 
-        // Read no bytes
-        if buf.len() == 0 {
-            return Ok(0);
-        }
-        // Calc the actual size to read
-        let read_size = min(buf.len(), file.inode.size() as usize - offset);
-        // Calc the start block of reading
-        let start_iblock = (offset / BLOCK_SIZE) as LBlockId;
-        // Calc the length that is not aligned to the block size
-        let misaligned = offset % BLOCK_SIZE;
+            // Read no bytes
+            if buf.len() == 0 {
+                return Ok(0);
+            }
+            let mut verity = if file.inode.flags().contains(InodeFlags::VERITY) {
+                Some(self.verity_reader(&file, contents_key.as_ref())?)
+            } else {
+                None
+            };
+            // Calc the actual size to read
+            let read_size = min(buf.len(), file.inode.size() as usize - offset);
+            // Calc the start block of reading
+            let start_iblock = (offset / BLOCK_SIZE) as LBlockId;
+            // Calc the length that is not aligned to the block size
+            let misaligned = offset % BLOCK_SIZE;
 
-        let mut cursor = 0;
-        let mut iblock = start_iblock;
-        // Read first block
-        if misaligned > 0 {
-            let read_len = min(BLOCK_SIZE - misaligned, read_size);
-            let fblock = self.extent_query(&mut file, start_iblock).unwrap();
-            let block = self.read_block(fblock);
-            // Copy data from block to the user buffer
-            buf[cursor..cursor + read_len].copy_from_slice(block.read_offset(misaligned, read_len));
-            cursor += read_len;
-            iblock += 1;
-        }
-        // Continue with full block reads
-        while cursor < read_size {
-            let read_len = min(BLOCK_SIZE, read_size - cursor);
-            let fblock = self.extent_query(&mut file, iblock).unwrap();
-            let block = self.read_block(fblock);
-            // Copy data from block to the user buffer
-            buf[cursor..cursor + read_len].copy_from_slice(block.read_offset(0, read_len));
-            cursor += read_len;
-            iblock += 1;
-        }
+            let mut cursor = 0;
+            let mut iblock = start_iblock;
+            // Read first block
+            if misaligned > 0 {
+                let read_len = min(BLOCK_SIZE - misaligned, read_size);
+                let fblock = self.extent_query(&mut file, start_iblock).unwrap();
+                let mut block = self.read_block(fblock);
+                if let Some(key) = &contents_key {
+                    key.decrypt_block(start_iblock, &mut block.data);
+                }
+                if let Some(verity) = &mut verity {
+                    let key = contents_key.as_ref();
+                    self.verity_verify(&file, verity, key, start_iblock, &mut block)?;
+                }
+                // Copy data from block to the user buffer
+                buf[cursor..cursor + read_len]
+                    .copy_from_slice(block.read_offset(misaligned, read_len));
+                cursor += read_len;
+                iblock += 1;
+            }
+            // Continue with full block reads
+            while cursor < read_size {
+                let read_len = min(BLOCK_SIZE, read_size - cursor);
+                let fblock = self.extent_query(&mut file, iblock).unwrap();
+                let mut block = self.read_block(fblock);
+                if let Some(key) = &contents_key {
+                    key.decrypt_block(iblock, &mut block.data);
+                }
+                if let Some(verity) = &mut verity {
+                    let key = contents_key.as_ref();
+                    self.verity_verify(&file, verity, key, iblock, &mut block)?;
+                }
+                // Copy data from block to the user buffer
+                buf[cursor..cursor + read_len].copy_from_slice(block.read_offset(0, read_len));
+                cursor += read_len;
+                iblock += 1;
+            }
+            // Update access time
+            self.touch_access(&mut file);
 
-        Ok(cursor)
+            Ok(cursor)
+        })
     }
 
     /// Write data to a file. This function will write exactly `data.len()` bytes.
     ///
+    /// Journaled data is written in transactions that fit in the log. If
+    /// one of them fails, the data written by the previous ones is kept.
+    ///
     /// # Params
     ///
     /// * `file` - the file handler, acquired by `open` or `create`
@@ -218,45 +295,95 @@ impl Ext4 {
     /// # Error
     ///
     /// * `EISDIR` - `file` is not a regular file
+    /// * `EPERM` - `file` is immutable or has fs-verity, or append-only
+    ///   and `offset` is not the end of the file
     /// * `ENOSPC` - no space left on device
+    /// * `ENOKEY` - `file` is encrypted and its key is not added
     pub fn write(&self, file: InodeId, offset: usize, data: &[u8]) -> Result<usize> {
-        // Get the inode of the file
-        let mut file = self.read_inode(file);
-        if !file.inode.is_file() {
-            return_error!(ErrCode::EISDIR, "Inode {} is not a file", file.id);
+        let chunk = if self.journal_data(&self.read_inode(file)) {
+            self.trans_chunk_blocks()? * BLOCK_SIZE
+        } else {
+            data.len()
+        };
+        let mut written = 0;
+        loop {
+            let end = min(written + chunk, data.len());
+            written += self.write_trans(file, offset + written, &data[written..end])?;
+            if written == data.len() {
+                return Ok(written);
+            }
         }
+    }
 
-        let write_size = data.len();
-        // Calc the start and end block of writing
-        let start_iblock = (offset / BLOCK_SIZE) as LBlockId;
-        let end_iblock = ((offset + write_size) / BLOCK_SIZE) as LBlockId;
-        // Append enough block for writing
-        let append_block_count = end_iblock as i64 + 1 - file.inode.fs_block_count() as i64;
-        for _ in 0..append_block_count {
-            self.inode_append_block(&mut file)?;
-        }
+    /// Write data to a file in one transaction.
+    fn write_trans(&self, file: InodeId, offset: usize, data: &[u8]) -> Result<usize> {
+        self.trans_run(|| {
+            // Get the inode of the file
+            let mut file = self.read_inode(file);
+            if !file.inode.is_file() {
+                return_error!(ErrCode::EISDIR, "Inode {} is not a file", file.id);
+            }
+            Self::check_flags(&file, InodeFlags::IMMUTABLE | InodeFlags::VERITY)?;
+            if file.inode.flags().contains(InodeFlags::APPEND) && offset as u64 != file.inode.size()
+            {
+                return_error!(ErrCode::EPERM, "Inode {} is append-only", file.id);
+            }
+            let contents_key = self.fscrypt_contents_key(&file)?;
 
-        // Write data
-        let mut cursor = 0;
-        let mut iblock = start_iblock;
-        while cursor < write_size {
-            let write_len = min(BLOCK_SIZE, write_size - cursor);
-            let fblock = self.extent_query(&mut file, iblock)?;
-            let mut block = self.read_block(fblock);
-            block.write_offset(
-                (offset + cursor) % BLOCK_SIZE,
-                &data[cursor..cursor + write_len],
-            );
-            self.write_block(&block);
-            cursor += write_len;
-            iblock += 1;
-        }
-        if offset + cursor > file.inode.size() as usize {
-            file.inode.set_size((offset + cursor) as u64);
-        }
-        self.write_inode_with_csum(&mut file);
+            let write_size = data.len();
+            // Calc the start and end block of writing
+            let start_iblock = (offset / BLOCK_SIZE) as LBlockId;
+            let end_iblock = ((offset + write_size) / BLOCK_SIZE) as LBlockId;
+            // Append enough block for writing
+            let first_new_block = self.inode_data_blocks(&file) as LBlockId;
+            let append_block_count = end_iblock as i64 + 1 - first_new_block as i64;
+            for _ in 0..append_block_count {
+                self.inode_append_block(&mut file)?;
+            }
+
+            // Write data
+            let journal_data = self.journal_data(&file);
+            let mut cursor = 0;
+            let mut iblock = start_iblock;
+            while cursor < write_size {
+                let block_offset = (offset + cursor) % BLOCK_SIZE;
+                let write_len = min(BLOCK_SIZE - block_offset, write_size - cursor);
+                let fblock = self.extent_query(&mut file, iblock)?;
+                let mut block = self.read_block(fblock);
+                if let Some(key) = &contents_key {
+                    // New blocks hold no data yet
+                    if iblock < first_new_block {
+                        key.decrypt_block(iblock, &mut block.data);
+                    } else {
+                        block.data.fill(0);
+                    }
+                }
+                block.write_offset(block_offset, &data[cursor..cursor + write_len]);
+                if let Some(key) = &contents_key {
+                    key.encrypt_block(iblock, &mut block.data);
+                }
+                if journal_data {
+                    self.write_block(&block);
+                } else {
+                    self.write_data_block(&block);
+                }
+                cursor += write_len;
+                iblock += 1;
+            }
+            if offset + cursor > file.inode.size() as usize {
+                file.inode.set_size((offset + cursor) as u64);
+            }
+            if cursor > 0 {
+                self.touch_modify(&mut file);
+            }
+            self.write_inode_with_csum(&mut file);
+            if !journal_data {
+                self.trans_fast_commit(file.id, first_new_block);
+            }
+            self.sync_if(&file, InodeFlags::SYNC);
 
-        Ok(cursor)
+            Ok(cursor)
+        })
     }
 
     /// Create a hard link. This function will not check name conflict,
@@ -270,20 +397,35 @@ impl Ext4 {
     /// # Error
     ///
     /// * `ENOTDIR` - `parent` is not a directory
+    /// * `EPERM` - `parent` is immutable, or `child` is immutable or
+    ///   append-only
+    /// * `EXDEV` - `child` is outside the project or the encryption
+    ///   policy of `parent`
     /// * `ENOSPC` - no space left on device
+    /// * `EFBIG` - `parent` is too large for another entry
+    /// * `ENOKEY` - `parent` is encrypted and its key is not added
+    /// * `EEXIST` - `parent` is case-insensitive and has `name` in another
+    ///   case
     pub fn link(&self, child: InodeId, parent: InodeId, name: &str) -> Result<()> {
-        let mut parent = self.read_inode(parent);
-        // Can only link to a directory
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        let mut child = self.read_inode(child);
-        // Cannot link a directory
-        if child.inode.is_dir() {
-            return_error!(ErrCode::EISDIR, "Cannot link a directory");
-        }
-        self.link_inode(&mut parent, &mut child, name)?;
-        Ok(())
+        self.trans_run(|| {
+            let mut parent = self.read_inode(parent);
+            // Can only link to a directory
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            let mut child = self.read_inode(child);
+            // Cannot link a directory
+            if child.inode.is_dir() {
+                return_error!(ErrCode::EISDIR, "Cannot link a directory");
+            }
+            Self::check_flags(&parent, InodeFlags::IMMUTABLE)?;
+            Self::check_flags(&child, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            self.project_check_move(&parent, &child)?;
+            self.fscrypt_check_move(&parent, &child)?;
+            self.link_inode(&mut parent, &mut child, name)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            Ok(())
+        })
     }
 
     /// Unlink a file.
@@ -298,22 +440,29 @@ impl Ext4 {
     /// * `ENOTDIR` - `parent` is not a directory
     /// * `ENOENT` - `name` does not exist in `parent`
     /// * `EISDIR` - `parent/name` is a directory
+    /// * `EPERM` - `parent` or the file is immutable or append-only
     pub fn unlink(&self, parent: InodeId, name: &str) -> Result<()> {
-        let mut parent = self.read_inode(parent);
-        // Can only unlink from a directory
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        // Cannot unlink directory
-        let child_id = self.dir_find_entry(&parent, name)?;
-        let mut child = self.read_inode(child_id);
-        if child.inode.is_dir() {
-            return_error!(ErrCode::EISDIR, "Cannot unlink a directory");
-        }
-        self.unlink_inode(&mut parent, &mut child, name, true)
+        self.trans_run(|| {
+            let mut parent = self.read_inode(parent);
+            // Can only unlink from a directory
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            // Cannot unlink directory
+            let child_id = self.dir_find_entry(&parent, name)?;
+            let mut child = self.read_inode(child_id);
+            if child.inode.is_dir() {
+                return_error!(ErrCode::EISDIR, "Cannot unlink a directory");
+            }
+            Self::check_flags_unlink(&parent, &child)?;
+            self.unlink_inode(&mut parent, &mut child, name, true)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            Ok(())
+        })
     }
 
-    /// Move a file.
+    /// Move a file. Moving a file to one of its own names, like `name` in
+    /// another case in a case-insensitive directory, does nothing.
     ///
     /// # Params
     ///
@@ -327,7 +476,13 @@ impl Ext4 {
     /// * `ENOTDIR` - `parent` or `new_parent` is not a directory
     /// * `ENOENT` - `name` does not exist in `parent`
     /// * `EEXIST` - `new_parent/new_name` already exists
+    /// * `EPERM` - `parent` or the file is immutable or append-only, or
+    ///   `new_parent` is immutable
+    /// * `EXDEV` - the file is moved into a different project or
+    ///   encryption policy
     /// * `ENOSPC` - no space left on device
+    /// * `EFBIG` - `new_parent` is too large for another entry
+    /// * `ENOKEY` - `new_parent` is encrypted and its key is not added
     pub fn rename(
         &self,
         parent: InodeId,
@@ -335,30 +490,49 @@ impl Ext4 {
         new_parent: InodeId,
         new_name: &str,
     ) -> Result<()> {
-        // Check parent
-        let mut parent = self.read_inode(parent);
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        // Check new parent
-        let mut new_parent = self.read_inode(new_parent);
-        if !new_parent.inode.is_dir() {
-            return_error!(
-                ErrCode::ENOTDIR,
-                "Inode {} is not a directory",
-                new_parent.id
-            );
-        }
-        // Check child existence
-        let child_id = self.dir_find_entry(&parent, name)?;
-        let mut child = self.read_inode(child_id);
-        // Check name conflict
-        if self.dir_find_entry(&new_parent, new_name).is_ok() {
-            return_error!(ErrCode::EEXIST, "Dest name {} already exists", new_name);
-        }
-        // Move
-        self.unlink_inode(&mut parent, &mut child, name, false)?;
-        self.link_inode(&mut new_parent, &mut child, new_name)
+        self.trans_run(|| {
+            // Check parent
+            let mut parent = self.read_inode(parent);
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            // Check new parent
+            let new_parent = self.read_inode(new_parent);
+            if !new_parent.inode.is_dir() {
+                return_error!(
+                    ErrCode::ENOTDIR,
+                    "Inode {} is not a directory",
+                    new_parent.id
+                );
+            }
+            // Check child existence
+            let child_id = self.dir_find_entry(&parent, name)?;
+            let mut child = self.read_inode(child_id);
+            Self::check_flags_unlink(&parent, &child)?;
+            Self::check_flags(&new_parent, InodeFlags::IMMUTABLE)?;
+            self.project_check_move(&new_parent, &child)?;
+            self.fscrypt_check_move(&new_parent, &child)?;
+            // Check name conflict, a name of the file itself is left as is
+            match self.dir_find_entry(&new_parent, new_name) {
+                Ok(existing) if existing == child_id => return Ok(()),
+                Ok(_) => {
+                    return Err(format_error!(
+                        ErrCode::EEXIST,
+                        "Dest name {} already exists",
+                        new_name
+                    ))
+                }
+                Err(_) => {}
+            }
+            // Move
+            self.unlink_inode(&mut parent, &mut child, name, false)?;
+            // Reload new parent in case it is the same directory as parent
+            let mut new_parent = self.read_inode(new_parent.id);
+            self.link_inode(&mut new_parent, &mut child, new_name)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            self.sync_if(&new_parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            Ok(())
+        })
     }
 
     /// Create a directory. This function will not check name conflict,
@@ -377,23 +551,47 @@ impl Ext4 {
     /// # Error
     ///
     /// * `ENOTDIR` - `parent` is not a directory
+    /// * `EPERM` - `parent` is immutable
     /// * `ENOSPC` - no space left on device
+    /// * `EFBIG` - `parent` is too large for another entry
+    /// * `EDQUOT` - quota exceeded
+    /// * `ENOKEY` - `parent` is encrypted and its key is not added
+    /// * `EEXIST` - `parent` is case-insensitive and has `name` in another
+    ///   case
     pub fn mkdir(&self, parent: InodeId, name: &str, mode: InodeMode) -> Result<InodeId> {
-        let mut parent = self.read_inode(parent);
-        // Can only create a directory in a directory
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        // Create file/directory
-        let mode = mode & InodeMode::PERM_MASK | InodeMode::DIRECTORY;
-        let mut child = self.create_inode(mode)?;
-        // Add "." entry
-        let child_self = child.clone();
-        self.dir_add_entry(&mut child, &child_self, ".")?;
-        child.inode.set_link_count(1);
-        // Link the new inode
-        self.link_inode(&mut parent, &mut child, name)?;
-        Ok(child.id)
+        self.mkdir_with_owner(parent, name, mode, 0, 0)
+    }
+
+    /// Create a directory owned by `uid` and `gid`. See `mkdir`.
+    pub(super) fn mkdir_with_owner(
+        &self,
+        parent: InodeId,
+        name: &str,
+        mode: InodeMode,
+        uid: u32,
+        gid: u32,
+    ) -> Result<InodeId> {
+        self.trans_run(|| {
+            let mut parent = self.read_inode(parent);
+            // Can only create a directory in a directory
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            Self::check_flags(&parent, InodeFlags::IMMUTABLE)?;
+            // Create file/directory
+            let mode = mode & InodeMode::PERM_MASK | InodeMode::DIRECTORY;
+            let mut child = self.create_inode(&parent, mode, uid, gid)?;
+            self.acl_inherit(&parent, &mut child)?;
+            self.fscrypt_inherit(&parent, &mut child)?;
+            // Add "." entry
+            let child_self = child.clone();
+            self.dir_add_entry(&mut child, &child_self, ".")?;
+            child.inode.set_link_count(1);
+            // Link the new inode
+            self.link_inode(&mut parent, &mut child, name)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            Ok(child.id)
+        })
     }
 
     /// Look up a directory entry by name.
@@ -434,12 +632,18 @@ impl Ext4 {
     ///
     /// `ENOTDIR` - `inode` is not a directory
     pub fn listdir(&self, inode: InodeId) -> Result<Vec<DirEntry>> {
-        let inode_ref = self.read_inode(inode);
-        // Can only list a directory
-        if inode_ref.inode.file_type() != FileType::Directory {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", inode);
-        }
-        Ok(self.dir_list_entries(&inode_ref))
+        self.trans_run(|| {
+            let mut inode_ref = self.read_inode(inode);
+            // Can only list a directory
+            if inode_ref.inode.file_type() != FileType::Directory {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", inode);
+            }
+            let mut entries = self.dir_list_entries(&inode_ref);
+            self.fscrypt_user_entries(&inode_ref, &mut entries)?;
+            // Update access time
+            self.touch_access(&mut inode_ref);
+            Ok(entries)
+        })
     }
 
     /// Remove an empty directory.
@@ -454,23 +658,29 @@ impl Ext4 {
     /// * `ENOTDIR` - `parent` or `child` is not a directory
     /// * `ENOENT` - `name` does not exist in `parent`
     /// * `ENOTEMPTY` - `child` is not empty
+    /// * `EPERM` - `parent` or `child` is immutable or append-only
     pub fn rmdir(&self, parent: InodeId, name: &str) -> Result<()> {
-        let mut parent = self.read_inode(parent);
-        // Can only remove a directory in a directory
-        if !parent.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
-        }
-        let mut child = self.read_inode(self.dir_find_entry(&parent, name)?);
-        // Child must be a directory
-        if !child.inode.is_dir() {
-            return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", child.id);
-        }
-        // Child must be empty
-        if self.dir_list_entries(&child).len() > 2 {
-            return_error!(ErrCode::ENOTEMPTY, "Directory {} is not empty", child.id);
-        }
-        // Remove directory entry
-        self.unlink_inode(&mut parent, &mut child, name, true)
+        self.trans_run(|| {
+            let mut parent = self.read_inode(parent);
+            // Can only remove a directory in a directory
+            if !parent.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", parent.id);
+            }
+            let mut child = self.read_inode(self.dir_find_entry(&parent, name)?);
+            // Child must be a directory
+            if !child.inode.is_dir() {
+                return_error!(ErrCode::ENOTDIR, "Inode {} is not a directory", child.id);
+            }
+            // Child must be empty
+            if self.dir_list_entries(&child).len() > 2 {
+                return_error!(ErrCode::ENOTEMPTY, "Directory {} is not empty", child.id);
+            }
+            Self::check_flags_unlink(&parent, &child)?;
+            // Remove directory entry
+            self.unlink_inode(&mut parent, &mut child, name, true)?;
+            self.sync_if(&parent, InodeFlags::SYNC | InodeFlags::DIRSYNC);
+            Ok(())
+        })
     }
 
     /// Get extended attribute of a file.
@@ -488,24 +698,31 @@ impl Ext4 {
     ///
     /// `ENODATA` - the attribute does not exist
     pub fn getxattr(&self, inode: InodeId, name: &str) -> Result<Vec<u8>> {
-        let inode_ref = self.read_inode(inode);
-        let xattr_block_id = inode_ref.inode.xattr_block();
-        if xattr_block_id == 0 {
+        if name == XATTR_NAME_ENCRYPTION_CONTEXT {
             return_error!(ErrCode::ENODATA, "Xattr {} does not exist", name);
         }
-        let xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
-        match xattr_block.get(name) {
-            Some(value) => Ok(value.to_owned()),
-            None => Err(format_error!(
-                ErrCode::ENODATA,
-                "Xattr {} does not exist",
-                name
-            )),
+        if let Some(ty) = AclType::from_xattr_name(name) {
+            return match self.get_acl(inode, ty)? {
+                Some(acl) => Ok(acl.to_xattr()),
+                None => Err(format_error!(
+                    ErrCode::ENODATA,
+                    "Xattr {} does not exist",
+                    name
+                )),
+            };
         }
+        let inode_ref = self.read_inode(inode);
+        self.xattr_get(&inode_ref, name).ok_or(format_error!(
+            ErrCode::ENODATA,
+            "Xattr {} does not exist",
+            name
+        ))
     }
 
-    /// Set extended attribute of a file. This function will not check name conflict,
-    /// call `getxattr` to check beforehand.
+    /// Set extended attribute of a file, replacing the one with the same name.
+    ///
+    /// POSIX ACLs (`system.posix_acl_access` and `system.posix_acl_default`)
+    /// are given in the VFS format and replace the existing ACL. See `set_acl`.
     ///
     /// # Params
     ///
@@ -515,30 +732,30 @@ impl Ext4 {
     ///
     /// # Error
     ///
-    /// `ENOSPC` - xattr block does not have enough space
+    /// * `EPERM` - the inode is immutable or append-only
+    /// * `ENOSPC` - neither the inode nor the xattr block has enough space
+    /// * `EINVAL` - the value of an ACL is malformed
+    /// * `EPERM` - the attribute is the encryption context
     pub fn setxattr(&self, inode: InodeId, name: &str, value: &[u8]) -> Result<()> {
-        let mut inode_ref = self.read_inode(inode);
-        let xattr_block_id = inode_ref.inode.xattr_block();
-        if xattr_block_id == 0 {
-            // lazy allocate xattr block
-            let pblock = self.alloc_block(&mut inode_ref)?;
-            inode_ref.inode.set_xattr_block(pblock);
+        self.trans_run(|| {
+            if name == XATTR_NAME_ENCRYPTION_CONTEXT {
+                return_error!(ErrCode::EPERM, "Xattr {} is reserved", name);
+            }
+            if let Some(ty) = AclType::from_xattr_name(name) {
+                let acl = PosixAcl::from_xattr(value).ok_or(format_error!(
+                    ErrCode::EINVAL,
+                    "Invalid ACL {}",
+                    name
+                ))?;
+                return self.set_acl(inode, ty, Some(&acl));
+            }
+            let mut inode_ref = self.read_inode(inode);
+            Self::check_flags(&inode_ref, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            self.xattr_set(&mut inode_ref, name, Some(value))?;
+            self.touch_change(&mut inode_ref);
             self.write_inode_with_csum(&mut inode_ref);
-        }
-        let mut xattr_block = XattrBlock::new(self.read_block(inode_ref.inode.xattr_block()));
-        if xattr_block_id == 0 {
-            xattr_block.init();
-        }
-        if xattr_block.insert(name, value) {
-            self.write_block(&xattr_block.block());
             Ok(())
-        } else {
-            return_error!(
-                ErrCode::ENOSPC,
-                "Xattr block of Inode {} does not have enough space",
-                inode
-            );
-        }
+        })
     }
 
     /// Remove extended attribute of a file.
@@ -550,20 +767,29 @@ impl Ext4 {
     ///
     /// # Error
     ///
-    /// `ENODATA` - the attribute does not exist
+    /// * `ENODATA` - the attribute does not exist
+    /// * `EPERM` - the inode is immutable or append-only
+    /// * `EPERM` - the attribute is the encryption context
     pub fn removexattr(&self, inode: InodeId, name: &str) -> Result<()> {
-        let inode_ref = self.read_inode(inode);
-        let xattr_block_id = inode_ref.inode.xattr_block();
-        if xattr_block_id == 0 {
-            return_error!(ErrCode::ENODATA, "Xattr {} does not exist", name);
-        }
-        let mut xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
-        if xattr_block.remove(name) {
-            self.write_block(&xattr_block.block());
+        self.trans_run(|| {
+            if name == XATTR_NAME_ENCRYPTION_CONTEXT {
+                return_error!(ErrCode::EPERM, "Xattr {} is reserved", name);
+            }
+            if let Some(ty) = AclType::from_xattr_name(name) {
+                if self.get_acl(inode, ty)?.is_none() {
+                    return_error!(ErrCode::ENODATA, "Xattr {} does not exist", name);
+                }
+                return self.set_acl(inode, ty, None);
+            }
+            let mut inode_ref = self.read_inode(inode);
+            Self::check_flags(&inode_ref, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            if !self.xattr_set(&mut inode_ref, name, None)? {
+                return_error!(ErrCode::ENODATA, "Xattr {} does not exist", name);
+            }
+            self.touch_change(&mut inode_ref);
+            self.write_inode_with_csum(&mut inode_ref);
             Ok(())
-        } else {
-            return_error!(ErrCode::ENODATA, "Xattr {} does not exist", name);
-        }
+        })
     }
 
     /// List extended attributes of a file.
@@ -577,12 +803,40 @@ impl Ext4 {
     /// A list of extended attributes of the file.
     pub fn listxattr(&self, inode: InodeId) -> Result<Vec<String>> {
         let inode_ref = self.read_inode(inode);
-        let xattr_block_id = inode_ref.inode.xattr_block();
-        if xattr_block_id == 0 {
-            return Ok(Vec::new());
-        }
-        let xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
-        Ok(xattr_block.list())
+        let mut names = self.xattr_list(&inode_ref);
+        names.retain(|name| name != XATTR_NAME_ENCRYPTION_CONTEXT);
+        Ok(names)
+    }
+
+    /// Get filesystem statistics. For a directory that passes its project
+    /// on to new entries, the statistics are limited by the project quota.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of any file in the filesystem
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EIO` - the project quota file is corrupted
+    pub fn statfs(&self, inode: InodeId) -> Result<StatFs> {
+        let inode_ref = self.read_inode(inode);
+        if inode_ref.inode.mode().bits() == 0 {
+            return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+        }
+        let sb = self.read_super_block();
+        let bfree = sb.free_blocks_count();
+        let mut stat = StatFs {
+            bsize: BLOCK_SIZE as u32,
+            blocks: sb.block_count(),
+            bfree,
+            bavail: bfree.saturating_sub(sb.reserved_blocks_count()),
+            files: sb.inode_count() as u64,
+            ffree: sb.free_inodes_count() as u64,
+            namelen: NAME_MAX as u32,
+        };
+        self.project_statfs(&inode_ref.inode, &mut stat)?;
+        Ok(stat)
     }
 
     /// Flush all dirty blocks in cache to disk.
@@ -2,15 +2,40 @@ use crate::constants::*;
 use crate::ext4_defs::*;
 use crate::prelude::*;
 use crate::return_error;
+use crate::sync::SpinMutex;
+use journal::{Journal, Transaction};
 
+mod acl;
 mod alloc;
+mod check;
+mod defrag;
 mod dir;
 mod extent;
+mod fast_commit;
+mod flags;
+mod format;
+mod fscrypt;
 mod high_level;
 mod journal;
 mod link;
 mod low_level;
+mod options;
+mod perm;
+mod project;
+mod quota;
+mod repair;
+mod resize;
 mod rw;
+mod shrink;
+mod time;
+mod verity;
+mod xattr;
+
+pub use check::{CheckProblem, CheckReport};
+pub use defrag::DefragStats;
+pub use options::*;
+pub use perm::{AccessMode, Capabilities, Credentials};
+pub use repair::{RepairAction, RepairReport};
 
 /// The Ext4 filesystem implementation.
 pub struct Ext4 {
@@ -18,11 +43,46 @@ pub struct Ext4 {
     block_cache: BlockCache,
     #[cfg(not(feature = "block_cache"))]
     block_device: Arc<dyn BlockDevice>,
+    /// The source of timestamps.
+    time_source: Arc<dyn TimeSource>,
+    /// Mount options.
+    options: MountOptions,
+    /// The journal, `None` if metadata updates are not journaled.
+    journal: Option<Journal>,
+    /// The running transaction.
+    transaction: SpinMutex<Transaction>,
+    /// The master keys of encrypted directories.
+    keyring: SpinMutex<fscrypt::Keyring>,
 }
 
 impl Ext4 {
     /// Opens and loads an Ext4 from the `block_device`.
-    pub fn load(block_device: Arc<dyn BlockDevice>) -> Result<Self> {
+    ///
+    /// Timestamps of inodes are taken from `time_source`.
+    pub fn load(
+        block_device: Arc<dyn BlockDevice>,
+        time_source: Arc<dyn TimeSource>,
+        options: MountOptions,
+    ) -> Result<Self> {
+        Self::load_with_journal(block_device, None, time_source, options)
+    }
+
+    /// Opens and loads an Ext4 from the `block_device`, whose journal is
+    /// on the external `journal_device`.
+    ///
+    /// Timestamps of inodes are taken from `time_source`.
+    ///
+    /// # Error
+    ///
+    /// * `ENODEV` - the journal is external but no journal device is given
+    /// * `EINVAL` - the journal device is not the journal of the filesystem
+    /// * `EINVAL` - the encoding of case-insensitive names is unknown
+    pub fn load_with_journal(
+        block_device: Arc<dyn BlockDevice>,
+        journal_device: Option<Arc<dyn BlockDevice>>,
+        time_source: Arc<dyn TimeSource>,
+        options: MountOptions,
+    ) -> Result<Self> {
         // Load the superblock
         // TODO: if the main superblock is corrupted, should we load the backup?
         let block = block_device.read_block(0);
@@ -32,6 +92,9 @@ impl Ext4 {
         if !sb.check_magic() {
             return_error!(ErrCode::EINVAL, "Invalid magic number");
         }
+        if sb.is_journal_dev() {
+            return_error!(ErrCode::EINVAL, "Device is an external journal");
+        }
         // Check inode size
         if sb.inode_size() != SB_GOOD_INODE_SIZE {
             return_error!(ErrCode::EINVAL, "Invalid inode size {}", sb.inode_size());
@@ -44,18 +107,43 @@ impl Ext4 {
                 sb.desc_size()
             );
         }
+        if sb.has_casefold() && !sb.has_utf8_encoding() {
+            return_error!(ErrCode::EINVAL, "Unknown filename encoding");
+        }
         // Create Ext4 instance
-        Ok(Self {
+        let mut ext4 = Self {
             #[cfg(feature = "block_cache")]
             block_cache: BlockCache::new(block_device),
             #[cfg(not(feature = "block_cache"))]
             block_device,
-        })
+            time_source,
+            options,
+            journal: None,
+            transaction: SpinMutex::new(Transaction::default()),
+            keyring: SpinMutex::new(fscrypt::Keyring::default()),
+        };
+        // Replay the journal if not unmounted cleanly
+        let journal = ext4.journal_open(journal_device)?;
+        if let Some(journal) = &journal {
+            ext4.journal_recover(journal)?;
+            ext4.journal_load(journal)?;
+        }
+        ext4.journal = journal;
+        Ok(ext4)
     }
     
     /// Initializes the root directory.
     pub fn init(&mut self) -> Result<()> {
-        // Create root directory
-        self.create_root_inode().map(|_| ())
+        self.trans_run(|| {
+            // Create root directory
+            self.create_root_inode().map(|_| ())
+        })
+    }
+}
+
+impl Drop for Ext4 {
+    fn drop(&mut self) {
+        // Commit the operations that were fast committed
+        self.trans_commit();
     }
 }
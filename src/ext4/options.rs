@@ -0,0 +1,105 @@
+use crate::ext4_defs::Timestamp;
+use crate::prelude::*;
+
+/// Policies for updating the access time of an inode on read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AtimePolicy {
+    /// Update atime on every access.
+    Strict,
+    /// Update atime only if it is older than mtime or ctime, or is
+    /// more than one day old.
+    #[default]
+    Relative,
+    /// Never update atime.
+    NoAtime,
+}
+
+/// Journaling modes of file data, metadata is always journaled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DataMode {
+    /// Data is written in place with no ordering against the metadata
+    /// commit. After a crash, files may contain stale data.
+    Writeback,
+    /// Data is written in place before the metadata that references it
+    /// is committed.
+    #[default]
+    Ordered,
+    /// Data is journaled together with metadata. Inodes with the
+    /// `JOURNAL_DATA` flag are journaled in every mode.
+    Journal,
+}
+
+/// Options to mount the filesystem with.
+#[derive(Debug, Default, Clone)]
+pub struct MountOptions {
+    /// Access time update policy.
+    pub atime: AtimePolicy,
+    /// Data journaling mode.
+    pub data: DataMode,
+}
+
+bitflags! {
+    /// Optional features of a new filesystem. Extents, 64-bit group
+    /// descriptors and metadata checksums are always enabled.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FormatFeatures: u32 {
+        /// Journal metadata updates in inode 8.
+        const JOURNAL = 0x1;
+        /// Track user and group quota in inodes 3 and 4, and project
+        /// quota in inode 12 if `PROJECT` is set.
+        const QUOTA = 0x2;
+        /// Let inodes carry a project id.
+        const PROJECT = 0x4;
+        /// Allow encrypted directories.
+        const ENCRYPT = 0x8;
+        /// Allow case-insensitive directories.
+        const CASEFOLD = 0x10;
+        /// Allow files with fs-verity.
+        const VERITY = 0x20;
+        /// Allow three levels of directory index and directories larger
+        /// than 2 GiB.
+        const LARGE_DIR = 0x40;
+        /// Index directories by the hashes of names once they outgrow
+        /// one block.
+        const DIR_INDEX = 0x80;
+        /// Reserve blocks in inode 7 for the group descriptor table to grow
+        /// when the filesystem is resized.
+        const RESIZE_INODE = 0x100;
+    }
+}
+
+/// Options to format a block device with.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Size of the filesystem in blocks.
+    pub block_count: u64,
+    /// Bytes of space per inode, which decides the number of inodes.
+    pub inode_ratio: u32,
+    /// Volume name, at most 16 bytes.
+    pub label: String,
+    /// Filesystem uuid. It should be random and is used to seed the
+    /// metadata checksums.
+    pub uuid: [u8; 16],
+    /// Optional features.
+    pub features: FormatFeatures,
+    /// Percentage of blocks reserved for the super user.
+    pub reserved_percent: u32,
+    /// Creation time of the filesystem and of the root directory.
+    pub time: Timestamp,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            block_count: 0,
+            inode_ratio: 16384,
+            label: String::new(),
+            uuid: [0; 16],
+            features: FormatFeatures::JOURNAL
+                | FormatFeatures::DIR_INDEX
+                | FormatFeatures::RESIZE_INODE,
+            reserved_percent: 5,
+            time: Timestamp::default(),
+        }
+    }
+}
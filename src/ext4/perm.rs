@@ -0,0 +1,482 @@
+//! Permission checking with POSIX mode bits and access control lists.
+//!
+//! Operations in `low_level` do not check permissions. Embedders that need
+//! access control pass the caller's `Credentials` to `Ext4::access` or to the
+//! credential-aware variants (`lookup_as`, `create_as`, ...), which check
+//! permissions and then perform the operation.
+
+use super::Ext4;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+bitflags! {
+    /// Capabilities that bypass permission checks.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct Capabilities: u32 {
+        /// Make arbitrary changes to file UIDs and GIDs.
+        const CHOWN = 1 << 0;
+        /// Bypass file read, write, and execute permission checks.
+        const DAC_OVERRIDE = 1 << 1;
+        /// Bypass file read and directory read/search permission checks.
+        const DAC_READ_SEARCH = 1 << 2;
+        /// Bypass checks that require the caller to own the file.
+        const FOWNER = 1 << 3;
+        /// Keep the set-group-ID bit when the caller is not in the file group.
+        const FSETID = 1 << 4;
+        /// Set or clear the `IMMUTABLE` and `APPEND` inode flags.
+        const LINUX_IMMUTABLE = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// Access mask for permission checks, the same as `R_OK`, `W_OK` and `X_OK`.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct AccessMode: u16 {
+        /// Read permission.
+        const READ = 0x4;
+        /// Write permission.
+        const WRITE = 0x2;
+        /// Execute or search permission.
+        const EXEC = 0x1;
+    }
+}
+
+/// The identity of a caller, used for permission checking.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    /// Filesystem user id.
+    pub uid: u32,
+    /// Filesystem group id.
+    pub gid: u32,
+    /// Supplementary group ids.
+    pub groups: Vec<u32>,
+    /// Effective capabilities.
+    pub caps: Capabilities,
+}
+
+impl Credentials {
+    /// Create credentials of a user. The superuser (uid 0) gets all
+    /// capabilities, other users get none.
+    pub fn new(uid: u32, gid: u32, groups: Vec<u32>) -> Self {
+        let caps = if uid == 0 {
+            Capabilities::all()
+        } else {
+            Capabilities::empty()
+        };
+        Self {
+            uid,
+            gid,
+            groups,
+            caps,
+        }
+    }
+
+    /// Credentials of the superuser.
+    pub fn root() -> Self {
+        Self::new(0, 0, Vec::new())
+    }
+
+    /// Check if the caller is a member of a group.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.groups.contains(&gid)
+    }
+
+    /// Check if the caller has a capability.
+    pub fn has_cap(&self, cap: Capabilities) -> bool {
+        self.caps.contains(cap)
+    }
+}
+
+impl Ext4 {
+    /// Check if the caller can access an inode.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode to check
+    /// * `cred` - the caller's credentials
+    /// * `mask` - the requested access
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EACCES` - permission denied
+    /// * `EPERM` - write access to an immutable inode
+    pub fn access(&self, inode: InodeId, cred: &Credentials, mask: AccessMode) -> Result<()> {
+        let inode_ref = self.read_inode(inode);
+        if inode_ref.inode.mode().bits() == 0 {
+            return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+        }
+        self.inode_permission(&inode_ref, cred, mask)
+    }
+
+    /// Look up a directory entry by name, requiring search permission
+    /// on `parent`. See `lookup`.
+    ///
+    /// # Error
+    ///
+    /// * `EACCES` - no search permission on `parent`
+    pub fn lookup_as(&self, cred: &Credentials, parent: InodeId, name: &str) -> Result<InodeId> {
+        let parent_ref = self.read_inode(parent);
+        if parent_ref.inode.is_dir() {
+            self.inode_permission(&parent_ref, cred, AccessMode::EXEC)?;
+        }
+        self.lookup(parent, name)
+    }
+
+    /// Create a file owned by the caller, requiring write and search
+    /// permission on `parent`. See `create`.
+    ///
+    /// The new file gets the caller's group, or the group of `parent`
+    /// if `parent` has the set-group-ID bit.
+    ///
+    /// # Error
+    ///
+    /// * `EACCES` - no write or search permission on `parent`
+    pub fn create_as(
+        &self,
+        cred: &Credentials,
+        parent: InodeId,
+        name: &str,
+        mode: InodeMode,
+    ) -> Result<InodeId> {
+        let parent_ref = self.read_inode(parent);
+        if parent_ref.inode.is_dir() {
+            self.inode_permission(&parent_ref, cred, AccessMode::WRITE | AccessMode::EXEC)?;
+        }
+        let (uid, gid, mode) = Self::new_inode_owner(&parent_ref, cred, mode);
+        self.create_with_owner(parent, name, mode, uid, gid)
+    }
+
+    /// Create a directory owned by the caller, requiring write and search
+    /// permission on `parent`. See `mkdir` and `create_as`.
+    ///
+    /// # Error
+    ///
+    /// * `EACCES` - no write or search permission on `parent`
+    pub fn mkdir_as(
+        &self,
+        cred: &Credentials,
+        parent: InodeId,
+        name: &str,
+        mode: InodeMode,
+    ) -> Result<InodeId> {
+        let parent_ref = self.read_inode(parent);
+        if parent_ref.inode.is_dir() {
+            self.inode_permission(&parent_ref, cred, AccessMode::WRITE | AccessMode::EXEC)?;
+        }
+        let mode = mode & InodeMode::PERM_MASK | InodeMode::DIRECTORY;
+        let (uid, gid, mode) = Self::new_inode_owner(&parent_ref, cred, mode);
+        self.mkdir_with_owner(parent, name, mode, uid, gid)
+    }
+
+    /// Unlink a file, requiring write and search permission on `parent`.
+    /// See `unlink`.
+    ///
+    /// # Error
+    ///
+    /// * `EACCES` - no write or search permission on `parent`
+    /// * `EPERM` - `parent` is sticky and the caller owns neither
+    ///   `parent` nor the file
+    pub fn unlink_as(&self, cred: &Credentials, parent: InodeId, name: &str) -> Result<()> {
+        let parent_ref = self.read_inode(parent);
+        if parent_ref.inode.is_dir() {
+            self.may_delete(&parent_ref, name, cred)?;
+        }
+        self.unlink(parent, name)
+    }
+
+    /// Move a file, requiring write and search permission on both
+    /// `parent` and `new_parent`. See `rename`.
+    ///
+    /// # Error
+    ///
+    /// * `EACCES` - no write or search permission on `parent` or
+    ///   `new_parent`, or no write permission on a directory moved
+    ///   to another parent
+    /// * `EPERM` - `parent` is sticky and the caller owns neither
+    ///   `parent` nor the file
+    pub fn rename_as(
+        &self,
+        cred: &Credentials,
+        parent: InodeId,
+        name: &str,
+        new_parent: InodeId,
+        new_name: &str,
+    ) -> Result<()> {
+        let parent_ref = self.read_inode(parent);
+        let new_parent_ref = self.read_inode(new_parent);
+        if parent_ref.inode.is_dir() && new_parent_ref.inode.is_dir() {
+            let child = self.may_delete(&parent_ref, name, cred)?;
+            self.inode_permission(&new_parent_ref, cred, AccessMode::WRITE | AccessMode::EXEC)?;
+            // Moving a directory to another parent rewrites its ".." entry
+            if child.inode.is_dir() && parent != new_parent {
+                self.inode_permission(&child, cred, AccessMode::WRITE)?;
+            }
+        }
+        self.rename(parent, name, new_parent, new_name)
+    }
+
+    /// Set file attributes with permission checks. See `setattr`.
+    ///
+    /// * Changing mode or timestamps requires owning the file.
+    /// * Changing uid requires `CHOWN`; the owner may change gid to a
+    ///   group it belongs to.
+    /// * Changing size requires write permission.
+    ///
+    /// Changing the owner clears the set-user-ID and set-group-ID bits
+    /// of a non-directory, and setting the set-group-ID bit is ignored
+    /// unless the caller is in the file group or has `FSETID`.
+    ///
+    /// # Error
+    ///
+    /// * `EPERM` - not permitted to change mode, owner or timestamps
+    /// * `EACCES` - no write permission to change size
+    pub fn setattr_as(&self, cred: &Credentials, id: InodeId, attr: SetAttr) -> Result<()> {
+        let SetAttr {
+            mode,
+            uid,
+            gid,
+            size,
+            atime,
+            mtime,
+            ctime,
+            crtime,
+        } = attr;
+        let inode_ref = self.read_inode(id);
+        if inode_ref.inode.mode().bits() == 0 {
+            return_error!(ErrCode::EINVAL, "Invalid inode {}", id);
+        }
+        let inode = &inode_ref.inode;
+        let is_owner = cred.uid == inode.uid() || cred.has_cap(Capabilities::FOWNER);
+        // Check owner change
+        let uid = uid.filter(|&uid| uid != inode.uid());
+        let gid = gid.filter(|&gid| gid != inode.gid());
+        if uid.is_some() && !cred.has_cap(Capabilities::CHOWN) {
+            return_error!(ErrCode::EPERM, "Cannot change owner of inode {}", id);
+        }
+        if let Some(gid) = gid {
+            if !(cred.has_cap(Capabilities::CHOWN) || is_owner && cred.in_group(gid)) {
+                return_error!(ErrCode::EPERM, "Cannot change group of inode {}", id);
+            }
+        }
+        // Check mode change
+        let mut mode = mode;
+        if let Some(m) = mode.as_mut() {
+            if !is_owner {
+                return_error!(ErrCode::EPERM, "Cannot change mode of inode {}", id);
+            }
+            let group = gid.unwrap_or(inode.gid());
+            if !cred.in_group(group) && !cred.has_cap(Capabilities::FSETID) {
+                m.remove(InodeMode::SET_GID);
+            }
+        }
+        // Check timestamp change
+        if (atime.is_some() || mtime.is_some() || ctime.is_some() || crtime.is_some()) && !is_owner
+        {
+            return_error!(ErrCode::EPERM, "Cannot change times of inode {}", id);
+        }
+        // Check size change
+        if size.is_some() {
+            self.inode_permission(&inode_ref, cred, AccessMode::WRITE)?;
+        }
+        // Changing owner drops privileges of a non-directory
+        if (uid.is_some() || gid.is_some()) && !inode.is_dir() {
+            let mut m = mode.unwrap_or(inode.mode());
+            m.remove(InodeMode::SET_UID);
+            if m.contains(InodeMode::GROUP_EXEC) {
+                m.remove(InodeMode::SET_GID);
+            }
+            mode = Some(m);
+        }
+        self.setattr(id, mode, uid, gid, size, atime, mtime, ctime, crtime)
+    }
+
+    /// Set the flags of an inode with permission checks. See `set_flags`.
+    ///
+    /// Changing flags requires owning the file, and changing `IMMUTABLE`
+    /// or `APPEND` requires `LINUX_IMMUTABLE`.
+    ///
+    /// # Error
+    ///
+    /// * `EPERM` - not permitted to change the flags
+    pub fn set_flags_as(
+        &self,
+        cred: &Credentials,
+        inode: InodeId,
+        flags: InodeFlags,
+    ) -> Result<()> {
+        let old = self.get_flags(inode)?;
+        let owner = self.read_inode(inode).inode.uid();
+        if cred.uid != owner && !cred.has_cap(Capabilities::FOWNER) {
+            return_error!(ErrCode::EPERM, "Cannot change flags of inode {}", inode);
+        }
+        let special = InodeFlags::IMMUTABLE | InodeFlags::APPEND;
+        if (old ^ flags).intersects(special) && !cred.has_cap(Capabilities::LINUX_IMMUTABLE) {
+            return_error!(
+                ErrCode::EPERM,
+                "Cannot change immutable or append-only flag of inode {}",
+                inode
+            );
+        }
+        self.set_flags(inode, flags)
+    }
+
+    /// Check if the caller has the requested access to an inode. Write
+    /// access to an immutable inode is never granted.
+    pub(super) fn inode_permission(
+        &self,
+        inode: &InodeRef,
+        cred: &Credentials,
+        mask: AccessMode,
+    ) -> Result<()> {
+        if mask.contains(AccessMode::WRITE) {
+            Self::check_flags(inode, InodeFlags::IMMUTABLE)?;
+        }
+        let mode = inode.inode.mode();
+        // Select the permission class of the caller. The access ACL,
+        // if any, replaces the group and other classes.
+        let acl = if cred.uid != inode.inode.uid()
+            && mode
+                .intersects(InodeMode::GROUP_READ | InodeMode::GROUP_WRITE | InodeMode::GROUP_EXEC)
+        {
+            self.read_acl(inode, AclType::Access)
+        } else {
+            None
+        };
+        let granted = if let Some(acl) = acl {
+            Self::acl_permission(&acl, inode, cred, mask)
+        } else {
+            let perm = if cred.uid == inode.inode.uid() {
+                mode.bits() >> 6
+            } else if cred.in_group(inode.inode.gid()) {
+                mode.bits() >> 3
+            } else {
+                mode.bits()
+            };
+            AccessMode::from_bits_truncate(perm).contains(mask)
+        };
+        if granted {
+            return Ok(());
+        }
+        // Check capabilities
+        let is_dir = inode.inode.is_dir();
+        let any_exec =
+            mode.intersects(InodeMode::USER_EXEC | InodeMode::GROUP_EXEC | InodeMode::OTHER_EXEC);
+        if cred.has_cap(Capabilities::DAC_OVERRIDE)
+            && (!mask.contains(AccessMode::EXEC) || is_dir || any_exec)
+        {
+            return Ok(());
+        }
+        if cred.has_cap(Capabilities::DAC_READ_SEARCH)
+            && !mask.contains(AccessMode::WRITE)
+            && (!mask.contains(AccessMode::EXEC) || is_dir)
+        {
+            return Ok(());
+        }
+        return_error!(
+            ErrCode::EACCES,
+            "Permission {:?} denied on inode {}",
+            mask,
+            inode.id
+        );
+    }
+
+    /// Check if an access ACL grants the requested access.
+    ///
+    /// The first entry matching the caller decides: the owner entry, a
+    /// named user entry, then the file group and named group entries
+    /// (any one of them granting the access), then the other entry.
+    /// Named user and group entries are limited by the mask entry.
+    fn acl_permission(
+        acl: &PosixAcl,
+        inode: &InodeRef,
+        cred: &Credentials,
+        mask: AccessMode,
+    ) -> bool {
+        let want = mask.bits();
+        let acl_mask = acl
+            .entries()
+            .iter()
+            .find(|e| e.tag == AclTag::Mask)
+            .map_or(0o7, |e| e.perm);
+        let mut group_found = false;
+        for e in acl.entries() {
+            match e.tag {
+                AclTag::UserObj if cred.uid == inode.inode.uid() => {
+                    return e.perm & want == want;
+                }
+                AclTag::User if cred.uid == e.id => {
+                    return e.perm & acl_mask & want == want;
+                }
+                AclTag::GroupObj if cred.in_group(inode.inode.gid()) => {
+                    group_found = true;
+                    if e.perm & want == want {
+                        return acl_mask & want == want;
+                    }
+                }
+                AclTag::Group if cred.in_group(e.id) => {
+                    group_found = true;
+                    if e.perm & want == want {
+                        return acl_mask & want == want;
+                    }
+                }
+                AclTag::Other => return !group_found && e.perm & want == want,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Check if the caller may remove `name` from directory `dir`.
+    /// Return the inode to remove.
+    fn may_delete(&self, dir: &InodeRef, name: &str, cred: &Credentials) -> Result<InodeRef> {
+        self.inode_permission(dir, cred, AccessMode::WRITE | AccessMode::EXEC)?;
+        let child = self.read_inode(self.dir_find_entry(dir, name)?);
+        // In a sticky directory, only the owner of the file or the directory
+        // can remove the file
+        if dir.inode.mode().contains(InodeMode::STICKY)
+            && cred.uid != dir.inode.uid()
+            && cred.uid != child.inode.uid()
+            && !cred.has_cap(Capabilities::FOWNER)
+        {
+            return_error!(
+                ErrCode::EPERM,
+                "Cannot remove {} from sticky directory {}",
+                name,
+                dir.id
+            );
+        }
+        Ok(child)
+    }
+
+    /// Get the owner of a new inode created by the caller in `parent`.
+    /// Return the uid, gid and the adjusted mode.
+    fn new_inode_owner(
+        parent: &InodeRef,
+        cred: &Credentials,
+        mode: InodeMode,
+    ) -> (u32, u32, InodeMode) {
+        let mut mode = mode;
+        let is_dir = mode.file_type() == FileType::Directory;
+        let gid = if parent.inode.mode().contains(InodeMode::SET_GID) {
+            // Inherit the group of a set-group-ID directory
+            if is_dir {
+                mode.insert(InodeMode::SET_GID);
+            }
+            parent.inode.gid()
+        } else {
+            cred.gid
+        };
+        // Drop set-group-ID of a new file that is group executable
+        // but not in the caller's groups
+        if !is_dir
+            && mode.contains(InodeMode::SET_GID | InodeMode::GROUP_EXEC)
+            && !cred.in_group(gid)
+            && !cred.has_cap(Capabilities::FSETID)
+        {
+            mode.remove(InodeMode::SET_GID);
+        }
+        (cred.uid, gid, mode)
+    }
+}
@@ -0,0 +1,109 @@
+//! Project ids group files of a directory subtree, typically to limit the
+//! space used by the subtree with a project quota.
+//!
+//! A directory with the PROJINHERIT flag passes its project id on to new
+//! entries, and new subdirectories inherit the flag itself (see
+//! `InodeFlags::inherited`). Files cannot be linked or moved into such a
+//! directory from another project.
+
+use super::Ext4;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+impl Ext4 {
+    /// Set the project id of an inode. Quota usage is moved to the new
+    /// project.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    /// * `projid` - the new project id
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - the inode is invalid (mode == 0)
+    /// * `EPERM` - the inode is immutable
+    /// * `ENOTSUP` - the filesystem does not support project ids
+    /// * `EDQUOT` - quota of the new project exceeded
+    pub fn set_projid(&self, inode: InodeId, projid: u32) -> Result<()> {
+        self.trans_run(|| {
+            let mut inode_ref = self.read_inode(inode);
+            if inode_ref.inode.mode().bits() == 0 {
+                return_error!(ErrCode::EINVAL, "Invalid inode {}", inode);
+            }
+            Self::check_flags(&inode_ref, InodeFlags::IMMUTABLE)?;
+            if !self.read_super_block().has_project() {
+                return_error!(ErrCode::ENOTSUP, "Project ids are not supported");
+            }
+            if inode_ref.inode.projid() == projid {
+                return Ok(());
+            }
+            let mut new = inode_ref.inode.clone();
+            new.set_projid(projid);
+            self.quota_transfer(&inode_ref, &new)?;
+            inode_ref.inode = new;
+            self.touch_change(&mut inode_ref);
+            self.write_inode_with_csum(&mut inode_ref);
+            Ok(())
+        })
+    }
+
+    /// Initialize the project of a new inode created in `parent`.
+    pub(super) fn project_inherit(&self, parent: &Inode, child: &mut Inode) {
+        if parent.proj_inherit() && self.read_super_block().has_project() {
+            child.set_projid(parent.projid());
+        }
+    }
+
+    /// Check that `child` may be linked into directory `dir`.
+    ///
+    /// # Error
+    ///
+    /// * `EXDEV` - `dir` inherits a project other than that of `child`
+    pub(super) fn project_check_move(&self, dir: &InodeRef, child: &InodeRef) -> Result<()> {
+        if dir.inode.proj_inherit() && dir.inode.projid() != child.inode.projid() {
+            return_error!(
+                ErrCode::EXDEV,
+                "Inode {} is not in project {} of directory {}",
+                child.id,
+                dir.inode.projid(),
+                dir.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Limit the statistics reported for `inode` to the project quota of
+    /// its project, if the inode inherits its project to new entries.
+    pub(super) fn project_statfs(&self, inode: &Inode, stat: &mut StatFs) -> Result<()> {
+        let sb = self.read_super_block();
+        if !inode.proj_inherit() || !sb.has_quota() || sb.prj_quota_inum() == 0 {
+            return Ok(());
+        }
+        let quota = self.get_quota(QuotaType::Project, inode.projid())?;
+        let limits = quota.limits;
+        let limit = min_not_zero(limits.space_soft, limits.space_hard) / stat.bsize as u64;
+        if limit != 0 && stat.blocks > limit {
+            let used = quota.space / stat.bsize as u64;
+            stat.blocks = limit;
+            stat.bfree = limit.saturating_sub(used);
+            stat.bavail = stat.bfree;
+        }
+        let limit = min_not_zero(limits.inode_soft, limits.inode_hard);
+        if limit != 0 && stat.files > limit {
+            stat.files = limit;
+            stat.ffree = limit.saturating_sub(quota.inodes);
+        }
+        Ok(())
+    }
+}
+
+/// The smaller of two limits, where 0 means no limit.
+fn min_not_zero(a: u64, b: u64) -> u64 {
+    match (a, b) {
+        (0, b) => b,
+        (a, 0) => a,
+        (a, b) => a.min(b),
+    }
+}
@@ -0,0 +1,643 @@
+//! Disk quota accounting and enforcement.
+//!
+//! Space and inodes are charged to the owners (user, group and project) of
+//! an inode when allocated (`alloc_block`, `alloc_inode`) and released when
+//! freed (`dealloc_block`, `dealloc_inode`). Changing the owner transfers
+//! the usage.
+//!
+//! Quota is only tracked when the superblock has the quota feature and the
+//! quota file of the type exists. Usage is kept for every owner; limits are
+//! only enforced when set (a limit of 0 means no limit).
+
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::format_error;
+use crate::prelude::*;
+use crate::return_error;
+
+/// Quota blocks in a filesystem block.
+const QUOTA_BLOCKS_PER_FS_BLOCK: u32 = (BLOCK_SIZE / QUOTA_BLOCK_SIZE) as u32;
+
+/// A quota record of one owner, with where it is stored.
+struct QuotaSlot {
+    /// The quota type.
+    ty: QuotaType,
+    /// Quota usage and limits.
+    quota: Quota,
+    /// Byte offset of the record in the quota file, `None` if the
+    /// record does not exist yet.
+    offset: Option<u64>,
+}
+
+impl Ext4 {
+    /// Get the usage and limits of a user, group or project.
+    ///
+    /// # Params
+    ///
+    /// * `ty` - quota type
+    /// * `id` - user, group or project id
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - quota of `ty` is not enabled
+    /// * `EIO` - the quota file is corrupted
+    pub fn get_quota(&self, ty: QuotaType, id: u32) -> Result<Quota> {
+        let qf = self.quota_file(ty)?.ok_or(format_error!(
+            ErrCode::ENOTSUP,
+            "Quota {:?} is not enabled",
+            ty
+        ))?;
+        Ok(self.quota_load(&qf, ty, id)?.quota)
+    }
+
+    /// Set the limits of a user, group or project. The grace period starts if the
+    /// current usage exceeds a new soft limit.
+    ///
+    /// # Params
+    ///
+    /// * `ty` - quota type
+    /// * `id` - user, group or project id
+    /// * `limits` - new limits, 0 for no limit
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - quota of `ty` is not enabled
+    /// * `EIO` - the quota file is corrupted
+    /// * `ENOSPC` - no space to extend the quota file
+    pub fn set_quota_limits(&self, ty: QuotaType, id: u32, limits: QuotaLimits) -> Result<()> {
+        self.trans_run(|| {
+            let mut qf = self.quota_file(ty)?.ok_or(format_error!(
+                ErrCode::ENOTSUP,
+                "Quota {:?} is not enabled",
+                ty
+            ))?;
+            let info = self.quota_read_info(&qf, ty)?;
+            let mut slot = self.quota_load(&qf, ty, id)?;
+            let now = self.now().sec;
+            let quota = &mut slot.quota;
+            quota.limits = limits;
+            Self::quota_reset_grace(
+                quota.space,
+                limits.space_soft,
+                &mut quota.space_grace_end,
+                now + info.block_grace as i64,
+            );
+            Self::quota_reset_grace(
+                quota.inodes,
+                limits.inode_soft,
+                &mut quota.inode_grace_end,
+                now + info.inode_grace as i64,
+            );
+            self.quota_store(&mut qf, &slot)
+        })
+    }
+
+    /// Get the grace periods of a quota type, in seconds.
+    ///
+    /// # Return
+    ///
+    /// `Ok((space_grace, inode_grace))`
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - quota of `ty` is not enabled
+    /// * `EIO` - the quota file is corrupted
+    pub fn get_quota_grace(&self, ty: QuotaType) -> Result<(u32, u32)> {
+        let qf = self.quota_file(ty)?.ok_or(format_error!(
+            ErrCode::ENOTSUP,
+            "Quota {:?} is not enabled",
+            ty
+        ))?;
+        let info = self.quota_read_info(&qf, ty)?;
+        Ok((info.block_grace, info.inode_grace))
+    }
+
+    /// Set the grace periods of a quota type, in seconds. Running grace
+    /// periods are not affected.
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - quota of `ty` is not enabled
+    /// * `EIO` - the quota file is corrupted
+    pub fn set_quota_grace(&self, ty: QuotaType, space_grace: u32, inode_grace: u32) -> Result<()> {
+        self.trans_run(|| {
+            let mut qf = self.quota_file(ty)?.ok_or(format_error!(
+                ErrCode::ENOTSUP,
+                "Quota {:?} is not enabled",
+                ty
+            ))?;
+            let mut info = self.quota_read_info(&qf, ty)?;
+            info.block_grace = space_grace;
+            info.inode_grace = inode_grace;
+            self.quota_write_info(&mut qf, &info)
+        })
+    }
+
+    /// Charge space of a newly allocated block to the owner of `inode`.
+    ///
+    /// # Error
+    ///
+    /// * `EDQUOT` - a quota limit would be exceeded
+    pub(super) fn quota_alloc_space(&self, inode: &InodeRef, bytes: u64) -> Result<()> {
+        if self.quota_exempt(inode.id) {
+            return Ok(());
+        }
+        let ids = self.quota_owner_ids(&inode.inode);
+        self.quota_charge(&ids, bytes as i64, 0)
+    }
+
+    /// Release space of a freed block from the owner of `inode`.
+    pub(super) fn quota_free_space(&self, inode: &InodeRef, bytes: u64) {
+        if self.quota_exempt(inode.id) {
+            return;
+        }
+        let ids = self.quota_owner_ids(&inode.inode);
+        if let Err(e) = self.quota_charge(&ids, -(bytes as i64), 0) {
+            warn!("Failed to release quota of inode {}: {:?}", inode.id, e);
+        }
+    }
+
+    /// Charge a new inode to its owner.
+    ///
+    /// # Error
+    ///
+    /// * `EDQUOT` - a quota limit would be exceeded
+    pub(super) fn quota_alloc_inode(&self, inode: &Inode) -> Result<()> {
+        let ids = self.quota_owner_ids(inode);
+        self.quota_charge(&ids, 0, 1)
+    }
+
+    /// Release a freed inode from its owner.
+    pub(super) fn quota_free_inode(&self, inode: &Inode) {
+        let ids = self.quota_owner_ids(inode);
+        if let Err(e) = self.quota_charge(&ids, 0, -1) {
+            warn!("Failed to release inode quota: {:?}", e);
+        }
+    }
+
+    /// Move the usage of `inode` to the owners of `new`, which is the
+    /// inode with its new uid, gid or project id. Limits of the new owners
+    /// are enforced.
+    ///
+    /// # Error
+    ///
+    /// * `EDQUOT` - a quota limit of the new owner would be exceeded
+    pub(super) fn quota_transfer(&self, inode: &InodeRef, new: &Inode) -> Result<()> {
+        if self.quota_exempt(inode.id) {
+            return Ok(());
+        }
+        let old = self.quota_owner_ids(&inode.inode);
+        let new = self.quota_owner_ids(new);
+        // Only transfer the types whose owner changes
+        let mut from = Vec::new();
+        let mut to = Vec::new();
+        for (o, n) in old.iter().zip(new.iter()) {
+            if o.1 != n.1 {
+                from.push(*o);
+                to.push(*n);
+            }
+        }
+        if to.is_empty() {
+            return Ok(());
+        }
+        let space = self.inode_space(inode) as i64;
+        self.quota_charge(&to, space, 1)?;
+        self.quota_charge(&from, -space, -1)
+    }
+
+    /// Space occupied by an inode, including data blocks, extent tree
+    /// blocks and the xattr block, as charged when they were allocated.
+    /// With bigalloc, this counts whole clusters.
+    fn inode_space(&self, inode: &InodeRef) -> u64 {
+        inode.inode.block_count() * INODE_BLOCK_SIZE as u64
+    }
+
+    /// Ids charged for an inode, for each quota type.
+    fn quota_owner_ids(&self, inode: &Inode) -> [(QuotaType, u32); 3] {
+        [
+            (QuotaType::User, inode.uid()),
+            (QuotaType::Group, inode.gid()),
+            (QuotaType::Project, inode.projid()),
+        ]
+    }
+
+    /// Reserved inodes (except the root) and quota files are not charged.
+    fn quota_exempt(&self, id: InodeId) -> bool {
+        let sb = self.read_super_block();
+        if !sb.has_quota() {
+            return true;
+        }
+        (id != EXT4_ROOT_INO && id < sb.first_inode())
+            || id == sb.usr_quota_inum()
+            || id == sb.grp_quota_inum()
+            || id == sb.prj_quota_inum()
+    }
+
+    /// Add `space` bytes and `inodes` inodes to the usage of each owner in
+    /// `ids`. Limits are checked for increases. Either all records are
+    /// updated or none.
+    fn quota_charge(&self, ids: &[(QuotaType, u32)], space: i64, inodes: i64) -> Result<()> {
+        let now = self.now().sec;
+        let mut slots = Vec::new();
+        for &(ty, id) in ids {
+            let qf = match self.quota_file(ty)? {
+                Some(qf) => qf,
+                None => continue,
+            };
+            let info = self.quota_read_info(&qf, ty)?;
+            let mut slot = self.quota_load(&qf, ty, id)?;
+            let quota = &mut slot.quota;
+            let limits = quota.limits;
+            if space > 0 {
+                let new = quota.space + space as u64;
+                let ok = Self::quota_check(
+                    new,
+                    limits.space_soft,
+                    limits.space_hard,
+                    &mut quota.space_grace_end,
+                    now,
+                    info.block_grace,
+                );
+                if !ok {
+                    return_error!(ErrCode::EDQUOT, "Space quota of {:?} {} exceeded", ty, id);
+                }
+                quota.space = new;
+            } else if space < 0 {
+                quota.space = quota.space.saturating_sub(-space as u64);
+                Self::quota_reset_grace(
+                    quota.space,
+                    limits.space_soft,
+                    &mut quota.space_grace_end,
+                    0,
+                );
+            }
+            if inodes > 0 {
+                let new = quota.inodes + inodes as u64;
+                let ok = Self::quota_check(
+                    new,
+                    limits.inode_soft,
+                    limits.inode_hard,
+                    &mut quota.inode_grace_end,
+                    now,
+                    info.inode_grace,
+                );
+                if !ok {
+                    return_error!(ErrCode::EDQUOT, "Inode quota of {:?} {} exceeded", ty, id);
+                }
+                quota.inodes = new;
+            } else if inodes < 0 {
+                quota.inodes = quota.inodes.saturating_sub(-inodes as u64);
+                Self::quota_reset_grace(
+                    quota.inodes,
+                    limits.inode_soft,
+                    &mut quota.inode_grace_end,
+                    0,
+                );
+            }
+            slots.push(slot);
+        }
+        for slot in slots {
+            // Reload the quota file, it may have changed while storing
+            // the previous record
+            let mut qf = self.quota_file(slot.ty)?.unwrap();
+            self.quota_store(&mut qf, &slot)?;
+        }
+        Ok(())
+    }
+
+    /// Check whether usage `new` is allowed. Start the grace period when
+    /// the soft limit is first exceeded.
+    fn quota_check(
+        new: u64,
+        soft: u64,
+        hard: u64,
+        grace_end: &mut i64,
+        now: i64,
+        grace: u32,
+    ) -> bool {
+        if hard != 0 && new > hard {
+            return false;
+        }
+        if soft != 0 && new > soft {
+            if *grace_end != 0 && now >= *grace_end {
+                // Grace period expired
+                return false;
+            }
+            if *grace_end == 0 {
+                *grace_end = now + grace as i64;
+            }
+        }
+        true
+    }
+
+    /// Clear the grace period if usage is within the soft limit, or start
+    /// it with end time `start_end` if usage exceeds the limit and no
+    /// grace period is running (`start_end` 0 to never start).
+    fn quota_reset_grace(usage: u64, soft: u64, grace_end: &mut i64, start_end: i64) {
+        if soft == 0 || usage <= soft {
+            *grace_end = 0;
+        } else if *grace_end == 0 {
+            *grace_end = start_end;
+        }
+    }
+
+    /// Get the quota file of a type, `None` if quota of the type is
+    /// not enabled.
+    fn quota_file(&self, ty: QuotaType) -> Result<Option<InodeRef>> {
+        let sb = self.read_super_block();
+        if !sb.has_quota() {
+            return Ok(None);
+        }
+        let ino = match ty {
+            QuotaType::User => sb.usr_quota_inum(),
+            QuotaType::Group => sb.grp_quota_inum(),
+            QuotaType::Project => sb.prj_quota_inum(),
+        };
+        if ino == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.read_inode(ino)))
+    }
+
+    /// Create an empty quota file of a type in inode `ino`, which must be
+    /// marked used already.
+    pub(super) fn quota_create_file(&self, ty: QuotaType, ino: InodeId) -> Result<()> {
+        let mut inode = Inode::default();
+        inode.set_mode(InodeMode::FILE | InodeMode::USER_READ | InodeMode::USER_WRITE);
+        inode.set_flags(InodeFlags::IMMUTABLE);
+        inode.extent_init();
+        inode.set_link_count(1);
+        let now = self.now();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_crtime(now);
+        let mut qf = InodeRef::new(ino, inode);
+        self.write_inode_with_csum(&mut qf);
+        // The header and info block, followed by an empty tree root
+        let header = QuotaHeader {
+            magic: ty.magic(),
+            version: QuotaHeader::VERSION,
+        };
+        let info = QuotaInfo {
+            block_grace: QUOTA_DEFAULT_GRACE,
+            inode_grace: QUOTA_DEFAULT_GRACE,
+            flags: 0,
+            blocks: QUOTA_TREE_ROOT + 1,
+            free_blk: 0,
+            free_entry: 0,
+        };
+        let mut block = [0; QUOTA_BLOCK_SIZE];
+        let offset = size_of::<QuotaHeader>();
+        block[..offset].copy_from_slice(header.to_bytes());
+        block[offset..offset + size_of::<QuotaInfo>()].copy_from_slice(info.to_bytes());
+        self.quota_write_block(&mut qf, 0, &block)?;
+        self.quota_write_block(&mut qf, QUOTA_TREE_ROOT, &[0; QUOTA_BLOCK_SIZE])
+    }
+
+    /// Read and validate the header and info block of a quota file.
+    fn quota_read_info(&self, qf: &InodeRef, ty: QuotaType) -> Result<QuotaInfo> {
+        let block = self.quota_read_block(qf, 0);
+        let header = QuotaHeader::from_bytes(&block);
+        if header.magic != ty.magic() || header.version != QuotaHeader::VERSION {
+            return_error!(ErrCode::EIO, "Invalid quota file {}", qf.id);
+        }
+        Ok(QuotaInfo::from_bytes(&block[size_of::<QuotaHeader>()..]))
+    }
+
+    /// Write the info block of a quota file.
+    fn quota_write_info(&self, qf: &mut InodeRef, info: &QuotaInfo) -> Result<()> {
+        let mut block = self.quota_read_block(qf, 0);
+        let offset = size_of::<QuotaHeader>();
+        block[offset..offset + size_of::<QuotaInfo>()].copy_from_slice(info.to_bytes());
+        self.quota_write_block(qf, 0, &block)
+    }
+
+    /// Find the quota record of an id. If not found, return an empty
+    /// record to be inserted on store.
+    fn quota_load(&self, qf: &InodeRef, ty: QuotaType, id: u32) -> Result<QuotaSlot> {
+        self.quota_read_info(qf, ty)?;
+        let mut blk = QUOTA_TREE_ROOT;
+        for depth in 0..QUOTA_TREE_DEPTH {
+            let block = self.quota_read_block(qf, blk);
+            blk = Self::quota_tree_ref(&block, id, depth);
+            if blk == 0 {
+                return Ok(QuotaSlot {
+                    ty,
+                    quota: Quota::new(id),
+                    offset: None,
+                });
+            }
+        }
+        // `blk` is now a data block
+        let block = self.quota_read_block(qf, blk);
+        for i in 0..QUOTA_ENTRIES_PER_BLOCK {
+            let offset = size_of::<QuotaDataHeader>() + i * size_of::<DiskQuota>();
+            if DiskQuota::is_unused(&block[offset..]) {
+                continue;
+            }
+            let quota = DiskQuota::from_bytes(&block[offset..]).to_quota();
+            if quota.id == id {
+                return Ok(QuotaSlot {
+                    ty,
+                    quota,
+                    offset: Some(blk as u64 * QUOTA_BLOCK_SIZE as u64 + offset as u64),
+                });
+            }
+        }
+        return_error!(
+            ErrCode::EIO,
+            "Quota of id {} not found in block {}",
+            id,
+            blk
+        );
+    }
+
+    /// Write a quota record back to the quota file, inserting it into the
+    /// tree if it does not exist.
+    fn quota_store(&self, qf: &mut InodeRef, slot: &QuotaSlot) -> Result<()> {
+        let offset = match slot.offset {
+            Some(offset) => offset,
+            None => self.quota_insert(qf, slot.ty, slot.quota.id)?,
+        };
+        let blk = (offset / QUOTA_BLOCK_SIZE as u64) as u32;
+        let offset = (offset % QUOTA_BLOCK_SIZE as u64) as usize;
+        let mut block = self.quota_read_block(qf, blk);
+        let dq = DiskQuota::from_quota(&slot.quota);
+        block[offset..offset + size_of::<DiskQuota>()].copy_from_slice(dq.to_bytes());
+        self.quota_write_block(qf, blk, &block)
+    }
+
+    /// Insert a record of `id` into the quota tree, return its byte offset
+    /// in the quota file.
+    fn quota_insert(&self, qf: &mut InodeRef, ty: QuotaType, id: u32) -> Result<u64> {
+        let mut info = self.quota_read_info(qf, ty)?;
+        let mut blk = QUOTA_TREE_ROOT;
+        for depth in 0..QUOTA_TREE_DEPTH - 1 {
+            let mut block = self.quota_read_block(qf, blk);
+            let mut next = Self::quota_tree_ref(&block, id, depth);
+            if next == 0 {
+                // Create a new tree block
+                next = self.quota_get_free_block(qf, &mut info)?;
+                Self::quota_set_tree_ref(&mut block, id, depth, next);
+                self.quota_write_block(qf, blk, &block)?;
+            }
+            blk = next;
+        }
+        // `blk` is the last level tree block, find a data block with free entries
+        let mut block = self.quota_read_block(qf, blk);
+        if Self::quota_tree_ref(&block, id, QUOTA_TREE_DEPTH - 1) != 0 {
+            return_error!(ErrCode::EIO, "Quota of id {} already exists", id);
+        }
+        let data_blk = self.quota_find_free_entry(qf, &mut info)?;
+        Self::quota_set_tree_ref(&mut block, id, QUOTA_TREE_DEPTH - 1, data_blk.0);
+        self.quota_write_block(qf, blk, &block)?;
+        self.quota_write_info(qf, &info)?;
+        Ok(data_blk.0 as u64 * QUOTA_BLOCK_SIZE as u64 + data_blk.1 as u64)
+    }
+
+    /// Reserve a free record in a data block. Return the data block and the
+    /// offset of the record in the block. The record is marked used by
+    /// writing a placeholder, and the block leaves the free entry list
+    /// when it gets full.
+    fn quota_find_free_entry(
+        &self,
+        qf: &mut InodeRef,
+        info: &mut QuotaInfo,
+    ) -> Result<(u32, usize)> {
+        let blk = if info.free_entry != 0 {
+            info.free_entry
+        } else {
+            let blk = self.quota_get_free_block(qf, info)?;
+            info.free_entry = blk;
+            blk
+        };
+        let mut block = self.quota_read_block(qf, blk);
+        let mut header = QuotaDataHeader::from_bytes(&block);
+        if header.entries as usize + 1 >= QUOTA_ENTRIES_PER_BLOCK {
+            // The block becomes full, remove it from the free entry list
+            self.quota_remove_free_entry(qf, info, &mut header)?;
+        }
+        header.entries += 1;
+        block[..size_of::<QuotaDataHeader>()].copy_from_slice(header.to_bytes());
+        // Find an unused record
+        let offset = (0..QUOTA_ENTRIES_PER_BLOCK)
+            .map(|i| size_of::<QuotaDataHeader>() + i * size_of::<DiskQuota>())
+            .find(|&offset| DiskQuota::is_unused(&block[offset..]))
+            .ok_or(format_error!(
+                ErrCode::EIO,
+                "No free quota entry in block {}",
+                blk
+            ))?;
+        // Mark the record used
+        let dq = DiskQuota::from_quota(&Quota::new(u32::MAX));
+        block[offset..offset + size_of::<DiskQuota>()].copy_from_slice(dq.to_bytes());
+        self.quota_write_block(qf, blk, &block)?;
+        Ok((blk, offset))
+    }
+
+    /// Unlink a data block from the free entry list.
+    fn quota_remove_free_entry(
+        &self,
+        qf: &mut InodeRef,
+        info: &mut QuotaInfo,
+        header: &mut QuotaDataHeader,
+    ) -> Result<()> {
+        if header.next_free != 0 {
+            let mut block = self.quota_read_block(qf, header.next_free);
+            let mut next = QuotaDataHeader::from_bytes(&block);
+            next.prev_free = header.prev_free;
+            block[..size_of::<QuotaDataHeader>()].copy_from_slice(next.to_bytes());
+            self.quota_write_block(qf, header.next_free, &block)?;
+        }
+        if header.prev_free != 0 {
+            let mut block = self.quota_read_block(qf, header.prev_free);
+            let mut prev = QuotaDataHeader::from_bytes(&block);
+            prev.next_free = header.next_free;
+            block[..size_of::<QuotaDataHeader>()].copy_from_slice(prev.to_bytes());
+            self.quota_write_block(qf, header.prev_free, &block)?;
+        } else {
+            info.free_entry = header.next_free;
+        }
+        header.next_free = 0;
+        header.prev_free = 0;
+        Ok(())
+    }
+
+    /// Get a zeroed quota block, from the free block list or by
+    /// extending the file.
+    fn quota_get_free_block(&self, qf: &mut InodeRef, info: &mut QuotaInfo) -> Result<u32> {
+        let blk = if info.free_blk != 0 {
+            let blk = info.free_blk;
+            let block = self.quota_read_block(qf, blk);
+            info.free_blk = QuotaDataHeader::from_bytes(&block).next_free;
+            blk
+        } else {
+            let blk = info.blocks;
+            info.blocks += 1;
+            blk
+        };
+        self.quota_write_block(qf, blk, &[0; QUOTA_BLOCK_SIZE])?;
+        Ok(blk)
+    }
+
+    /// Get the reference to the next level of a tree block for an id.
+    fn quota_tree_ref(block: &[u8; QUOTA_BLOCK_SIZE], id: u32, depth: usize) -> u32 {
+        let offset = Self::quota_tree_index(id, depth) * 4;
+        u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Set the reference to the next level of a tree block for an id.
+    fn quota_set_tree_ref(block: &mut [u8; QUOTA_BLOCK_SIZE], id: u32, depth: usize, blk: u32) {
+        let offset = Self::quota_tree_index(id, depth) * 4;
+        block[offset..offset + 4].copy_from_slice(&blk.to_le_bytes());
+    }
+
+    /// Index of an id in a tree block at `depth`, one byte of the id from
+    /// the most significant.
+    fn quota_tree_index(id: u32, depth: usize) -> usize {
+        let shift = 8 * (QUOTA_TREE_DEPTH - 1 - depth);
+        (id as usize >> shift) % QUOTA_REFS_PER_BLOCK
+    }
+
+    /// Read a quota block of a quota file. Blocks beyond the end of the
+    /// file read as zeros.
+    fn quota_read_block(&self, qf: &InodeRef, blk: u32) -> [u8; QUOTA_BLOCK_SIZE] {
+        let mut data = [0; QUOTA_BLOCK_SIZE];
+        let iblock = blk / QUOTA_BLOCKS_PER_FS_BLOCK;
+        if let Ok(pblock) = self.extent_query(qf, iblock) {
+            let block = self.read_block(pblock);
+            let offset = (blk % QUOTA_BLOCKS_PER_FS_BLOCK) as usize * QUOTA_BLOCK_SIZE;
+            data.copy_from_slice(block.read_offset(offset, QUOTA_BLOCK_SIZE));
+        }
+        data
+    }
+
+    /// Write a quota block of a quota file, extending the file if needed.
+    fn quota_write_block(
+        &self,
+        qf: &mut InodeRef,
+        blk: u32,
+        data: &[u8; QUOTA_BLOCK_SIZE],
+    ) -> Result<()> {
+        let iblock = blk / QUOTA_BLOCKS_PER_FS_BLOCK;
+        while self.inode_data_blocks(qf) <= iblock as u64 {
+            let (_, pblock) = self.inode_append_block(qf)?;
+            self.write_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+        }
+        let pblock = self.extent_query(qf, iblock)?;
+        let mut block = self.read_block(pblock);
+        let offset = (blk % QUOTA_BLOCKS_PER_FS_BLOCK) as usize * QUOTA_BLOCK_SIZE;
+        block.write_offset(offset, data);
+        self.write_block(&block);
+        // Quota records are only logged by full commits
+        self.trans_fc_ineligible();
+        let end = (blk as u64 + 1) * QUOTA_BLOCK_SIZE as u64;
+        if qf.inode.size() < end {
+            qf.inode.set_size(end);
+        }
+        self.write_inode_with_csum(qf);
+        Ok(())
+    }
+}
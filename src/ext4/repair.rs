@@ -0,0 +1,472 @@
+//! Repair of common damage found by the consistency check.
+//!
+//! The repair runs in phases, each based on a new check:
+//!
+//! 1. Extents pointing outside the filesystem are removed or shortened,
+//!    directory entries pointing to free inodes are removed, bad
+//!    checksums are rewritten, and bad xattrs are rehashed or dropped.
+//! 2. Bitmaps are rebuilt from the blocks and inodes in use, and free
+//!    counts and block counts of inodes are recomputed.
+//! 3. Unattached inodes are reconnected into `lost+found`.
+//! 4. Link counts are set to the number of references.
+//!
+//! Damaged extent tree nodes and directory blocks are reported by the
+//! check but not repaired.
+
+use super::check::{CheckProblem, Checker};
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+
+/// A fix made or planned by `Ext4::repair`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairAction {
+    /// Remove or shorten the extent or index of an inode that maps
+    /// `block` outside the filesystem.
+    TruncateExtent { inode: InodeId, block: PBlockId },
+    /// Remove a directory entry pointing to an inode not in use.
+    RemoveDirEntry { dir: InodeId, name: String },
+    /// Rewrite the superblock checksum.
+    SuperBlockChecksum,
+    /// Rewrite the checksum of a block group descriptor.
+    GroupDescChecksum { group: BlockGroupId },
+    /// Rewrite the checksum of an inode.
+    InodeChecksum { inode: InodeId },
+    /// Rewrite the checksum of an extent tree block.
+    ExtentBlockChecksum { inode: InodeId, block: PBlockId },
+    /// Rewrite the checksum of a directory block.
+    DirBlockChecksum { inode: InodeId, block: PBlockId },
+    /// Rewrite the checksum of an xattr block.
+    XattrBlockChecksum { inode: InodeId, block: PBlockId },
+    /// Rewrite the entries and hashes of an xattr block, or drop the block
+    /// if its entries can't be read.
+    XattrBlock { inode: InodeId, block: PBlockId },
+    /// Rewrite the entries and hashes of the xattrs in the body of an
+    /// inode, or drop them if they can't be read.
+    XattrIbody { inode: InodeId },
+    /// Set the block count of an inode, in 512-byte units.
+    BlockCount { inode: InodeId, blocks: u64 },
+    /// Rebuild the block bitmap of a block group.
+    BlockBitmap { group: BlockGroupId },
+    /// Rebuild the inode bitmap of a block group.
+    InodeBitmap { group: BlockGroupId },
+    /// Recompute the free counts of a block group.
+    GroupCounts { group: BlockGroupId },
+    /// Recompute the free counts of the superblock.
+    FreeCounts,
+    /// Link an unattached inode into `lost+found` as `#<inode>`.
+    Reconnect { inode: InodeId },
+    /// Set the link count of an inode.
+    LinkCount { inode: InodeId, links: u16 },
+}
+
+impl RepairAction {
+    /// The phase of the repair making this fix.
+    fn phase(&self) -> usize {
+        match self {
+            Self::BlockBitmap { .. }
+            | Self::InodeBitmap { .. }
+            | Self::GroupCounts { .. }
+            | Self::FreeCounts
+            | Self::BlockCount { .. } => 1,
+            Self::Reconnect { .. } => 2,
+            Self::LinkCount { .. } => 3,
+            _ => 0,
+        }
+    }
+}
+
+/// The result of `Ext4::repair`.
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    /// The fixes in the order they were made, or planned in a dry run.
+    pub actions: Vec<RepairAction>,
+}
+
+impl Ext4 {
+    /// Repair the damage found by `check`.
+    ///
+    /// Block and inode bitmaps are rebuilt, free counts and block counts
+    /// recomputed, link counts fixed, unattached inodes reconnected into
+    /// `lost+found`, extents outside the filesystem truncated, bad xattrs
+    /// rehashed or dropped and bad checksums rewritten. Each phase of the
+    /// repair is based on a new check, and its fixes are made in
+    /// transactions that fit in the log. After a crash, running the
+    /// repair again finishes it.
+    ///
+    /// # Params
+    ///
+    /// * `dry_run` - only list the fixes for the damage found now. Later
+    ///   phases of a real repair may find more to fix, e.g. the link count
+    ///   of a reconnected inode.
+    ///
+    /// # Return
+    ///
+    /// The fixes made, or planned in a dry run
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - no space left to create `lost+found` or its entries
+    pub fn repair(&self, dry_run: bool) -> Result<RepairReport> {
+        if dry_run {
+            return Ok(RepairReport {
+                actions: Self::plan_repair(&self.run_check()),
+            });
+        }
+        let max_blocks = self.trans_chunk_blocks()?;
+        let mut actions = Vec::new();
+        for phase in 0..4 {
+            let checker = self.run_check();
+            let mut planned = Self::plan_repair(&checker)
+                .into_iter()
+                .filter(|action| action.phase() == phase)
+                .peekable();
+            // Commit the fixes before they outgrow the log
+            while planned.peek().is_some() {
+                self.trans_run(|| {
+                    for action in planned.by_ref() {
+                        self.repair_one(&checker, &action)?;
+                        actions.push(action);
+                        if self.trans_pending_blocks() >= max_blocks {
+                            break;
+                        }
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(RepairReport { actions })
+    }
+
+    /// List the fixes for the problems found by a check.
+    fn plan_repair(checker: &Checker) -> Vec<RepairAction> {
+        let sb = &checker.sb;
+        let block_group = |block: PBlockId| {
+            ((block - sb.first_data_block() as PBlockId) / sb.blocks_per_group() as PBlockId)
+                as BlockGroupId
+        };
+        let inode_group = |inode: InodeId| (inode - 1) / sb.inodes_per_group();
+        let mut actions = Vec::new();
+        for problem in &checker.problems {
+            let action = match problem {
+                CheckProblem::BlockOutOfRange { inode, block } if *inode != 0 => {
+                    RepairAction::TruncateExtent {
+                        inode: *inode,
+                        block: *block,
+                    }
+                }
+                CheckProblem::DirEntryBadInode { dir, name, .. } => RepairAction::RemoveDirEntry {
+                    dir: *dir,
+                    name: name.clone(),
+                },
+                CheckProblem::SuperBlockChecksum => RepairAction::SuperBlockChecksum,
+                CheckProblem::GroupDescChecksum { group } => {
+                    RepairAction::GroupDescChecksum { group: *group }
+                }
+                CheckProblem::InodeChecksum { inode } => {
+                    RepairAction::InodeChecksum { inode: *inode }
+                }
+                CheckProblem::ExtentBlockChecksum { inode, block } => {
+                    RepairAction::ExtentBlockChecksum {
+                        inode: *inode,
+                        block: *block,
+                    }
+                }
+                CheckProblem::DirBlockChecksum { inode, block } => RepairAction::DirBlockChecksum {
+                    inode: *inode,
+                    block: *block,
+                },
+                CheckProblem::XattrBlockChecksum { inode, block } => {
+                    RepairAction::XattrBlockChecksum {
+                        inode: *inode,
+                        block: *block,
+                    }
+                }
+                CheckProblem::BadXattrBlock { inode, block } => RepairAction::XattrBlock {
+                    inode: *inode,
+                    block: *block,
+                },
+                CheckProblem::BadXattrIbody { inode } => RepairAction::XattrIbody { inode: *inode },
+                CheckProblem::BlockCount {
+                    inode, expected, ..
+                } => RepairAction::BlockCount {
+                    inode: *inode,
+                    blocks: *expected,
+                },
+                CheckProblem::BlockBitmapChecksum { group } => {
+                    RepairAction::BlockBitmap { group: *group }
+                }
+                CheckProblem::BlockNotMarked { block }
+                | CheckProblem::BlockMarkedUsed { block } => RepairAction::BlockBitmap {
+                    group: block_group(*block),
+                },
+                CheckProblem::InodeBitmapChecksum { group } => {
+                    RepairAction::InodeBitmap { group: *group }
+                }
+                CheckProblem::InodeNotMarked { inode }
+                | CheckProblem::InodeMarkedUsed { inode } => RepairAction::InodeBitmap {
+                    group: inode_group(*inode),
+                },
+                CheckProblem::GroupFreeBlocks { group, .. }
+                | CheckProblem::GroupFreeInodes { group, .. }
+                | CheckProblem::GroupUsedDirs { group, .. } => {
+                    RepairAction::GroupCounts { group: *group }
+                }
+                CheckProblem::FreeBlocks { .. } | CheckProblem::FreeInodes { .. } => {
+                    RepairAction::FreeCounts
+                }
+                CheckProblem::UnattachedInode { inode } => {
+                    RepairAction::Reconnect { inode: *inode }
+                }
+                CheckProblem::LinkCount {
+                    inode, expected, ..
+                } => RepairAction::LinkCount {
+                    inode: *inode,
+                    links: *expected,
+                },
+                _ => continue,
+            };
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+
+    /// Make a fix, based on what `checker` found in use.
+    fn repair_one(&self, checker: &Checker, action: &RepairAction) -> Result<()> {
+        let uuid = self.read_super_block().uuid();
+        match *action {
+            RepairAction::TruncateExtent { inode, .. } => {
+                let mut inode_ref = self.read_inode(inode);
+                if inode_ref.inode.flags().contains(InodeFlags::EXTENTS) {
+                    let (id, generation) = (inode_ref.id, inode_ref.inode.generation());
+                    let mut root = inode_ref.inode.extent_root_mut();
+                    if self.repair_extent_node(&mut root, id, generation) {
+                        self.write_inode_with_csum(&mut inode_ref);
+                    }
+                }
+            }
+            RepairAction::RemoveDirEntry { dir, ref name } => {
+                let mut dir = self.read_inode(dir);
+                self.dir_remove_disk_entry(&mut dir, name.as_bytes(), false)?;
+            }
+            RepairAction::SuperBlockChecksum => {
+                self.write_super_block(&self.read_super_block());
+            }
+            RepairAction::GroupDescChecksum { group } => {
+                self.write_block_group_with_csum(&mut self.read_block_group(group));
+            }
+            RepairAction::InodeChecksum { inode } => {
+                self.write_inode_with_csum(&mut self.read_inode(inode));
+            }
+            RepairAction::ExtentBlockChecksum { inode, block } => {
+                let inode = self.read_inode(inode);
+                let mut block = self.read_block(block);
+                ExtentNodeMut::from_bytes(&mut block.data).set_checksum(
+                    &uuid,
+                    inode.id,
+                    inode.inode.generation(),
+                );
+                self.write_block(&block);
+            }
+            RepairAction::DirBlockChecksum { inode, block } => {
+                let inode = self.read_inode(inode);
+                let mut dir_block = DirBlock::new(self.read_block(block));
+                dir_block.set_checksum(&uuid, inode.id, inode.inode.generation());
+                self.write_block(dir_block.block());
+            }
+            RepairAction::XattrBlockChecksum { block, .. } => {
+                let mut xattr_block = XattrBlock::new(self.read_block(block));
+                xattr_block.set_checksum(&uuid);
+                self.write_block(&xattr_block.block());
+            }
+            RepairAction::XattrBlock { inode, block } => {
+                let mut xattr_block = XattrBlock::new(self.read_block(block));
+                if xattr_block.rehash() {
+                    xattr_block.set_checksum(&uuid);
+                    self.write_block(&xattr_block.block());
+                } else {
+                    // The block is freed when the bitmaps are rebuilt
+                    let mut inode = self.read_inode(inode);
+                    inode.inode.set_xattr_block(0);
+                    self.write_inode_with_csum(&mut inode);
+                }
+            }
+            RepairAction::XattrIbody { inode } => {
+                let mut inode = self.read_inode(inode);
+                let mut ibody = XattrIbody::new(self.read_inode_xattr_area(&inode));
+                if !ibody.rehash() {
+                    ibody.clear();
+                }
+                self.write_inode_xattr_area(&inode, ibody.area());
+                self.write_inode_with_csum(&mut inode);
+            }
+            RepairAction::BlockCount { inode, blocks } => {
+                let mut inode = self.read_inode(inode);
+                inode.inode.set_block_count(blocks);
+                self.write_inode_with_csum(&mut inode);
+            }
+            RepairAction::BlockBitmap { group } => self.rebuild_block_bitmap(checker, group),
+            RepairAction::InodeBitmap { group } => self.rebuild_inode_bitmap(checker, group),
+            RepairAction::GroupCounts { group } => {
+                let mut bg = self.read_block_group(group);
+                let free_blocks = checker.group_free_blocks(group);
+                if bg.desc.flags().contains(BlockGroupFlags::BLOCK_UNINIT)
+                    && bg.desc.get_free_blocks_count() != free_blocks
+                {
+                    // Blocks are used in a group without a block bitmap
+                    self.rebuild_block_bitmap(checker, group);
+                    bg = self.read_block_group(group);
+                }
+                bg.desc.set_free_blocks_count(free_blocks);
+                bg.desc
+                    .set_free_inodes_count(checker.group_free_inodes(group));
+                bg.desc.set_used_dirs_count(checker.group_dirs(group));
+                self.write_block_group_with_csum(&mut bg);
+            }
+            RepairAction::FreeCounts => {
+                let mut sb = self.read_super_block();
+                let groups = 0..sb.block_group_count();
+                let free_clusters: u64 = groups
+                    .clone()
+                    .map(|bgid| checker.group_free_blocks(bgid))
+                    .sum();
+                sb.set_free_blocks_count(free_clusters * sb.cluster_ratio() as u64);
+                sb.set_free_inodes_count(groups.map(|bgid| checker.group_free_inodes(bgid)).sum());
+                self.write_super_block(&sb);
+            }
+            RepairAction::Reconnect { inode } => {
+                let mut lost_found = self.lost_found()?;
+                let mut child = self.read_inode(inode);
+                self.dir_add_entry(&mut lost_found, &child, &format!("#{}", inode))?;
+                if child.inode.is_dir() {
+                    // Point ".." to lost+found
+                    let _ = self.dir_remove_entry(&mut child, "..");
+                    self.dir_add_entry(&mut child, &lost_found, "..")?;
+                    lost_found
+                        .inode
+                        .set_link_count(lost_found.inode.link_count() + 1);
+                    self.write_inode_with_csum(&mut lost_found);
+                }
+            }
+            RepairAction::LinkCount { inode, links } => {
+                let mut inode = self.read_inode(inode);
+                inode.inode.set_link_count(links);
+                self.write_inode_with_csum(&mut inode);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the extents and indexes of a node that map blocks outside
+    /// the filesystem, and shorten the extents that end outside. Return
+    /// true if the node is modified.
+    fn repair_extent_node(&self, node: &mut ExtentNodeMut, ino: InodeId, generation: u32) -> bool {
+        let sb = self.read_super_block();
+        let in_range = |block: PBlockId| {
+            block >= sb.first_data_block() as PBlockId && block < sb.block_count()
+        };
+        let mut modified = false;
+        let mut i = 0;
+        while i < node.header().entries_count() as usize {
+            if node.header().depth() == 0 {
+                let extent = node.extent_mut_at(i);
+                let start = extent.start_pblock();
+                if !in_range(start) {
+                    node.remove_extent(i);
+                    modified = true;
+                    continue;
+                }
+                if !in_range(start + extent.block_count() as PBlockId - 1) {
+                    let unwritten = extent.is_unwritten();
+                    extent.set_block_count((sb.block_count() - start) as LBlockId);
+                    if unwritten {
+                        extent.mark_unwritten();
+                    }
+                    modified = true;
+                }
+            } else {
+                let leaf = node.extent_index_at(i).leaf();
+                if !in_range(leaf) {
+                    node.remove_extent(i);
+                    modified = true;
+                    continue;
+                }
+                let mut block = self.read_block(leaf);
+                let mut child = ExtentNodeMut::from_bytes(&mut block.data);
+                if child.header().check_magic()
+                    && self.repair_extent_node(&mut child, ino, generation)
+                {
+                    child.set_checksum(&sb.uuid(), ino, generation);
+                    self.write_block(&block);
+                }
+            }
+            i += 1;
+        }
+        modified
+    }
+
+    /// Rebuild the block bitmap of a group from the claimed blocks, with
+    /// one bit per cluster.
+    fn rebuild_block_bitmap(&self, checker: &Checker, bgid: BlockGroupId) {
+        let sb = self.read_super_block();
+        let mut bg = self.read_block_group(bgid);
+        let mut block = self.read_block(bg.desc.block_bitmap_block());
+        let first = sb.group_first_block(bgid);
+        let ratio = sb.cluster_ratio() as PBlockId;
+        let count = sb.clusters_in_group(bgid) as usize;
+        let mut bitmap = Bitmap::new(&mut block.data, sb.clusters_per_group() as usize);
+        for i in 0..sb.clusters_per_group() as usize {
+            // Clusters past the end of the filesystem are marked used
+            if i >= count || checker.is_cluster_claimed(first + i as PBlockId * ratio) {
+                bitmap.set_bit(i);
+            } else {
+                bitmap.clear_bit(i);
+            }
+        }
+        bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+        bg.desc
+            .set_flags(bg.desc.flags() - BlockGroupFlags::BLOCK_UNINIT);
+        self.write_block(&block);
+        self.write_block_group_with_csum(&mut bg);
+    }
+
+    /// Rebuild the inode bitmap of a group from the inodes in use.
+    fn rebuild_inode_bitmap(&self, checker: &Checker, bgid: BlockGroupId) {
+        let sb = self.read_super_block();
+        let mut bg = self.read_block_group(bgid);
+        let mut block = self.read_block(bg.desc.inode_bitmap_block());
+        let ipg = sb.inodes_per_group();
+        let first_ino = bgid * ipg + 1;
+        let count = sb.inode_count_in_group(bgid);
+        // Bits past the inodes of the group are marked used
+        block.data[ipg as usize / 8..].fill(0xFF);
+        let mut bitmap = Bitmap::new(&mut block.data, ipg as usize);
+        for i in 0..ipg {
+            if i >= count || checker.inode_in_use(first_ino + i) {
+                bitmap.set_bit(i as usize);
+            } else {
+                bitmap.clear_bit(i as usize);
+            }
+        }
+        bg.desc.set_inode_bitmap_csum(&sb.uuid(), &bitmap);
+        self.write_block(&block);
+        self.write_block_group_with_csum(&mut bg);
+    }
+
+    /// Find `lost+found` in the root directory, or create it.
+    fn lost_found(&self) -> Result<InodeRef> {
+        let root = self.read_root_inode();
+        let id = match self.dir_find_entry(&root, "lost+found") {
+            Ok(id) => id,
+            Err(_) => self.mkdir(
+                EXT4_ROOT_INO,
+                "lost+found",
+                InodeMode::from_bits_retain(0o700),
+            )?,
+        };
+        Ok(self.read_inode(id))
+    }
+}
@@ -0,0 +1,274 @@
+//! Growing a loaded filesystem.
+//!
+//! The last block group is extended, then new block groups are appended
+//! with the layout of `Ext4::format`: the superblock backup, the group
+//! descriptor table and its reserved blocks, the bitmaps and the inode
+//! table, followed by the data blocks.
+//!
+//! When the descriptors of the new groups do not fit in the descriptor
+//! table, it grows into the blocks reserved after it. They are taken out
+//! of the resize inode, which lists each reserved block and its backups
+//! in the other groups with a superblock. Backups of the remaining
+//! reserved blocks are added to it for the new groups. Filesystems with
+//! `meta_bg` are not supported.
+
+use super::format::MIN_GROUP_DATA_BLOCKS;
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+use core::ops::Range;
+
+/// The resize inode, which owns the reserved group descriptor blocks.
+pub(super) const RESIZE_INO: InodeId = 7;
+
+/// The double indirect block pointer of the resize inode.
+pub(super) const RESIZE_DIND_BLOCK: usize = 13;
+
+/// Number of block numbers in an indirect block.
+pub(super) const ADDRS_PER_BLOCK: usize = BLOCK_SIZE / 4;
+
+impl Ext4 {
+    /// Grow the filesystem to `new_block_count` blocks while it stays
+    /// loaded. The block device must already hold that many blocks. A new
+    /// last block group too small for its metadata is left out.
+    ///
+    /// # Params
+    ///
+    /// * `new_block_count` - the new number of blocks of the filesystem
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - `new_block_count` is smaller than the current size
+    /// * `ENOSPC` - the group descriptor table has no reserved block left
+    ///   to grow, or the journal is too small for the update
+    /// * `EIO` - the resize inode does not list a reserved block
+    /// * `ENOTSUP` - the filesystem uses bigalloc
+    pub fn resize(&self, new_block_count: u64) -> Result<()> {
+        self.trans_run(|| {
+            let old = self.read_super_block();
+            if new_block_count < old.block_count() {
+                return_error!(
+                    ErrCode::EINVAL,
+                    "Cannot shrink from {} to {} blocks",
+                    old.block_count(),
+                    new_block_count
+                );
+            }
+            if old.has_bigalloc() {
+                return_error!(ErrCode::ENOTSUP, "Cannot resize with bigalloc");
+            }
+            let mut sb = old;
+            sb.set_block_count(new_block_count);
+            sb.set_block_count(Self::resize_geometry(&old, &sb));
+            if sb.block_count() == old.block_count() {
+                return Ok(());
+            }
+            let old_groups = old.block_group_count();
+            let new_groups = sb.block_group_count();
+
+            // Grow the group descriptor table into the reserved blocks
+            let old_gdt = old.group_desc_blocks();
+            let new_gdt = sb.group_desc_blocks();
+            if new_gdt > old_gdt {
+                if !old.has_resize_inode() || old.reserved_gdt_blocks() < new_gdt - old_gdt {
+                    return_error!(
+                        ErrCode::ENOSPC,
+                        "No reserved block to grow the group descriptor table to {} blocks",
+                        new_gdt
+                    );
+                }
+                sb.set_reserved_gdt_blocks(old.reserved_gdt_blocks() - (new_gdt - old_gdt));
+                self.resize_take_reserved_gdt(&old, old_gdt..new_gdt)?;
+            }
+
+            let mut free_blocks = old.free_blocks_count();
+            let mut free_inodes = old.free_inodes_count();
+            free_blocks += self.resize_extend_group(&old, &sb, old_groups - 1);
+            // Descriptors of the new groups are located with the new superblock
+            sb.set_inode_count(new_groups * sb.inodes_per_group());
+            self.write_super_block(&sb);
+            for bgid in old_groups..new_groups {
+                let (mut bg, bitmaps) = Self::format_group(&sb, bgid, None);
+                for block in bitmaps.iter() {
+                    self.write_block(block);
+                }
+                for pblock in Self::format_group_inode_table(&sb, bgid) {
+                    self.write_data_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+                }
+                free_blocks += bg.desc.get_free_blocks_count();
+                free_inodes += bg.desc.free_inodes_count();
+                self.write_block_group_with_csum(&mut bg);
+            }
+            if sb.has_resize_inode() && sb.reserved_gdt_blocks() > 0 {
+                self.resize_add_reserved_backups(&sb, old_groups..new_groups);
+            }
+
+            // Keep the same share of reserved blocks
+            let reserved = old.reserved_blocks_count() as u128 * sb.block_count() as u128
+                / old.block_count() as u128;
+            sb.set_reserved_blocks_count(reserved as u64);
+            sb.set_free_blocks_count(free_blocks);
+            sb.set_free_inodes_count(free_inodes);
+            self.write_super_block(&sb);
+            self.write_backup_super();
+            // Tags of a large filesystem need 64-bit block numbers
+            if let Some(journal) = &self.journal {
+                self.journal_load(journal)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The block count of `sb`, without a new last group too small for
+    /// its metadata. `old` is the superblock before the resize.
+    fn resize_geometry(old: &SuperBlock, sb: &SuperBlock) -> u64 {
+        let last = sb.block_group_count() - 1;
+        if last < old.block_group_count() {
+            return sb.block_count();
+        }
+        let first = sb.group_first_block(last);
+        let overhead = Self::format_group_inode_table(sb, last).end - first;
+        if sb.block_count() - first >= overhead + MIN_GROUP_DATA_BLOCKS {
+            sb.block_count()
+        } else {
+            first
+        }
+    }
+
+    /// Take the reserved blocks at `gdt` indexes of the group descriptor
+    /// table out of the resize inode. Their backups become backups of the
+    /// descriptor table.
+    fn resize_take_reserved_gdt(&self, old: &SuperBlock, gdt: Range<u32>) -> Result<()> {
+        let mut resize = self.read_inode(RESIZE_INO);
+        let dind = resize.inode.block_pointers()[RESIZE_DIND_BLOCK] as PBlockId;
+        let mut dind = self.read_block(dind);
+        let mut released = 0;
+        for i in gdt {
+            let pblock = old.first_data_block() as PBlockId + 1 + i as PBlockId;
+            let offset = i as usize % ADDRS_PER_BLOCK * 4;
+            let ptr = u32::from_le_bytes(dind.data[offset..offset + 4].try_into().unwrap());
+            if ptr as PBlockId != pblock {
+                return_error!(
+                    ErrCode::EIO,
+                    "Resize inode does not reserve block {}",
+                    pblock
+                );
+            }
+            let backups = self.read_block(pblock);
+            released += 1 + backups
+                .data
+                .chunks_exact(4)
+                .filter(|ptr| *ptr != [0; 4])
+                .count() as u64;
+            dind.write_offset(offset, &0u32.to_le_bytes());
+            self.write_block(&Block::new(pblock, [0; BLOCK_SIZE]));
+        }
+        self.write_block(&dind);
+        let blocks = resize.inode.fs_block_count() - released;
+        resize.inode.set_fs_block_count(blocks);
+        self.write_inode_with_csum(&mut resize);
+        Ok(())
+    }
+
+    /// Extend the block bitmap of group `bgid` from its size in `old` to
+    /// its size in `sb`. Return the number of blocks added.
+    fn resize_extend_group(&self, old: &SuperBlock, sb: &SuperBlock, bgid: BlockGroupId) -> u64 {
+        let old_count = old.blocks_in_group(bgid);
+        let new_count = sb.blocks_in_group(bgid);
+        if new_count == old_count {
+            return 0;
+        }
+        let mut bg = self.read_block_group(bgid);
+        let mut block = if bg.desc.flags().contains(BlockGroupFlags::BLOCK_UNINIT) {
+            self.resize_init_block_bitmap(old, bgid)
+        } else {
+            self.read_block(bg.desc.block_bitmap_block())
+        };
+        let mut bitmap = Bitmap::new(&mut block.data, 8 * BLOCK_SIZE);
+        for bit in old_count..new_count {
+            bitmap.clear_bit(bit as usize);
+        }
+        bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+        bg.desc
+            .set_flags(bg.desc.flags() - BlockGroupFlags::BLOCK_UNINIT);
+        let free = bg.desc.get_free_blocks_count() + new_count - old_count;
+        bg.desc.set_free_blocks_count(free);
+        self.write_block(&block);
+        self.write_block_group_with_csum(&mut bg);
+        new_count - old_count
+    }
+
+    /// Build the block bitmap of an uninitialized group: the superblock
+    /// backup and the metadata of any group in it are used, and clusters
+    /// past the end of the group are padded as used.
+    pub(super) fn resize_init_block_bitmap(&self, sb: &SuperBlock, bgid: BlockGroupId) -> Block {
+        let bg = self.read_block_group(bgid);
+        let first = sb.group_first_block(bgid);
+        let ratio = sb.cluster_ratio() as PBlockId;
+        let count = sb.clusters_in_group(bgid);
+        let mut block = Block::new(bg.desc.block_bitmap_block(), [0; BLOCK_SIZE]);
+        let mut bitmap = Bitmap::new(&mut block.data, 8 * BLOCK_SIZE);
+        let mut mark = |pblock: PBlockId| {
+            if (first..first + count * ratio).contains(&pblock) {
+                bitmap.set_bit(((pblock - first) / ratio) as usize);
+            }
+        };
+        if sb.group_has_super(bgid) {
+            let len = 1 + sb.group_desc_blocks() + sb.reserved_gdt_blocks();
+            (first..first + len as PBlockId).for_each(&mut mark);
+        }
+        let itable_blocks = (sb.inodes_per_group() as usize * sb.inode_size() / BLOCK_SIZE) as u64;
+        for other in 0..sb.block_group_count() {
+            let desc = self.read_block_group(other).desc;
+            mark(desc.block_bitmap_block());
+            mark(desc.inode_bitmap_block());
+            let itable = desc.inode_table_first_block();
+            (itable..itable + itable_blocks).for_each(&mut mark);
+        }
+        for bit in count as usize..8 * BLOCK_SIZE {
+            bitmap.set_bit(bit);
+        }
+        block
+    }
+
+    /// List the backups of the reserved group descriptor blocks in the
+    /// new groups with a superblock in the resize inode.
+    fn resize_add_reserved_backups(&self, sb: &SuperBlock, new_groups: Range<BlockGroupId>) {
+        let backup_groups: Vec<BlockGroupId> = new_groups
+            .filter(|&bgid| sb.group_has_super(bgid))
+            .collect();
+        if backup_groups.is_empty() {
+            return;
+        }
+        let mut resize = self.read_inode(RESIZE_INO);
+        let mut added = 0;
+        for i in 0..sb.reserved_gdt_blocks() {
+            let pblock =
+                sb.first_data_block() as PBlockId + 1 + (sb.group_desc_blocks() + i) as PBlockId;
+            let mut block = self.read_block(pblock);
+            // Backups are listed in group order, after those of the old groups
+            let mut offset = block
+                .data
+                .chunks_exact(4)
+                .position(|ptr| ptr == [0; 4])
+                .unwrap_or(ADDRS_PER_BLOCK)
+                * 4;
+            for &bgid in backup_groups.iter() {
+                if offset == BLOCK_SIZE {
+                    break;
+                }
+                let backup =
+                    pblock + sb.group_first_block(bgid) - sb.first_data_block() as PBlockId;
+                block.write_offset(offset, &(backup as u32).to_le_bytes());
+                offset += 4;
+                added += 1;
+            }
+            self.write_block(&block);
+        }
+        let blocks = resize.inode.fs_block_count() + added;
+        resize.inode.set_fs_block_count(blocks);
+        self.write_inode_with_csum(&mut resize);
+    }
+}
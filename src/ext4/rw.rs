@@ -4,8 +4,30 @@ use crate::ext4_defs::*;
 use crate::prelude::*;
 
 impl Ext4 {
-    /// Read a block from block device
+    /// Read a block, from the running transaction if written by it
     pub(super) fn read_block(&self, block_id: PBlockId) -> Block {
+        if let Some(block) = self.trans_read_block(block_id) {
+            return block;
+        }
+        self.device_read_block(block_id)
+    }
+
+    /// Write a metadata block, to the running transaction if any
+    pub(super) fn write_block(&self, block: &Block) {
+        self.trans_save_block(block.id);
+        if !self.trans_write_block(block) {
+            self.device_write_block(block)
+        }
+    }
+
+    /// Write a file data block in place, bypassing the journal
+    pub(super) fn write_data_block(&self, block: &Block) {
+        self.trans_forget_block(block.id);
+        self.device_write_block(block)
+    }
+
+    /// Read a block from block device
+    pub(super) fn device_read_block(&self, block_id: PBlockId) -> Block {
         #[cfg(feature = "block_cache")]
         {
             self.block_cache.read_block(block_id)
@@ -17,7 +39,7 @@ impl Ext4 {
     }
 
     /// Write a block to block device
-    pub(super) fn write_block(&self, block: &Block) {
+    pub(super) fn device_write_block(&self, block: &Block) {
         #[cfg(feature = "block_cache")]
         {
             self.block_cache.write_block(block)
@@ -35,10 +57,12 @@ impl Ext4 {
         block.read_offset_as(BASE_OFFSET)
     }
 
-    /// Write super block to block device
+    /// Write super block to block device with checksum
     pub(super) fn write_super_block(&self, sb: &SuperBlock) {
-        let mut block = Block::new(0, [0; BLOCK_SIZE]);
-        block.write_offset_as(BASE_OFFSET, sb);
+        let mut sb = *sb;
+        sb.set_checksum();
+        let mut block = self.read_block(0);
+        block.write_offset_as(BASE_OFFSET, &sb);
         self.write_block(&block)
     }
 
@@ -51,6 +75,25 @@ impl Ext4 {
         inode
     }
 
+    /// Read the in-inode extended attribute area of an inode.
+    pub(super) fn read_inode_xattr_area(&self, inode_ref: &InodeRef) -> Vec<u8> {
+        let (block_id, offset) = self.inode_disk_pos(inode_ref.id);
+        let block = self.read_block(block_id);
+        let end = offset + self.read_super_block().inode_size();
+        let start = (offset + 128 + inode_ref.inode.extra_isize() as usize).min(end);
+        block.data[start..end].to_vec()
+    }
+
+    /// Write the in-inode extended attribute area of an inode. The inode
+    /// checksum is updated when the inode is written.
+    pub(super) fn write_inode_xattr_area(&self, inode_ref: &InodeRef, area: &[u8]) {
+        let (block_id, offset) = self.inode_disk_pos(inode_ref.id);
+        let mut block = self.read_block(block_id);
+        let start = offset + 128 + inode_ref.inode.extra_isize() as usize;
+        block.data[start..start + area.len()].copy_from_slice(area);
+        self.write_block(&block);
+    }
+
     /// Read the root inode from block device
     #[allow(unused)]
     pub(super) fn read_root_inode(&self) -> InodeRef {
@@ -60,7 +103,11 @@ impl Ext4 {
     /// Write an inode to block device with checksum
     pub(super) fn write_inode_with_csum(&self, inode_ref: &mut InodeRef) {
         let super_block = self.read_super_block();
-        inode_ref.set_checksum(&super_block.uuid());
+        let (block_id, offset) = self.inode_disk_pos(inode_ref.id);
+        let block = self.read_block(block_id);
+        // The checksum covers the whole on-disk inode
+        let tail = &block.data[offset + size_of::<Inode>()..offset + super_block.inode_size()];
+        inode_ref.set_checksum(&super_block.uuid(), tail);
         self.write_inode_without_csum(inode_ref);
     }
 
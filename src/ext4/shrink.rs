@@ -0,0 +1,642 @@
+//! Shrinking a filesystem that is not in use.
+//!
+//! The shrink is planned before anything is written: every extent, extent
+//! tree block and xattr block in the dropped block groups gets a new
+//! location in the remaining groups, and every inode in them a new number.
+//! If something does not fit, the shrink fails without any change.
+//!
+//! The plan is then applied in one transaction:
+//!
+//! 1. Moved blocks are copied and marked used, the old locations are
+//!    freed.
+//! 2. Extent trees and xattr block pointers are rewritten, and renumbered
+//!    inodes are written to their new slots. Checksums that cover the
+//!    inode number are recomputed.
+//! 3. Directory entries pointing to renumbered inodes are rewritten.
+//! 4. The groups are dropped: their metadata in the remaining groups is
+//!    freed, the group descriptor table shrinks, backups in the resize
+//!    inode are removed, and the superblock is updated.
+//!
+//! The journal inode is moved like any file, and the backup of its extents
+//! in the superblock is updated. The transaction is logged in the old
+//! blocks of the journal, which are still on the device, then the journal
+//! follows its inode to the new blocks.
+
+use super::check::Checker;
+use super::format::MIN_GROUP_DATA_BLOCKS;
+use super::resize::{ADDRS_PER_BLOCK, RESIZE_DIND_BLOCK, RESIZE_INO};
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+use core::ops::Range;
+
+/// Where a shrink moves blocks and inodes.
+struct ShrinkPlan {
+    /// The superblock after the shrink.
+    sb: SuperBlock,
+    /// Moved blocks, from the old to the new location.
+    blocks: BTreeMap<PBlockId, PBlockId>,
+    /// Renumbered inodes, from the old to the new number.
+    inodes: BTreeMap<InodeId, InodeId>,
+}
+
+/// The free blocks of the remaining groups while planning a shrink.
+struct ShrinkAlloc {
+    /// One bit for each used block.
+    used: Vec<u64>,
+    /// Where the next search starts.
+    next: PBlockId,
+}
+
+impl ShrinkAlloc {
+    /// The blocks before `end` that are not claimed by the check.
+    fn new(checker: &Checker, end: PBlockId) -> Self {
+        let mut used = vec![0; (end as usize).div_ceil(64)];
+        for block in 0..end {
+            if checker.is_claimed(block) {
+                used[block as usize / 64] |= 1 << (block % 64);
+            }
+        }
+        // Bits past `end` are used
+        for block in end..used.len() as PBlockId * 64 {
+            used[block as usize / 64] |= 1 << (block % 64);
+        }
+        Self { used, next: 0 }
+    }
+
+    fn is_used(&self, block: PBlockId) -> bool {
+        self.used[block as usize / 64] & (1 << (block % 64)) != 0
+    }
+
+    /// Allocate `len` contiguous blocks, searching from after the last
+    /// allocation.
+    fn alloc(&mut self, len: u64) -> Result<PBlockId> {
+        let end = self.used.len() as PBlockId * 64;
+        for (from, to) in [(self.next, end), (0, self.next)] {
+            let mut start = from;
+            let mut block = from;
+            while block < to {
+                if self.is_used(block) {
+                    start = block + 1;
+                } else if block + 1 - start == len {
+                    for b in start..start + len {
+                        self.used[b as usize / 64] |= 1 << (b % 64);
+                    }
+                    self.next = start + len;
+                    return Ok(start);
+                }
+                block += 1;
+            }
+        }
+        return_error!(
+            ErrCode::ENOSPC,
+            "No room for {} contiguous blocks in the remaining groups",
+            len
+        );
+    }
+}
+
+impl Ext4 {
+    /// Shrink the filesystem to `new_block_count` blocks. Data blocks,
+    /// extent tree blocks, xattr blocks and inodes are moved out of the
+    /// dropped block groups first, and extent trees and directory entries
+    /// are rewritten to refer to them. The block device can then be
+    /// truncated. A last block group too small for its metadata is dropped
+    /// as well.
+    ///
+    /// The filesystem must be consistent and not in use by anything else.
+    /// Nothing is modified if an error is returned.
+    ///
+    /// # Params
+    ///
+    /// * `new_block_count` - the new number of blocks of the filesystem
+    ///
+    /// # Error
+    ///
+    /// * `EINVAL` - `new_block_count` is larger than the current size, or
+    ///   too small for the metadata of the remaining groups
+    /// * `EIO` - the consistency check found problems
+    /// * `ENOSPC` - the blocks or inodes in the dropped groups do not fit
+    ///   in the remaining ones
+    /// * `ENOTSUP` - a file with a block map, an inline directory or a
+    ///   hashed directory would have to be moved or changed, or the
+    ///   filesystem uses bigalloc
+    pub fn shrink(&self, new_block_count: u64) -> Result<()> {
+        let old = self.read_super_block();
+        if new_block_count > old.block_count() {
+            return_error!(
+                ErrCode::EINVAL,
+                "Cannot grow from {} to {} blocks",
+                old.block_count(),
+                new_block_count
+            );
+        }
+        if new_block_count == old.block_count() {
+            return Ok(());
+        }
+        if old.has_bigalloc() {
+            return_error!(ErrCode::ENOTSUP, "Cannot shrink with bigalloc");
+        }
+        let checker = self.run_check();
+        if !checker.problems.is_empty() {
+            return_error!(
+                ErrCode::EIO,
+                "Filesystem has {} problems, repair it first",
+                checker.problems.len()
+            );
+        }
+        let sb = self.shrink_geometry(&old, new_block_count)?;
+        let plan = self.shrink_plan(&checker, sb)?;
+        self.trans_run(|| {
+            self.shrink_apply(&checker, &plan);
+            Ok(())
+        })?;
+        self.journal_reload_inode()
+    }
+
+    /// The superblock after shrinking `old` to `block_count` blocks. The
+    /// last group is dropped while it is too small for its metadata.
+    fn shrink_geometry(&self, old: &SuperBlock, block_count: u64) -> Result<SuperBlock> {
+        let itable_blocks =
+            (old.inodes_per_group() as usize * old.inode_size() / BLOCK_SIZE) as u64;
+        let mut block_count = block_count;
+        loop {
+            let mut sb = *old;
+            sb.set_block_count(block_count);
+            sb.set_inode_count(sb.block_group_count() * sb.inodes_per_group());
+            if sb.has_resize_inode() {
+                // Blocks no longer used by the descriptor table are reserved
+                let freed = old.group_desc_blocks() - sb.group_desc_blocks();
+                sb.set_reserved_gdt_blocks(old.reserved_gdt_blocks() + freed);
+            }
+            let last = sb.block_group_count() - 1;
+            let first = sb.group_first_block(last);
+            // The end of the metadata of the remaining groups
+            let mut meta_end = first;
+            for bgid in 0..=last {
+                if sb.group_has_super(bgid) {
+                    let len = 1 + sb.group_desc_blocks() + sb.reserved_gdt_blocks();
+                    meta_end = meta_end.max(sb.group_first_block(bgid) + len as PBlockId);
+                }
+                let desc = self.read_block_group(bgid).desc;
+                meta_end = meta_end
+                    .max(desc.block_bitmap_block() + 1)
+                    .max(desc.inode_bitmap_block() + 1)
+                    .max(desc.inode_table_first_block() + itable_blocks);
+            }
+            if meta_end + MIN_GROUP_DATA_BLOCKS <= block_count {
+                return Ok(sb);
+            }
+            if last == 0 {
+                return_error!(
+                    ErrCode::EINVAL,
+                    "Filesystem of {} blocks is too small",
+                    block_count
+                );
+            }
+            block_count = first;
+        }
+    }
+
+    /// Plan where the blocks and inodes in the dropped groups move.
+    fn shrink_plan(&self, checker: &Checker, sb: SuperBlock) -> Result<ShrinkPlan> {
+        let end = sb.block_count();
+        let mut alloc = ShrinkAlloc::new(checker, end);
+        let mut plan = ShrinkPlan {
+            sb,
+            blocks: BTreeMap::new(),
+            inodes: BTreeMap::new(),
+        };
+        let mut next_ino = sb.first_inode();
+        for ino in checker.inodes() {
+            // Its reserved blocks are released with the groups
+            if ino == RESIZE_INO {
+                continue;
+            }
+            if ino > sb.inode_count() {
+                while next_ino <= sb.inode_count() && checker.inode_in_use(next_ino) {
+                    next_ino += 1;
+                }
+                if next_ino > sb.inode_count() {
+                    return_error!(
+                        ErrCode::ENOSPC,
+                        "No free inode for inode {} in the remaining groups",
+                        ino
+                    );
+                }
+                plan.inodes.insert(ino, next_ino);
+                next_ino += 1;
+            }
+            let inode = self.read_inode(ino).inode;
+            if inode.flags().contains(InodeFlags::EXTENTS) {
+                self.shrink_plan_extents(&inode.extent_root(), end, &mut alloc, &mut plan)?;
+            } else if self.shrink_block_map_beyond(&inode, end) {
+                return_error!(
+                    ErrCode::ENOTSUP,
+                    "Inode {} has blocks to move in a block map",
+                    ino
+                );
+            }
+            let xattr_block = inode.xattr_block();
+            if xattr_block >= end && !plan.blocks.contains_key(&xattr_block) {
+                plan.blocks.insert(xattr_block, alloc.alloc(1)?);
+            }
+        }
+        // Directory entries outside of directory blocks cannot be rewritten
+        for ino in checker.inodes() {
+            let inode = self.read_inode(ino).inode;
+            if !inode.is_dir() || plan.inodes.is_empty() {
+                continue;
+            }
+            let flags = inode.flags();
+            if flags.contains(InodeFlags::INLINE_DATA) {
+                return_error!(
+                    ErrCode::ENOTSUP,
+                    "Inline directory {} cannot be updated",
+                    ino
+                );
+            }
+            if flags.contains(InodeFlags::INDEX) {
+                let root = checker
+                    .dir_blocks(ino)
+                    .iter()
+                    .find(|(lblock, _)| *lblock == 0);
+                let dotdot = root.map_or(0, |&(_, pblock)| {
+                    self.read_block(pblock)
+                        .read_offset_as::<DirEntry>(12)
+                        .inode()
+                });
+                if plan.inodes.contains_key(&ino) || plan.inodes.contains_key(&dotdot) {
+                    return_error!(
+                        ErrCode::ENOTSUP,
+                        "Hashed directory {} cannot be updated",
+                        ino
+                    );
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Plan new locations for the extents and tree blocks of an extent
+    /// node that are at or after `end`. An extent is moved as a whole.
+    fn shrink_plan_extents(
+        &self,
+        node: &ExtentNode,
+        end: PBlockId,
+        alloc: &mut ShrinkAlloc,
+        plan: &mut ShrinkPlan,
+    ) -> Result<()> {
+        for i in 0..node.header().entries_count() as usize {
+            if node.header().depth() == 0 {
+                let extent = node.extent_at(i);
+                let (start, len) = (extent.start_pblock(), extent.block_count() as u64);
+                if start + len > end {
+                    let to = alloc.alloc(len)?;
+                    for j in 0..len {
+                        plan.blocks.insert(start + j, to + j);
+                    }
+                }
+                continue;
+            }
+            let child = node.extent_index_at(i).leaf();
+            if child >= end {
+                plan.blocks.insert(child, alloc.alloc(1)?);
+            }
+            let block = self.read_block(child);
+            self.shrink_plan_extents(&ExtentNode::from_bytes(&block.data), end, alloc, plan)?;
+        }
+        Ok(())
+    }
+
+    /// Whether an inode with a block map maps a block at or after `end`.
+    fn shrink_block_map_beyond(&self, inode: &Inode, end: PBlockId) -> bool {
+        let ptrs = inode.block_pointers();
+        ptrs[..12].iter().any(|&ptr| ptr as PBlockId >= end)
+            || ptrs[12..]
+                .iter()
+                .enumerate()
+                .any(|(level, &ptr)| self.shrink_indirect_beyond(ptr as PBlockId, level + 1, end))
+    }
+
+    /// Whether an indirect block of `level` is at or after `end`, or maps
+    /// a block there.
+    fn shrink_indirect_beyond(&self, pblock: PBlockId, level: usize, end: PBlockId) -> bool {
+        if pblock == 0 {
+            return false;
+        }
+        if pblock >= end {
+            return true;
+        }
+        let block = self.read_block(pblock);
+        block.data.chunks_exact(4).any(|ptr| {
+            let ptr = u32::from_le_bytes(ptr.try_into().unwrap()) as PBlockId;
+            if level > 1 {
+                self.shrink_indirect_beyond(ptr, level - 1, end)
+            } else {
+                ptr >= end
+            }
+        })
+    }
+
+    /// Apply a shrink plan.
+    fn shrink_apply(&self, checker: &Checker, plan: &ShrinkPlan) {
+        let old = self.read_super_block();
+        let sb = &plan.sb;
+        let end = sb.block_count();
+        let uuid = sb.uuid();
+
+        // The remaining groups get blocks and inodes, initialize them
+        for bgid in 0..sb.block_group_count() {
+            self.shrink_init_group(&old, bgid);
+        }
+
+        // Copy the moved blocks
+        for (&from, &to) in plan.blocks.iter() {
+            let block = self.read_block(from);
+            self.write_data_block(&Block::new(to, block.data));
+        }
+        let to: Vec<PBlockId> = plan.blocks.values().copied().collect();
+        self.shrink_mark_blocks(&old, &to, true);
+        let from: Vec<PBlockId> = plan.blocks.keys().copied().filter(|&b| b < end).collect();
+        self.shrink_mark_blocks(&old, &from, false);
+
+        // Rewrite the inodes
+        for ino in checker.inodes() {
+            if ino == RESIZE_INO {
+                continue;
+            }
+            let mut inode = self.read_inode(ino).inode;
+            let id = plan.inodes.get(&ino).copied().unwrap_or(ino);
+            let mut changed = id != ino;
+            if inode.flags().contains(InodeFlags::EXTENTS) {
+                let generation = inode.generation();
+                let mut root = inode.extent_root_mut();
+                changed |= self.shrink_move_extents(&mut root, id, generation, id != ino, plan);
+            }
+            if let Some(&to) = plan.blocks.get(&inode.xattr_block()) {
+                inode.set_xattr_block(to);
+                let mut block = XattrBlock::new(self.read_block(to));
+                block.set_checksum(&uuid);
+                self.write_block(&block.block());
+                changed = true;
+            }
+            if id != ino {
+                self.mark_inode(&inode, id, true);
+            }
+            if changed {
+                self.write_inode_with_csum(&mut InodeRef::new(id, inode));
+            }
+        }
+
+        // Rewrite the directory entries
+        for dir in checker.inodes() {
+            let id = plan.inodes.get(&dir).copied().unwrap_or(dir);
+            let inode = self.read_inode(id).inode;
+            if !inode.is_dir() {
+                continue;
+            }
+            let indexed = inode.flags().contains(InodeFlags::INDEX);
+            for &(lblock, pblock) in checker.dir_blocks(dir) {
+                let pblock = plan.blocks.get(&pblock).copied().unwrap_or(pblock);
+                let block = self.read_block(pblock);
+                // The root and interior nodes of a hashed directory are unchanged
+                let rec_len = u16::from_le_bytes([block.data[4], block.data[5]]) as usize;
+                if indexed && (lblock == 0 || (block.data[..4] == [0; 4] && rec_len == BLOCK_SIZE))
+                {
+                    continue;
+                }
+                let mut block = DirBlock::new(block);
+                if block.renumber(&plan.inodes) || id != dir {
+                    if sb.has_metadata_csum() {
+                        block.set_checksum(&uuid, id, inode.generation());
+                    }
+                    self.write_block(block.block());
+                }
+            }
+        }
+
+        self.shrink_drop_groups(&old, sb);
+
+        // Update the superblock
+        let mut new_sb = self.read_super_block();
+        new_sb.set_block_count(end);
+        new_sb.set_inode_count(sb.inode_count());
+        new_sb.set_reserved_gdt_blocks(sb.reserved_gdt_blocks());
+        let reserved =
+            old.reserved_blocks_count() as u128 * end as u128 / old.block_count() as u128;
+        new_sb.set_reserved_blocks_count(reserved as u64);
+        let (mut free_blocks, mut free_inodes) = (0, 0);
+        for bgid in 0..sb.block_group_count() {
+            let desc = self.read_block_group(bgid).desc;
+            free_blocks += desc.get_free_blocks_count();
+            free_inodes += desc.free_inodes_count();
+        }
+        new_sb.set_free_blocks_count(free_blocks);
+        new_sb.set_free_inodes_count(free_inodes);
+        let journal = new_sb.journal_inode_number();
+        if journal != 0 {
+            let inode = self.read_inode(journal).inode;
+            new_sb.set_journal_inode(journal, inode.block_pointers(), inode.size());
+        }
+        if let Some(&prj) = plan.inodes.get(&new_sb.prj_quota_inum()) {
+            let (usr, grp) = (new_sb.usr_quota_inum(), new_sb.grp_quota_inum());
+            new_sb.set_quota_inums(usr, grp, prj);
+        }
+        self.write_super_block(&new_sb);
+        self.write_backup_super();
+    }
+
+    /// Initialize the bitmaps of an uninitialized group of `sb`.
+    fn shrink_init_group(&self, sb: &SuperBlock, bgid: BlockGroupId) {
+        let uuid = sb.uuid();
+        let mut bg = self.read_block_group(bgid);
+        let flags = bg.desc.flags();
+        if flags.contains(BlockGroupFlags::BLOCK_UNINIT) {
+            let mut block = self.resize_init_block_bitmap(sb, bgid);
+            let bitmap = Bitmap::new(&mut block.data, 8 * BLOCK_SIZE);
+            bg.desc.set_block_bitmap_csum(&uuid, &bitmap);
+            self.write_block(&block);
+        }
+        if flags.contains(BlockGroupFlags::INODE_UNINIT) {
+            let ipg = sb.inodes_per_group() as usize;
+            let mut block = Block::new(bg.desc.inode_bitmap_block(), [0; BLOCK_SIZE]);
+            block.data[ipg / 8..].fill(0xFF);
+            let bitmap = Bitmap::new(&mut block.data, ipg);
+            bg.desc.set_inode_bitmap_csum(&uuid, &bitmap);
+            self.write_block(&block);
+        }
+        let uninit = BlockGroupFlags::BLOCK_UNINIT | BlockGroupFlags::INODE_UNINIT;
+        if flags.intersects(uninit) {
+            bg.desc.set_flags(flags - uninit);
+            self.write_block_group_with_csum(&mut bg);
+        }
+    }
+
+    /// Mark `blocks`, sorted, as used or free in the block bitmaps and
+    /// the group descriptors. The superblock is not updated.
+    fn shrink_mark_blocks(&self, sb: &SuperBlock, blocks: &[PBlockId], used: bool) {
+        let bpg = sb.blocks_per_group() as PBlockId;
+        let first_data = sb.first_data_block() as PBlockId;
+        for group in blocks.chunk_by(|a, b| (a - first_data) / bpg == (b - first_data) / bpg) {
+            let bgid = ((group[0] - first_data) / bpg) as BlockGroupId;
+            let mut bg = self.read_block_group(bgid);
+            let mut block = self.read_block(bg.desc.block_bitmap_block());
+            let mut bitmap = Bitmap::new(&mut block.data, 8 * BLOCK_SIZE);
+            let mut free = bg.desc.get_free_blocks_count();
+            for &pblock in group {
+                let bit = ((pblock - first_data) % bpg) as usize;
+                if used {
+                    bitmap.set_bit(bit);
+                    free -= 1;
+                } else {
+                    bitmap.clear_bit(bit);
+                    free += 1;
+                }
+            }
+            bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+            bg.desc.set_free_blocks_count(free);
+            self.write_block(&block);
+            self.write_block_group_with_csum(&mut bg);
+        }
+    }
+
+    /// Move the extents and tree blocks of an extent node as planned, and
+    /// return whether the node is changed. Tree blocks are rewritten with
+    /// checksums for inode `ino` if they move, change, or `renumbered` is
+    /// set.
+    fn shrink_move_extents(
+        &self,
+        node: &mut ExtentNodeMut,
+        ino: InodeId,
+        generation: u32,
+        renumbered: bool,
+        plan: &ShrinkPlan,
+    ) -> bool {
+        let mut changed = false;
+        for i in 0..node.header().entries_count() as usize {
+            if node.header().depth() == 0 {
+                let extent = node.extent_mut_at(i);
+                if let Some(&to) = plan.blocks.get(&extent.start_pblock()) {
+                    extent.set_start_pblock(to);
+                    changed = true;
+                }
+                continue;
+            }
+            let from = node.extent_index_at(i).leaf();
+            let to = plan.blocks.get(&from).copied().unwrap_or(from);
+            let mut block = self.read_block(to);
+            let mut child = ExtentNodeMut::from_bytes(&mut block.data);
+            let child_changed =
+                self.shrink_move_extents(&mut child, ino, generation, renumbered, plan);
+            if child_changed || renumbered || to != from {
+                child.set_checksum(&plan.sb.uuid(), ino, generation);
+                self.write_block(&block);
+            }
+            if to != from {
+                node.extent_index_mut_at(i).set_leaf(to);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Drop the groups of `old` after the last one of `sb`: free their
+    /// metadata in the remaining groups, shrink the group descriptor
+    /// table, remove their backups from the resize inode, and mark the
+    /// blocks past the end of the new last group as used.
+    fn shrink_drop_groups(&self, old: &SuperBlock, sb: &SuperBlock) {
+        let end = sb.block_count();
+        let groups = sb.block_group_count();
+        let itable_blocks =
+            (old.inodes_per_group() as usize * old.inode_size() / BLOCK_SIZE) as u64;
+        let mut freed = Vec::new();
+        for bgid in groups..old.block_group_count() {
+            let desc = self.read_block_group(bgid).desc;
+            let itable = desc.inode_table_first_block();
+            freed.push(desc.block_bitmap_block());
+            freed.push(desc.inode_bitmap_block());
+            freed.extend(itable..itable + itable_blocks);
+        }
+
+        // Group descriptor blocks no longer needed
+        let gdt = sb.group_desc_blocks()..old.group_desc_blocks();
+        if old.has_resize_inode() {
+            self.shrink_resize_inode(sb, gdt);
+        } else {
+            for bgid in (0..groups).filter(|&bgid| sb.group_has_super(bgid)) {
+                let first = sb.group_first_block(bgid) + 1;
+                freed.extend(gdt.clone().map(|i| first + i as PBlockId));
+            }
+        }
+        freed.retain(|&block| block < end);
+        freed.sort_unstable();
+        self.shrink_mark_blocks(old, &freed, false);
+
+        // Blocks past the end of the last group are padded as used
+        let last = groups - 1;
+        let (new_len, old_len) = (sb.blocks_in_group(last), old.blocks_in_group(last));
+        let mut bg = self.read_block_group(last);
+        let mut block = self.read_block(bg.desc.block_bitmap_block());
+        let mut bitmap = Bitmap::new(&mut block.data, 8 * BLOCK_SIZE);
+        let mut free = bg.desc.get_free_blocks_count();
+        for bit in new_len as usize..old_len as usize {
+            if bitmap.is_bit_clear(bit) {
+                bitmap.set_bit(bit);
+                free -= 1;
+            }
+        }
+        bg.desc.set_block_bitmap_csum(&sb.uuid(), &bitmap);
+        bg.desc.set_free_blocks_count(free);
+        self.write_block(&block);
+        self.write_block_group_with_csum(&mut bg);
+    }
+
+    /// Update the resize inode for the groups of `sb`: remove the backups
+    /// in dropped groups, and reserve the group descriptor blocks at `gdt`
+    /// indexes again.
+    fn shrink_resize_inode(&self, sb: &SuperBlock, gdt: Range<u32>) {
+        let first_data = sb.first_data_block() as PBlockId;
+        let mut resize = self.read_inode(RESIZE_INO);
+        let mut blocks = resize.inode.fs_block_count();
+        let dind = resize.inode.block_pointers()[RESIZE_DIND_BLOCK] as PBlockId;
+        let mut dind = self.read_block(dind);
+        let reserved = sb.group_desc_blocks()..sb.group_desc_blocks() + sb.reserved_gdt_blocks();
+        for i in reserved {
+            let pblock = first_data + 1 + i as PBlockId;
+            let mut block = Block::new(pblock, [0; BLOCK_SIZE]);
+            if gdt.contains(&i) {
+                // A descriptor block becomes reserved
+                let offset = i as usize % ADDRS_PER_BLOCK * 4;
+                dind.write_offset(offset, &(pblock as u32).to_le_bytes());
+                blocks += 1;
+            } else {
+                block = self.read_block(pblock);
+            }
+            // Backups are listed in group order
+            let backups = (1..sb.block_group_count())
+                .filter(|&bgid| sb.group_has_super(bgid))
+                .map(|bgid| pblock + sb.group_first_block(bgid) - first_data);
+            let mut count = 0;
+            for (j, backup) in backups.take(ADDRS_PER_BLOCK).enumerate() {
+                block.write_offset(j * 4, &(backup as u32).to_le_bytes());
+                count = j + 1;
+            }
+            for ptr in block.data[count * 4..].chunks_exact_mut(4) {
+                if ptr != [0; 4] {
+                    ptr.fill(0);
+                    blocks -= 1;
+                }
+            }
+            if gdt.contains(&i) {
+                blocks += count as u64;
+            }
+            self.write_block(&block);
+        }
+        self.write_block(&dind);
+        resize.inode.set_fs_block_count(blocks);
+        self.write_inode_with_csum(&mut resize);
+    }
+}
@@ -0,0 +1,50 @@
+use super::{AtimePolicy, Ext4};
+use crate::ext4_defs::*;
+
+impl Ext4 {
+    /// Seconds after which `relatime` refreshes a stale access time.
+    const RELATIME_INTERVAL: i64 = 24 * 60 * 60;
+
+    /// Get the current time from the time source.
+    pub(super) fn now(&self) -> Timestamp {
+        self.time_source.now()
+    }
+
+    /// Mark the data of an inode as modified, updating mtime and ctime.
+    /// The inode is not written to disk.
+    pub(super) fn touch_modify(&self, inode: &mut InodeRef) {
+        let now = self.now();
+        inode.inode.set_mtime(now);
+        inode.inode.set_ctime(now);
+    }
+
+    /// Mark the metadata of an inode as changed, updating ctime.
+    /// The inode is not written to disk.
+    pub(super) fn touch_change(&self, inode: &mut InodeRef) {
+        inode.inode.set_ctime(self.now());
+    }
+
+    /// Update the access time of an inode according to the atime policy,
+    /// unless the inode has the `NOATIME` flag. The inode is written to
+    /// disk if atime is changed.
+    pub(super) fn touch_access(&self, inode: &mut InodeRef) {
+        if inode.inode.flags().contains(InodeFlags::NOATIME) {
+            return;
+        }
+        let now = self.now();
+        let atime = inode.inode.atime();
+        let update = match self.options.atime {
+            AtimePolicy::Strict => true,
+            AtimePolicy::Relative => {
+                atime <= inode.inode.mtime()
+                    || atime <= inode.inode.ctime()
+                    || now.sec - atime.sec >= Self::RELATIME_INTERVAL
+            }
+            AtimePolicy::NoAtime => false,
+        };
+        if update && atime != now {
+            inode.inode.set_atime(now);
+            self.write_inode_with_csum(inode);
+        }
+    }
+}
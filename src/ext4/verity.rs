@@ -0,0 +1,261 @@
+//! fs-verity, modeled on `FS_IOC_ENABLE_VERITY` and `FS_IOC_MEASURE_VERITY`.
+//!
+//! Enabling verity on a regular file builds the Merkle tree of its data,
+//! writes the tree and the descriptor past the end of the file, and sets
+//! the `VERITY` flag. From then on the data of the file is read-only: it
+//! cannot be written or resized. Each block read from it is verified
+//! against the tree up to the root hash in the descriptor, and the digest
+//! of the descriptor measures the whole file.
+//!
+//! The tree and the descriptor of an encrypted file are encrypted like
+//! its data.
+
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::format_error;
+use crate::prelude::*;
+use crate::return_error;
+
+/// The Merkle tree of a verity file being read, with the tree blocks
+/// verified so far.
+pub(super) struct VerityReader {
+    desc: VerityDescriptor,
+    tree: MerkleTree,
+    /// Verified tree blocks by their logical blocks.
+    verified: BTreeMap<LBlockId, Block>,
+}
+
+impl Ext4 {
+    /// Enable fs-verity on a regular file. The file must not have blocks
+    /// mapped past the start of its Merkle tree.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    ///
+    /// # Error
+    ///
+    /// * `EISDIR` - `inode` is not a regular file
+    /// * `ENOTSUP` - the filesystem does not have the `verity` feature, or
+    ///   the file uses a block map or inline data
+    /// * `EEXIST` - the file already has fs-verity
+    /// * `EPERM` - the file is immutable or append-only
+    /// * `EINVAL` - the file has blocks mapped past its data
+    /// * `ENOKEY` - the file is encrypted and its key is not added
+    /// * `ENOSPC` - no space left on device
+    pub fn enable_verity(&self, inode: InodeId) -> Result<()> {
+        self.trans_run(|| {
+            let mut file = self.read_inode(inode);
+            if !file.inode.is_file() {
+                return_error!(ErrCode::EISDIR, "Inode {} is not a file", inode);
+            }
+            if !self.read_super_block().has_verity() {
+                return_error!(ErrCode::ENOTSUP, "Filesystem does not support verity");
+            }
+            if file.inode.flags().contains(InodeFlags::VERITY) {
+                return_error!(ErrCode::EEXIST, "Inode {} already has verity", inode);
+            }
+            Self::check_flags(&file, InodeFlags::IMMUTABLE | InodeFlags::APPEND)?;
+            if !file.inode.flags().contains(InodeFlags::EXTENTS)
+                || file.inode.flags().contains(InodeFlags::INLINE_DATA)
+            {
+                return_error!(
+                    ErrCode::ENOTSUP,
+                    "Inode {} does not store data in extents",
+                    inode
+                );
+            }
+            let contents_key = self.fscrypt_contents_key(&file)?;
+            let data_size = file.inode.size();
+            let mut desc = VerityDescriptor::new(data_size);
+            let tree = MerkleTree::new(data_size);
+            if self.extent_end_block(&file) > tree.first_block() {
+                return_error!(
+                    ErrCode::EINVAL,
+                    "Inode {} has blocks mapped past its data",
+                    inode
+                );
+            }
+
+            // Hash the data and build the tree
+            let data_blocks = data_size.div_ceil(BLOCK_SIZE as u64) as LBlockId;
+            let mut hashes = Vec::with_capacity(data_blocks as usize);
+            for iblock in 0..data_blocks {
+                let mut block = self.read_block(self.extent_query(&file, iblock)?);
+                if let Some(key) = &contents_key {
+                    key.decrypt_block(iblock, &mut block.data);
+                }
+                Self::verity_zero_tail(&desc, iblock, &mut block);
+                hashes.push(desc.hash_block(&block.data));
+            }
+            let (tree_blocks, root_hash) = tree.build(&desc, hashes);
+            desc.set_root_hash(&root_hash);
+
+            // Write the tree and the descriptor past the data, in place
+            // even if journaled, since the blocks are newly mapped
+            let metadata = tree_blocks.into_iter().chain([desc.to_block()]);
+            for (iblock, data) in (tree.first_block()..).zip(metadata) {
+                let pblock = self.inode_map_block(&mut file, iblock)?;
+                let mut block = Block::new(pblock, data);
+                if let Some(key) = &contents_key {
+                    key.encrypt_block(iblock, &mut block.data);
+                }
+                self.write_data_block(&block);
+            }
+            file.inode
+                .set_flags(file.inode.flags() | InodeFlags::VERITY);
+            self.touch_change(&mut file);
+            self.write_inode_with_csum(&mut file);
+            self.sync_if(&file, InodeFlags::SYNC);
+            Ok(())
+        })
+    }
+
+    /// Measure a verity file.
+    ///
+    /// # Params
+    ///
+    /// * `inode` - the inode of the file
+    ///
+    /// # Return
+    ///
+    /// The SHA-256 digest of the file, the same as `fsverity digest`.
+    ///
+    /// # Error
+    ///
+    /// * `ENODATA` - the file does not have fs-verity
+    /// * `ENOKEY` - the file is encrypted and its key is not added
+    /// * `ENOTSUP` - the file is not hashed with SHA-256 in 4 KiB blocks
+    /// * `EIO` - the descriptor is corrupted
+    pub fn measure_verity(&self, inode: InodeId) -> Result<[u8; FS_VERITY_DIGEST_SIZE]> {
+        let file = self.read_inode(inode);
+        if !file.inode.flags().contains(InodeFlags::VERITY) {
+            return_error!(ErrCode::ENODATA, "Inode {} does not have verity", inode);
+        }
+        let contents_key = self.fscrypt_contents_key(&file)?;
+        let reader = self.verity_reader(&file, contents_key.as_ref())?;
+        Ok(reader.desc.digest())
+    }
+
+    /// Load the descriptor of a verity file to verify its blocks.
+    ///
+    /// # Error
+    ///
+    /// * `ENOTSUP` - the file is not hashed with SHA-256 in 4 KiB blocks
+    /// * `EIO` - the descriptor is corrupted or does not match the file
+    pub(super) fn verity_reader(
+        &self,
+        file: &InodeRef,
+        key: Option<&ContentsKey>,
+    ) -> Result<VerityReader> {
+        let corrupted = || {
+            format_error!(
+                ErrCode::EIO,
+                "Verity descriptor of inode {} is corrupted",
+                file.id
+            )
+        };
+        let end = self.extent_end_block(file);
+        if end == 0 {
+            return Err(corrupted());
+        }
+        let mut block = self.read_block(self.extent_query(file, end - 1)?);
+        if let Some(key) = key {
+            key.decrypt_block(end - 1, &mut block.data);
+        }
+        let desc = VerityDescriptor::from_block(&block.data).ok_or_else(corrupted)?;
+        if !desc.is_supported() {
+            return_error!(
+                ErrCode::ENOTSUP,
+                "Unsupported verity descriptor of inode {}",
+                file.id
+            );
+        }
+        let tree = MerkleTree::new(desc.data_size());
+        if desc.data_size() != file.inode.size() || tree.descriptor_block() != end - 1 {
+            return Err(corrupted());
+        }
+        Ok(VerityReader {
+            desc,
+            tree,
+            verified: BTreeMap::new(),
+        })
+    }
+
+    /// Verify a data block of a verity file, zeroing the part past the end
+    /// of the data.
+    ///
+    /// # Error
+    ///
+    /// * `EIO` - the block or a tree block does not match its hash
+    pub(super) fn verity_verify(
+        &self,
+        file: &InodeRef,
+        reader: &mut VerityReader,
+        key: Option<&ContentsKey>,
+        iblock: LBlockId,
+        block: &mut Block,
+    ) -> Result<()> {
+        Self::verity_zero_tail(&reader.desc, iblock, block);
+        let expected = self.verity_expected_hash(file, reader, key, 0, iblock as u64)?;
+        if reader.desc.hash_block(&block.data) != expected {
+            return_error!(
+                ErrCode::EIO,
+                "Block {} of verity inode {} does not match its hash",
+                iblock,
+                file.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Get the hash of data block `index` for level 0, or of block `index`
+    /// of the level below `level`, verifying the tree blocks on the way to
+    /// the root.
+    fn verity_expected_hash(
+        &self,
+        file: &InodeRef,
+        reader: &mut VerityReader,
+        key: Option<&ContentsKey>,
+        level: usize,
+        index: u64,
+    ) -> Result<[u8; FS_VERITY_DIGEST_SIZE]> {
+        if level == reader.tree.levels() {
+            return Ok(reader.desc.root_hash());
+        }
+        let (tree_index, offset) = reader.tree.locate(index);
+        let iblock = reader.tree.tree_block(level, tree_index);
+        if !reader.verified.contains_key(&iblock) {
+            let mut block = self.read_block(self.extent_query(file, iblock)?);
+            if let Some(key) = key {
+                key.decrypt_block(iblock, &mut block.data);
+            }
+            let expected = self.verity_expected_hash(file, reader, key, level + 1, tree_index)?;
+            if reader.desc.hash_block(&block.data) != expected {
+                return_error!(
+                    ErrCode::EIO,
+                    "Merkle tree block {} of verity inode {} does not match its hash",
+                    iblock,
+                    file.id
+                );
+            }
+            reader.verified.insert(iblock, block);
+        }
+        let block = &reader.verified[&iblock];
+        Ok(block.data[offset..offset + FS_VERITY_DIGEST_SIZE]
+            .try_into()
+            .unwrap())
+    }
+
+    /// Zero the part of a data block past the end of the data, which is
+    /// hashed as zeros.
+    fn verity_zero_tail(desc: &VerityDescriptor, iblock: LBlockId, block: &mut Block) {
+        let start = iblock as u64 * BLOCK_SIZE as u64;
+        let valid = desc
+            .data_size()
+            .saturating_sub(start)
+            .min(BLOCK_SIZE as u64);
+        block.data[valid as usize..].fill(0);
+    }
+}
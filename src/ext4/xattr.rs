@@ -0,0 +1,98 @@
+//! Extended attributes of inodes.
+//!
+//! A xattr is kept in the inode body if it fits, as the kernel does, and
+//! in the xattr block otherwise. The xattr block is allocated when it is
+//! first needed and freed when it becomes empty.
+
+use super::Ext4;
+use crate::constants::*;
+use crate::ext4_defs::*;
+use crate::prelude::*;
+use crate::return_error;
+
+impl Ext4 {
+    /// Get the value of a xattr from the inode body or the xattr block.
+    pub(super) fn xattr_get(&self, inode: &InodeRef, name: &str) -> Option<Vec<u8>> {
+        let ibody = XattrIbody::new(self.read_inode_xattr_area(inode));
+        if let Some(value) = ibody.get(name) {
+            return Some(value.to_vec());
+        }
+        let xattr_block_id = inode.inode.xattr_block();
+        if xattr_block_id == 0 {
+            return None;
+        }
+        let xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
+        xattr_block.get(name).map(<[u8]>::to_vec)
+    }
+
+    /// List the names of the xattrs in the inode body and the xattr block.
+    pub(super) fn xattr_list(&self, inode: &InodeRef) -> Vec<String> {
+        let ibody = XattrIbody::new(self.read_inode_xattr_area(inode));
+        let mut names = ibody.list();
+        let xattr_block_id = inode.inode.xattr_block();
+        if xattr_block_id != 0 {
+            let xattr_block = XattrBlock::new(self.read_block(xattr_block_id));
+            names.extend(xattr_block.list());
+        }
+        names
+    }
+
+    /// Set or remove (`value` is `None`) a xattr of an inode. The inode is
+    /// modified but not written. Return whether the xattr existed.
+    ///
+    /// # Error
+    ///
+    /// * `ENOSPC` - neither the inode body nor the xattr block has enough
+    ///   space, or no block can be allocated
+    pub(super) fn xattr_set(
+        &self,
+        inode: &mut InodeRef,
+        name: &str,
+        value: Option<&[u8]>,
+    ) -> Result<bool> {
+        let mut ibody = XattrIbody::new(self.read_inode_xattr_area(inode));
+        let in_ibody = ibody.remove(name);
+        let mut xattr_block = match inode.inode.xattr_block() {
+            0 => None,
+            id => Some(XattrBlock::new(self.read_block(id))),
+        };
+        let in_block = xattr_block
+            .as_mut()
+            .is_some_and(|xattr_block| xattr_block.remove(name));
+
+        let to_ibody = value.is_some_and(|value| ibody.set(name, value));
+        if in_ibody || to_ibody {
+            self.write_inode_xattr_area(inode, ibody.area());
+        }
+        if let Some(value) = value.filter(|_| !to_ibody) {
+            if xattr_block.is_none() {
+                // lazy allocate xattr block
+                let pblock = self.alloc_block(inode)?;
+                inode.inode.set_xattr_block(pblock);
+                let mut new_block = XattrBlock::new(Block::new(pblock, [0; BLOCK_SIZE]));
+                new_block.init();
+                xattr_block = Some(new_block);
+            }
+            if !xattr_block.as_mut().unwrap().insert(name, value) {
+                return_error!(
+                    ErrCode::ENOSPC,
+                    "Xattr block of Inode {} does not have enough space",
+                    inode.id
+                );
+            }
+        } else if !in_block {
+            return Ok(in_ibody);
+        }
+
+        let mut xattr_block = xattr_block.unwrap();
+        if xattr_block.is_empty() {
+            let pblock = inode.inode.xattr_block();
+            self.dealloc_block(inode, pblock)?;
+            inode.inode.set_xattr_block(0);
+        } else {
+            xattr_block.set_checksum(&self.read_super_block().uuid());
+            self.write_block(&xattr_block.block());
+        }
+        Ok(in_ibody || in_block)
+    }
+}
@@ -0,0 +1,349 @@
+//! POSIX access control lists are stored as extended attributes named
+//! `system.posix_acl_access` and `system.posix_acl_default`.
+//!
+//! On disk, ext4 uses a compact format: a 4-byte header followed by entries.
+//! Entries for the file owner, group, mask and others omit the id field and
+//! take 4 bytes, while named user and group entries take 8 bytes.
+//!
+//! The xattr interface exchanges ACLs in the generic VFS format instead,
+//! where every entry takes 8 bytes. `PosixAcl` converts between both.
+
+use crate::prelude::*;
+
+/// Xattr name of the access ACL.
+pub const XATTR_NAME_ACL_ACCESS: &str = "system.posix_acl_access";
+/// Xattr name of the default ACL.
+pub const XATTR_NAME_ACL_DEFAULT: &str = "system.posix_acl_default";
+
+/// The type of an ACL attached to an inode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclType {
+    /// The ACL checked on access.
+    Access,
+    /// The ACL inherited by new entries of a directory.
+    Default,
+}
+
+impl AclType {
+    /// Get the ACL type stored under an xattr name.
+    pub fn from_xattr_name(name: &str) -> Option<Self> {
+        match name {
+            XATTR_NAME_ACL_ACCESS => Some(AclType::Access),
+            XATTR_NAME_ACL_DEFAULT => Some(AclType::Default),
+            _ => None,
+        }
+    }
+
+    /// The xattr name under which the ACL is stored.
+    pub fn xattr_name(&self) -> &'static str {
+        match self {
+            AclType::Access => XATTR_NAME_ACL_ACCESS,
+            AclType::Default => XATTR_NAME_ACL_DEFAULT,
+        }
+    }
+}
+
+/// The tag of an ACL entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u16)]
+pub enum AclTag {
+    /// The file owner.
+    UserObj = 0x01,
+    /// A named user.
+    User = 0x02,
+    /// The file group.
+    GroupObj = 0x04,
+    /// A named group.
+    Group = 0x08,
+    /// The maximum permissions of the group class.
+    Mask = 0x10,
+    /// Everyone else.
+    Other = 0x20,
+}
+
+impl AclTag {
+    fn from_u16(tag: u16) -> Option<Self> {
+        match tag {
+            0x01 => Some(AclTag::UserObj),
+            0x02 => Some(AclTag::User),
+            0x04 => Some(AclTag::GroupObj),
+            0x08 => Some(AclTag::Group),
+            0x10 => Some(AclTag::Mask),
+            0x20 => Some(AclTag::Other),
+            _ => None,
+        }
+    }
+
+    /// Whether the entry carries a user or group id.
+    fn has_id(&self) -> bool {
+        matches!(self, AclTag::User | AclTag::Group)
+    }
+}
+
+/// An ACL entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AclEntry {
+    /// Entry tag.
+    pub tag: AclTag,
+    /// Permission bits, read (4), write (2) and execute (1).
+    pub perm: u16,
+    /// User or group id for `User` and `Group` entries, ignored otherwise.
+    pub id: u32,
+}
+
+impl AclEntry {
+    /// Create an ACL entry.
+    pub fn new(tag: AclTag, perm: u16, id: u32) -> Self {
+        Self {
+            tag,
+            perm: perm & 0o7,
+            id: if tag.has_id() { id } else { 0 },
+        }
+    }
+}
+
+/// A POSIX access control list.
+///
+/// A valid ACL contains exactly one `UserObj`, `GroupObj` and `Other` entry,
+/// and a `Mask` entry if there are any `User` or `Group` entries. Entries
+/// are sorted by tag and then by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosixAcl {
+    entries: Vec<AclEntry>,
+}
+
+impl PosixAcl {
+    /// Version in the ext4 on-disk header.
+    const DISK_VERSION: u32 = 1;
+    /// Version in the VFS xattr header.
+    const XATTR_VERSION: u32 = 2;
+    /// Permission bits of an entry.
+    const PERM_MASK: u16 = 0o7;
+
+    /// Create an ACL from entries. Entries are sorted and the result is
+    /// validated.
+    pub fn new(mut entries: Vec<AclEntry>) -> Option<Self> {
+        entries.sort_by_key(|e| (e.tag, e.id));
+        let acl = Self { entries };
+        if acl.is_valid() {
+            Some(acl)
+        } else {
+            None
+        }
+    }
+
+    /// Create a minimal ACL equivalent to the permission bits of `mode`.
+    pub fn from_mode(mode: u16) -> Self {
+        Self {
+            entries: vec![
+                AclEntry::new(AclTag::UserObj, mode >> 6, 0),
+                AclEntry::new(AclTag::GroupObj, mode >> 3, 0),
+                AclEntry::new(AclTag::Other, mode, 0),
+            ],
+        }
+    }
+
+    /// Get all entries.
+    pub fn entries(&self) -> &[AclEntry] {
+        &self.entries
+    }
+
+    /// Decode an ACL from the ext4 on-disk format.
+    pub fn from_disk(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || read_u32(bytes, 0) != Self::DISK_VERSION {
+            return None;
+        }
+        let mut entries = Vec::new();
+        let mut offset = 4;
+        while offset < bytes.len() {
+            if offset + 4 > bytes.len() {
+                return None;
+            }
+            let tag = AclTag::from_u16(read_u16(bytes, offset))?;
+            let perm = read_u16(bytes, offset + 2);
+            let id = if tag.has_id() {
+                if offset + 8 > bytes.len() {
+                    return None;
+                }
+                let id = read_u32(bytes, offset + 4);
+                offset += 8;
+                id
+            } else {
+                offset += 4;
+                0
+            };
+            entries.push(AclEntry::new(tag, perm, id));
+        }
+        Self::new(entries)
+    }
+
+    /// Encode the ACL to the ext4 on-disk format.
+    pub fn to_disk(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&Self::DISK_VERSION.to_le_bytes());
+        for e in &self.entries {
+            bytes.extend_from_slice(&(e.tag as u16).to_le_bytes());
+            bytes.extend_from_slice(&e.perm.to_le_bytes());
+            if e.tag.has_id() {
+                bytes.extend_from_slice(&e.id.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Decode an ACL from the VFS xattr format.
+    pub fn from_xattr(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || !(bytes.len() - 4).is_multiple_of(8) {
+            return None;
+        }
+        if read_u32(bytes, 0) != Self::XATTR_VERSION {
+            return None;
+        }
+        let entries = bytes[4..]
+            .chunks(8)
+            .map(|e| {
+                let tag = AclTag::from_u16(read_u16(e, 0))?;
+                Some(AclEntry::new(tag, read_u16(e, 2), read_u32(e, 4)))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Self::new(entries)
+    }
+
+    /// Encode the ACL to the VFS xattr format.
+    pub fn to_xattr(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&Self::XATTR_VERSION.to_le_bytes());
+        for e in &self.entries {
+            // Unused ids are set to -1 (ACL_UNDEFINED_ID)
+            let id = if e.tag.has_id() { e.id } else { u32::MAX };
+            bytes.extend_from_slice(&(e.tag as u16).to_le_bytes());
+            bytes.extend_from_slice(&e.perm.to_le_bytes());
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// If the ACL can be represented by permission bits alone, return
+    /// the equivalent permission bits.
+    pub fn equiv_mode(&self) -> Option<u16> {
+        let mut mode = 0;
+        for e in &self.entries {
+            match e.tag {
+                AclTag::UserObj => mode |= e.perm << 6,
+                AclTag::GroupObj => mode |= e.perm << 3,
+                AclTag::Other => mode |= e.perm,
+                AclTag::Mask | AclTag::User | AclTag::Group => return None,
+            }
+        }
+        Some(mode)
+    }
+
+    /// Compute the permission bits shown by `stat` for this ACL. The
+    /// group bits reflect the mask entry if there is one.
+    pub fn mode(&self) -> u16 {
+        let mut mode = 0;
+        let mut group_obj = 0;
+        let mut mask = None;
+        for e in &self.entries {
+            match e.tag {
+                AclTag::UserObj => mode |= e.perm << 6,
+                AclTag::GroupObj => group_obj = e.perm,
+                AclTag::Mask => mask = Some(e.perm),
+                AclTag::Other => mode |= e.perm,
+                _ => {}
+            }
+        }
+        mode | mask.unwrap_or(group_obj) << 3
+    }
+
+    /// Update the ACL after the permission bits of the file are changed,
+    /// so that the ACL stays consistent with `mode`.
+    pub fn chmod(&mut self, mode: u16) {
+        let mut has_mask = false;
+        for e in self.entries.iter_mut() {
+            match e.tag {
+                AclTag::UserObj => e.perm = (mode >> 6) & Self::PERM_MASK,
+                AclTag::Mask => {
+                    e.perm = (mode >> 3) & Self::PERM_MASK;
+                    has_mask = true;
+                }
+                AclTag::Other => e.perm = mode & Self::PERM_MASK,
+                _ => {}
+            }
+        }
+        if !has_mask {
+            for e in self.entries.iter_mut() {
+                if e.tag == AclTag::GroupObj {
+                    e.perm = (mode >> 3) & Self::PERM_MASK;
+                }
+            }
+        }
+    }
+
+    /// Apply the permission bits requested at creation to an inherited
+    /// ACL. Return the resulting permission bits of the new file, and
+    /// whether the ACL carries more information than the bits.
+    pub fn create_masq(&mut self, mode: u16) -> (u16, bool) {
+        let mut mode = mode;
+        let mut not_equiv = false;
+        let mut has_mask = false;
+        for e in self.entries.iter_mut() {
+            match e.tag {
+                AclTag::UserObj => {
+                    e.perm &= (mode >> 6) & Self::PERM_MASK;
+                    mode &= (e.perm << 6) | !0o700;
+                }
+                AclTag::User | AclTag::Group => not_equiv = true,
+                AclTag::Other => {
+                    e.perm &= mode & Self::PERM_MASK;
+                    mode &= e.perm | !0o007;
+                }
+                AclTag::Mask => {
+                    e.perm &= (mode >> 3) & Self::PERM_MASK;
+                    mode &= (e.perm << 3) | !0o070;
+                    has_mask = true;
+                    not_equiv = true;
+                }
+                AclTag::GroupObj => {}
+            }
+        }
+        if !has_mask {
+            for e in self.entries.iter_mut() {
+                if e.tag == AclTag::GroupObj {
+                    e.perm &= (mode >> 3) & Self::PERM_MASK;
+                    mode &= (e.perm << 3) | !0o070;
+                }
+            }
+        }
+        (mode, not_equiv)
+    }
+
+    /// Check the ACL invariants.
+    fn is_valid(&self) -> bool {
+        let count = |tag| self.entries.iter().filter(|e| e.tag == tag).count();
+        let named = count(AclTag::User) + count(AclTag::Group);
+        // No duplicate named entries (entries are sorted)
+        let dup = self
+            .entries
+            .windows(2)
+            .any(|w| w[0].tag == w[1].tag && w[0].id == w[1].id);
+        count(AclTag::UserObj) == 1
+            && count(AclTag::GroupObj) == 1
+            && count(AclTag::Other) == 1
+            && count(AclTag::Mask)
+                == if named > 0 {
+                    1
+                } else {
+                    count(AclTag::Mask).min(1)
+                }
+            && !dup
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
@@ -0,0 +1,277 @@
+//! AES-256 and the block cipher modes used by fscrypt: XTS for file
+//! contents and CBC with ciphertext stealing for file names.
+//!
+//! This is a plain byte-oriented implementation without lookup tables
+//! beyond the S-box, favoring clarity over speed.
+
+/// The AES S-box.
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// The inverse of the AES S-box.
+const INV_SBOX: [u8; 256] = {
+    let mut inv = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inv[SBOX[i] as usize] = i as u8;
+        i += 1;
+    }
+    inv
+};
+
+/// Size of an AES block in bytes.
+pub const AES_BLOCK_SIZE: usize = 16;
+
+/// Number of rounds of AES-256.
+const ROUNDS: usize = 14;
+
+/// Multiply by x in GF(2^8).
+fn xtime(a: u8) -> u8 {
+    (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// Multiply in GF(2^8).
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+fn xor_block(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+/// The AES-256 block cipher.
+pub struct Aes256 {
+    round_keys: [[u8; AES_BLOCK_SIZE]; ROUNDS + 1],
+}
+
+impl Aes256 {
+    /// Expand a 256-bit key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        let mut words = [[0u8; 4]; 4 * (ROUNDS + 1)];
+        for (i, word) in words.iter_mut().take(8).enumerate() {
+            word.copy_from_slice(&key[4 * i..4 * i + 4]);
+        }
+        let mut rcon = 1;
+        for i in 8..words.len() {
+            let mut temp = words[i - 1];
+            if i % 8 == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(|b| SBOX[b as usize]);
+                temp[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if i % 8 == 4 {
+                temp = temp.map(|b| SBOX[b as usize]);
+            }
+            for j in 0..4 {
+                words[i][j] = words[i - 8][j] ^ temp[j];
+            }
+        }
+        let mut round_keys = [[0; AES_BLOCK_SIZE]; ROUNDS + 1];
+        for (round, key) in round_keys.iter_mut().enumerate() {
+            for j in 0..4 {
+                key[4 * j..4 * j + 4].copy_from_slice(&words[4 * round + j]);
+            }
+        }
+        Self { round_keys }
+    }
+
+    /// Encrypt a block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; AES_BLOCK_SIZE]) {
+        xor_block(block, &self.round_keys[0]);
+        for round in 1..=ROUNDS {
+            for b in block.iter_mut() {
+                *b = SBOX[*b as usize];
+            }
+            // Row r is rotated left by r columns
+            let state = *block;
+            for c in 0..4 {
+                for r in 0..4 {
+                    block[4 * c + r] = state[4 * ((c + r) % 4) + r];
+                }
+            }
+            if round != ROUNDS {
+                for column in block.chunks_exact_mut(4) {
+                    let a = [column[0], column[1], column[2], column[3]];
+                    for r in 0..4 {
+                        column[r] = xtime(a[r])
+                            ^ xtime(a[(r + 1) % 4])
+                            ^ a[(r + 1) % 4]
+                            ^ a[(r + 2) % 4]
+                            ^ a[(r + 3) % 4];
+                    }
+                }
+            }
+            xor_block(block, &self.round_keys[round]);
+        }
+    }
+
+    /// Decrypt a block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; AES_BLOCK_SIZE]) {
+        xor_block(block, &self.round_keys[ROUNDS]);
+        for round in (0..ROUNDS).rev() {
+            let state = *block;
+            for c in 0..4 {
+                for r in 0..4 {
+                    block[4 * ((c + r) % 4) + r] = INV_SBOX[state[4 * c + r] as usize];
+                }
+            }
+            xor_block(block, &self.round_keys[round]);
+            if round != 0 {
+                for column in block.chunks_exact_mut(4) {
+                    let a = [column[0], column[1], column[2], column[3]];
+                    for r in 0..4 {
+                        column[r] = gmul(a[r], 14)
+                            ^ gmul(a[(r + 1) % 4], 11)
+                            ^ gmul(a[(r + 2) % 4], 13)
+                            ^ gmul(a[(r + 3) % 4], 9);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// AES-256 in XTS mode, with a 512-bit key. Each data unit is encrypted
+/// with its own 128-bit tweak, and must be a multiple of the AES block
+/// size.
+pub struct Aes256Xts {
+    data: Aes256,
+    tweak: Aes256,
+}
+
+impl Aes256Xts {
+    pub fn new(key: &[u8; 64]) -> Self {
+        Self {
+            data: Aes256::new(key[..32].try_into().unwrap()),
+            tweak: Aes256::new(key[32..].try_into().unwrap()),
+        }
+    }
+
+    /// Encrypt a data unit in place.
+    pub fn encrypt(&self, iv: &[u8; AES_BLOCK_SIZE], data: &mut [u8]) {
+        self.crypt(iv, data, |block| self.data.encrypt_block(block));
+    }
+
+    /// Decrypt a data unit in place.
+    pub fn decrypt(&self, iv: &[u8; AES_BLOCK_SIZE], data: &mut [u8]) {
+        self.crypt(iv, data, |block| self.data.decrypt_block(block));
+    }
+
+    fn crypt(&self, iv: &[u8; AES_BLOCK_SIZE], data: &mut [u8], cipher: impl Fn(&mut [u8; 16])) {
+        let mut tweak = *iv;
+        self.tweak.encrypt_block(&mut tweak);
+        for chunk in data.chunks_exact_mut(AES_BLOCK_SIZE) {
+            let block: &mut [u8; AES_BLOCK_SIZE] = chunk.try_into().unwrap();
+            xor_block(block, &tweak);
+            cipher(block);
+            xor_block(block, &tweak);
+            // Multiply the tweak by x in GF(2^128), little endian
+            let carry = tweak[15] >> 7;
+            for i in (1..AES_BLOCK_SIZE).rev() {
+                tweak[i] = (tweak[i] << 1) | (tweak[i - 1] >> 7);
+            }
+            tweak[0] = (tweak[0] << 1) ^ (carry * 0x87);
+        }
+    }
+}
+
+/// AES-256 in CBC mode with ciphertext stealing, the CS3 variant of
+/// Linux `cts(cbc(aes))`: the last two blocks are always swapped. The
+/// message is at least one block long.
+pub struct Aes256Cts(Aes256);
+
+impl Aes256Cts {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self(Aes256::new(key))
+    }
+
+    /// Encrypt a message in place.
+    pub fn encrypt(&self, iv: &[u8; AES_BLOCK_SIZE], data: &mut [u8]) {
+        let blocks = data.len().div_ceil(AES_BLOCK_SIZE);
+        let tail = data.len() - (blocks - 1) * AES_BLOCK_SIZE;
+        let mut prev = *iv;
+        for i in 0..blocks {
+            let mut block = [0; AES_BLOCK_SIZE];
+            let chunk = &data[i * AES_BLOCK_SIZE..data.len().min((i + 1) * AES_BLOCK_SIZE)];
+            block[..chunk.len()].copy_from_slice(chunk);
+            xor_block(&mut block, &prev);
+            self.0.encrypt_block(&mut block);
+            if i + 1 < blocks {
+                data[i * AES_BLOCK_SIZE..(i + 1) * AES_BLOCK_SIZE].copy_from_slice(&block);
+            } else if blocks > 1 {
+                // Steal the head of the previous ciphertext block
+                let last = (blocks - 1) * AES_BLOCK_SIZE;
+                data[last..].copy_from_slice(&prev[..tail]);
+                data[last - AES_BLOCK_SIZE..last].copy_from_slice(&block);
+            } else {
+                data.copy_from_slice(&block);
+            }
+            prev = block;
+        }
+    }
+
+    /// Decrypt a message in place.
+    pub fn decrypt(&self, iv: &[u8; AES_BLOCK_SIZE], data: &mut [u8]) {
+        let blocks = data.len().div_ceil(AES_BLOCK_SIZE);
+        if blocks == 1 {
+            let block: &mut [u8; AES_BLOCK_SIZE] = data.try_into().unwrap();
+            self.0.decrypt_block(block);
+            xor_block(block, iv);
+            return;
+        }
+        let tail = data.len() - (blocks - 1) * AES_BLOCK_SIZE;
+        let last = (blocks - 1) * AES_BLOCK_SIZE;
+        // The ciphertext block before the two swapped ones
+        let mut prev = *iv;
+        if blocks > 2 {
+            prev.copy_from_slice(&data[last - 2 * AES_BLOCK_SIZE..last - AES_BLOCK_SIZE]);
+        }
+        // Recover the last plaintext and the stolen ciphertext block
+        let mut padded: [u8; AES_BLOCK_SIZE] =
+            data[last - AES_BLOCK_SIZE..last].try_into().unwrap();
+        self.0.decrypt_block(&mut padded);
+        let mut stolen = padded;
+        stolen[..tail].copy_from_slice(&data[last..]);
+        xor_block(&mut padded[..tail], &data[last..]);
+        let mut second_last = stolen;
+        self.0.decrypt_block(&mut second_last);
+        xor_block(&mut second_last, &prev);
+        // Plain CBC for the blocks before
+        let mut chain = *iv;
+        for chunk in data[..last - AES_BLOCK_SIZE].chunks_exact_mut(AES_BLOCK_SIZE) {
+            let block: &mut [u8; AES_BLOCK_SIZE] = chunk.try_into().unwrap();
+            let cipher = *block;
+            self.0.decrypt_block(block);
+            xor_block(block, &chain);
+            chain = cipher;
+        }
+        data[last - AES_BLOCK_SIZE..last].copy_from_slice(&second_last);
+        data[last..].copy_from_slice(&padded[..tail]);
+    }
+}
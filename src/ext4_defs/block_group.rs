@@ -11,6 +11,19 @@ use super::Bitmap;
 use crate::constants::*;
 use crate::prelude::*;
 
+bitflags! {
+    /// Block group flags.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct BlockGroupFlags: u16 {
+        /// The inode table and bitmap are not initialized.
+        const INODE_UNINIT = 0x1;
+        /// The block bitmap is not initialized.
+        const BLOCK_UNINIT = 0x2;
+        /// The inode table is zeroed.
+        const ITABLE_ZEROED = 0x4;
+    }
+}
+
 /// The Block Group Descriptor.
 ///
 /// Each block group on the filesystem has one of these descriptors associated with it.
@@ -58,6 +71,20 @@ impl BlockGroupDesc {
     #[allow(unused)]
     const MAX_BLOCK_GROUP_DESC_SIZE: usize = 64;
 
+    /// Create the descriptor of a block group with its bitmaps and inode
+    /// table at the given blocks.
+    pub fn new(block_bitmap: PBlockId, inode_bitmap: PBlockId, inode_table: PBlockId) -> Self {
+        Self {
+            block_bitmap_lo: block_bitmap as u32,
+            block_bitmap_hi: (block_bitmap >> 32) as u32,
+            inode_bitmap_lo: inode_bitmap as u32,
+            inode_bitmap_hi: (inode_bitmap >> 32) as u32,
+            inode_table_first_block_lo: inode_table as u32,
+            inode_table_first_block_hi: (inode_table >> 32) as u32,
+            ..Self::default()
+        }
+    }
+
     pub fn block_bitmap_block(&self) -> PBlockId {
         (self.block_bitmap_hi as PBlockId) << 32 | self.block_bitmap_lo as PBlockId
     }
@@ -97,6 +124,7 @@ impl BlockGroupDesc {
         ((self.inode_table_first_block_hi as u64) << 32) | self.inode_table_first_block_lo as u64
     }
 
+    /// Number of free blocks, counted in clusters with `bigalloc`.
     pub fn get_free_blocks_count(&self) -> u64 {
         ((self.free_blocks_count_hi as u64) << 32) | self.free_blocks_count_lo as u64
     }
@@ -106,11 +134,19 @@ impl BlockGroupDesc {
         self.free_blocks_count_hi = (cnt >> 32) as u16;
     }
 
+    pub fn flags(&self) -> BlockGroupFlags {
+        BlockGroupFlags::from_bits_retain(self.flags)
+    }
+
+    pub fn set_flags(&mut self, flags: BlockGroupFlags) {
+        self.flags = flags.bits();
+    }
+
     pub fn set_inode_bitmap_csum(&mut self, uuid: &[u8], bitmap: &Bitmap) {
         let mut csum = crc32(CRC32_INIT, &uuid);
         csum = crc32(csum, &bitmap.as_bytes());
         self.inode_bitmap_csum_lo = csum as u16;
-        self.block_bitmap_csum_hi = (csum >> 16) as u16;
+        self.inode_bitmap_csum_hi = (csum >> 16) as u16;
     }
 
     pub fn set_block_bitmap_csum(&mut self, uuid: &[u8], bitmap: &Bitmap) {
@@ -119,6 +155,18 @@ impl BlockGroupDesc {
         self.block_bitmap_csum_lo = csum as u16;
         self.block_bitmap_csum_hi = (csum >> 16) as u16;
     }
+
+    /// Check the checksum of the inode bitmap.
+    pub fn verify_inode_bitmap_csum(&self, uuid: &[u8], bitmap: &Bitmap) -> bool {
+        let csum = self.inode_bitmap_csum_lo as u32 | (self.inode_bitmap_csum_hi as u32) << 16;
+        csum == crc32(crc32(CRC32_INIT, uuid), bitmap.as_bytes())
+    }
+
+    /// Check the checksum of the block bitmap.
+    pub fn verify_block_bitmap_csum(&self, uuid: &[u8], bitmap: &Bitmap) -> bool {
+        let csum = self.block_bitmap_csum_lo as u32 | (self.block_bitmap_csum_hi as u32) << 16;
+        csum == crc32(crc32(CRC32_INIT, uuid), bitmap.as_bytes())
+    }
 }
 
 /// A combination of a `BlockGroupDesc` and its id
@@ -136,9 +184,21 @@ impl BlockGroupRef {
     }
 
     pub fn set_checksum(&mut self, uuid: &[u8]) {
+        self.desc.checksum = self.calc_checksum(uuid);
+    }
+
+    /// Check the block group descriptor checksum.
+    pub fn verify_checksum(&self, uuid: &[u8]) -> bool {
+        self.calc_checksum(uuid) == self.desc.checksum
+    }
+
+    /// crc32c(uuid+group+desc) & 0xFFFF, with the checksum field zeroed
+    fn calc_checksum(&self, uuid: &[u8]) -> u16 {
+        let mut desc = self.desc;
+        desc.checksum = 0;
         let mut checksum = crc32(CRC32_INIT, uuid);
         checksum = crc32(checksum, &self.id.to_le_bytes());
-        checksum = crc32(checksum, &self.desc.to_bytes());
-        self.desc.checksum = checksum as u16;
+        checksum = crc32(checksum, desc.to_bytes());
+        checksum as u16
     }
 }
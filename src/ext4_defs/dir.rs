@@ -2,6 +2,7 @@
 //! linear array of directory entries.
 
 use super::crc::*;
+use super::unicode;
 use super::AsBytes;
 use super::FileType;
 use crate::constants::*;
@@ -64,10 +65,10 @@ unsafe impl AsBytes for DirEntry {
 
 impl DirEntry {
     /// Create a new directory entry
-    pub fn new(inode: InodeId, rec_len: u16, name: &str, file_type: FileType) -> Self {
+    pub fn new(inode: InodeId, rec_len: u16, name: &[u8], file_type: FileType) -> Self {
         let mut name_bytes = [0u8; 255];
-        let name_len = name.as_bytes().len();
-        name_bytes[..name_len].copy_from_slice(name.as_bytes());
+        let name_len = name.len();
+        name_bytes[..name_len].copy_from_slice(name);
         Self {
             inode,
             rec_len,
@@ -90,9 +91,31 @@ impl DirEntry {
         }
     }
 
+    /// Get the name of the directory entry as stored, which is not UTF-8
+    /// in an encrypted directory
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name[..self.name_len as usize]
+    }
+
+    /// Replace the name of the directory entry, keeping its record length
+    pub fn set_name(&mut self, name: &[u8]) {
+        self.name[..name.len()].copy_from_slice(name);
+        self.name_len = name.len() as u8;
+    }
+
     /// Compare the name of the directory entry with a given name
-    pub fn compare_name(&self, name: &str) -> bool {
-        &self.name[..name.len()] == name.as_bytes()
+    pub fn compare_name(&self, name: &[u8]) -> bool {
+        self.name_bytes() == name
+    }
+
+    /// Compare the name of the directory entry with a given name, ignoring
+    /// case if `folded`, the folded form of `name`, is given. Names that
+    /// are not valid UTF-8 are compared exactly.
+    pub fn compare_name_folded(&self, name: &[u8], folded: Option<&str>) -> bool {
+        match (folded, core::str::from_utf8(self.name_bytes())) {
+            (Some(folded), Ok(own)) => unicode::casefold(own) == folded,
+            _ => self.compare_name(name),
+        }
     }
 
     /// Check if the directory entry is unused (inode = 0)
@@ -150,10 +173,20 @@ impl DirEntryTail {
     }
 
     pub fn set_checksum(&mut self, uuid: &[u8], ino: InodeId, ino_gen: u32, block: &Block) {
-        let mut csum = crc32(CRC32_INIT, &uuid);
+        self.checksum = Self::calc_checksum(uuid, ino, ino_gen, block);
+    }
+
+    /// Check the checksum of the directory block holding the tail.
+    pub fn verify_checksum(&self, uuid: &[u8], ino: InodeId, ino_gen: u32, block: &Block) -> bool {
+        self.checksum == Self::calc_checksum(uuid, ino, ino_gen, block)
+    }
+
+    /// crc32c(uuid+inum+generation+entries), the entries end at the tail
+    fn calc_checksum(uuid: &[u8], ino: InodeId, ino_gen: u32, block: &Block) -> u32 {
+        let mut csum = crc32(CRC32_INIT, uuid);
         csum = crc32(csum, &ino.to_le_bytes());
         csum = crc32(csum, &ino_gen.to_le_bytes());
-        self.checksum = crc32(csum, &block.data[..size_of::<DirEntryTail>()]);
+        crc32(csum, &block.data[..BLOCK_SIZE - size_of::<DirEntryTail>()])
     }
 }
 
@@ -175,18 +208,20 @@ impl DirBlock {
     /// and the dir entry tail.
     pub fn init(&mut self) {
         let tail_offset = BLOCK_SIZE - size_of::<DirEntryTail>();
-        let entry = DirEntry::new(0, tail_offset as u16, "", FileType::Unknown);
+        let entry = DirEntry::new(0, tail_offset as u16, b"", FileType::Unknown);
         self.0.write_offset_as(0, &entry);
         let tail = DirEntryTail::new();
         self.0.write_offset_as(tail_offset, &tail);
     }
 
     /// Get a directory entry by name, return the inode id of the entry.
-    pub fn get(&self, name: &str) -> Option<InodeId> {
+    /// Names are compared ignoring case if `casefold`.
+    pub fn get(&self, name: &[u8], casefold: bool) -> Option<InodeId> {
+        let folded = Self::fold(name, casefold);
         let mut offset = 0;
         while offset < BLOCK_SIZE {
             let de: DirEntry = self.0.read_offset_as(offset);
-            if !de.unused() && de.compare_name(name) {
+            if !de.unused() && de.compare_name_folded(name, folded.as_deref()) {
                 return Some(de.inode);
             }
             offset += de.rec_len as usize;
@@ -209,15 +244,16 @@ impl DirBlock {
 
     /// Insert a directory entry to the block. Return true if success or false
     /// if the block doesn't have enough space.
-    pub fn insert(&mut self, name: &str, inode: InodeId, file_type: FileType) -> bool {
+    pub fn insert(&mut self, name: &[u8], inode: InodeId, file_type: FileType) -> bool {
         let required_size = DirEntry::required_size(name.len());
         let mut offset = 0;
-        while offset < BLOCK_SIZE {
+        // Never reuse the dir entry tail
+        while offset < BLOCK_SIZE - size_of::<DirEntryTail>() {
             // Read a dir entry
             let mut de: DirEntry = self.0.read_offset_as(offset);
             let rec_len = de.rec_len as usize;
-            // The size that `de` actually uses
-            let used_size = de.used_size();
+            // The size that `de` actually uses, an unused entry is replaced
+            let used_size = if de.unused() { 0 } else { de.used_size() };
             // The rest size
             let free_size = rec_len - used_size;
             // Try splitting dir entry
@@ -229,8 +265,10 @@ impl DirBlock {
             }
             // Has enough space
             // Update the old entry
-            de.rec_len = used_size as u16;
-            self.0.write_offset_as(offset, &de);
+            if used_size > 0 {
+                de.rec_len = used_size as u16;
+                self.0.write_offset_as(offset, &de);
+            }
             // Insert the new entry
             let new_entry = DirEntry::new(inode, free_size as u16, name, file_type);
             self.0.write_offset_as(offset + used_size, &new_entry);
@@ -240,12 +278,14 @@ impl DirBlock {
     }
 
     /// Remove a directory entry from the block. Return true if success or false
-    /// if the entry doesn't exist.
-    pub fn remove(&mut self, name: &str) -> bool {
+    /// if the entry doesn't exist. Names are compared ignoring case if
+    /// `casefold`.
+    pub fn remove(&mut self, name: &[u8], casefold: bool) -> bool {
+        let folded = Self::fold(name, casefold);
         let mut offset = 0;
         while offset < BLOCK_SIZE {
             let mut de: DirEntry = self.0.read_offset_as(offset);
-            if !de.unused() && de.compare_name(name) {
+            if !de.unused() && de.compare_name_folded(name, folded.as_deref()) {
                 // Mark the target entry as unused
                 de.set_unused();
                 self.0.write_offset_as(offset, &de);
@@ -256,6 +296,33 @@ impl DirBlock {
         false
     }
 
+    /// The folded form of `name` to compare with, if `casefold` and
+    /// `name` is valid UTF-8.
+    fn fold(name: &[u8], casefold: bool) -> Option<String> {
+        if casefold {
+            core::str::from_utf8(name).ok().map(unicode::casefold)
+        } else {
+            None
+        }
+    }
+
+    /// Point the entries to the inodes in `renumbered` to their new
+    /// numbers. Return true if an entry is changed.
+    pub fn renumber(&mut self, renumbered: &BTreeMap<InodeId, InodeId>) -> bool {
+        let mut changed = false;
+        let mut offset = 0;
+        while offset < BLOCK_SIZE {
+            let mut de: DirEntry = self.0.read_offset_as(offset);
+            if let Some(&inode) = renumbered.get(&de.inode) {
+                de.inode = inode;
+                self.0.write_offset_as(offset, &de);
+                changed = true;
+            }
+            offset += de.rec_len as usize;
+        }
+        changed
+    }
+
     /// Calc and set block checksum
     pub fn set_checksum(&mut self, uuid: &[u8], ino: InodeId, ino_gen: u32) {
         let tail_offset = BLOCK_SIZE - size_of::<DirEntryTail>();
@@ -263,4 +330,11 @@ impl DirBlock {
         tail.set_checksum(uuid, ino, ino_gen, &self.0);
         self.0.write_offset_as(tail_offset, &tail);
     }
+
+    /// Check block checksum
+    pub fn verify_checksum(&self, uuid: &[u8], ino: InodeId, ino_gen: u32) -> bool {
+        let tail_offset = BLOCK_SIZE - size_of::<DirEntryTail>();
+        let tail: DirEntryTail = self.0.read_offset_as(tail_offset);
+        tail.verify_checksum(uuid, ino, ino_gen, &self.0)
+    }
 }
@@ -13,6 +13,8 @@
 //! inode.i_block, which allows for the first four extents to be recorded without
 //! the use of extra metadata blocks.
 
+use super::crc::*;
+use crate::constants::*;
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -52,6 +54,11 @@ impl ExtentHeader {
         }
     }
 
+    /// Check the magic number of the extent header
+    pub fn check_magic(&self) -> bool {
+        self.magic == Self::EXTENT_MAGIC
+    }
+
     /// 获取extent header的条目数
     pub fn entries_count(&self) -> u16 {
         self.entries_count
@@ -126,6 +133,12 @@ impl ExtentIndex {
     pub fn leaf(&self) -> PBlockId {
         (self.leaf_hi as PBlockId) << 32 | self.leaf_lo as PBlockId
     }
+
+    /// Set the physical block number of the child node
+    pub fn set_leaf(&mut self, leaf: PBlockId) {
+        self.leaf_lo = leaf as u32;
+        self.leaf_hi = (leaf >> 32) as u16;
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -279,6 +292,15 @@ impl<'a> ExtentNode<'a> {
         unsafe { &*((self.header() as *const ExtentHeader).add(1) as *const ExtentIndex).add(pos) }
     }
 
+    /// Check the checksum of a node stored in a block, which follows the
+    /// last entry slot.
+    pub fn verify_checksum(&self, uuid: &[u8], ino: InodeId, ino_gen: u32) -> bool {
+        let tail = tail_offset(self.header());
+        tail + 4 <= self.raw_data.len()
+            && self.raw_data[tail..tail + 4]
+                == calc_checksum(&self.raw_data[..tail], uuid, ino, ino_gen).to_le_bytes()
+    }
+
     /// Find the extent that covers the given logical block number.
     ///
     /// Return `Ok(index)` if found, and `eh.extent_at(index)` is the extent that covers
@@ -421,6 +443,14 @@ impl<'a> ExtentNodeMut<'a> {
         }
     }
 
+    /// Calc and set the checksum of a node stored in a block, which follows
+    /// the last entry slot.
+    pub fn set_checksum(&mut self, uuid: &[u8], ino: InodeId, ino_gen: u32) {
+        let tail = tail_offset(self.header());
+        let checksum = calc_checksum(&self.raw_data[..tail], uuid, ino, ino_gen);
+        self.raw_data[tail..tail + 4].copy_from_slice(&checksum.to_le_bytes());
+    }
+
     /// Initialize the extent node
     pub fn init(&mut self, depth: u16, generation: u32) {
         let max_entries_count =
@@ -514,6 +544,17 @@ impl<'a> ExtentNodeMut<'a> {
         Err(split)
     }
 
+    /// Remove the extent at `pos` and move the following extents.
+    ///
+    /// This function requires this extent node to be a leaf node.
+    pub fn remove_extent(&mut self, pos: usize) {
+        let count = self.header().entries_count() as usize;
+        for i in pos..count - 1 {
+            *self.extent_mut_at(i) = *self.extent_at(i + 1);
+        }
+        self.header_mut().entries_count -= 1;
+    }
+
     /// Insert a new extent index into current node.
     ///
     /// Return `Ok(())` if the insertion is successful. Return `Err(extent_indexs)` if
@@ -563,3 +604,16 @@ impl<'a> ExtentNodeMut<'a> {
         Err(split)
     }
 }
+
+/// Offset of the checksum of a node stored in a block.
+fn tail_offset(header: &ExtentHeader) -> usize {
+    size_of::<ExtentHeader>() + header.max_entries_count() as usize * size_of::<Extent>()
+}
+
+/// crc32c(uuid+inum+generation+node), the node ends at the checksum
+fn calc_checksum(node: &[u8], uuid: &[u8], ino: InodeId, ino_gen: u32) -> u32 {
+    let mut csum = crc32(CRC32_INIT, uuid);
+    csum = crc32(csum, &ino.to_le_bytes());
+    csum = crc32(csum, &ino_gen.to_le_bytes());
+    crc32(csum, node)
+}
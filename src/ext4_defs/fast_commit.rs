@@ -0,0 +1,322 @@
+//! Fast commits record inode-level changes in the fast commit area, the
+//! last `jsb.num_fc_blocks` blocks of the journal, instead of copies of
+//! whole metadata blocks. All fields are little-endian.
+//!
+//! The area is a stream of tag-length-value records which do not cross
+//! block boundaries, a `Pad` record fills the rest of a block if the next
+//! record does not fit. A fast commit is a sequence of records ended by a
+//! `Tail` record, which holds the crc32c of every record since the
+//! previous tail. The first fast commit after a full commit starts with a
+//! `Head` record. Fast commits belong to the running transaction, and are
+//! only valid until the transaction is committed.
+
+use super::crc::*;
+use super::Extent;
+use crate::constants::*;
+use crate::prelude::*;
+
+/// A dentry change recorded by a fast commit.
+#[derive(Debug, Clone)]
+pub struct FastCommitDentry {
+    /// The parent directory.
+    pub parent: InodeId,
+    /// The inode the entry points to.
+    pub inode: InodeId,
+    /// The entry name.
+    pub name: String,
+}
+
+/// A record in the fast commit area.
+#[derive(Debug, Clone)]
+pub enum FastCommitTag {
+    /// Blocks mapped to an inode.
+    AddRange { inode: InodeId, extent: Extent },
+    /// Blocks unmapped from an inode.
+    DelRange {
+        inode: InodeId,
+        lblock: LBlockId,
+        len: u32,
+    },
+    /// A new inode linked to a directory.
+    Create(FastCommitDentry),
+    /// An existing inode linked to a directory.
+    Link(FastCommitDentry),
+    /// An inode unlinked from a directory.
+    Unlink(FastCommitDentry),
+    /// The on-disk inode, excluding its block map.
+    Inode { inode: InodeId, raw: Vec<u8> },
+    /// Padding to the end of the block.
+    Pad,
+    /// The end of a fast commit.
+    Tail { tid: u32, crc: u32 },
+    /// The start of the fast commits of a transaction.
+    Head { features: u32, tid: u32 },
+}
+
+impl FastCommitTag {
+    const ADD_RANGE: u16 = 1;
+    const DEL_RANGE: u16 = 2;
+    const CREATE: u16 = 3;
+    const LINK: u16 = 4;
+    const UNLINK: u16 = 5;
+    const INODE: u16 = 6;
+    const PAD: u16 = 7;
+    const TAIL: u16 = 8;
+    const HEAD: u16 = 9;
+
+    /// Size of the tag and length fields of a record.
+    const HEADER_SIZE: usize = 4;
+
+    /// Parse the value of a record, `None` if the tag is unknown or the
+    /// length is invalid.
+    fn parse(tag: u16, value: &[u8]) -> Option<Self> {
+        let u32_at = |offset: usize| read_le32(value, offset);
+        let dentry = || {
+            if value.len() <= 8 || value.len() > 8 + NAME_MAX {
+                return None;
+            }
+            Some(FastCommitDentry {
+                parent: u32_at(0),
+                inode: u32_at(4),
+                name: String::from_utf8_lossy(&value[8..]).into_owned(),
+            })
+        };
+        match tag {
+            Self::ADD_RANGE if value.len() == 16 => {
+                let start = u32_at(12) as PBlockId | (read_le16(value, 10) as PBlockId) << 32;
+                Some(Self::AddRange {
+                    inode: u32_at(0),
+                    extent: Extent::new(u32_at(4), start, read_le16(value, 8)),
+                })
+            }
+            Self::DEL_RANGE if value.len() == 12 => Some(Self::DelRange {
+                inode: u32_at(0),
+                lblock: u32_at(4),
+                len: u32_at(8),
+            }),
+            Self::CREATE => dentry().map(Self::Create),
+            Self::LINK => dentry().map(Self::Link),
+            Self::UNLINK => dentry().map(Self::Unlink),
+            Self::INODE if value.len() >= 4 + 128 && value.len() <= 4 + SB_GOOD_INODE_SIZE => {
+                Some(Self::Inode {
+                    inode: u32_at(0),
+                    raw: value[4..].to_vec(),
+                })
+            }
+            Self::PAD => Some(Self::Pad),
+            Self::TAIL if value.len() >= 8 => Some(Self::Tail {
+                tid: u32_at(0),
+                crc: u32_at(4),
+            }),
+            Self::HEAD if value.len() == 8 => Some(Self::Head {
+                features: u32_at(0),
+                tid: u32_at(4),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The tag and value of the record. Tails are written by
+    /// `FastCommitBuilder::finish`.
+    fn serialize(&self) -> (u16, Vec<u8>) {
+        let mut value = Vec::new();
+        let dentry = |value: &mut Vec<u8>, dentry: &FastCommitDentry| {
+            value.extend_from_slice(&dentry.parent.to_le_bytes());
+            value.extend_from_slice(&dentry.inode.to_le_bytes());
+            value.extend_from_slice(dentry.name.as_bytes());
+        };
+        let tag = match self {
+            Self::AddRange { inode, extent } => {
+                let mut len = extent.block_count() as u16;
+                if extent.is_unwritten() {
+                    len += 32768;
+                }
+                value.extend_from_slice(&inode.to_le_bytes());
+                value.extend_from_slice(&extent.start_lblock().to_le_bytes());
+                value.extend_from_slice(&len.to_le_bytes());
+                value.extend_from_slice(&((extent.start_pblock() >> 32) as u16).to_le_bytes());
+                value.extend_from_slice(&(extent.start_pblock() as u32).to_le_bytes());
+                Self::ADD_RANGE
+            }
+            Self::DelRange { inode, lblock, len } => {
+                value.extend_from_slice(&inode.to_le_bytes());
+                value.extend_from_slice(&lblock.to_le_bytes());
+                value.extend_from_slice(&len.to_le_bytes());
+                Self::DEL_RANGE
+            }
+            Self::Create(d) => {
+                dentry(&mut value, d);
+                Self::CREATE
+            }
+            Self::Link(d) => {
+                dentry(&mut value, d);
+                Self::LINK
+            }
+            Self::Unlink(d) => {
+                dentry(&mut value, d);
+                Self::UNLINK
+            }
+            Self::Inode { inode, raw } => {
+                value.extend_from_slice(&inode.to_le_bytes());
+                value.extend_from_slice(raw);
+                Self::INODE
+            }
+            Self::Pad => Self::PAD,
+            Self::Tail { tid, crc } => {
+                value.extend_from_slice(&tid.to_le_bytes());
+                value.extend_from_slice(&crc.to_le_bytes());
+                Self::TAIL
+            }
+            Self::Head { features, tid } => {
+                value.extend_from_slice(&features.to_le_bytes());
+                value.extend_from_slice(&tid.to_le_bytes());
+                Self::HEAD
+            }
+        };
+        (tag, value)
+    }
+}
+
+/// Scans the fast commit area block by block, and collects the records
+/// of the valid fast commits of a transaction.
+pub struct FastCommitScanner {
+    /// The transaction the fast commits belong to.
+    tid: u32,
+    /// crc32c of the records since the last tail.
+    crc: u32,
+    /// Records since the last tail.
+    pending: Vec<FastCommitTag>,
+    /// Records of the valid fast commits.
+    tags: Vec<FastCommitTag>,
+    /// Set when the end of the valid fast commits is found.
+    done: bool,
+}
+
+impl FastCommitScanner {
+    /// Scan the fast commits of transaction `tid`.
+    pub fn new(tid: u32) -> Self {
+        Self {
+            tid,
+            crc: 0,
+            pending: Vec::new(),
+            tags: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Scan the next block of the area. Return `false` when the end of
+    /// the valid fast commits is found.
+    pub fn scan_block(&mut self, block: &[u8]) -> bool {
+        let mut offset = 0;
+        while !self.done && offset + FastCommitTag::HEADER_SIZE <= BLOCK_SIZE {
+            let tag = read_le16(block, offset);
+            let len = read_le16(block, offset + 2) as usize;
+            let end = offset + FastCommitTag::HEADER_SIZE + len;
+            if end > BLOCK_SIZE {
+                self.done = true;
+                break;
+            }
+            let value = &block[offset + FastCommitTag::HEADER_SIZE..end];
+            match FastCommitTag::parse(tag, value) {
+                Some(FastCommitTag::Tail { tid, crc }) => {
+                    // The crc ends before its own field
+                    self.crc = crc32(self.crc, &block[offset..offset + 8]);
+                    if tid != self.tid || crc != self.crc {
+                        self.done = true;
+                        break;
+                    }
+                    self.tags.append(&mut self.pending);
+                    self.crc = 0;
+                }
+                Some(FastCommitTag::Head { features, tid }) if features != 0 || tid != self.tid => {
+                    self.done = true;
+                }
+                Some(record) => {
+                    self.crc = crc32(self.crc, &block[offset..end]);
+                    self.pending.push(record);
+                }
+                None => self.done = true,
+            }
+            offset = end;
+        }
+        !self.done
+    }
+
+    /// The records of the valid fast commits, in order.
+    pub fn into_tags(self) -> Vec<FastCommitTag> {
+        self.tags
+    }
+}
+
+/// Builds the blocks of a fast commit.
+pub struct FastCommitBuilder {
+    blocks: Vec<[u8; BLOCK_SIZE]>,
+    /// Offset of the next record in the last block.
+    offset: usize,
+    /// crc32c of the records so far.
+    crc: u32,
+}
+
+impl FastCommitBuilder {
+    pub fn new() -> Self {
+        Self {
+            blocks: vec![[0; BLOCK_SIZE]],
+            offset: 0,
+            crc: 0,
+        }
+    }
+
+    /// Append a record.
+    pub fn push(&mut self, tag: &FastCommitTag) {
+        let (tag, value) = tag.serialize();
+        let size = FastCommitTag::HEADER_SIZE + value.len();
+        let offset = self.reserve(size);
+        let bytes = &mut self.blocks.last_mut().unwrap()[offset..offset + size];
+        bytes[0..2].copy_from_slice(&tag.to_le_bytes());
+        bytes[2..4].copy_from_slice(&(value.len() as u16).to_le_bytes());
+        bytes[4..].copy_from_slice(&value);
+        self.crc = crc32(self.crc, bytes);
+    }
+
+    /// End the fast commit of transaction `tid` with a tail filling the
+    /// rest of the block, and return its blocks.
+    pub fn finish(mut self, tid: u32) -> Vec<[u8; BLOCK_SIZE]> {
+        let offset = self.reserve(FastCommitTag::HEADER_SIZE + 8);
+        let len = BLOCK_SIZE - offset - FastCommitTag::HEADER_SIZE;
+        let block = self.blocks.last_mut().unwrap();
+        block[offset..offset + 2].copy_from_slice(&FastCommitTag::TAIL.to_le_bytes());
+        block[offset + 2..offset + 4].copy_from_slice(&(len as u16).to_le_bytes());
+        block[offset + 4..offset + 8].copy_from_slice(&tid.to_le_bytes());
+        let crc = crc32(self.crc, &block[offset..offset + 8]);
+        block[offset + 8..offset + 12].copy_from_slice(&crc.to_le_bytes());
+        self.blocks
+    }
+
+    /// Reserve `size` bytes for a record in the last block and return
+    /// their offset, padding the block and starting a new one if the
+    /// record does not fit.
+    fn reserve(&mut self, size: usize) -> usize {
+        // Keep room for a pad record at the end of the block
+        if self.offset + size + FastCommitTag::HEADER_SIZE > BLOCK_SIZE {
+            let len = BLOCK_SIZE - self.offset - FastCommitTag::HEADER_SIZE;
+            let offset = self.offset;
+            let block = self.blocks.last_mut().unwrap();
+            block[offset..offset + 2].copy_from_slice(&FastCommitTag::PAD.to_le_bytes());
+            block[offset + 2..offset + 4].copy_from_slice(&(len as u16).to_le_bytes());
+            self.crc = crc32(self.crc, &block[offset..]);
+            self.blocks.push([0; BLOCK_SIZE]);
+            self.offset = 0;
+        }
+        let offset = self.offset;
+        self.offset += size;
+        offset
+    }
+}
+
+fn read_le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_le32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
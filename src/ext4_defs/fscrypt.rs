@@ -0,0 +1,336 @@
+//! Encryption of directories and files with fscrypt policy v2.
+//!
+//! An encrypted inode has the `ENCRYPT` flag and an encryption context
+//! in the xattr named `c` of the encryption index. The context holds the
+//! policy of the directory tree, i.e. the identifier of the master key
+//! and the encryption modes, and a random nonce of the inode.
+//!
+//! Keys are derived from the master key with HKDF-SHA512:
+//!
+//! - the key identifier, with the context byte 1 and no info;
+//! - the key of an inode, with the context byte 2 and its nonce as info.
+//!   A regular file uses it as an AES-256-XTS key for its contents, a
+//!   directory as an AES-256-CTS key for the names of its entries.
+//!
+//! Each block of a file is encrypted with the logical block number as
+//! tweak. Names are padded with NULs and encrypted with a zero IV.
+
+use super::aes::{Aes256Cts, Aes256Xts, AES_BLOCK_SIZE};
+use super::sha::{HkdfSha512, Sha256};
+use super::AsBytes;
+use crate::constants::*;
+use crate::prelude::*;
+
+/// The xattr that stores the encryption context of an inode.
+pub const XATTR_NAME_ENCRYPTION_CONTEXT: &str = "encryption.c";
+
+/// Size of a master key identifier in bytes.
+pub const FSCRYPT_KEY_IDENTIFIER_SIZE: usize = 16;
+
+/// Minimum size of a master key in bytes.
+pub const FSCRYPT_MIN_KEY_SIZE: usize = 16;
+
+/// Maximum size of a master key in bytes.
+pub const FSCRYPT_MAX_KEY_SIZE: usize = 64;
+
+/// Size of the nonce of an inode.
+const FSCRYPT_FILE_NONCE_SIZE: usize = 16;
+
+/// Version of the encryption context and policy.
+const FSCRYPT_CONTEXT_V2: u8 = 2;
+
+/// Padding of names, as the log2 of 4 bytes.
+const FSCRYPT_POLICY_FLAGS_PAD_MASK: u8 = 0x3;
+
+/// Names are encrypted to at least one AES block.
+const FSCRYPT_FNAME_MIN_MSG_LEN: usize = AES_BLOCK_SIZE;
+
+/// HKDF context of the key identifier.
+const HKDF_CONTEXT_KEY_IDENTIFIER: u8 = 1;
+
+/// HKDF context of the key of an inode.
+const HKDF_CONTEXT_PER_FILE_ENC_KEY: u8 = 2;
+
+/// HKDF context of the nonces of new inodes. Not used by Linux, nonces
+/// are random there.
+const HKDF_CONTEXT_NONCE: u8 = 0x80;
+
+/// Number of ciphertext bytes kept in a no-key name.
+const NOKEY_NAME_BYTES: usize = 149;
+
+/// Size of a no-key name of a long name: the directory hash, the head of
+/// the ciphertext and the SHA-256 of its rest.
+const NOKEY_NAME_MAX: usize = 8 + NOKEY_NAME_BYTES + 32;
+
+/// The encryption policy of a directory tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionPolicy {
+    /// Encryption mode of file contents.
+    pub contents_mode: u8,
+    /// Encryption mode of file names.
+    pub filenames_mode: u8,
+    /// Policy flags, the low 2 bits are the padding of names.
+    pub flags: u8,
+    /// Identifier of the master key, as returned by
+    /// `Ext4::add_encryption_key`.
+    pub master_key_identifier: [u8; FSCRYPT_KEY_IDENTIFIER_SIZE],
+}
+
+impl EncryptionPolicy {
+    /// AES-256-XTS, the only supported mode of file contents.
+    pub const MODE_AES_256_XTS: u8 = 1;
+    /// AES-256-CTS, the only supported mode of file names.
+    pub const MODE_AES_256_CTS: u8 = 4;
+    /// Names are padded to 32 bytes.
+    pub const FLAGS_PAD_32: u8 = 0x3;
+
+    /// The default policy for a master key: AES-256-XTS contents and
+    /// AES-256-CTS names padded to 32 bytes.
+    pub fn new(master_key_identifier: [u8; FSCRYPT_KEY_IDENTIFIER_SIZE]) -> Self {
+        Self {
+            contents_mode: Self::MODE_AES_256_XTS,
+            filenames_mode: Self::MODE_AES_256_CTS,
+            flags: Self::FLAGS_PAD_32,
+            master_key_identifier,
+        }
+    }
+
+    /// Whether the modes and flags are supported.
+    pub fn is_supported(&self) -> bool {
+        self.contents_mode == Self::MODE_AES_256_XTS
+            && self.filenames_mode == Self::MODE_AES_256_CTS
+            && self.flags & !FSCRYPT_POLICY_FLAGS_PAD_MASK == 0
+    }
+}
+
+/// The encryption context of an inode, version 2.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EncryptionContext {
+    version: u8,
+    contents_mode: u8,
+    filenames_mode: u8,
+    flags: u8,
+    /// Log2 of the data unit size, 0 for the block size.
+    log2_data_unit_size: u8,
+    reserved: [u8; 3],
+    master_key_identifier: [u8; FSCRYPT_KEY_IDENTIFIER_SIZE],
+    nonce: [u8; FSCRYPT_FILE_NONCE_SIZE],
+}
+
+unsafe impl AsBytes for EncryptionContext {}
+
+impl EncryptionContext {
+    /// Create the context of a new inode.
+    pub fn new(policy: &EncryptionPolicy, nonce: [u8; FSCRYPT_FILE_NONCE_SIZE]) -> Self {
+        Self {
+            version: FSCRYPT_CONTEXT_V2,
+            contents_mode: policy.contents_mode,
+            filenames_mode: policy.filenames_mode,
+            flags: policy.flags,
+            log2_data_unit_size: 0,
+            reserved: [0; 3],
+            master_key_identifier: policy.master_key_identifier,
+            nonce,
+        }
+    }
+
+    /// Parse the context from an xattr value. Return `None` if it is not
+    /// a v2 context.
+    pub fn from_xattr(value: &[u8]) -> Option<Self> {
+        if value.len() != size_of::<Self>() || value[0] != FSCRYPT_CONTEXT_V2 {
+            return None;
+        }
+        Some(Self::from_bytes(value))
+    }
+
+    /// The policy of the inode.
+    pub fn policy(&self) -> EncryptionPolicy {
+        EncryptionPolicy {
+            contents_mode: self.contents_mode,
+            filenames_mode: self.filenames_mode,
+            flags: self.flags,
+            master_key_identifier: self.master_key_identifier,
+        }
+    }
+
+    /// Whether data units are blocks, the only supported size.
+    pub fn has_block_data_units(&self) -> bool {
+        self.log2_data_unit_size == 0 || 1 << self.log2_data_unit_size as usize == BLOCK_SIZE
+    }
+
+    /// The nonce of the inode.
+    pub fn nonce(&self) -> &[u8; FSCRYPT_FILE_NONCE_SIZE] {
+        &self.nonce
+    }
+}
+
+/// A master key provided by the user.
+pub struct MasterKey {
+    hkdf: HkdfSha512,
+}
+
+impl MasterKey {
+    /// Create a master key from its secret, of `FSCRYPT_MIN_KEY_SIZE` to
+    /// `FSCRYPT_MAX_KEY_SIZE` bytes.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            hkdf: HkdfSha512::new(secret),
+        }
+    }
+
+    /// The identifier of the key.
+    pub fn identifier(&self) -> [u8; FSCRYPT_KEY_IDENTIFIER_SIZE] {
+        let mut identifier = [0; FSCRYPT_KEY_IDENTIFIER_SIZE];
+        self.derive(HKDF_CONTEXT_KEY_IDENTIFIER, &[], &mut identifier);
+        identifier
+    }
+
+    /// The key of the contents of a regular file.
+    pub fn contents_key(&self, context: &EncryptionContext) -> ContentsKey {
+        let mut key = [0; 64];
+        self.derive(HKDF_CONTEXT_PER_FILE_ENC_KEY, context.nonce(), &mut key);
+        ContentsKey(Aes256Xts::new(&key))
+    }
+
+    /// The key of the entry names of a directory.
+    pub fn filenames_key(&self, context: &EncryptionContext) -> FilenameKey {
+        let mut key = [0; 32];
+        self.derive(HKDF_CONTEXT_PER_FILE_ENC_KEY, context.nonce(), &mut key);
+        FilenameKey {
+            cipher: Aes256Cts::new(&key),
+            padding: 4 << (context.flags & FSCRYPT_POLICY_FLAGS_PAD_MASK),
+        }
+    }
+
+    /// A nonce for a new inode, unique for each `seed`. It is derived
+    /// from the key as there is no source of randomness.
+    pub fn nonce(&self, seed: &[u8]) -> [u8; FSCRYPT_FILE_NONCE_SIZE] {
+        let mut nonce = [0; FSCRYPT_FILE_NONCE_SIZE];
+        self.derive(HKDF_CONTEXT_NONCE, seed, &mut nonce);
+        nonce
+    }
+
+    fn derive(&self, context: u8, info: &[u8], out: &mut [u8]) {
+        self.hkdf.expand(&[b"fscrypt\0", &[context], info], out);
+    }
+}
+
+/// The key of the contents of a regular file.
+pub struct ContentsKey(Aes256Xts);
+
+impl ContentsKey {
+    /// Encrypt the block `iblock` of the file in place.
+    pub fn encrypt_block(&self, iblock: LBlockId, data: &mut [u8]) {
+        self.0.encrypt(&Self::iv(iblock), data);
+    }
+
+    /// Decrypt the block `iblock` of the file in place.
+    pub fn decrypt_block(&self, iblock: LBlockId, data: &mut [u8]) {
+        self.0.decrypt(&Self::iv(iblock), data);
+    }
+
+    /// The tweak of a block, its logical block number in little endian.
+    fn iv(iblock: LBlockId) -> [u8; AES_BLOCK_SIZE] {
+        let mut iv = [0; AES_BLOCK_SIZE];
+        iv[..8].copy_from_slice(&(iblock as u64).to_le_bytes());
+        iv
+    }
+}
+
+/// The key of the entry names of a directory.
+pub struct FilenameKey {
+    cipher: Aes256Cts,
+    /// Names are padded to a multiple of this size.
+    padding: usize,
+}
+
+impl FilenameKey {
+    /// Encrypt a name. The ciphertext is at least 16 bytes long.
+    pub fn encrypt(&self, name: &[u8]) -> Vec<u8> {
+        let len = name.len().max(FSCRYPT_FNAME_MIN_MSG_LEN);
+        let len = len.next_multiple_of(self.padding).min(NAME_MAX);
+        let mut data = vec![0; len];
+        data[..name.len()].copy_from_slice(name);
+        self.cipher.encrypt(&[0; AES_BLOCK_SIZE], &mut data);
+        data
+    }
+
+    /// Decrypt a name. Return `None` if the ciphertext is too short.
+    pub fn decrypt(&self, disk_name: &[u8]) -> Option<Vec<u8>> {
+        if disk_name.len() < FSCRYPT_FNAME_MIN_MSG_LEN {
+            return None;
+        }
+        let mut data = disk_name.to_vec();
+        self.cipher.decrypt(&[0; AES_BLOCK_SIZE], &mut data);
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        data.truncate(len);
+        Some(data)
+    }
+}
+
+/// The name under which an entry of a directory is listed without its
+/// key: the base64url encoding of the ciphertext, or for long names of
+/// its head and the SHA-256 of the rest. The directory hash is 0 as
+/// directories are not indexed.
+pub fn nokey_name(disk_name: &[u8]) -> String {
+    let mut raw = vec![0u8; 8];
+    if disk_name.len() <= NOKEY_NAME_BYTES {
+        raw.extend_from_slice(disk_name);
+    } else {
+        raw.extend_from_slice(&disk_name[..NOKEY_NAME_BYTES]);
+        raw.extend_from_slice(&Sha256::digest(&disk_name[NOKEY_NAME_BYTES..]));
+    }
+    base64url_encode(&raw)
+}
+
+/// Whether `name` is the no-key name of the entry named `disk_name`.
+pub fn nokey_name_matches(name: &str, disk_name: &[u8]) -> bool {
+    let Some(raw) = base64url_decode(name) else {
+        return false;
+    };
+    if raw.len() == NOKEY_NAME_MAX {
+        disk_name.len() > NOKEY_NAME_BYTES
+            && raw[8..8 + NOKEY_NAME_BYTES] == disk_name[..NOKEY_NAME_BYTES]
+            && raw[8 + NOKEY_NAME_BYTES..] == Sha256::digest(&disk_name[NOKEY_NAME_BYTES..])
+    } else {
+        raw.len() > 8 && raw[8..] == *disk_name
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base64url encoding without padding.
+fn base64url_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let mut bits = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            bits |= (b as u32) << (16 - 8 * i);
+        }
+        for i in 0..chunk.len() + 1 {
+            out.push(BASE64URL[(bits >> (18 - 6 * i)) as usize & 0x3f] as char);
+        }
+    }
+    out
+}
+
+/// Base64url decoding without padding. Return `None` if `s` is not
+/// encoded so.
+fn base64url_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut bits = 0u32;
+    let mut nbits = 0;
+    for c in s.bytes() {
+        let value = BASE64URL.iter().position(|&b| b == c)? as u32;
+        bits = bits << 6 | value;
+        nbits += 6;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push((bits >> nbits) as u8);
+            bits &= (1 << nbits) - 1;
+        }
+    }
+    // Leftover bits must be padding zeros
+    (nbits < 6 && bits == 0).then_some(out)
+}
@@ -0,0 +1,428 @@
+//! A hashed directory (HTree) indexes its leaf blocks by the hashes of
+//! the names they hold. Logical block 0 is the root of the index, which
+//! also holds the "." and ".." entries. Interior nodes look like empty
+//! directory blocks to readers that do not know the index. Leaf blocks
+//! are normal directory blocks.
+//!
+//! There is at most one level of interior nodes below the root, or two
+//! with the `large_dir` feature.
+
+use super::crc::*;
+use super::unicode;
+use super::AsBytes;
+use super::{DirEntry, FileType};
+use crate::constants::*;
+use crate::prelude::*;
+use crate::Block;
+
+/// Hash versions of the index. The unsigned variants are used where
+/// `char` is unsigned, as recorded in the superblock.
+const DX_HASH_LEGACY: u8 = 0;
+const DX_HASH_HALF_MD4: u8 = 1;
+const DX_HASH_TEA: u8 = 2;
+const DX_HASH_LEGACY_UNSIGNED: u8 = 3;
+const DX_HASH_HALF_MD4_UNSIGNED: u8 = 4;
+const DX_HASH_TEA_UNSIGNED: u8 = 5;
+
+/// The hash that marks the end of a directory stream, never given to a name.
+const HTREE_EOF_32BIT: u32 = 0x7FFF_FFFF;
+
+/// Size of the checksum tail after the entries of an index block.
+const DX_TAIL_SIZE: usize = 8;
+
+/// Compute the hash of a name in a hashed directory, the same as
+/// `ext4fs_dirhash` of Linux. Names in a case-insensitive directory are
+/// hashed in their folded form.
+///
+/// # Params
+///
+/// * `name` - the name as stored in the directory
+/// * `casefold` - the directory is case-insensitive
+/// * `version` - the hash version, with the unsigned variants selected
+/// * `seed` - the hash seed of the filesystem
+///
+/// # Return
+///
+/// The hash of the name, or `None` if the hash version is unknown.
+pub fn dx_hash(name: &[u8], casefold: bool, version: u8, seed: &[u32; 4]) -> Option<u32> {
+    let folded = match core::str::from_utf8(name) {
+        Ok(name) if casefold => Some(unicode::casefold(name)),
+        _ => None,
+    };
+    let name = folded.as_ref().map_or(name, |folded| folded.as_bytes());
+    let mut buf = if seed.iter().any(|&word| word != 0) {
+        *seed
+    } else {
+        [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476]
+    };
+    let hash = match version {
+        DX_HASH_LEGACY => dx_hack_hash(name, true),
+        DX_HASH_LEGACY_UNSIGNED => dx_hack_hash(name, false),
+        DX_HASH_HALF_MD4 | DX_HASH_HALF_MD4_UNSIGNED => {
+            let signed = version == DX_HASH_HALF_MD4;
+            for offset in (0..name.len()).step_by(32) {
+                half_md4_transform(&mut buf, &str2hashbuf::<8>(&name[offset..], signed));
+            }
+            buf[1]
+        }
+        DX_HASH_TEA | DX_HASH_TEA_UNSIGNED => {
+            let signed = version == DX_HASH_TEA;
+            for offset in (0..name.len()).step_by(16) {
+                tea_transform(&mut buf, &str2hashbuf::<4>(&name[offset..], signed));
+            }
+            buf[0]
+        }
+        _ => return None,
+    };
+    let hash = hash & !1;
+    if hash == HTREE_EOF_32BIT << 1 {
+        Some((HTREE_EOF_32BIT - 1) << 1)
+    } else {
+        Some(hash)
+    }
+}
+
+/// A byte of a name, sign-extended if the hash uses signed chars.
+fn hash_char(byte: u8, signed: bool) -> u32 {
+    if signed {
+        byte as i8 as i32 as u32
+    } else {
+        byte as u32
+    }
+}
+
+/// The legacy hash of ext3.
+fn dx_hack_hash(name: &[u8], signed: bool) -> u32 {
+    let (mut hash0, mut hash1): (u32, u32) = (0x12a3fe2d, 0x37abe8f9);
+    for &byte in name {
+        let mut hash = hash1.wrapping_add(hash0 ^ hash_char(byte, signed).wrapping_mul(7152373));
+        if hash & 0x8000_0000 != 0 {
+            hash = hash.wrapping_sub(0x7fff_ffff);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+    hash0 << 1
+}
+
+/// Pack the first `4 * N` bytes of a name into words, padded with its
+/// length.
+fn str2hashbuf<const N: usize>(name: &[u8], signed: bool) -> [u32; N] {
+    let len = name.len() as u32;
+    let mut pad = len | (len << 8);
+    pad |= pad << 16;
+    let mut buf = [pad; N];
+    let mut val = pad;
+    let mut word = 0;
+    for (i, &byte) in name.iter().take(4 * N).enumerate() {
+        val = hash_char(byte, signed).wrapping_add(val << 8);
+        if i % 4 == 3 {
+            buf[word] = val;
+            val = pad;
+            word += 1;
+        }
+    }
+    if word < N {
+        buf[word] = val;
+    }
+    buf
+}
+
+/// The cut-down MD4 transform of the half MD4 hash.
+fn half_md4_transform(buf: &mut [u32; 4], input: &[u32; 8]) {
+    const K2: u32 = 0o13240474631;
+    const K3: u32 = 0o15666365641;
+    let f = |x: u32, y: u32, z: u32| z ^ (x & (y ^ z));
+    let g = |x: u32, y: u32, z: u32| (x & y).wrapping_add((x ^ y) & z);
+    let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+    let [mut a, mut b, mut c, mut d] = *buf;
+    macro_rules! round {
+        ($f:expr, $a:ident, $b:ident, $c:ident, $d:ident, $x:expr, $s:expr) => {
+            $a = $a
+                .wrapping_add($f($b, $c, $d))
+                .wrapping_add($x)
+                .rotate_left($s);
+        };
+    }
+    // Round 1
+    for i in [0, 4] {
+        round!(f, a, b, c, d, input[i], 3);
+        round!(f, d, a, b, c, input[i + 1], 7);
+        round!(f, c, d, a, b, input[i + 2], 11);
+        round!(f, b, c, d, a, input[i + 3], 19);
+    }
+    // Round 2
+    for i in [1, 0] {
+        round!(g, a, b, c, d, input[i].wrapping_add(K2), 3);
+        round!(g, d, a, b, c, input[i + 2].wrapping_add(K2), 5);
+        round!(g, c, d, a, b, input[i + 4].wrapping_add(K2), 9);
+        round!(g, b, c, d, a, input[i + 6].wrapping_add(K2), 13);
+    }
+    // Round 3
+    for i in [3, 1] {
+        round!(h, a, b, c, d, input[i].wrapping_add(K3), 3);
+        round!(h, d, a, b, c, input[i + 4].wrapping_add(K3), 9);
+        round!(h, c, d, a, b, input[i - 1].wrapping_add(K3), 11);
+        round!(h, b, c, d, a, input[i + 3].wrapping_add(K3), 15);
+    }
+    buf[0] = buf[0].wrapping_add(a);
+    buf[1] = buf[1].wrapping_add(b);
+    buf[2] = buf[2].wrapping_add(c);
+    buf[3] = buf[3].wrapping_add(d);
+}
+
+/// The TEA transform of the TEA hash.
+fn tea_transform(buf: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9E3779B9;
+    let (mut sum, mut b0, mut b1) = (0u32, buf[0], buf[1]);
+    let [a, b, c, d] = *input;
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add(
+            (b1 << 4).wrapping_add(a) ^ b1.wrapping_add(sum) ^ (b1 >> 5).wrapping_add(b),
+        );
+        b1 = b1.wrapping_add(
+            (b0 << 4).wrapping_add(c) ^ b0.wrapping_add(sum) ^ (b0 >> 5).wrapping_add(d),
+        );
+    }
+    buf[0] = buf[0].wrapping_add(b0);
+    buf[1] = buf[1].wrapping_add(b1);
+}
+
+/// The header of the root of an HTree, after the "." and ".." entries.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DxRootInfo {
+    reserved_zero: u32,
+    /// Hash version of the directory.
+    hash_version: u8,
+    /// Length of this header, always 8.
+    info_length: u8,
+    /// Depth of the index below the root.
+    indirect_levels: u8,
+    unused_flags: u8,
+}
+unsafe impl AsBytes for DxRootInfo {}
+
+impl DxRootInfo {
+    /// Offset of the header in the root block.
+    const OFFSET: usize = 24;
+
+    /// Check that the header is the one of an HTree root, with at most
+    /// `max_indirect_levels` levels of interior nodes.
+    pub fn is_valid(&self, large_dir: bool) -> bool {
+        self.reserved_zero == 0
+            && self.info_length == 8
+            && self.indirect_levels <= Self::max_indirect_levels(large_dir)
+    }
+
+    /// Get the most levels of interior nodes below the root: two with the
+    /// `large_dir` feature, one without.
+    pub fn max_indirect_levels(large_dir: bool) -> u8 {
+        if large_dir {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Get the hash version of the directory, the unsigned variant if
+    /// the filesystem hashes `unsigned` chars.
+    pub fn hash_version(&self, unsigned: bool) -> u8 {
+        if unsigned && self.hash_version <= DX_HASH_TEA {
+            self.hash_version + DX_HASH_LEGACY_UNSIGNED
+        } else {
+            self.hash_version
+        }
+    }
+
+    /// Get the number of levels of interior nodes below the root.
+    pub fn indirect_levels(&self) -> u8 {
+        self.indirect_levels
+    }
+
+    pub fn set_indirect_levels(&mut self, levels: u8) {
+        self.indirect_levels = levels;
+    }
+}
+
+/// An index entry: the least hash of the names in a subtree, and the
+/// logical block of the subtree.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DxEntry {
+    hash: u32,
+    block: u32,
+}
+unsafe impl AsBytes for DxEntry {}
+
+impl DxEntry {
+    pub fn new(hash: u32, block: LBlockId) -> Self {
+        Self { hash, block }
+    }
+
+    /// Get the least hash of the subtree.
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    /// Get the logical block of the subtree.
+    pub fn block(&self) -> LBlockId {
+        self.block & 0x0FFF_FFFF
+    }
+
+    /// Find the entry of the subtree that covers `hash` among sorted
+    /// `entries`, the last one whose least hash is not greater.
+    pub fn search(entries: &[DxEntry], hash: u32) -> usize {
+        entries[1..].partition_point(|entry| entry.hash <= hash)
+    }
+}
+
+/// An index block of an HTree, the root or an interior node.
+pub struct DxBlock(Block);
+
+impl DxBlock {
+    /// Wrap a directory block to an index block.
+    pub fn new(block: Block) -> Self {
+        DxBlock(block)
+    }
+
+    /// Create a root with no entries for directory `ino` in `parent`. The
+    /// ".." entry spans the rest of the block, over the index.
+    pub fn new_root(block_id: PBlockId, ino: InodeId, parent: InodeId, hash_version: u8) -> Self {
+        let mut block = Block::new(block_id, [0; BLOCK_SIZE]);
+        block.write_offset_as(0, &DirEntry::new(ino, 12, b".", FileType::Directory));
+        let dotdot = DirEntry::new(parent, BLOCK_SIZE as u16 - 12, b"..", FileType::Directory);
+        block.write_offset_as(12, &dotdot);
+        let mut root = DxBlock(block);
+        root.set_root_info(&DxRootInfo {
+            reserved_zero: 0,
+            hash_version,
+            info_length: 8,
+            indirect_levels: 0,
+            unused_flags: 0,
+        });
+        let offset = root.entries_offset(true);
+        let limit = (BLOCK_SIZE - offset - DX_TAIL_SIZE) / size_of::<DxEntry>();
+        root.0.data[offset..offset + 2].copy_from_slice(&(limit as u16).to_le_bytes());
+        root
+    }
+
+    /// Create an interior node with no entries. It starts with an unused
+    /// directory entry that spans the whole block.
+    pub fn new_node(block_id: PBlockId) -> Self {
+        let mut block = Block::new(block_id, [0; BLOCK_SIZE]);
+        block.data[4..6].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+        let mut node = DxBlock(block);
+        let offset = node.entries_offset(false);
+        let limit = (BLOCK_SIZE - offset - DX_TAIL_SIZE) / size_of::<DxEntry>();
+        node.0.data[offset..offset + 2].copy_from_slice(&(limit as u16).to_le_bytes());
+        node
+    }
+
+    /// Get the wrapped block.
+    pub fn block(&self) -> &Block {
+        &self.0
+    }
+
+    /// Get the header of the root block.
+    pub fn root_info(&self) -> DxRootInfo {
+        self.0.read_offset_as(DxRootInfo::OFFSET)
+    }
+
+    pub fn set_root_info(&mut self, info: &DxRootInfo) {
+        self.0.write_offset_as(DxRootInfo::OFFSET, info);
+    }
+
+    /// Get the maximum number of index entries of the block.
+    pub fn limit(&self, root: bool) -> usize {
+        let offset = self.entries_offset(root);
+        u16::from_le_bytes([self.0.data[offset], self.0.data[offset + 1]]) as usize
+    }
+
+    /// Get the index entries of the block. The first one has no hash and
+    /// covers the hashes below the second one.
+    ///
+    /// # Return
+    ///
+    /// The entries, or `None` if their count or limit is invalid.
+    pub fn entries(&self, root: bool) -> Option<Vec<DxEntry>> {
+        let offset = self.entries_offset(root);
+        let limit = self.limit(root);
+        let count = u16::from_le_bytes([self.0.data[offset + 2], self.0.data[offset + 3]]) as usize;
+        if count == 0 || count > limit || offset + limit * size_of::<DxEntry>() > BLOCK_SIZE {
+            return None;
+        }
+        let mut entries: Vec<DxEntry> = (0..count)
+            .map(|i| self.0.read_offset_as(offset + i * size_of::<DxEntry>()))
+            .collect();
+        entries[0].hash = 0;
+        Some(entries)
+    }
+
+    /// Replace the index entries of the block, at most its limit. The hash
+    /// of the first one is not stored.
+    pub fn set_entries(&mut self, root: bool, entries: &[DxEntry]) {
+        let offset = self.entries_offset(root);
+        let count = entries.len() as u16;
+        self.0.data[offset + 2..offset + 4].copy_from_slice(&count.to_le_bytes());
+        self.0.data[offset + 4..offset + 8].copy_from_slice(&entries[0].block.to_le_bytes());
+        for (i, entry) in entries.iter().enumerate().skip(1) {
+            self.0
+                .write_offset_as(offset + i * size_of::<DxEntry>(), entry);
+        }
+    }
+
+    /// Calc and set the checksum in the tail after the entries, if the
+    /// block has room for it.
+    pub fn set_checksum(&mut self, root: bool, uuid: &[u8], ino: InodeId, ino_gen: u32) {
+        if let Some((tail, csum)) = self.calc_checksum(root, uuid, ino, ino_gen) {
+            self.0.data[tail + 4..tail + 8].copy_from_slice(&csum.to_le_bytes());
+        }
+    }
+
+    /// Check the checksum in the tail after the entries. A block with no
+    /// room for the tail, or a count past its limit, fails the check.
+    pub fn verify_checksum(&self, root: bool, uuid: &[u8], ino: InodeId, ino_gen: u32) -> bool {
+        match self.calc_checksum(root, uuid, ino, ino_gen) {
+            Some((tail, csum)) => self.0.data[tail + 4..tail + 8] == csum.to_le_bytes(),
+            None => false,
+        }
+    }
+
+    /// crc32c(uuid+inum+generation+entries+tail), with the checksum in the
+    /// tail zeroed. Return the offset of the tail and the checksum, or
+    /// `None` if the block has no room for the tail.
+    fn calc_checksum(
+        &self,
+        root: bool,
+        uuid: &[u8],
+        ino: InodeId,
+        ino_gen: u32,
+    ) -> Option<(usize, u32)> {
+        let offset = self.entries_offset(root);
+        let limit = self.limit(root);
+        let tail = offset + limit * size_of::<DxEntry>();
+        let count = u16::from_le_bytes([self.0.data[offset + 2], self.0.data[offset + 3]]) as usize;
+        if tail + DX_TAIL_SIZE > BLOCK_SIZE || count > limit {
+            return None;
+        }
+        let mut csum = crc32(CRC32_INIT, uuid);
+        csum = crc32(csum, &ino.to_le_bytes());
+        csum = crc32(csum, &ino_gen.to_le_bytes());
+        csum = crc32(csum, &self.0.data[..offset + count * size_of::<DxEntry>()]);
+        csum = crc32(csum, &self.0.data[tail..tail + 4]);
+        csum = crc32(csum, &[0; 4]);
+        Some((tail, csum))
+    }
+
+    /// Offset of the count and limit, which share the place of the hash
+    /// of the first entry.
+    fn entries_offset(&self, root: bool) -> usize {
+        if root {
+            DxRootInfo::OFFSET + self.root_info().info_length as usize
+        } else {
+            size_of::<DxEntry>()
+        }
+    }
+}
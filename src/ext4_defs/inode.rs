@@ -7,7 +7,7 @@
 
 use super::crc::*;
 use super::AsBytes;
-use super::{ExtentNode, ExtentNodeMut};
+use super::{ExtentNode, ExtentNodeMut, Timestamp};
 use crate::constants::*;
 use crate::prelude::*;
 use crate::FileType;
@@ -26,6 +26,10 @@ bitflags! {
         const OTHER_READ = 0x4;
         const OTHER_WRITE = 0x2;
         const OTHER_EXEC = 0x1;
+        // Special bits
+        const SET_UID = 0x800;
+        const SET_GID = 0x400;
+        const STICKY = 0x200;
         // File type
         const TYPE_MASK = 0xF000;
         const FIFO = 0x1000;
@@ -76,6 +80,106 @@ impl InodeMode {
     }
 }
 
+bitflags! {
+    /// Inode flags, the same as the `FS_IOC_GETFLAGS` attributes.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct InodeFlags: u32 {
+        /// Secure deletion (not implemented).
+        const SECRM = 0x1;
+        /// Undelete (not implemented).
+        const UNRM = 0x2;
+        /// Compress file (not implemented).
+        const COMPR = 0x4;
+        /// Writes are synchronous.
+        const SYNC = 0x8;
+        /// The file cannot be modified, linked, unlinked or renamed.
+        const IMMUTABLE = 0x10;
+        /// Data can only be appended to the file.
+        const APPEND = 0x20;
+        /// Do not dump the file.
+        const NODUMP = 0x40;
+        /// Do not update access time.
+        const NOATIME = 0x80;
+        /// The file is encrypted.
+        const ENCRYPT = 0x800;
+        /// The directory has a hashed index.
+        const INDEX = 0x1000;
+        /// File data is written through the journal.
+        const JOURNAL_DATA = 0x4000;
+        /// No tail merging (not implemented).
+        const NOTAIL = 0x8000;
+        /// Directory changes are synchronous.
+        const DIRSYNC = 0x10000;
+        /// Top of a directory hierarchy.
+        const TOPDIR = 0x20000;
+        /// The file is huge.
+        const HUGE_FILE = 0x40000;
+        /// The inode uses extents.
+        const EXTENTS = 0x80000;
+        /// The file has fs-verity enabled.
+        const VERITY = 0x100000;
+        /// The inode stores a large extended attribute value.
+        const EA_INODE = 0x200000;
+        /// The inode has inline data.
+        const INLINE_DATA = 0x10000000;
+        /// New entries of the directory inherit its project id.
+        const PROJINHERIT = 0x20000000;
+        /// Names in the directory are case-insensitive.
+        const CASEFOLD = 0x40000000;
+    }
+}
+
+impl InodeFlags {
+    /// Flags that can be changed with `Ext4::set_flags`.
+    pub const USER_MODIFIABLE: InodeFlags = InodeFlags::from_bits_retain(
+        Self::SECRM.bits()
+            | Self::UNRM.bits()
+            | Self::COMPR.bits()
+            | Self::SYNC.bits()
+            | Self::IMMUTABLE.bits()
+            | Self::APPEND.bits()
+            | Self::NODUMP.bits()
+            | Self::NOATIME.bits()
+            | Self::JOURNAL_DATA.bits()
+            | Self::NOTAIL.bits()
+            | Self::DIRSYNC.bits()
+            | Self::TOPDIR.bits()
+            | Self::PROJINHERIT.bits()
+            | Self::CASEFOLD.bits(),
+    );
+    /// Flags inherited by new entries of a directory.
+    const INHERITED: InodeFlags = InodeFlags::from_bits_retain(
+        Self::SECRM.bits()
+            | Self::UNRM.bits()
+            | Self::COMPR.bits()
+            | Self::SYNC.bits()
+            | Self::NODUMP.bits()
+            | Self::NOATIME.bits()
+            | Self::JOURNAL_DATA.bits()
+            | Self::NOTAIL.bits()
+            | Self::DIRSYNC.bits()
+            | Self::PROJINHERIT.bits()
+            | Self::CASEFOLD.bits(),
+    );
+
+    /// Remove the flags that do not apply to `file_type`.
+    pub fn mask(self, file_type: FileType) -> Self {
+        match file_type {
+            FileType::Directory => self,
+            FileType::RegularFile => {
+                self - (Self::DIRSYNC | Self::TOPDIR | Self::CASEFOLD | Self::PROJINHERIT)
+            }
+            _ => self & (Self::NODUMP | Self::NOATIME),
+        }
+    }
+
+    /// Get the flags a new inode of `file_type` inherits from its parent
+    /// directory with flags `parent`.
+    pub fn inherited(parent: InodeFlags, file_type: FileType) -> Self {
+        (parent & Self::INHERITED).mask(file_type)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct Linux2 {
@@ -175,8 +279,6 @@ impl Default for Inode {
 unsafe impl AsBytes for Inode {}
 
 impl Inode {
-    const FLAG_EXTENTS: u32 = 0x00080000;
-
     pub fn mode(&self) -> InodeMode {
         InodeMode::from_bits_truncate(self.mode)
     }
@@ -240,28 +342,28 @@ impl Inode {
         self.size_hi = (size >> 32) as u32;
     }
 
-    pub fn atime(&self) -> u32 {
-        self.atime
+    pub fn atime(&self) -> Timestamp {
+        Timestamp::decode(self.atime, self.atime_extra)
     }
 
-    pub fn set_atime(&mut self, atime: u32) {
-        self.atime = atime;
+    pub fn set_atime(&mut self, atime: Timestamp) {
+        (self.atime, self.atime_extra) = atime.encode();
     }
 
-    pub fn ctime(&self) -> u32 {
-        self.ctime
+    pub fn ctime(&self) -> Timestamp {
+        Timestamp::decode(self.ctime, self.ctime_extra)
     }
 
-    pub fn set_ctime(&mut self, ctime: u32) {
-        self.ctime = ctime;
+    pub fn set_ctime(&mut self, ctime: Timestamp) {
+        (self.ctime, self.ctime_extra) = ctime.encode();
     }
 
-    pub fn mtime(&self) -> u32 {
-        self.mtime
+    pub fn mtime(&self) -> Timestamp {
+        Timestamp::decode(self.mtime, self.mtime_extra)
     }
 
-    pub fn set_mtime(&mut self, mtime: u32) {
-        self.mtime = mtime;
+    pub fn set_mtime(&mut self, mtime: Timestamp) {
+        (self.mtime, self.mtime_extra) = mtime.encode();
     }
 
     pub fn dtime(&self) -> u32 {
@@ -272,12 +374,12 @@ impl Inode {
         self.dtime = dtime;
     }
 
-    pub fn crtime(&self) -> u32 {
-        self.crtime
+    pub fn crtime(&self) -> Timestamp {
+        Timestamp::decode(self.crtime, self.crtime_extra)
     }
 
-    pub fn set_crtime(&mut self, crtime: u32) {
-        self.crtime = crtime;
+    pub fn set_crtime(&mut self, crtime: Timestamp) {
+        (self.crtime, self.crtime_extra) = crtime.encode();
     }
 
     /// Get the number of 512-byte blocks (`INODE_BLOCK_SIZE`) used by the inode.
@@ -313,12 +415,31 @@ impl Inode {
         self.generation = generation;
     }
 
-    pub fn flags(&self) -> u32 {
-        self.flags
+    pub fn flags(&self) -> InodeFlags {
+        InodeFlags::from_bits_retain(self.flags)
+    }
+
+    pub fn set_flags(&mut self, flags: InodeFlags) {
+        self.flags = flags.bits();
+    }
+
+    /// Whether new entries of this directory inherit its project id.
+    pub fn proj_inherit(&self) -> bool {
+        self.flags().contains(InodeFlags::PROJINHERIT)
+    }
+
+    pub fn projid(&self) -> u32 {
+        self.projid
+    }
+
+    pub fn set_projid(&mut self, projid: u32) {
+        self.projid = projid;
     }
 
-    pub fn set_flags(&mut self, f: u32) {
-        self.flags |= f;
+    /// Size of the fields after the first 128 bytes. The in-inode
+    /// extended attributes follow them.
+    pub fn extra_isize(&self) -> u16 {
+        self.extra_isize
     }
 
     pub fn xattr_block(&self) -> PBlockId {
@@ -346,11 +467,33 @@ impl Inode {
         })
     }
 
+    /// Get the block pointers of an inode that does not use extents:
+    /// 12 direct blocks, the indirect, double and triple indirect blocks
+    pub fn block_pointers(&self) -> [u32; 15] {
+        let mut ptrs = [0u32; 15];
+        for (i, ptr) in ptrs.iter_mut().enumerate() {
+            *ptr = u32::from_le_bytes(self.block[i * 4..i * 4 + 4].try_into().unwrap());
+        }
+        ptrs
+    }
+
+    /// Set the block pointers of an inode that does not use extents
+    pub fn set_block_pointers(&mut self, ptrs: &[u32; 15]) {
+        for (i, ptr) in ptrs.iter().enumerate() {
+            self.block[i * 4..i * 4 + 4].copy_from_slice(&ptr.to_le_bytes());
+        }
+    }
+
+    /// Replace the block map or extent root with the one of `other`
+    pub fn copy_block_map(&mut self, other: &Inode) {
+        self.block = other.block;
+    }
+
     /// Initialize the `flags` and `block` field of inode. Mark the
     /// inode to use extent for block mapping. Initialize the root
     /// node of the extent tree
     pub fn extent_init(&mut self) {
-        self.set_flags(Self::FLAG_EXTENTS);
+        self.set_flags(self.flags() | InodeFlags::EXTENTS);
         self.extent_root_mut().init(0, 0);
     }
 }
@@ -367,13 +510,31 @@ impl InodeRef {
         Self { id, inode }
     }
 
-    pub fn set_checksum(&mut self, uuid: &[u8]) {
+    /// Calc and set the inode checksum. `tail` is the rest of the on-disk
+    /// inode following the `Inode` structure.
+    pub fn set_checksum(&mut self, uuid: &[u8], tail: &[u8]) {
+        let checksum = self.calc_checksum(uuid, tail);
+        self.inode.osd2.l_checksum_lo = checksum as u16;
+        self.inode.checksum_hi = (checksum >> 16) as u16;
+    }
+
+    /// Check the inode checksum. `tail` is the rest of the on-disk inode
+    /// following the `Inode` structure.
+    pub fn verify_checksum(&self, uuid: &[u8], tail: &[u8]) -> bool {
+        let checksum = self.inode.osd2.l_checksum_lo as u32 | (self.inode.checksum_hi as u32) << 16;
+        self.calc_checksum(uuid, tail) == checksum
+    }
+
+    /// crc32c(uuid+inum+generation+inode), with the checksum fields zeroed
+    fn calc_checksum(&self, uuid: &[u8], tail: &[u8]) -> u32 {
+        let mut inode = self.inode.clone();
+        inode.osd2.l_checksum_lo = 0;
+        inode.checksum_hi = 0;
         let mut checksum = crc32(CRC32_INIT, uuid);
         checksum = crc32(checksum, &self.id.to_le_bytes());
         checksum = crc32(checksum, &self.inode.generation.to_le_bytes());
-        checksum = crc32(checksum, &self.inode.to_bytes());
-        self.inode.osd2.l_checksum_lo = checksum as u16;
-        self.inode.checksum_hi = (checksum >> 16) as u16;
+        checksum = crc32(checksum, inode.to_bytes());
+        crc32(checksum, tail)
     }
 }
 
@@ -381,14 +542,29 @@ impl InodeRef {
 pub struct FileAttr {
     pub ino: InodeId,
     pub size: u64,
-    pub atime: u32,
-    pub mtime: u32,
-    pub ctime: u32,
-    pub crtime: u32,
+    pub atime: Timestamp,
+    pub mtime: Timestamp,
+    pub ctime: Timestamp,
+    pub crtime: Timestamp,
     pub blocks: u64,
     pub ftype: FileType,
     pub perm: InodeMode,
     pub links: u16,
     pub uid: u32,
     pub gid: u32,
+    pub projid: u32,
+}
+
+/// File attributes to change with `Ext4::setattr_as`, `None` for those
+/// left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct SetAttr {
+    pub mode: Option<InodeMode>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub size: Option<u64>,
+    pub atime: Option<Timestamp>,
+    pub mtime: Option<Timestamp>,
+    pub ctime: Option<Timestamp>,
+    pub crtime: Option<Timestamp>,
 }
@@ -0,0 +1,577 @@
+//! The journal (JBD2) is a circular log of metadata blocks stored in the
+//! journal inode (`sb.journal_inode_number`). All fields are big-endian.
+//!
+//! Block 0 of the journal holds the journal superblock. The remaining
+//! blocks form the log, which is a sequence of transactions:
+//!
+//! - Descriptor block: a `JournalHeader` followed by block tags, each
+//!   giving the home location of one of the data blocks that follow.
+//! - Data blocks: copies of the metadata blocks written by the
+//!   transaction.
+//! - Revoke block: blocks that must not be replayed from earlier
+//!   transactions.
+//! - Commit block: marks the transaction as complete.
+
+use super::crc::*;
+use super::AsBytes;
+use super::Timestamp;
+use crate::constants::*;
+use crate::prelude::*;
+use core::ops::Range;
+
+/// Magic number in every journal metadata block.
+pub const JBD2_MAGIC: u32 = 0xC03B3998;
+
+/// The type of a journal metadata block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum JournalBlockType {
+    /// Descriptor block.
+    Descriptor = 1,
+    /// Commit block.
+    Commit = 2,
+    /// Journal superblock version 1.
+    SuperBlockV1 = 3,
+    /// Journal superblock version 2.
+    SuperBlockV2 = 4,
+    /// Revoke block.
+    Revoke = 5,
+}
+
+impl JournalBlockType {
+    fn from_u32(ty: u32) -> Option<Self> {
+        match ty {
+            1 => Some(Self::Descriptor),
+            2 => Some(Self::Commit),
+            3 => Some(Self::SuperBlockV1),
+            4 => Some(Self::SuperBlockV2),
+            5 => Some(Self::Revoke),
+            _ => None,
+        }
+    }
+}
+
+/// Header of every journal metadata block.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JournalHeader {
+    magic: u32,
+    block_type: u32,
+    sequence: u32,
+}
+
+unsafe impl AsBytes for JournalHeader {}
+
+impl JournalHeader {
+    /// Create a header of a journal metadata block.
+    pub fn new(block_type: JournalBlockType, sequence: u32) -> Self {
+        Self {
+            magic: JBD2_MAGIC.to_be(),
+            block_type: (block_type as u32).to_be(),
+            sequence: sequence.to_be(),
+        }
+    }
+
+    /// The block type, `None` if the block is not a journal metadata block.
+    pub fn block_type(&self) -> Option<JournalBlockType> {
+        if u32::from_be(self.magic) != JBD2_MAGIC {
+            return None;
+        }
+        JournalBlockType::from_u32(u32::from_be(self.block_type))
+    }
+
+    /// The transaction the block belongs to.
+    pub fn sequence(&self) -> u32 {
+        u32::from_be(self.sequence)
+    }
+}
+
+/// The journal superblock, in block 0 of the journal.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JournalSuperBlock {
+    header: JournalHeader,
+    /// Journal device block size.
+    block_size: u32,
+    /// Total number of blocks in the journal.
+    max_len: u32,
+    /// First block of log information.
+    first: u32,
+    /// First commit id expected in the log.
+    sequence: u32,
+    /// Block number of the start of the log, 0 if the journal is clean.
+    start: u32,
+    /// Error value, as set by `jbd2_journal_abort`.
+    errno: u32,
+    /// Compatible feature set.
+    feature_compat: u32,
+    /// Incompatible feature set.
+    feature_incompat: u32,
+    /// Read-only compatible feature set.
+    feature_ro_compat: u32,
+    /// 128-bit uuid of the journal.
+    uuid: [u8; 16],
+    /// Number of filesystems sharing the journal.
+    nr_users: u32,
+    /// Location of the dynamic superblock copy (unused).
+    dyn_super: u32,
+    /// Limit of journal blocks per transaction (unused).
+    max_transaction: u32,
+    /// Limit of data blocks per transaction (unused).
+    max_trans_data: u32,
+    /// Checksum algorithm, 4 for crc32c.
+    checksum_type: u8,
+    padding1: [u8; 3],
+    /// Number of fast commit blocks at the end of the journal.
+    num_fc_blocks: u32,
+    /// Block number of the head of the log (unused).
+    head: u32,
+    padding2: [u32; 40],
+    /// crc32c of the superblock.
+    checksum: u32,
+    /// Uuids of the filesystems sharing the journal.
+    users: [u8; 16 * 48],
+}
+
+unsafe impl AsBytes for JournalSuperBlock {}
+
+impl JournalSuperBlock {
+    /// Compatible feature: checksums of data blocks in commit blocks.
+    pub const FEATURE_COMPAT_CHECKSUM: u32 = 0x1;
+    /// Incompatible feature: the journal has revoke blocks.
+    pub const FEATURE_INCOMPAT_REVOKE: u32 = 0x1;
+    /// Incompatible feature: block numbers are 64-bit.
+    pub const FEATURE_INCOMPAT_64BIT: u32 = 0x2;
+    /// Incompatible feature: commit blocks may be written asynchronously.
+    #[allow(unused)]
+    pub const FEATURE_INCOMPAT_ASYNC_COMMIT: u32 = 0x4;
+    /// Incompatible feature: journal checksums version 2.
+    pub const FEATURE_INCOMPAT_CSUM_V2: u32 = 0x8;
+    /// Incompatible feature: journal checksums version 3.
+    pub const FEATURE_INCOMPAT_CSUM_V3: u32 = 0x10;
+    /// Checksum algorithm crc32c.
+    const CHECKSUM_TYPE_CRC32C: u8 = 4;
+    /// Incompatible feature: the journal has a fast commit area.
+    pub const FEATURE_INCOMPAT_FAST_COMMIT: u32 = 0x20;
+
+    /// Create the superblock of an empty journal of `max_len` blocks used
+    /// by the filesystem `uuid`.
+    pub fn new(max_len: u32, uuid: [u8; 16]) -> Self {
+        let mut jsb: Self = unsafe { mem::zeroed() };
+        jsb.header = JournalHeader::new(JournalBlockType::SuperBlockV2, 0);
+        jsb.block_size = (BLOCK_SIZE as u32).to_be();
+        jsb.max_len = max_len.to_be();
+        jsb.first = 1u32.to_be();
+        jsb.sequence = 1u32.to_be();
+        jsb.uuid = uuid;
+        jsb.nr_users = 1u32.to_be();
+        jsb
+    }
+
+    /// Check the magic number, block type and block size.
+    pub fn is_valid(&self) -> bool {
+        matches!(
+            self.header.block_type(),
+            Some(JournalBlockType::SuperBlockV1 | JournalBlockType::SuperBlockV2)
+        ) && u32::from_be(self.block_size) as usize == BLOCK_SIZE
+    }
+
+    /// Whether the superblock is version 2, which has feature fields.
+    fn is_v2(&self) -> bool {
+        self.header.block_type() == Some(JournalBlockType::SuperBlockV2)
+    }
+
+    /// Total number of blocks in the journal.
+    pub fn max_len(&self) -> u32 {
+        u32::from_be(self.max_len)
+    }
+
+    /// First block of the log.
+    pub fn first(&self) -> u32 {
+        u32::from_be(self.first)
+    }
+
+    /// Set incompatible features.
+    pub fn set_feature_incompat(&mut self, feature: u32) {
+        self.feature_incompat = (u32::from_be(self.feature_incompat) | feature).to_be();
+    }
+
+    /// Use checksums version 3, as Linux does on filesystems with
+    /// metadata checksums.
+    pub fn set_csum_v3(&mut self) {
+        let compat = u32::from_be(self.feature_compat) & !Self::FEATURE_COMPAT_CHECKSUM;
+        let incompat = (u32::from_be(self.feature_incompat) & !Self::FEATURE_INCOMPAT_CSUM_V2)
+            | Self::FEATURE_INCOMPAT_CSUM_V3;
+        self.feature_compat = compat.to_be();
+        self.feature_incompat = incompat.to_be();
+        self.checksum_type = Self::CHECKSUM_TYPE_CRC32C;
+    }
+
+    /// The sequence of the first transaction in the log.
+    pub fn sequence(&self) -> u32 {
+        u32::from_be(self.sequence)
+    }
+
+    pub fn set_sequence(&mut self, sequence: u32) {
+        self.sequence = sequence.to_be();
+    }
+
+    /// Block number of the start of the log, 0 if the journal is clean.
+    pub fn start(&self) -> u32 {
+        u32::from_be(self.start)
+    }
+
+    pub fn set_start(&mut self, start: u32) {
+        self.start = start.to_be();
+    }
+
+    /// The uuid of the journal.
+    pub fn uuid(&self) -> [u8; 16] {
+        self.uuid
+    }
+
+    fn has_incompat(&self, feature: u32) -> bool {
+        self.is_v2() && u32::from_be(self.feature_incompat) & feature != 0
+    }
+
+    /// Whether block numbers are 64-bit.
+    pub fn has_64bit(&self) -> bool {
+        self.has_incompat(Self::FEATURE_INCOMPAT_64BIT)
+    }
+
+    /// Whether journal checksums version 2 are used.
+    pub fn has_csum_v2(&self) -> bool {
+        self.has_incompat(Self::FEATURE_INCOMPAT_CSUM_V2)
+    }
+
+    /// Whether journal checksums version 3 are used.
+    pub fn has_csum_v3(&self) -> bool {
+        self.has_incompat(Self::FEATURE_INCOMPAT_CSUM_V3)
+    }
+
+    /// Whether the journal has a fast commit area.
+    pub fn has_fast_commit(&self) -> bool {
+        self.has_incompat(Self::FEATURE_INCOMPAT_FAST_COMMIT)
+    }
+
+    /// Number of blocks in the fast commit area, Linux uses 256 if not
+    /// set.
+    pub fn num_fc_blocks(&self) -> u32 {
+        match u32::from_be(self.num_fc_blocks) {
+            0 => 256,
+            n => n,
+        }
+    }
+
+    /// The block following the last block of the log.
+    pub fn log_end(&self) -> u32 {
+        if self.has_fast_commit() {
+            self.max_len() - self.num_fc_blocks()
+        } else {
+            self.max_len()
+        }
+    }
+
+    /// The blocks of the fast commit area. As in Linux, the block at
+    /// `log_end` belongs to neither the log nor the area.
+    pub fn fast_commit_area(&self) -> Range<u32> {
+        self.log_end() + 1..self.max_len()
+    }
+
+    /// Whether descriptor and revoke blocks end with a checksum tail.
+    pub fn has_block_tail(&self) -> bool {
+        self.has_csum_v2() || self.has_csum_v3()
+    }
+
+    /// The size of a block tag in descriptor blocks, excluding the uuid.
+    pub fn tag_size(&self) -> usize {
+        if self.has_csum_v3() {
+            return 16;
+        }
+        let size = if self.has_csum_v2() { 14 } else { 12 };
+        if self.has_64bit() {
+            size
+        } else {
+            size - 4
+        }
+    }
+
+    /// The size of a block number in revoke blocks.
+    pub fn revoke_record_size(&self) -> usize {
+        if self.has_64bit() {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Number of log blocks of a transaction that writes `blocks` blocks
+    /// and revokes `revoked` blocks: the revoke blocks, the descriptor
+    /// blocks followed by the blocks they describe, and the commit block.
+    pub fn transaction_len(&self, blocks: usize, revoked: usize) -> usize {
+        let tail_size = if self.has_block_tail() { 4 } else { 0 };
+        let revoke_header_size = size_of::<JournalHeader>() + 4;
+        let records = (BLOCK_SIZE - revoke_header_size - tail_size) / self.revoke_record_size();
+        // The first tag of a descriptor block is followed by the journal uuid
+        let desc_header_size = size_of::<JournalHeader>() + 16;
+        let tags = (BLOCK_SIZE - desc_header_size - tail_size) / self.tag_size();
+        revoked.div_ceil(records) + blocks.div_ceil(tags) + blocks + 1
+    }
+
+    /// The seed of journal checksums, computed from the journal uuid.
+    fn checksum_seed(&self) -> u32 {
+        crc32(CRC32_INIT, &self.uuid)
+    }
+
+    /// Set the checksum in the tail of a descriptor or revoke block.
+    pub fn set_tail_checksum(&self, block: &mut [u8]) {
+        if self.has_block_tail() {
+            block[BLOCK_SIZE - 4..].fill(0);
+            let csum = crc32(self.checksum_seed(), block);
+            block[BLOCK_SIZE - 4..].copy_from_slice(&csum.to_be_bytes());
+        }
+    }
+
+    /// Verify the checksum in the tail of a descriptor or revoke block.
+    pub fn verify_tail_checksum(&self, block: &[u8]) -> bool {
+        if !self.has_block_tail() {
+            return true;
+        }
+        let mut copy = [0; BLOCK_SIZE];
+        copy.copy_from_slice(block);
+        self.set_tail_checksum(&mut copy);
+        copy[BLOCK_SIZE - 4..] == block[BLOCK_SIZE - 4..]
+    }
+
+    /// Set the checksum of a commit block.
+    pub fn set_commit_checksum(&self, block: &mut [u8]) {
+        if self.has_block_tail() {
+            // Checksum type and size are unused, the checksum covers the
+            // whole block
+            block[12..20].fill(0);
+            let csum = crc32(self.checksum_seed(), block);
+            block[16..20].copy_from_slice(&csum.to_be_bytes());
+        }
+    }
+
+    /// Verify the checksum of a commit block.
+    pub fn verify_commit_checksum(&self, block: &[u8]) -> bool {
+        if !self.has_block_tail() {
+            return true;
+        }
+        let mut copy = [0; BLOCK_SIZE];
+        copy.copy_from_slice(block);
+        self.set_commit_checksum(&mut copy);
+        copy[16..20] == block[16..20]
+    }
+
+    /// The checksum of a logged block in transaction `sequence`, as
+    /// stored in its tag.
+    pub fn data_checksum(&self, sequence: u32, data: &[u8]) -> u32 {
+        if !self.has_block_tail() {
+            return 0;
+        }
+        let csum = crc32(self.checksum_seed(), &sequence.to_be_bytes());
+        let csum = crc32(csum, data);
+        if self.has_csum_v3() {
+            csum
+        } else {
+            csum & 0xFFFF
+        }
+    }
+
+    /// Compute and set the checksum of the superblock.
+    pub fn set_checksum(&mut self) {
+        self.checksum = 0;
+        self.checksum = crc32(CRC32_INIT, self.to_bytes()).to_be();
+    }
+}
+
+/// A block tag in a descriptor block.
+#[derive(Debug, Clone, Copy)]
+pub struct JournalBlockTag {
+    /// Home location of the logged block.
+    pub block: PBlockId,
+    /// Tag flags.
+    pub flags: u32,
+    /// Checksum of the logged block.
+    pub checksum: u32,
+}
+
+impl JournalBlockTag {
+    /// The first 4 bytes of the logged block were the journal magic
+    /// number and have been zeroed.
+    pub const FLAG_ESCAPE: u32 = 0x1;
+    /// The tag has the same uuid as the previous one, so the uuid is
+    /// omitted.
+    pub const FLAG_SAME_UUID: u32 = 0x2;
+    /// The block was deleted by this transaction (unused).
+    #[allow(unused)]
+    pub const FLAG_DELETED: u32 = 0x4;
+    /// The last tag in the descriptor block.
+    pub const FLAG_LAST_TAG: u32 = 0x8;
+
+    /// Parse the tags of a descriptor block.
+    pub fn parse_all(block: &[u8], jsb: &JournalSuperBlock) -> Vec<Self> {
+        let tag_size = jsb.tag_size();
+        let tail_size = if jsb.has_block_tail() { 4 } else { 0 };
+        let end = BLOCK_SIZE - tail_size;
+        let mut tags = Vec::new();
+        let mut offset = size_of::<JournalHeader>();
+        while offset + tag_size <= end {
+            let tag = Self::parse(&block[offset..offset + tag_size], jsb);
+            offset += tag_size;
+            if tag.flags & Self::FLAG_SAME_UUID == 0 {
+                offset += 16;
+            }
+            tags.push(tag);
+            if tag.flags & Self::FLAG_LAST_TAG != 0 {
+                break;
+            }
+        }
+        tags
+    }
+
+    /// Write the tag to `bytes`, which is `jsb.tag_size()` long.
+    pub fn write(&self, bytes: &mut [u8], jsb: &JournalSuperBlock) {
+        bytes[0..4].copy_from_slice(&(self.block as u32).to_be_bytes());
+        if jsb.has_64bit() {
+            bytes[8..12].copy_from_slice(&((self.block >> 32) as u32).to_be_bytes());
+        }
+        if jsb.has_csum_v3() {
+            bytes[4..8].copy_from_slice(&self.flags.to_be_bytes());
+            bytes[12..16].copy_from_slice(&self.checksum.to_be_bytes());
+        } else {
+            bytes[4..6].copy_from_slice(&(self.checksum as u16).to_be_bytes());
+            bytes[6..8].copy_from_slice(&(self.flags as u16).to_be_bytes());
+        }
+    }
+
+    /// Parse a tag, `bytes` is `jsb.tag_size()` long.
+    fn parse(bytes: &[u8], jsb: &JournalSuperBlock) -> Self {
+        let block_lo = read_be32(bytes, 0) as u64;
+        if jsb.has_csum_v3() {
+            // blocknr, flags, blocknr_high, checksum
+            let block_hi = if jsb.has_64bit() {
+                read_be32(bytes, 8) as u64
+            } else {
+                0
+            };
+            Self {
+                block: block_hi << 32 | block_lo,
+                flags: read_be32(bytes, 4),
+                checksum: read_be32(bytes, 12),
+            }
+        } else {
+            // blocknr, checksum (16-bit), flags (16-bit), blocknr_high
+            let block_hi = if jsb.has_64bit() {
+                read_be32(bytes, 8) as u64
+            } else {
+                0
+            };
+            Self {
+                block: block_hi << 32 | block_lo,
+                flags: read_be16(bytes, 6) as u32,
+                checksum: read_be16(bytes, 4) as u32,
+            }
+        }
+    }
+}
+
+/// The commit block, which ends a transaction.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JournalCommitBlock {
+    header: JournalHeader,
+    /// Checksum algorithm of `checksum` (unused with checksums v2 and v3).
+    checksum_type: u8,
+    /// Size of `checksum` in bytes.
+    checksum_size: u8,
+    padding: [u8; 2],
+    /// Checksum of the commit block.
+    checksum: [u32; 8],
+    /// Commit time, seconds.
+    commit_sec: u64,
+    /// Commit time, nanoseconds.
+    commit_nsec: u32,
+}
+
+unsafe impl AsBytes for JournalCommitBlock {}
+
+impl JournalCommitBlock {
+    /// Create the commit block of transaction `sequence`, committed at
+    /// `time`.
+    pub fn new(sequence: u32, time: Timestamp) -> Self {
+        Self {
+            header: JournalHeader::new(JournalBlockType::Commit, sequence),
+            checksum_type: 0,
+            checksum_size: 0,
+            padding: [0; 2],
+            checksum: [0; 8],
+            commit_sec: (time.sec as u64).to_be(),
+            commit_nsec: time.nsec.to_be(),
+        }
+    }
+}
+
+/// Build revoke blocks of transaction `sequence` recording `blocks`.
+pub fn build_revoke_blocks(
+    blocks: &[PBlockId],
+    sequence: u32,
+    jsb: &JournalSuperBlock,
+) -> Vec<[u8; BLOCK_SIZE]> {
+    let header_size = size_of::<JournalHeader>() + 4;
+    let tail_size = if jsb.has_block_tail() { 4 } else { 0 };
+    let record_size = jsb.revoke_record_size();
+    let per_block = (BLOCK_SIZE - header_size - tail_size) / record_size;
+    let header = JournalHeader::new(JournalBlockType::Revoke, sequence);
+    blocks
+        .chunks(per_block)
+        .map(|chunk| {
+            let mut block = [0; BLOCK_SIZE];
+            block[..size_of::<JournalHeader>()].copy_from_slice(header.to_bytes());
+            let count = header_size + chunk.len() * record_size;
+            block[size_of::<JournalHeader>()..header_size]
+                .copy_from_slice(&(count as u32).to_be_bytes());
+            for (i, &pblock) in chunk.iter().enumerate() {
+                let offset = header_size + i * record_size;
+                if record_size == 8 {
+                    block[offset..offset + 8].copy_from_slice(&pblock.to_be_bytes());
+                } else {
+                    block[offset..offset + 4].copy_from_slice(&(pblock as u32).to_be_bytes());
+                }
+            }
+            jsb.set_tail_checksum(&mut block);
+            block
+        })
+        .collect()
+}
+
+/// Parse the block numbers recorded in a revoke block.
+pub fn parse_revoke_records(block: &[u8], jsb: &JournalSuperBlock) -> Vec<PBlockId> {
+    // The header is followed by the number of bytes used in the block
+    let header_size = size_of::<JournalHeader>() + 4;
+    let count = read_be32(block, size_of::<JournalHeader>()) as usize;
+    let end = count.min(BLOCK_SIZE);
+    let record_size = jsb.revoke_record_size();
+    let mut records = Vec::new();
+    let mut offset = header_size;
+    while offset + record_size <= end {
+        let record = if record_size == 8 {
+            (read_be32(block, offset) as u64) << 32 | read_be32(block, offset + 4) as u64
+        } else {
+            read_be32(block, offset) as u64
+        };
+        records.push(record);
+        offset += record_size;
+    }
+    records
+}
+
+fn read_be16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_be32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
@@ -14,27 +14,46 @@
 //! For the special case of block group 0, the first 1024 bytes are unused.
 //! For all other block groups, there is no padding.
 
+mod acl;
+mod aes;
 mod bitmap;
 mod block;
 mod block_group;
 mod crc;
 mod dir;
 mod extent;
+mod fast_commit;
+mod fscrypt;
+mod htree;
 mod inode;
+mod journal;
 mod mount_point;
+mod quota;
+mod sha;
 mod super_block;
+mod time;
+mod unicode;
+mod verity;
 mod xattr;
 
 #[cfg(feature = "block_cache")]
 mod cache;
 
+pub use acl::*;
 pub use bitmap::*;
 pub use block::*;
 pub use block_group::*;
 pub use dir::*;
 pub use extent::*;
+pub use fast_commit::*;
+pub use fscrypt::*;
+pub use htree::*;
 pub use inode::*;
+pub use journal::*;
+pub use quota::*;
 pub use super_block::*;
+pub use time::*;
+pub use verity::*;
 pub use xattr::*;
 
 #[cfg(feature = "block_cache")]
@@ -0,0 +1,245 @@
+//! Disk quotas limit the space and the number of inodes used by each user,
+//! group and project.
+//!
+//! Quota records are kept in hidden quota files referenced by the superblock
+//! (`usr_quota_inum`, `grp_quota_inum`, `prj_quota_inum`), using the Linux "vfsv1" quota tree
+//! format. A quota file is divided into 1 KiB quota blocks:
+//!
+//! - Block 0: file header (`QuotaHeader`) and quota info (`QuotaInfo`).
+//! - Block 1: root of a radix tree of depth 4. Each tree block holds 256
+//!   block references, indexed by one byte of the 32-bit id.
+//! - Leaf references point to data blocks, which start with a
+//!   `QuotaDataHeader` followed by an array of `DiskQuota` records.
+
+use super::AsBytes;
+use crate::prelude::*;
+
+/// The size of a quota block.
+pub const QUOTA_BLOCK_SIZE: usize = 1024;
+
+/// The quota block of the tree root.
+pub const QUOTA_TREE_ROOT: u32 = 1;
+
+/// The depth of the quota tree.
+pub const QUOTA_TREE_DEPTH: usize = 4;
+
+/// Number of block references in a quota tree block.
+pub const QUOTA_REFS_PER_BLOCK: usize = QUOTA_BLOCK_SIZE / 4;
+
+/// Number of quota records in a quota data block.
+pub const QUOTA_ENTRIES_PER_BLOCK: usize =
+    (QUOTA_BLOCK_SIZE - size_of::<QuotaDataHeader>()) / size_of::<DiskQuota>();
+
+/// Default grace period of soft limits, in seconds (one week).
+pub const QUOTA_DEFAULT_GRACE: u32 = 7 * 24 * 3600;
+
+/// Limits are stored on disk in units of 1 KiB.
+const QUOTA_LIMIT_UNIT: u64 = 1024;
+
+/// The kind of owner a quota applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaType {
+    /// Quota of a user, indexed by uid.
+    User,
+    /// Quota of a group, indexed by gid.
+    Group,
+    /// Quota of a project, indexed by project id.
+    Project,
+}
+
+impl QuotaType {
+    /// The magic number of the quota file.
+    pub fn magic(&self) -> u32 {
+        match self {
+            QuotaType::User => 0xd9c01f11,
+            QuotaType::Group => 0xd9c01927,
+            QuotaType::Project => 0xd9c03f14,
+        }
+    }
+}
+
+/// Header at the beginning of a quota file.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaHeader {
+    /// Magic number, depends on the quota type.
+    pub magic: u32,
+    /// Format version, 1 for 64-bit records.
+    pub version: u32,
+}
+
+unsafe impl AsBytes for QuotaHeader {}
+
+impl QuotaHeader {
+    /// Format version with 64-bit quota records.
+    pub const VERSION: u32 = 1;
+}
+
+/// Quota file information, following `QuotaHeader`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaInfo {
+    /// Grace period for the space soft limit, in seconds.
+    pub block_grace: u32,
+    /// Grace period for the inode soft limit, in seconds.
+    pub inode_grace: u32,
+    /// Flags (unused).
+    pub flags: u32,
+    /// Number of quota blocks in the file.
+    pub blocks: u32,
+    /// Head of the list of free quota blocks.
+    pub free_blk: u32,
+    /// Head of the list of data blocks with free records.
+    pub free_entry: u32,
+}
+
+unsafe impl AsBytes for QuotaInfo {}
+
+/// Header of a quota data block.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaDataHeader {
+    /// Next block in the free list.
+    pub next_free: u32,
+    /// Previous block in the free list.
+    pub prev_free: u32,
+    /// Number of used records in this block.
+    pub entries: u16,
+    pad1: u16,
+    pad2: u32,
+}
+
+unsafe impl AsBytes for QuotaDataHeader {}
+
+/// On-disk quota record of a single id.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskQuota {
+    /// User, group or project id.
+    id: u32,
+    pad: u32,
+    /// Inode hard limit.
+    inode_hard: u64,
+    /// Inode soft limit.
+    inode_soft: u64,
+    /// Number of inodes used.
+    cur_inodes: u64,
+    /// Space hard limit, in KiB.
+    block_hard: u64,
+    /// Space soft limit, in KiB.
+    block_soft: u64,
+    /// Space used, in bytes.
+    cur_space: u64,
+    /// Time when the space soft limit grace period ends.
+    block_time: u64,
+    /// Time when the inode soft limit grace period ends.
+    inode_time: u64,
+}
+
+unsafe impl AsBytes for DiskQuota {}
+
+impl DiskQuota {
+    /// Check if the record slot is unused (all zero).
+    pub fn is_unused(bytes: &[u8]) -> bool {
+        bytes[..size_of::<DiskQuota>()].iter().all(|&b| b == 0)
+    }
+
+    /// Decode a record into a `Quota`.
+    pub fn to_quota(self) -> Quota {
+        let mut quota = Quota {
+            id: self.id,
+            space: self.cur_space,
+            inodes: self.cur_inodes,
+            limits: QuotaLimits {
+                space_soft: self.block_soft * QUOTA_LIMIT_UNIT,
+                space_hard: self.block_hard * QUOTA_LIMIT_UNIT,
+                inode_soft: self.inode_soft,
+                inode_hard: self.inode_hard,
+            },
+            space_grace_end: self.block_time as i64,
+            inode_grace_end: self.inode_time as i64,
+        };
+        // An all-zero record of id 0 is saved with `inode_time` 1
+        // to tell it from an unused slot
+        if quota.id == 0 && self == Self::escaped_empty() {
+            quota.inode_grace_end = 0;
+        }
+        quota
+    }
+
+    /// Encode a `Quota` into a record.
+    pub fn from_quota(quota: &Quota) -> Self {
+        let dq = Self {
+            id: quota.id,
+            pad: 0,
+            inode_hard: quota.limits.inode_hard,
+            inode_soft: quota.limits.inode_soft,
+            cur_inodes: quota.inodes,
+            block_hard: quota.limits.space_hard.div_ceil(QUOTA_LIMIT_UNIT),
+            block_soft: quota.limits.space_soft.div_ceil(QUOTA_LIMIT_UNIT),
+            cur_space: quota.space,
+            block_time: quota.space_grace_end as u64,
+            inode_time: quota.inode_grace_end as u64,
+        };
+        if dq == Self::default() {
+            Self::escaped_empty()
+        } else {
+            dq
+        }
+    }
+
+    fn escaped_empty() -> Self {
+        Self {
+            inode_time: 1,
+            ..Self::default()
+        }
+    }
+}
+
+impl PartialEq for DiskQuota {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+/// Quota limits. A limit of 0 means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaLimits {
+    /// Space soft limit in bytes.
+    pub space_soft: u64,
+    /// Space hard limit in bytes.
+    pub space_hard: u64,
+    /// Inode soft limit.
+    pub inode_soft: u64,
+    /// Inode hard limit.
+    pub inode_hard: u64,
+}
+
+/// Usage and limits of a user, group or project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// User, group or project id.
+    pub id: u32,
+    /// Space used in bytes.
+    pub space: u64,
+    /// Number of inodes used.
+    pub inodes: u64,
+    /// Limits.
+    pub limits: QuotaLimits,
+    /// Time (seconds since the epoch) when the grace period of the
+    /// space soft limit ends, 0 if the soft limit is not exceeded.
+    pub space_grace_end: i64,
+    /// Time (seconds since the epoch) when the grace period of the
+    /// inode soft limit ends, 0 if the soft limit is not exceeded.
+    pub inode_grace_end: i64,
+}
+
+impl Quota {
+    /// Create an empty quota record.
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            ..Self::default()
+        }
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! See [`super::block_group`] for details.
 
+use super::crc::*;
 use super::AsBytes;
+use super::Inode;
+use crate::constants::*;
 use crate::prelude::*;
 
 // 结构体表示超级块
@@ -104,7 +107,14 @@ pub struct SuperBlock {
     encrypt_algos: [u8; 4],    // 使用的加密算法
     encrypt_pw_salt: [u8; 16], // 用于string2key算法的盐
     lpf_ino: u32,              // lost+found节点的位置
-    padding: [u32; 100],       // 块的末尾的填充
+    prj_quota_inum: u32,       // 用于跟踪项目配额的节点
+    checksum_seed: u32,        // crc32c(uuid)，如果设置了csum_seed
+    time_hi: [u8; 6],          // 各时间戳的高8位
+    first_error_errcode: u8,
+    last_error_errcode: u8,
+    encoding: u16,             // 文件名的字符编码
+    encoding_flags: u16,       // 文件名编码的标志
+    padding: [u32; 95],        // 块的末尾的填充
     checksum: u32,             // crc32c(superblock)
 }
 
@@ -112,6 +122,146 @@ unsafe impl AsBytes for SuperBlock {}
 
 impl SuperBlock {
     const SB_MAGIC: u16 = 0xEF53;
+    /// Compatible feature: the filesystem has a journal.
+    const FEATURE_COMPAT_HAS_JOURNAL: u32 = 0x4;
+    /// Compatible feature: inodes may have extended attributes.
+    const FEATURE_COMPAT_EXT_ATTR: u32 = 0x8;
+    /// Compatible feature: directories may have hashed indexes.
+    const FEATURE_COMPAT_DIR_INDEX: u32 = 0x20;
+    /// Compatible feature: inode 7 reserves blocks to grow the group
+    /// descriptor table.
+    const FEATURE_COMPAT_RESIZE_INODE: u32 = 0x10;
+    /// Compatible feature: backup superblocks are only in `backup_bgs`.
+    const FEATURE_COMPAT_SPARSE_SUPER2: u32 = 0x200;
+    /// Compatible feature: the journal has a fast commit area.
+    const FEATURE_COMPAT_FAST_COMMIT: u32 = 0x400;
+    /// Incompatible feature: directory entries record the file type.
+    const FEATURE_INCOMPAT_FILETYPE: u32 = 0x2;
+    /// Incompatible feature: the journal needs to be replayed.
+    const FEATURE_INCOMPAT_RECOVER: u32 = 0x4;
+    /// Incompatible feature: the device is an external journal.
+    const FEATURE_INCOMPAT_JOURNAL_DEV: u32 = 0x8;
+    /// Incompatible feature: files use extent trees.
+    const FEATURE_INCOMPAT_EXTENTS: u32 = 0x40;
+    /// Incompatible feature: block numbers are 64-bit and group
+    /// descriptors are 64 bytes.
+    const FEATURE_INCOMPAT_64BIT: u32 = 0x80;
+    /// Incompatible feature: hashed directories may have three levels of
+    /// index, and directories may be larger than 2 GiB.
+    const FEATURE_INCOMPAT_LARGEDIR: u32 = 0x4000;
+    /// Incompatible feature: directories and files may be encrypted.
+    const FEATURE_INCOMPAT_ENCRYPT: u32 = 0x10000;
+    /// Incompatible feature: directories may be case-insensitive.
+    const FEATURE_INCOMPAT_CASEFOLD: u32 = 0x20000;
+    /// Read-only compatible feature: backup superblocks are only in some
+    /// block groups.
+    const FEATURE_RO_COMPAT_SPARSE_SUPER: u32 = 0x1;
+    /// Read-only compatible feature: files may be larger than 2 GiB.
+    const FEATURE_RO_COMPAT_LARGE_FILE: u32 = 0x2;
+    /// Read-only compatible feature: inode block counts may exceed 2^32
+    /// sectors.
+    const FEATURE_RO_COMPAT_HUGE_FILE: u32 = 0x8;
+    /// Read-only compatible feature: block group descriptors are
+    /// checksummed and may be uninitialized.
+    const FEATURE_RO_COMPAT_GDT_CSUM: u32 = 0x10;
+    /// Read-only compatible feature: directories may have more than 65000
+    /// subdirectories.
+    const FEATURE_RO_COMPAT_DIR_NLINK: u32 = 0x20;
+    /// Read-only compatible feature: inodes reserve `want_extra_isize`
+    /// bytes beyond the original 128 bytes.
+    const FEATURE_RO_COMPAT_EXTRA_ISIZE: u32 = 0x40;
+    /// Read-only compatible feature: quota is tracked in hidden inodes.
+    const FEATURE_RO_COMPAT_QUOTA: u32 = 0x100;
+    /// Read-only compatible feature: blocks are allocated in clusters, and
+    /// block bitmaps track clusters.
+    const FEATURE_RO_COMPAT_BIGALLOC: u32 = 0x200;
+    /// Read-only compatible feature: metadata is checksummed.
+    const FEATURE_RO_COMPAT_METADATA_CSUM: u32 = 0x400;
+    /// Read-only compatible feature: inodes carry a project id.
+    const FEATURE_RO_COMPAT_PROJECT: u32 = 0x2000;
+    /// Read-only compatible feature: files may have fs-verity enabled.
+    const FEATURE_RO_COMPAT_VERITY: u32 = 0x8000;
+    /// The filesystem was unmounted cleanly.
+    const STATE_VALID: u16 = 0x1;
+    /// Continue on errors.
+    const ERRORS_CONTINUE: u16 = 0x1;
+    /// Revision with dynamic inode sizes.
+    const DYNAMIC_REV: u32 = 1;
+    /// Directory hashes are computed with signed chars.
+    const FLAGS_SIGNED_HASH: u32 = 0x1;
+    /// Directory hashes are computed with unsigned chars.
+    const FLAGS_UNSIGNED_HASH: u32 = 0x2;
+    /// Half MD4 directory hash.
+    const HASH_HALF_MD4: u8 = 1;
+    /// The journal inode blocks are backed up in `journal_blocks`.
+    const JOURNAL_BACKUP_BLOCKS: u8 = 1;
+    /// Filename encoding UTF-8 with Unicode 12.1.
+    const ENCODING_UTF8_12_1: u16 = 1;
+    /// Checksum type crc32c.
+    const CHECKSUM_CRC32C: u8 = 1;
+
+    /// Create the superblock of a new filesystem with every block and
+    /// inode free. The filesystem has the features this implementation
+    /// requires: extents, 64-bit group descriptors, 256-byte inodes,
+    /// file types in directory entries and metadata checksums.
+    ///
+    /// Groups have `BLOCK_SIZE * 8` blocks and start from block 0.
+    pub fn new(
+        block_count: u64,
+        inodes_per_group: u32,
+        reserved_blocks: u64,
+        uuid: [u8; 16],
+        time: u32,
+    ) -> Self {
+        let mut sb: Self = unsafe { mem::zeroed() };
+        let blocks_per_group = (BLOCK_SIZE * 8) as u32;
+        let groups = block_count.div_ceil(blocks_per_group as u64) as u32;
+        let log_block_size = BLOCK_SIZE.trailing_zeros() - 10;
+        sb.inode_count = inodes_per_group * groups;
+        sb.block_count_lo = block_count as u32;
+        sb.block_count_hi = (block_count >> 32) as u32;
+        sb.reserved_block_count_lo = reserved_blocks as u32;
+        sb.reserved_blocks_count_hi = (reserved_blocks >> 32) as u32;
+        sb.set_free_blocks_count(block_count);
+        sb.free_inode_count = sb.inode_count;
+        sb.log_block_size = log_block_size;
+        sb.log_cluster_size = log_block_size;
+        sb.blocks_per_group = blocks_per_group;
+        sb.frags_per_group = blocks_per_group;
+        sb.inodes_per_group = inodes_per_group;
+        sb.write_time = time;
+        sb.max_mount_count = u16::MAX;
+        sb.magic = Self::SB_MAGIC;
+        sb.state = Self::STATE_VALID;
+        sb.errors = Self::ERRORS_CONTINUE;
+        sb.last_check_time = time;
+        sb.rev_level = Self::DYNAMIC_REV;
+        sb.first_inode = 11;
+        sb.inode_size = SB_GOOD_INODE_SIZE as u16;
+        sb.features_compatible = Self::FEATURE_COMPAT_EXT_ATTR;
+        sb.features_incompatible = Self::FEATURE_INCOMPAT_FILETYPE
+            | Self::FEATURE_INCOMPAT_EXTENTS
+            | Self::FEATURE_INCOMPAT_64BIT;
+        sb.features_read_only = Self::FEATURE_RO_COMPAT_SPARSE_SUPER
+            | Self::FEATURE_RO_COMPAT_LARGE_FILE
+            | Self::FEATURE_RO_COMPAT_HUGE_FILE
+            | Self::FEATURE_RO_COMPAT_DIR_NLINK
+            | Self::FEATURE_RO_COMPAT_EXTRA_ISIZE
+            | Self::FEATURE_RO_COMPAT_METADATA_CSUM;
+        sb.uuid = uuid;
+        // The hash seed only needs to be unique to the filesystem
+        for (i, seed) in sb.hash_seed.iter_mut().enumerate() {
+            *seed = u32::from_le_bytes(uuid[i * 4..i * 4 + 4].try_into().unwrap());
+        }
+        sb.default_hash_version = Self::HASH_HALF_MD4;
+        sb.desc_size = SB_GOOD_DESC_SIZE as u16;
+        sb.mkfs_time = time;
+        sb.min_extra_isize = (size_of::<Inode>() - 128) as u16;
+        sb.want_extra_isize = sb.min_extra_isize;
+        sb.flags = Self::FLAGS_SIGNED_HASH;
+        sb.checksum_type = Self::CHECKSUM_CRC32C;
+        sb
+    }
 
     pub fn check_magic(&self) -> bool {
         self.magic == Self::SB_MAGIC
@@ -129,23 +279,59 @@ impl SuperBlock {
         self.uuid
     }
 
+    /// Set the volume name, truncated to 16 bytes.
+    pub fn set_volume_name(&mut self, name: &str) {
+        let len = name.len().min(self.volume_name.len());
+        self.volume_name = [0; 16];
+        self.volume_name[..len].copy_from_slice(&name.as_bytes()[..len]);
+    }
+
+    /// Set the block group this copy of the superblock is stored in.
+    pub fn set_block_group_index(&mut self, bgid: BlockGroupId) {
+        self.block_group_index = bgid as u16;
+    }
+
     /// Total number of inodes.
-    #[allow(unused)]
     pub fn inode_count(&self) -> u32 {
         self.inode_count
     }
 
+    pub fn set_inode_count(&mut self, count: u32) {
+        self.inode_count = count;
+    }
+
     /// Total number of blocks.
     pub fn block_count(&self) -> u64 {
         self.block_count_lo as u64 | ((self.block_count_hi as u64) << 32)
     }
 
+    pub fn set_block_count(&mut self, count: u64) {
+        self.block_count_lo = count as u32;
+        self.block_count_hi = (count >> 32) as u32;
+    }
+
     /// The number of blocks in each block group.
     #[allow(unused)]
     pub fn blocks_per_group(&self) -> u32 {
         self.blocks_per_group
     }
 
+    /// The number of blocks in a cluster, the unit of block allocation. A
+    /// cluster is a single block without the `bigalloc` feature.
+    pub fn cluster_ratio(&self) -> u32 {
+        if self.has_bigalloc() {
+            1 << (self.log_cluster_size - self.log_block_size)
+        } else {
+            1
+        }
+    }
+
+    /// The number of clusters in each block group, the bits of a block
+    /// bitmap.
+    pub fn clusters_per_group(&self) -> u32 {
+        self.blocks_per_group / self.cluster_ratio()
+    }
+
     /// The number of inodes in each block group.
     pub fn inodes_per_group(&self) -> u32 {
         self.inodes_per_group
@@ -186,12 +372,319 @@ impl SuperBlock {
         self.free_inode_count = count;
     }
 
+    /// Number of free blocks, always counted in blocks.
     pub fn free_blocks_count(&self) -> u64 {
         self.free_block_count_lo as u64 | ((self.free_blocks_count_hi as u64) << 32).to_le()
     }
 
+    /// The first non-reserved inode.
+    pub fn first_inode(&self) -> InodeId {
+        self.first_inode
+    }
+
+    /// Whether directories and files may be encrypted.
+    pub fn has_encrypt(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_ENCRYPT != 0
+    }
+
+    /// Allow directories and files to be encrypted.
+    pub fn set_encrypt(&mut self) {
+        self.features_incompatible |= Self::FEATURE_INCOMPAT_ENCRYPT;
+    }
+
+    /// Whether directories may be case-insensitive.
+    pub fn has_casefold(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_CASEFOLD != 0
+    }
+
+    /// Allow directories to be case-insensitive, with names in UTF-8.
+    pub fn set_casefold(&mut self) {
+        self.features_incompatible |= Self::FEATURE_INCOMPAT_CASEFOLD;
+        self.encoding = Self::ENCODING_UTF8_12_1;
+        self.encoding_flags = 0;
+    }
+
+    /// Whether names in case-insensitive directories are folded as UTF-8
+    /// with Unicode 12.1, the only encoding supported.
+    pub fn has_utf8_encoding(&self) -> bool {
+        self.encoding == Self::ENCODING_UTF8_12_1
+    }
+
+    /// Whether directories may have hashed indexes.
+    pub fn has_dir_index(&self) -> bool {
+        self.features_compatible & Self::FEATURE_COMPAT_DIR_INDEX != 0
+    }
+
+    /// Allow hashed directory indexes.
+    pub fn set_dir_index(&mut self) {
+        self.features_compatible |= Self::FEATURE_COMPAT_DIR_INDEX;
+    }
+
+    /// Whether hashed directories may have three levels of index, and
+    /// directories may be larger than 2 GiB.
+    pub fn has_large_dir(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_LARGEDIR != 0
+    }
+
+    /// Allow three levels of index and directories larger than 2 GiB.
+    pub fn set_large_dir(&mut self) {
+        self.features_incompatible |= Self::FEATURE_INCOMPAT_LARGEDIR;
+    }
+
+    /// The seed of directory hashes.
+    pub fn hash_seed(&self) -> [u32; 4] {
+        self.hash_seed
+    }
+
+    /// The hash version of new hashed directories.
+    pub fn default_hash_version(&self) -> u8 {
+        self.default_hash_version
+    }
+
+    /// Whether directory hashes are computed with unsigned chars.
+    pub fn has_unsigned_hash(&self) -> bool {
+        self.flags & Self::FLAGS_UNSIGNED_HASH != 0
+    }
+
+    /// Whether quota tracking is enabled.
+    pub fn has_quota(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_QUOTA != 0
+    }
+
+    /// The inode of the user quota file, 0 if none.
+    pub fn usr_quota_inum(&self) -> InodeId {
+        self.usr_quota_inum
+    }
+
+    /// The inode of the group quota file, 0 if none.
+    pub fn grp_quota_inum(&self) -> InodeId {
+        self.grp_quota_inum
+    }
+
+    /// Enable quota tracking in the given quota files, 0 for the quota
+    /// types that are not tracked.
+    pub fn set_quota_inums(&mut self, usr: InodeId, grp: InodeId, prj: InodeId) {
+        self.features_read_only |= Self::FEATURE_RO_COMPAT_QUOTA;
+        self.usr_quota_inum = usr;
+        self.grp_quota_inum = grp;
+        self.prj_quota_inum = prj;
+    }
+
+    /// Whether metadata is checksummed.
+    pub fn has_metadata_csum(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_METADATA_CSUM != 0
+    }
+
+    /// Whether inodes carry a project id.
+    pub fn has_project(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_PROJECT != 0
+    }
+
+    /// Let inodes carry a project id.
+    pub fn set_project(&mut self) {
+        self.features_read_only |= Self::FEATURE_RO_COMPAT_PROJECT;
+    }
+
+    /// Whether blocks are allocated in clusters.
+    pub fn has_bigalloc(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_BIGALLOC != 0
+    }
+
+    /// Whether files may have fs-verity enabled.
+    pub fn has_verity(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_VERITY != 0
+    }
+
+    /// Allow files to have fs-verity enabled.
+    pub fn set_verity(&mut self) {
+        self.features_read_only |= Self::FEATURE_RO_COMPAT_VERITY;
+    }
+
+    /// The inode of the project quota file, 0 if none.
+    pub fn prj_quota_inum(&self) -> InodeId {
+        self.prj_quota_inum
+    }
+
+    /// Number of blocks reserved for the super user.
+    pub fn reserved_blocks_count(&self) -> u64 {
+        self.reserved_block_count_lo as u64 | ((self.reserved_blocks_count_hi as u64) << 32)
+    }
+
+    pub fn set_reserved_blocks_count(&mut self, count: u64) {
+        self.reserved_block_count_lo = count as u32;
+        self.reserved_blocks_count_hi = (count >> 32) as u32;
+    }
+
+    /// Whether the filesystem has a journal.
+    pub fn has_journal(&self) -> bool {
+        self.features_compatible & Self::FEATURE_COMPAT_HAS_JOURNAL != 0
+    }
+
+    /// Whether fast commits are enabled.
+    pub fn has_fast_commit(&self) -> bool {
+        self.features_compatible & Self::FEATURE_COMPAT_FAST_COMMIT != 0
+    }
+
+    /// Whether the journal has transactions that are not checkpointed.
+    pub fn needs_recovery(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_RECOVER != 0
+    }
+
+    pub fn set_needs_recovery(&mut self, recover: bool) {
+        if recover {
+            self.features_incompatible |= Self::FEATURE_INCOMPAT_RECOVER;
+        } else {
+            self.features_incompatible &= !Self::FEATURE_INCOMPAT_RECOVER;
+        }
+    }
+
+    /// The inode of the journal, 0 if the journal is on another device.
+    pub fn journal_inode_number(&self) -> InodeId {
+        self.journal_inode_number
+    }
+
+    /// Use the journal in inode `ino`. The block map of the inode and its
+    /// size are backed up in the superblock.
+    pub fn set_journal_inode(&mut self, ino: InodeId, blocks: [u32; 15], size: u64) {
+        self.features_compatible |= Self::FEATURE_COMPAT_HAS_JOURNAL;
+        self.journal_inode_number = ino;
+        self.journal_backup_type = Self::JOURNAL_BACKUP_BLOCKS;
+        self.journal_blocks[..15].copy_from_slice(&blocks);
+        self.journal_blocks[15] = (size >> 32) as u32;
+        self.journal_blocks[16] = size as u32;
+    }
+
+    /// The uuid of the external journal device.
+    pub fn journal_uuid(&self) -> [u8; 16] {
+        self.journal_uuid
+    }
+
+    /// Whether the device is an external journal, not a filesystem.
+    pub fn is_journal_dev(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_JOURNAL_DEV != 0
+    }
+
+    /// Compute and set the checksum of the superblock.
+    pub fn set_checksum(&mut self) {
+        self.checksum = self.calc_checksum();
+    }
+
+    /// Check the checksum of the superblock.
+    pub fn verify_checksum(&self) -> bool {
+        self.checksum == self.calc_checksum()
+    }
+
+    fn calc_checksum(&self) -> u32 {
+        let bytes = self.to_bytes();
+        crc32(CRC32_INIT, &bytes[..size_of::<SuperBlock>() - 4])
+    }
+
+    /// Whether directory entries record the file type.
+    pub fn has_filetype(&self) -> bool {
+        self.features_incompatible & Self::FEATURE_INCOMPAT_FILETYPE != 0
+    }
+
+    /// Whether directories may have more than 65000 subdirectories, then
+    /// their link count is 1.
+    pub fn has_dir_nlink(&self) -> bool {
+        self.features_read_only & Self::FEATURE_RO_COMPAT_DIR_NLINK != 0
+    }
+
+    /// Whether inode 7 reserves blocks to grow the group descriptor table.
+    pub fn has_resize_inode(&self) -> bool {
+        self.features_compatible & Self::FEATURE_COMPAT_RESIZE_INODE != 0
+    }
+
+    /// Reserve `reserved_gdt_blocks` blocks after the group descriptor
+    /// table, owned by the resize inode.
+    pub fn set_resize_inode(&mut self, reserved_gdt_blocks: u32) {
+        self.features_compatible |= Self::FEATURE_COMPAT_RESIZE_INODE;
+        self.set_reserved_gdt_blocks(reserved_gdt_blocks);
+    }
+
+    /// Whether block group descriptors are checksummed, then block groups
+    /// may be uninitialized and `itable_unused` is valid.
+    pub fn has_group_csum(&self) -> bool {
+        self.features_read_only
+            & (Self::FEATURE_RO_COMPAT_GDT_CSUM | Self::FEATURE_RO_COMPAT_METADATA_CSUM)
+            != 0
+    }
+
+    /// Number of blocks reserved after the group descriptor table to grow
+    /// it.
+    pub fn reserved_gdt_blocks(&self) -> u32 {
+        self.s_reserved_gdt_blocks as u32
+    }
+
+    pub fn set_reserved_gdt_blocks(&mut self, count: u32) {
+        self.s_reserved_gdt_blocks = count as u16;
+    }
+
+    /// Number of blocks of the group descriptor table.
+    pub fn group_desc_blocks(&self) -> u32 {
+        let desc_per_block = (BLOCK_SIZE / self.desc_size()) as u32;
+        (self.block_group_count() + desc_per_block - 1) / desc_per_block
+    }
+
+    /// Whether a block group starts with a copy of the superblock and of
+    /// the group descriptor table.
+    pub fn group_has_super(&self, bgid: BlockGroupId) -> bool {
+        if bgid == 0 {
+            return true;
+        }
+        if self.features_compatible & Self::FEATURE_COMPAT_SPARSE_SUPER2 != 0 {
+            return self.backup_bgs.contains(&bgid);
+        }
+        if self.features_read_only & Self::FEATURE_RO_COMPAT_SPARSE_SUPER == 0 || bgid == 1 {
+            return true;
+        }
+        // Powers of 3, 5 and 7
+        [3u64, 5, 7].iter().any(|&base| {
+            let mut n = base;
+            while n < bgid as u64 {
+                n *= base;
+            }
+            n == bgid as u64
+        })
+    }
+
+    /// The first block of a block group.
+    pub fn group_first_block(&self, bgid: BlockGroupId) -> PBlockId {
+        self.first_data_block as PBlockId + bgid as PBlockId * self.blocks_per_group as PBlockId
+    }
+
+    /// Number of blocks in a block group, the last group may be smaller.
+    pub fn blocks_in_group(&self, bgid: BlockGroupId) -> u64 {
+        (self.blocks_per_group as u64).min(self.block_count() - self.group_first_block(bgid))
+    }
+
+    /// Number of clusters in a block group, the last group may be smaller.
+    pub fn clusters_in_group(&self, bgid: BlockGroupId) -> u64 {
+        self.blocks_in_group(bgid)
+            .div_ceil(self.cluster_ratio() as u64)
+    }
+
     pub fn set_free_blocks_count(&mut self, free_blocks: u64) {
         self.free_block_count_lo = ((free_blocks << 32) >> 32).to_le() as u32;
         self.free_blocks_count_hi = (free_blocks >> 32) as u32;
     }
 }
+
+/// Filesystem statistics, as reported by `statfs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatFs {
+    /// Block size in bytes.
+    pub bsize: u32,
+    /// Total number of blocks.
+    pub blocks: u64,
+    /// Number of free blocks.
+    pub bfree: u64,
+    /// Number of free blocks available to unprivileged users.
+    pub bavail: u64,
+    /// Total number of inodes.
+    pub files: u64,
+    /// Number of free inodes.
+    pub ffree: u64,
+    /// Maximum length of file names.
+    pub namelen: u32,
+}
@@ -0,0 +1,72 @@
+//! Inode timestamps.
+//!
+//! An ext4 inode stores each timestamp as a 32-bit signed second count plus
+//! an optional 32-bit "extra" field. The lower 2 bits of the extra field extend
+//! the second count beyond 2038 (epoch bits), and the upper 30 bits hold the
+//! nanoseconds.
+
+/// A timestamp with nanosecond precision, relative to the Unix epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    /// Seconds since the epoch. Negative values are before 1970.
+    pub sec: i64,
+    /// Nanoseconds within the second, in `[0, 1_000_000_000)`.
+    pub nsec: u32,
+}
+
+impl Timestamp {
+    /// Number of bits of the extra field used to extend the seconds.
+    const EPOCH_BITS: u32 = 2;
+    /// Mask of the epoch bits in the extra field.
+    const EPOCH_MASK: u32 = (1 << Self::EPOCH_BITS) - 1;
+    /// Mask of the nanosecond bits in the extra field.
+    const NSEC_MASK: u32 = !Self::EPOCH_MASK;
+
+    /// Create a timestamp from seconds and nanoseconds.
+    pub const fn new(sec: i64, nsec: u32) -> Self {
+        Self { sec, nsec }
+    }
+
+    /// Create a timestamp with whole seconds.
+    pub const fn from_secs(sec: i64) -> Self {
+        Self { sec, nsec: 0 }
+    }
+
+    /// Decode a timestamp from the on-disk seconds and extra field.
+    pub fn decode(sec: u32, extra: u32) -> Self {
+        let mut s = sec as i32 as i64;
+        s += ((extra & Self::EPOCH_MASK) as i64) << 32;
+        Self {
+            sec: s,
+            nsec: (extra & Self::NSEC_MASK) >> Self::EPOCH_BITS,
+        }
+    }
+
+    /// Encode the timestamp to the on-disk seconds and extra field.
+    pub fn encode(&self) -> (u32, u32) {
+        let epoch = ((self.sec - self.sec as i32 as i64) >> 32) as u32 & Self::EPOCH_MASK;
+        (self.sec as u32, epoch | (self.nsec << Self::EPOCH_BITS))
+    }
+}
+
+/// A source of the current time, supplied when loading the filesystem.
+///
+/// The library is `no_std` and has no clock of its own. Embedders provide
+/// the wall clock (or a fake one) by implementing this trait.
+pub trait TimeSource: Send + Sync {
+    /// Get the current time.
+    fn now(&self) -> Timestamp;
+}
+
+/// A time source that always returns the same time.
+///
+/// Timestamps written by the filesystem then depend only on the sequence of
+/// operations, which keeps states reproducible during model checking.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FixedTime(pub Timestamp);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
@@ -0,0 +1,1061 @@
+//! Case folding of file names in case-insensitive directories, the same
+//! as `utf8_casefold` of Linux with the `utf8-12.1` encoding.
+//!
+//! A name is folded to the canonical decomposition (NFD) of its full case
+//! folding. Code points that are not assigned in Unicode 12.1 are kept as
+//! they are. The tables below are generated from the Unicode Character
+//! Database 12.1: `UnicodeData.txt` and `CaseFolding.txt`.
+
+use crate::prelude::*;
+
+/// First precomposed Hangul syllable.
+const HANGUL_S_BASE: u32 = 0xAC00;
+/// First leading consonant (choseong) jamo.
+const HANGUL_L_BASE: u32 = 0x1100;
+/// First vowel (jungseong) jamo.
+const HANGUL_V_BASE: u32 = 0x1161;
+/// The trailing consonant (jongseong) jamo before the first one.
+const HANGUL_T_BASE: u32 = 0x11A7;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_S_COUNT: u32 = 19 * HANGUL_V_COUNT * HANGUL_T_COUNT;
+
+/// Fold a name for case-insensitive comparison.
+pub fn casefold(name: &str) -> String {
+    let mut chars: Vec<char> = Vec::with_capacity(name.len());
+    for c in name.chars() {
+        let cp = c as u32;
+        if (HANGUL_S_BASE..HANGUL_S_BASE + HANGUL_S_COUNT).contains(&cp) {
+            // Hangul syllables decompose algorithmically
+            let s = cp - HANGUL_S_BASE;
+            let l = HANGUL_L_BASE + s / (HANGUL_V_COUNT * HANGUL_T_COUNT);
+            let v = HANGUL_V_BASE + s % (HANGUL_V_COUNT * HANGUL_T_COUNT) / HANGUL_T_COUNT;
+            let t = HANGUL_T_BASE + s % HANGUL_T_COUNT;
+            chars.extend([l, v].iter().filter_map(|&jamo| char::from_u32(jamo)));
+            if t != HANGUL_T_BASE {
+                chars.extend(char::from_u32(t));
+            }
+            continue;
+        }
+        match FOLD_TABLE.binary_search_by_key(&cp, |&(from, _)| from) {
+            Ok(i) => chars.extend(FOLD_TABLE[i].1.chars()),
+            Err(_) => chars.push(c),
+        }
+    }
+    // Put each run of combining marks in the canonical order
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let mut end = start + 1;
+        while end < chars.len() && combining_class(chars[end]) != 0 {
+            end += 1;
+        }
+        chars[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+    chars.into_iter().collect()
+}
+
+/// The canonical combining class of a code point.
+fn combining_class(c: char) -> u8 {
+    let cp = c as u32;
+    let i = CCC_TABLE.partition_point(|&(_, last, _)| last < cp);
+    match CCC_TABLE.get(i) {
+        Some(&(first, _, class)) if first <= cp => class,
+        _ => 0,
+    }
+}
+
+/// The folded forms of the code points that change, sorted by code point.
+#[rustfmt::skip]
+const FOLD_TABLE: &[(u32, &str)] = &[
+    (0x41, "a"), (0x42, "b"), (0x43, "c"), (0x44, "d"), (0x45, "e"), (0x46, "f"), (0x47, "g"),
+    (0x48, "h"), (0x49, "i"), (0x4a, "j"), (0x4b, "k"), (0x4c, "l"), (0x4d, "m"), (0x4e, "n"),
+    (0x4f, "o"), (0x50, "p"), (0x51, "q"), (0x52, "r"), (0x53, "s"), (0x54, "t"), (0x55, "u"),
+    (0x56, "v"), (0x57, "w"), (0x58, "x"), (0x59, "y"), (0x5a, "z"), (0xb5, "\u{3bc}"),
+    (0xc0, "a\u{300}"), (0xc1, "a\u{301}"), (0xc2, "a\u{302}"), (0xc3, "a\u{303}"),
+    (0xc4, "a\u{308}"), (0xc5, "a\u{30a}"), (0xc6, "\u{e6}"), (0xc7, "c\u{327}"),
+    (0xc8, "e\u{300}"), (0xc9, "e\u{301}"), (0xca, "e\u{302}"), (0xcb, "e\u{308}"),
+    (0xcc, "i\u{300}"), (0xcd, "i\u{301}"), (0xce, "i\u{302}"), (0xcf, "i\u{308}"),
+    (0xd0, "\u{f0}"), (0xd1, "n\u{303}"), (0xd2, "o\u{300}"), (0xd3, "o\u{301}"),
+    (0xd4, "o\u{302}"), (0xd5, "o\u{303}"), (0xd6, "o\u{308}"), (0xd8, "\u{f8}"),
+    (0xd9, "u\u{300}"), (0xda, "u\u{301}"), (0xdb, "u\u{302}"), (0xdc, "u\u{308}"),
+    (0xdd, "y\u{301}"), (0xde, "\u{fe}"), (0xdf, "ss"), (0xe0, "a\u{300}"), (0xe1, "a\u{301}"),
+    (0xe2, "a\u{302}"), (0xe3, "a\u{303}"), (0xe4, "a\u{308}"), (0xe5, "a\u{30a}"),
+    (0xe7, "c\u{327}"), (0xe8, "e\u{300}"), (0xe9, "e\u{301}"), (0xea, "e\u{302}"),
+    (0xeb, "e\u{308}"), (0xec, "i\u{300}"), (0xed, "i\u{301}"), (0xee, "i\u{302}"),
+    (0xef, "i\u{308}"), (0xf1, "n\u{303}"), (0xf2, "o\u{300}"), (0xf3, "o\u{301}"),
+    (0xf4, "o\u{302}"), (0xf5, "o\u{303}"), (0xf6, "o\u{308}"), (0xf9, "u\u{300}"),
+    (0xfa, "u\u{301}"), (0xfb, "u\u{302}"), (0xfc, "u\u{308}"), (0xfd, "y\u{301}"),
+    (0xff, "y\u{308}"), (0x100, "a\u{304}"), (0x101, "a\u{304}"), (0x102, "a\u{306}"),
+    (0x103, "a\u{306}"), (0x104, "a\u{328}"), (0x105, "a\u{328}"), (0x106, "c\u{301}"),
+    (0x107, "c\u{301}"), (0x108, "c\u{302}"), (0x109, "c\u{302}"), (0x10a, "c\u{307}"),
+    (0x10b, "c\u{307}"), (0x10c, "c\u{30c}"), (0x10d, "c\u{30c}"), (0x10e, "d\u{30c}"),
+    (0x10f, "d\u{30c}"), (0x110, "\u{111}"), (0x112, "e\u{304}"), (0x113, "e\u{304}"),
+    (0x114, "e\u{306}"), (0x115, "e\u{306}"), (0x116, "e\u{307}"), (0x117, "e\u{307}"),
+    (0x118, "e\u{328}"), (0x119, "e\u{328}"), (0x11a, "e\u{30c}"), (0x11b, "e\u{30c}"),
+    (0x11c, "g\u{302}"), (0x11d, "g\u{302}"), (0x11e, "g\u{306}"), (0x11f, "g\u{306}"),
+    (0x120, "g\u{307}"), (0x121, "g\u{307}"), (0x122, "g\u{327}"), (0x123, "g\u{327}"),
+    (0x124, "h\u{302}"), (0x125, "h\u{302}"), (0x126, "\u{127}"), (0x128, "i\u{303}"),
+    (0x129, "i\u{303}"), (0x12a, "i\u{304}"), (0x12b, "i\u{304}"), (0x12c, "i\u{306}"),
+    (0x12d, "i\u{306}"), (0x12e, "i\u{328}"), (0x12f, "i\u{328}"), (0x130, "i\u{307}"),
+    (0x132, "\u{133}"), (0x134, "j\u{302}"), (0x135, "j\u{302}"), (0x136, "k\u{327}"),
+    (0x137, "k\u{327}"), (0x139, "l\u{301}"), (0x13a, "l\u{301}"), (0x13b, "l\u{327}"),
+    (0x13c, "l\u{327}"), (0x13d, "l\u{30c}"), (0x13e, "l\u{30c}"), (0x13f, "\u{140}"),
+    (0x141, "\u{142}"), (0x143, "n\u{301}"), (0x144, "n\u{301}"), (0x145, "n\u{327}"),
+    (0x146, "n\u{327}"), (0x147, "n\u{30c}"), (0x148, "n\u{30c}"), (0x149, "\u{2bc}n"),
+    (0x14a, "\u{14b}"), (0x14c, "o\u{304}"), (0x14d, "o\u{304}"), (0x14e, "o\u{306}"),
+    (0x14f, "o\u{306}"), (0x150, "o\u{30b}"), (0x151, "o\u{30b}"), (0x152, "\u{153}"),
+    (0x154, "r\u{301}"), (0x155, "r\u{301}"), (0x156, "r\u{327}"), (0x157, "r\u{327}"),
+    (0x158, "r\u{30c}"), (0x159, "r\u{30c}"), (0x15a, "s\u{301}"), (0x15b, "s\u{301}"),
+    (0x15c, "s\u{302}"), (0x15d, "s\u{302}"), (0x15e, "s\u{327}"), (0x15f, "s\u{327}"),
+    (0x160, "s\u{30c}"), (0x161, "s\u{30c}"), (0x162, "t\u{327}"), (0x163, "t\u{327}"),
+    (0x164, "t\u{30c}"), (0x165, "t\u{30c}"), (0x166, "\u{167}"), (0x168, "u\u{303}"),
+    (0x169, "u\u{303}"), (0x16a, "u\u{304}"), (0x16b, "u\u{304}"), (0x16c, "u\u{306}"),
+    (0x16d, "u\u{306}"), (0x16e, "u\u{30a}"), (0x16f, "u\u{30a}"), (0x170, "u\u{30b}"),
+    (0x171, "u\u{30b}"), (0x172, "u\u{328}"), (0x173, "u\u{328}"), (0x174, "w\u{302}"),
+    (0x175, "w\u{302}"), (0x176, "y\u{302}"), (0x177, "y\u{302}"), (0x178, "y\u{308}"),
+    (0x179, "z\u{301}"), (0x17a, "z\u{301}"), (0x17b, "z\u{307}"), (0x17c, "z\u{307}"),
+    (0x17d, "z\u{30c}"), (0x17e, "z\u{30c}"), (0x17f, "s"), (0x181, "\u{253}"),
+    (0x182, "\u{183}"), (0x184, "\u{185}"), (0x186, "\u{254}"), (0x187, "\u{188}"),
+    (0x189, "\u{256}"), (0x18a, "\u{257}"), (0x18b, "\u{18c}"), (0x18e, "\u{1dd}"),
+    (0x18f, "\u{259}"), (0x190, "\u{25b}"), (0x191, "\u{192}"), (0x193, "\u{260}"),
+    (0x194, "\u{263}"), (0x196, "\u{269}"), (0x197, "\u{268}"), (0x198, "\u{199}"),
+    (0x19c, "\u{26f}"), (0x19d, "\u{272}"), (0x19f, "\u{275}"), (0x1a0, "o\u{31b}"),
+    (0x1a1, "o\u{31b}"), (0x1a2, "\u{1a3}"), (0x1a4, "\u{1a5}"), (0x1a6, "\u{280}"),
+    (0x1a7, "\u{1a8}"), (0x1a9, "\u{283}"), (0x1ac, "\u{1ad}"), (0x1ae, "\u{288}"),
+    (0x1af, "u\u{31b}"), (0x1b0, "u\u{31b}"), (0x1b1, "\u{28a}"), (0x1b2, "\u{28b}"),
+    (0x1b3, "\u{1b4}"), (0x1b5, "\u{1b6}"), (0x1b7, "\u{292}"), (0x1b8, "\u{1b9}"),
+    (0x1bc, "\u{1bd}"), (0x1c4, "\u{1c6}"), (0x1c5, "\u{1c6}"), (0x1c7, "\u{1c9}"),
+    (0x1c8, "\u{1c9}"), (0x1ca, "\u{1cc}"), (0x1cb, "\u{1cc}"), (0x1cd, "a\u{30c}"),
+    (0x1ce, "a\u{30c}"), (0x1cf, "i\u{30c}"), (0x1d0, "i\u{30c}"), (0x1d1, "o\u{30c}"),
+    (0x1d2, "o\u{30c}"), (0x1d3, "u\u{30c}"), (0x1d4, "u\u{30c}"), (0x1d5, "u\u{308}\u{304}"),
+    (0x1d6, "u\u{308}\u{304}"), (0x1d7, "u\u{308}\u{301}"), (0x1d8, "u\u{308}\u{301}"),
+    (0x1d9, "u\u{308}\u{30c}"), (0x1da, "u\u{308}\u{30c}"), (0x1db, "u\u{308}\u{300}"),
+    (0x1dc, "u\u{308}\u{300}"), (0x1de, "a\u{308}\u{304}"), (0x1df, "a\u{308}\u{304}"),
+    (0x1e0, "a\u{307}\u{304}"), (0x1e1, "a\u{307}\u{304}"), (0x1e2, "\u{e6}\u{304}"),
+    (0x1e3, "\u{e6}\u{304}"), (0x1e4, "\u{1e5}"), (0x1e6, "g\u{30c}"), (0x1e7, "g\u{30c}"),
+    (0x1e8, "k\u{30c}"), (0x1e9, "k\u{30c}"), (0x1ea, "o\u{328}"), (0x1eb, "o\u{328}"),
+    (0x1ec, "o\u{328}\u{304}"), (0x1ed, "o\u{328}\u{304}"), (0x1ee, "\u{292}\u{30c}"),
+    (0x1ef, "\u{292}\u{30c}"), (0x1f0, "j\u{30c}"), (0x1f1, "\u{1f3}"), (0x1f2, "\u{1f3}"),
+    (0x1f4, "g\u{301}"), (0x1f5, "g\u{301}"), (0x1f6, "\u{195}"), (0x1f7, "\u{1bf}"),
+    (0x1f8, "n\u{300}"), (0x1f9, "n\u{300}"), (0x1fa, "a\u{30a}\u{301}"),
+    (0x1fb, "a\u{30a}\u{301}"), (0x1fc, "\u{e6}\u{301}"), (0x1fd, "\u{e6}\u{301}"),
+    (0x1fe, "\u{f8}\u{301}"), (0x1ff, "\u{f8}\u{301}"), (0x200, "a\u{30f}"),
+    (0x201, "a\u{30f}"), (0x202, "a\u{311}"), (0x203, "a\u{311}"), (0x204, "e\u{30f}"),
+    (0x205, "e\u{30f}"), (0x206, "e\u{311}"), (0x207, "e\u{311}"), (0x208, "i\u{30f}"),
+    (0x209, "i\u{30f}"), (0x20a, "i\u{311}"), (0x20b, "i\u{311}"), (0x20c, "o\u{30f}"),
+    (0x20d, "o\u{30f}"), (0x20e, "o\u{311}"), (0x20f, "o\u{311}"), (0x210, "r\u{30f}"),
+    (0x211, "r\u{30f}"), (0x212, "r\u{311}"), (0x213, "r\u{311}"), (0x214, "u\u{30f}"),
+    (0x215, "u\u{30f}"), (0x216, "u\u{311}"), (0x217, "u\u{311}"), (0x218, "s\u{326}"),
+    (0x219, "s\u{326}"), (0x21a, "t\u{326}"), (0x21b, "t\u{326}"), (0x21c, "\u{21d}"),
+    (0x21e, "h\u{30c}"), (0x21f, "h\u{30c}"), (0x220, "\u{19e}"), (0x222, "\u{223}"),
+    (0x224, "\u{225}"), (0x226, "a\u{307}"), (0x227, "a\u{307}"), (0x228, "e\u{327}"),
+    (0x229, "e\u{327}"), (0x22a, "o\u{308}\u{304}"), (0x22b, "o\u{308}\u{304}"),
+    (0x22c, "o\u{303}\u{304}"), (0x22d, "o\u{303}\u{304}"), (0x22e, "o\u{307}"),
+    (0x22f, "o\u{307}"), (0x230, "o\u{307}\u{304}"), (0x231, "o\u{307}\u{304}"),
+    (0x232, "y\u{304}"), (0x233, "y\u{304}"), (0x23a, "\u{2c65}"), (0x23b, "\u{23c}"),
+    (0x23d, "\u{19a}"), (0x23e, "\u{2c66}"), (0x241, "\u{242}"), (0x243, "\u{180}"),
+    (0x244, "\u{289}"), (0x245, "\u{28c}"), (0x246, "\u{247}"), (0x248, "\u{249}"),
+    (0x24a, "\u{24b}"), (0x24c, "\u{24d}"), (0x24e, "\u{24f}"), (0x340, "\u{300}"),
+    (0x341, "\u{301}"), (0x343, "\u{313}"), (0x344, "\u{308}\u{301}"), (0x345, "\u{3b9}"),
+    (0x370, "\u{371}"), (0x372, "\u{373}"), (0x374, "\u{2b9}"), (0x376, "\u{377}"),
+    (0x37e, ";"), (0x37f, "\u{3f3}"), (0x385, "\u{a8}\u{301}"), (0x386, "\u{3b1}\u{301}"),
+    (0x387, "\u{b7}"), (0x388, "\u{3b5}\u{301}"), (0x389, "\u{3b7}\u{301}"),
+    (0x38a, "\u{3b9}\u{301}"), (0x38c, "\u{3bf}\u{301}"), (0x38e, "\u{3c5}\u{301}"),
+    (0x38f, "\u{3c9}\u{301}"), (0x390, "\u{3b9}\u{308}\u{301}"), (0x391, "\u{3b1}"),
+    (0x392, "\u{3b2}"), (0x393, "\u{3b3}"), (0x394, "\u{3b4}"), (0x395, "\u{3b5}"),
+    (0x396, "\u{3b6}"), (0x397, "\u{3b7}"), (0x398, "\u{3b8}"), (0x399, "\u{3b9}"),
+    (0x39a, "\u{3ba}"), (0x39b, "\u{3bb}"), (0x39c, "\u{3bc}"), (0x39d, "\u{3bd}"),
+    (0x39e, "\u{3be}"), (0x39f, "\u{3bf}"), (0x3a0, "\u{3c0}"), (0x3a1, "\u{3c1}"),
+    (0x3a3, "\u{3c3}"), (0x3a4, "\u{3c4}"), (0x3a5, "\u{3c5}"), (0x3a6, "\u{3c6}"),
+    (0x3a7, "\u{3c7}"), (0x3a8, "\u{3c8}"), (0x3a9, "\u{3c9}"), (0x3aa, "\u{3b9}\u{308}"),
+    (0x3ab, "\u{3c5}\u{308}"), (0x3ac, "\u{3b1}\u{301}"), (0x3ad, "\u{3b5}\u{301}"),
+    (0x3ae, "\u{3b7}\u{301}"), (0x3af, "\u{3b9}\u{301}"), (0x3b0, "\u{3c5}\u{308}\u{301}"),
+    (0x3c2, "\u{3c3}"), (0x3ca, "\u{3b9}\u{308}"), (0x3cb, "\u{3c5}\u{308}"),
+    (0x3cc, "\u{3bf}\u{301}"), (0x3cd, "\u{3c5}\u{301}"), (0x3ce, "\u{3c9}\u{301}"),
+    (0x3cf, "\u{3d7}"), (0x3d0, "\u{3b2}"), (0x3d1, "\u{3b8}"), (0x3d3, "\u{3d2}\u{301}"),
+    (0x3d4, "\u{3d2}\u{308}"), (0x3d5, "\u{3c6}"), (0x3d6, "\u{3c0}"), (0x3d8, "\u{3d9}"),
+    (0x3da, "\u{3db}"), (0x3dc, "\u{3dd}"), (0x3de, "\u{3df}"), (0x3e0, "\u{3e1}"),
+    (0x3e2, "\u{3e3}"), (0x3e4, "\u{3e5}"), (0x3e6, "\u{3e7}"), (0x3e8, "\u{3e9}"),
+    (0x3ea, "\u{3eb}"), (0x3ec, "\u{3ed}"), (0x3ee, "\u{3ef}"), (0x3f0, "\u{3ba}"),
+    (0x3f1, "\u{3c1}"), (0x3f4, "\u{3b8}"), (0x3f5, "\u{3b5}"), (0x3f7, "\u{3f8}"),
+    (0x3f9, "\u{3f2}"), (0x3fa, "\u{3fb}"), (0x3fd, "\u{37b}"), (0x3fe, "\u{37c}"),
+    (0x3ff, "\u{37d}"), (0x400, "\u{435}\u{300}"), (0x401, "\u{435}\u{308}"),
+    (0x402, "\u{452}"), (0x403, "\u{433}\u{301}"), (0x404, "\u{454}"), (0x405, "\u{455}"),
+    (0x406, "\u{456}"), (0x407, "\u{456}\u{308}"), (0x408, "\u{458}"), (0x409, "\u{459}"),
+    (0x40a, "\u{45a}"), (0x40b, "\u{45b}"), (0x40c, "\u{43a}\u{301}"),
+    (0x40d, "\u{438}\u{300}"), (0x40e, "\u{443}\u{306}"), (0x40f, "\u{45f}"),
+    (0x410, "\u{430}"), (0x411, "\u{431}"), (0x412, "\u{432}"), (0x413, "\u{433}"),
+    (0x414, "\u{434}"), (0x415, "\u{435}"), (0x416, "\u{436}"), (0x417, "\u{437}"),
+    (0x418, "\u{438}"), (0x419, "\u{438}\u{306}"), (0x41a, "\u{43a}"), (0x41b, "\u{43b}"),
+    (0x41c, "\u{43c}"), (0x41d, "\u{43d}"), (0x41e, "\u{43e}"), (0x41f, "\u{43f}"),
+    (0x420, "\u{440}"), (0x421, "\u{441}"), (0x422, "\u{442}"), (0x423, "\u{443}"),
+    (0x424, "\u{444}"), (0x425, "\u{445}"), (0x426, "\u{446}"), (0x427, "\u{447}"),
+    (0x428, "\u{448}"), (0x429, "\u{449}"), (0x42a, "\u{44a}"), (0x42b, "\u{44b}"),
+    (0x42c, "\u{44c}"), (0x42d, "\u{44d}"), (0x42e, "\u{44e}"), (0x42f, "\u{44f}"),
+    (0x439, "\u{438}\u{306}"), (0x450, "\u{435}\u{300}"), (0x451, "\u{435}\u{308}"),
+    (0x453, "\u{433}\u{301}"), (0x457, "\u{456}\u{308}"), (0x45c, "\u{43a}\u{301}"),
+    (0x45d, "\u{438}\u{300}"), (0x45e, "\u{443}\u{306}"), (0x460, "\u{461}"),
+    (0x462, "\u{463}"), (0x464, "\u{465}"), (0x466, "\u{467}"), (0x468, "\u{469}"),
+    (0x46a, "\u{46b}"), (0x46c, "\u{46d}"), (0x46e, "\u{46f}"), (0x470, "\u{471}"),
+    (0x472, "\u{473}"), (0x474, "\u{475}"), (0x476, "\u{475}\u{30f}"),
+    (0x477, "\u{475}\u{30f}"), (0x478, "\u{479}"), (0x47a, "\u{47b}"), (0x47c, "\u{47d}"),
+    (0x47e, "\u{47f}"), (0x480, "\u{481}"), (0x48a, "\u{48b}"), (0x48c, "\u{48d}"),
+    (0x48e, "\u{48f}"), (0x490, "\u{491}"), (0x492, "\u{493}"), (0x494, "\u{495}"),
+    (0x496, "\u{497}"), (0x498, "\u{499}"), (0x49a, "\u{49b}"), (0x49c, "\u{49d}"),
+    (0x49e, "\u{49f}"), (0x4a0, "\u{4a1}"), (0x4a2, "\u{4a3}"), (0x4a4, "\u{4a5}"),
+    (0x4a6, "\u{4a7}"), (0x4a8, "\u{4a9}"), (0x4aa, "\u{4ab}"), (0x4ac, "\u{4ad}"),
+    (0x4ae, "\u{4af}"), (0x4b0, "\u{4b1}"), (0x4b2, "\u{4b3}"), (0x4b4, "\u{4b5}"),
+    (0x4b6, "\u{4b7}"), (0x4b8, "\u{4b9}"), (0x4ba, "\u{4bb}"), (0x4bc, "\u{4bd}"),
+    (0x4be, "\u{4bf}"), (0x4c0, "\u{4cf}"), (0x4c1, "\u{436}\u{306}"),
+    (0x4c2, "\u{436}\u{306}"), (0x4c3, "\u{4c4}"), (0x4c5, "\u{4c6}"), (0x4c7, "\u{4c8}"),
+    (0x4c9, "\u{4ca}"), (0x4cb, "\u{4cc}"), (0x4cd, "\u{4ce}"), (0x4d0, "\u{430}\u{306}"),
+    (0x4d1, "\u{430}\u{306}"), (0x4d2, "\u{430}\u{308}"), (0x4d3, "\u{430}\u{308}"),
+    (0x4d4, "\u{4d5}"), (0x4d6, "\u{435}\u{306}"), (0x4d7, "\u{435}\u{306}"),
+    (0x4d8, "\u{4d9}"), (0x4da, "\u{4d9}\u{308}"), (0x4db, "\u{4d9}\u{308}"),
+    (0x4dc, "\u{436}\u{308}"), (0x4dd, "\u{436}\u{308}"), (0x4de, "\u{437}\u{308}"),
+    (0x4df, "\u{437}\u{308}"), (0x4e0, "\u{4e1}"), (0x4e2, "\u{438}\u{304}"),
+    (0x4e3, "\u{438}\u{304}"), (0x4e4, "\u{438}\u{308}"), (0x4e5, "\u{438}\u{308}"),
+    (0x4e6, "\u{43e}\u{308}"), (0x4e7, "\u{43e}\u{308}"), (0x4e8, "\u{4e9}"),
+    (0x4ea, "\u{4e9}\u{308}"), (0x4eb, "\u{4e9}\u{308}"), (0x4ec, "\u{44d}\u{308}"),
+    (0x4ed, "\u{44d}\u{308}"), (0x4ee, "\u{443}\u{304}"), (0x4ef, "\u{443}\u{304}"),
+    (0x4f0, "\u{443}\u{308}"), (0x4f1, "\u{443}\u{308}"), (0x4f2, "\u{443}\u{30b}"),
+    (0x4f3, "\u{443}\u{30b}"), (0x4f4, "\u{447}\u{308}"), (0x4f5, "\u{447}\u{308}"),
+    (0x4f6, "\u{4f7}"), (0x4f8, "\u{44b}\u{308}"), (0x4f9, "\u{44b}\u{308}"),
+    (0x4fa, "\u{4fb}"), (0x4fc, "\u{4fd}"), (0x4fe, "\u{4ff}"), (0x500, "\u{501}"),
+    (0x502, "\u{503}"), (0x504, "\u{505}"), (0x506, "\u{507}"), (0x508, "\u{509}"),
+    (0x50a, "\u{50b}"), (0x50c, "\u{50d}"), (0x50e, "\u{50f}"), (0x510, "\u{511}"),
+    (0x512, "\u{513}"), (0x514, "\u{515}"), (0x516, "\u{517}"), (0x518, "\u{519}"),
+    (0x51a, "\u{51b}"), (0x51c, "\u{51d}"), (0x51e, "\u{51f}"), (0x520, "\u{521}"),
+    (0x522, "\u{523}"), (0x524, "\u{525}"), (0x526, "\u{527}"), (0x528, "\u{529}"),
+    (0x52a, "\u{52b}"), (0x52c, "\u{52d}"), (0x52e, "\u{52f}"), (0x531, "\u{561}"),
+    (0x532, "\u{562}"), (0x533, "\u{563}"), (0x534, "\u{564}"), (0x535, "\u{565}"),
+    (0x536, "\u{566}"), (0x537, "\u{567}"), (0x538, "\u{568}"), (0x539, "\u{569}"),
+    (0x53a, "\u{56a}"), (0x53b, "\u{56b}"), (0x53c, "\u{56c}"), (0x53d, "\u{56d}"),
+    (0x53e, "\u{56e}"), (0x53f, "\u{56f}"), (0x540, "\u{570}"), (0x541, "\u{571}"),
+    (0x542, "\u{572}"), (0x543, "\u{573}"), (0x544, "\u{574}"), (0x545, "\u{575}"),
+    (0x546, "\u{576}"), (0x547, "\u{577}"), (0x548, "\u{578}"), (0x549, "\u{579}"),
+    (0x54a, "\u{57a}"), (0x54b, "\u{57b}"), (0x54c, "\u{57c}"), (0x54d, "\u{57d}"),
+    (0x54e, "\u{57e}"), (0x54f, "\u{57f}"), (0x550, "\u{580}"), (0x551, "\u{581}"),
+    (0x552, "\u{582}"), (0x553, "\u{583}"), (0x554, "\u{584}"), (0x555, "\u{585}"),
+    (0x556, "\u{586}"), (0x587, "\u{565}\u{582}"), (0x622, "\u{627}\u{653}"),
+    (0x623, "\u{627}\u{654}"), (0x624, "\u{648}\u{654}"), (0x625, "\u{627}\u{655}"),
+    (0x626, "\u{64a}\u{654}"), (0x6c0, "\u{6d5}\u{654}"), (0x6c2, "\u{6c1}\u{654}"),
+    (0x6d3, "\u{6d2}\u{654}"), (0x929, "\u{928}\u{93c}"), (0x931, "\u{930}\u{93c}"),
+    (0x934, "\u{933}\u{93c}"), (0x958, "\u{915}\u{93c}"), (0x959, "\u{916}\u{93c}"),
+    (0x95a, "\u{917}\u{93c}"), (0x95b, "\u{91c}\u{93c}"), (0x95c, "\u{921}\u{93c}"),
+    (0x95d, "\u{922}\u{93c}"), (0x95e, "\u{92b}\u{93c}"), (0x95f, "\u{92f}\u{93c}"),
+    (0x9cb, "\u{9c7}\u{9be}"), (0x9cc, "\u{9c7}\u{9d7}"), (0x9dc, "\u{9a1}\u{9bc}"),
+    (0x9dd, "\u{9a2}\u{9bc}"), (0x9df, "\u{9af}\u{9bc}"), (0xa33, "\u{a32}\u{a3c}"),
+    (0xa36, "\u{a38}\u{a3c}"), (0xa59, "\u{a16}\u{a3c}"), (0xa5a, "\u{a17}\u{a3c}"),
+    (0xa5b, "\u{a1c}\u{a3c}"), (0xa5e, "\u{a2b}\u{a3c}"), (0xb48, "\u{b47}\u{b56}"),
+    (0xb4b, "\u{b47}\u{b3e}"), (0xb4c, "\u{b47}\u{b57}"), (0xb5c, "\u{b21}\u{b3c}"),
+    (0xb5d, "\u{b22}\u{b3c}"), (0xb94, "\u{b92}\u{bd7}"), (0xbca, "\u{bc6}\u{bbe}"),
+    (0xbcb, "\u{bc7}\u{bbe}"), (0xbcc, "\u{bc6}\u{bd7}"), (0xc48, "\u{c46}\u{c56}"),
+    (0xcc0, "\u{cbf}\u{cd5}"), (0xcc7, "\u{cc6}\u{cd5}"), (0xcc8, "\u{cc6}\u{cd6}"),
+    (0xcca, "\u{cc6}\u{cc2}"), (0xccb, "\u{cc6}\u{cc2}\u{cd5}"), (0xd4a, "\u{d46}\u{d3e}"),
+    (0xd4b, "\u{d47}\u{d3e}"), (0xd4c, "\u{d46}\u{d57}"), (0xdda, "\u{dd9}\u{dca}"),
+    (0xddc, "\u{dd9}\u{dcf}"), (0xddd, "\u{dd9}\u{dcf}\u{dca}"), (0xdde, "\u{dd9}\u{ddf}"),
+    (0xf43, "\u{f42}\u{fb7}"), (0xf4d, "\u{f4c}\u{fb7}"), (0xf52, "\u{f51}\u{fb7}"),
+    (0xf57, "\u{f56}\u{fb7}"), (0xf5c, "\u{f5b}\u{fb7}"), (0xf69, "\u{f40}\u{fb5}"),
+    (0xf73, "\u{f71}\u{f72}"), (0xf75, "\u{f71}\u{f74}"), (0xf76, "\u{fb2}\u{f80}"),
+    (0xf78, "\u{fb3}\u{f80}"), (0xf81, "\u{f71}\u{f80}"), (0xf93, "\u{f92}\u{fb7}"),
+    (0xf9d, "\u{f9c}\u{fb7}"), (0xfa2, "\u{fa1}\u{fb7}"), (0xfa7, "\u{fa6}\u{fb7}"),
+    (0xfac, "\u{fab}\u{fb7}"), (0xfb9, "\u{f90}\u{fb5}"), (0x1026, "\u{1025}\u{102e}"),
+    (0x10a0, "\u{2d00}"), (0x10a1, "\u{2d01}"), (0x10a2, "\u{2d02}"), (0x10a3, "\u{2d03}"),
+    (0x10a4, "\u{2d04}"), (0x10a5, "\u{2d05}"), (0x10a6, "\u{2d06}"), (0x10a7, "\u{2d07}"),
+    (0x10a8, "\u{2d08}"), (0x10a9, "\u{2d09}"), (0x10aa, "\u{2d0a}"), (0x10ab, "\u{2d0b}"),
+    (0x10ac, "\u{2d0c}"), (0x10ad, "\u{2d0d}"), (0x10ae, "\u{2d0e}"), (0x10af, "\u{2d0f}"),
+    (0x10b0, "\u{2d10}"), (0x10b1, "\u{2d11}"), (0x10b2, "\u{2d12}"), (0x10b3, "\u{2d13}"),
+    (0x10b4, "\u{2d14}"), (0x10b5, "\u{2d15}"), (0x10b6, "\u{2d16}"), (0x10b7, "\u{2d17}"),
+    (0x10b8, "\u{2d18}"), (0x10b9, "\u{2d19}"), (0x10ba, "\u{2d1a}"), (0x10bb, "\u{2d1b}"),
+    (0x10bc, "\u{2d1c}"), (0x10bd, "\u{2d1d}"), (0x10be, "\u{2d1e}"), (0x10bf, "\u{2d1f}"),
+    (0x10c0, "\u{2d20}"), (0x10c1, "\u{2d21}"), (0x10c2, "\u{2d22}"), (0x10c3, "\u{2d23}"),
+    (0x10c4, "\u{2d24}"), (0x10c5, "\u{2d25}"), (0x10c7, "\u{2d27}"), (0x10cd, "\u{2d2d}"),
+    (0x13f8, "\u{13f0}"), (0x13f9, "\u{13f1}"), (0x13fa, "\u{13f2}"), (0x13fb, "\u{13f3}"),
+    (0x13fc, "\u{13f4}"), (0x13fd, "\u{13f5}"), (0x1b06, "\u{1b05}\u{1b35}"),
+    (0x1b08, "\u{1b07}\u{1b35}"), (0x1b0a, "\u{1b09}\u{1b35}"), (0x1b0c, "\u{1b0b}\u{1b35}"),
+    (0x1b0e, "\u{1b0d}\u{1b35}"), (0x1b12, "\u{1b11}\u{1b35}"), (0x1b3b, "\u{1b3a}\u{1b35}"),
+    (0x1b3d, "\u{1b3c}\u{1b35}"), (0x1b40, "\u{1b3e}\u{1b35}"), (0x1b41, "\u{1b3f}\u{1b35}"),
+    (0x1b43, "\u{1b42}\u{1b35}"), (0x1c80, "\u{432}"), (0x1c81, "\u{434}"), (0x1c82, "\u{43e}"),
+    (0x1c83, "\u{441}"), (0x1c84, "\u{442}"), (0x1c85, "\u{442}"), (0x1c86, "\u{44a}"),
+    (0x1c87, "\u{463}"), (0x1c88, "\u{a64b}"), (0x1c90, "\u{10d0}"), (0x1c91, "\u{10d1}"),
+    (0x1c92, "\u{10d2}"), (0x1c93, "\u{10d3}"), (0x1c94, "\u{10d4}"), (0x1c95, "\u{10d5}"),
+    (0x1c96, "\u{10d6}"), (0x1c97, "\u{10d7}"), (0x1c98, "\u{10d8}"), (0x1c99, "\u{10d9}"),
+    (0x1c9a, "\u{10da}"), (0x1c9b, "\u{10db}"), (0x1c9c, "\u{10dc}"), (0x1c9d, "\u{10dd}"),
+    (0x1c9e, "\u{10de}"), (0x1c9f, "\u{10df}"), (0x1ca0, "\u{10e0}"), (0x1ca1, "\u{10e1}"),
+    (0x1ca2, "\u{10e2}"), (0x1ca3, "\u{10e3}"), (0x1ca4, "\u{10e4}"), (0x1ca5, "\u{10e5}"),
+    (0x1ca6, "\u{10e6}"), (0x1ca7, "\u{10e7}"), (0x1ca8, "\u{10e8}"), (0x1ca9, "\u{10e9}"),
+    (0x1caa, "\u{10ea}"), (0x1cab, "\u{10eb}"), (0x1cac, "\u{10ec}"), (0x1cad, "\u{10ed}"),
+    (0x1cae, "\u{10ee}"), (0x1caf, "\u{10ef}"), (0x1cb0, "\u{10f0}"), (0x1cb1, "\u{10f1}"),
+    (0x1cb2, "\u{10f2}"), (0x1cb3, "\u{10f3}"), (0x1cb4, "\u{10f4}"), (0x1cb5, "\u{10f5}"),
+    (0x1cb6, "\u{10f6}"), (0x1cb7, "\u{10f7}"), (0x1cb8, "\u{10f8}"), (0x1cb9, "\u{10f9}"),
+    (0x1cba, "\u{10fa}"), (0x1cbd, "\u{10fd}"), (0x1cbe, "\u{10fe}"), (0x1cbf, "\u{10ff}"),
+    (0x1e00, "a\u{325}"), (0x1e01, "a\u{325}"), (0x1e02, "b\u{307}"), (0x1e03, "b\u{307}"),
+    (0x1e04, "b\u{323}"), (0x1e05, "b\u{323}"), (0x1e06, "b\u{331}"), (0x1e07, "b\u{331}"),
+    (0x1e08, "c\u{327}\u{301}"), (0x1e09, "c\u{327}\u{301}"), (0x1e0a, "d\u{307}"),
+    (0x1e0b, "d\u{307}"), (0x1e0c, "d\u{323}"), (0x1e0d, "d\u{323}"), (0x1e0e, "d\u{331}"),
+    (0x1e0f, "d\u{331}"), (0x1e10, "d\u{327}"), (0x1e11, "d\u{327}"), (0x1e12, "d\u{32d}"),
+    (0x1e13, "d\u{32d}"), (0x1e14, "e\u{304}\u{300}"), (0x1e15, "e\u{304}\u{300}"),
+    (0x1e16, "e\u{304}\u{301}"), (0x1e17, "e\u{304}\u{301}"), (0x1e18, "e\u{32d}"),
+    (0x1e19, "e\u{32d}"), (0x1e1a, "e\u{330}"), (0x1e1b, "e\u{330}"),
+    (0x1e1c, "e\u{327}\u{306}"), (0x1e1d, "e\u{327}\u{306}"), (0x1e1e, "f\u{307}"),
+    (0x1e1f, "f\u{307}"), (0x1e20, "g\u{304}"), (0x1e21, "g\u{304}"), (0x1e22, "h\u{307}"),
+    (0x1e23, "h\u{307}"), (0x1e24, "h\u{323}"), (0x1e25, "h\u{323}"), (0x1e26, "h\u{308}"),
+    (0x1e27, "h\u{308}"), (0x1e28, "h\u{327}"), (0x1e29, "h\u{327}"), (0x1e2a, "h\u{32e}"),
+    (0x1e2b, "h\u{32e}"), (0x1e2c, "i\u{330}"), (0x1e2d, "i\u{330}"),
+    (0x1e2e, "i\u{308}\u{301}"), (0x1e2f, "i\u{308}\u{301}"), (0x1e30, "k\u{301}"),
+    (0x1e31, "k\u{301}"), (0x1e32, "k\u{323}"), (0x1e33, "k\u{323}"), (0x1e34, "k\u{331}"),
+    (0x1e35, "k\u{331}"), (0x1e36, "l\u{323}"), (0x1e37, "l\u{323}"),
+    (0x1e38, "l\u{323}\u{304}"), (0x1e39, "l\u{323}\u{304}"), (0x1e3a, "l\u{331}"),
+    (0x1e3b, "l\u{331}"), (0x1e3c, "l\u{32d}"), (0x1e3d, "l\u{32d}"), (0x1e3e, "m\u{301}"),
+    (0x1e3f, "m\u{301}"), (0x1e40, "m\u{307}"), (0x1e41, "m\u{307}"), (0x1e42, "m\u{323}"),
+    (0x1e43, "m\u{323}"), (0x1e44, "n\u{307}"), (0x1e45, "n\u{307}"), (0x1e46, "n\u{323}"),
+    (0x1e47, "n\u{323}"), (0x1e48, "n\u{331}"), (0x1e49, "n\u{331}"), (0x1e4a, "n\u{32d}"),
+    (0x1e4b, "n\u{32d}"), (0x1e4c, "o\u{303}\u{301}"), (0x1e4d, "o\u{303}\u{301}"),
+    (0x1e4e, "o\u{303}\u{308}"), (0x1e4f, "o\u{303}\u{308}"), (0x1e50, "o\u{304}\u{300}"),
+    (0x1e51, "o\u{304}\u{300}"), (0x1e52, "o\u{304}\u{301}"), (0x1e53, "o\u{304}\u{301}"),
+    (0x1e54, "p\u{301}"), (0x1e55, "p\u{301}"), (0x1e56, "p\u{307}"), (0x1e57, "p\u{307}"),
+    (0x1e58, "r\u{307}"), (0x1e59, "r\u{307}"), (0x1e5a, "r\u{323}"), (0x1e5b, "r\u{323}"),
+    (0x1e5c, "r\u{323}\u{304}"), (0x1e5d, "r\u{323}\u{304}"), (0x1e5e, "r\u{331}"),
+    (0x1e5f, "r\u{331}"), (0x1e60, "s\u{307}"), (0x1e61, "s\u{307}"), (0x1e62, "s\u{323}"),
+    (0x1e63, "s\u{323}"), (0x1e64, "s\u{301}\u{307}"), (0x1e65, "s\u{301}\u{307}"),
+    (0x1e66, "s\u{30c}\u{307}"), (0x1e67, "s\u{30c}\u{307}"), (0x1e68, "s\u{323}\u{307}"),
+    (0x1e69, "s\u{323}\u{307}"), (0x1e6a, "t\u{307}"), (0x1e6b, "t\u{307}"),
+    (0x1e6c, "t\u{323}"), (0x1e6d, "t\u{323}"), (0x1e6e, "t\u{331}"), (0x1e6f, "t\u{331}"),
+    (0x1e70, "t\u{32d}"), (0x1e71, "t\u{32d}"), (0x1e72, "u\u{324}"), (0x1e73, "u\u{324}"),
+    (0x1e74, "u\u{330}"), (0x1e75, "u\u{330}"), (0x1e76, "u\u{32d}"), (0x1e77, "u\u{32d}"),
+    (0x1e78, "u\u{303}\u{301}"), (0x1e79, "u\u{303}\u{301}"), (0x1e7a, "u\u{304}\u{308}"),
+    (0x1e7b, "u\u{304}\u{308}"), (0x1e7c, "v\u{303}"), (0x1e7d, "v\u{303}"),
+    (0x1e7e, "v\u{323}"), (0x1e7f, "v\u{323}"), (0x1e80, "w\u{300}"), (0x1e81, "w\u{300}"),
+    (0x1e82, "w\u{301}"), (0x1e83, "w\u{301}"), (0x1e84, "w\u{308}"), (0x1e85, "w\u{308}"),
+    (0x1e86, "w\u{307}"), (0x1e87, "w\u{307}"), (0x1e88, "w\u{323}"), (0x1e89, "w\u{323}"),
+    (0x1e8a, "x\u{307}"), (0x1e8b, "x\u{307}"), (0x1e8c, "x\u{308}"), (0x1e8d, "x\u{308}"),
+    (0x1e8e, "y\u{307}"), (0x1e8f, "y\u{307}"), (0x1e90, "z\u{302}"), (0x1e91, "z\u{302}"),
+    (0x1e92, "z\u{323}"), (0x1e93, "z\u{323}"), (0x1e94, "z\u{331}"), (0x1e95, "z\u{331}"),
+    (0x1e96, "h\u{331}"), (0x1e97, "t\u{308}"), (0x1e98, "w\u{30a}"), (0x1e99, "y\u{30a}"),
+    (0x1e9a, "a\u{2be}"), (0x1e9b, "s\u{307}"), (0x1e9e, "ss"), (0x1ea0, "a\u{323}"),
+    (0x1ea1, "a\u{323}"), (0x1ea2, "a\u{309}"), (0x1ea3, "a\u{309}"),
+    (0x1ea4, "a\u{302}\u{301}"), (0x1ea5, "a\u{302}\u{301}"), (0x1ea6, "a\u{302}\u{300}"),
+    (0x1ea7, "a\u{302}\u{300}"), (0x1ea8, "a\u{302}\u{309}"), (0x1ea9, "a\u{302}\u{309}"),
+    (0x1eaa, "a\u{302}\u{303}"), (0x1eab, "a\u{302}\u{303}"), (0x1eac, "a\u{323}\u{302}"),
+    (0x1ead, "a\u{323}\u{302}"), (0x1eae, "a\u{306}\u{301}"), (0x1eaf, "a\u{306}\u{301}"),
+    (0x1eb0, "a\u{306}\u{300}"), (0x1eb1, "a\u{306}\u{300}"), (0x1eb2, "a\u{306}\u{309}"),
+    (0x1eb3, "a\u{306}\u{309}"), (0x1eb4, "a\u{306}\u{303}"), (0x1eb5, "a\u{306}\u{303}"),
+    (0x1eb6, "a\u{323}\u{306}"), (0x1eb7, "a\u{323}\u{306}"), (0x1eb8, "e\u{323}"),
+    (0x1eb9, "e\u{323}"), (0x1eba, "e\u{309}"), (0x1ebb, "e\u{309}"), (0x1ebc, "e\u{303}"),
+    (0x1ebd, "e\u{303}"), (0x1ebe, "e\u{302}\u{301}"), (0x1ebf, "e\u{302}\u{301}"),
+    (0x1ec0, "e\u{302}\u{300}"), (0x1ec1, "e\u{302}\u{300}"), (0x1ec2, "e\u{302}\u{309}"),
+    (0x1ec3, "e\u{302}\u{309}"), (0x1ec4, "e\u{302}\u{303}"), (0x1ec5, "e\u{302}\u{303}"),
+    (0x1ec6, "e\u{323}\u{302}"), (0x1ec7, "e\u{323}\u{302}"), (0x1ec8, "i\u{309}"),
+    (0x1ec9, "i\u{309}"), (0x1eca, "i\u{323}"), (0x1ecb, "i\u{323}"), (0x1ecc, "o\u{323}"),
+    (0x1ecd, "o\u{323}"), (0x1ece, "o\u{309}"), (0x1ecf, "o\u{309}"),
+    (0x1ed0, "o\u{302}\u{301}"), (0x1ed1, "o\u{302}\u{301}"), (0x1ed2, "o\u{302}\u{300}"),
+    (0x1ed3, "o\u{302}\u{300}"), (0x1ed4, "o\u{302}\u{309}"), (0x1ed5, "o\u{302}\u{309}"),
+    (0x1ed6, "o\u{302}\u{303}"), (0x1ed7, "o\u{302}\u{303}"), (0x1ed8, "o\u{323}\u{302}"),
+    (0x1ed9, "o\u{323}\u{302}"), (0x1eda, "o\u{31b}\u{301}"), (0x1edb, "o\u{31b}\u{301}"),
+    (0x1edc, "o\u{31b}\u{300}"), (0x1edd, "o\u{31b}\u{300}"), (0x1ede, "o\u{31b}\u{309}"),
+    (0x1edf, "o\u{31b}\u{309}"), (0x1ee0, "o\u{31b}\u{303}"), (0x1ee1, "o\u{31b}\u{303}"),
+    (0x1ee2, "o\u{31b}\u{323}"), (0x1ee3, "o\u{31b}\u{323}"), (0x1ee4, "u\u{323}"),
+    (0x1ee5, "u\u{323}"), (0x1ee6, "u\u{309}"), (0x1ee7, "u\u{309}"),
+    (0x1ee8, "u\u{31b}\u{301}"), (0x1ee9, "u\u{31b}\u{301}"), (0x1eea, "u\u{31b}\u{300}"),
+    (0x1eeb, "u\u{31b}\u{300}"), (0x1eec, "u\u{31b}\u{309}"), (0x1eed, "u\u{31b}\u{309}"),
+    (0x1eee, "u\u{31b}\u{303}"), (0x1eef, "u\u{31b}\u{303}"), (0x1ef0, "u\u{31b}\u{323}"),
+    (0x1ef1, "u\u{31b}\u{323}"), (0x1ef2, "y\u{300}"), (0x1ef3, "y\u{300}"),
+    (0x1ef4, "y\u{323}"), (0x1ef5, "y\u{323}"), (0x1ef6, "y\u{309}"), (0x1ef7, "y\u{309}"),
+    (0x1ef8, "y\u{303}"), (0x1ef9, "y\u{303}"), (0x1efa, "\u{1efb}"), (0x1efc, "\u{1efd}"),
+    (0x1efe, "\u{1eff}"), (0x1f00, "\u{3b1}\u{313}"), (0x1f01, "\u{3b1}\u{314}"),
+    (0x1f02, "\u{3b1}\u{313}\u{300}"), (0x1f03, "\u{3b1}\u{314}\u{300}"),
+    (0x1f04, "\u{3b1}\u{313}\u{301}"), (0x1f05, "\u{3b1}\u{314}\u{301}"),
+    (0x1f06, "\u{3b1}\u{313}\u{342}"), (0x1f07, "\u{3b1}\u{314}\u{342}"),
+    (0x1f08, "\u{3b1}\u{313}"), (0x1f09, "\u{3b1}\u{314}"), (0x1f0a, "\u{3b1}\u{313}\u{300}"),
+    (0x1f0b, "\u{3b1}\u{314}\u{300}"), (0x1f0c, "\u{3b1}\u{313}\u{301}"),
+    (0x1f0d, "\u{3b1}\u{314}\u{301}"), (0x1f0e, "\u{3b1}\u{313}\u{342}"),
+    (0x1f0f, "\u{3b1}\u{314}\u{342}"), (0x1f10, "\u{3b5}\u{313}"), (0x1f11, "\u{3b5}\u{314}"),
+    (0x1f12, "\u{3b5}\u{313}\u{300}"), (0x1f13, "\u{3b5}\u{314}\u{300}"),
+    (0x1f14, "\u{3b5}\u{313}\u{301}"), (0x1f15, "\u{3b5}\u{314}\u{301}"),
+    (0x1f18, "\u{3b5}\u{313}"), (0x1f19, "\u{3b5}\u{314}"), (0x1f1a, "\u{3b5}\u{313}\u{300}"),
+    (0x1f1b, "\u{3b5}\u{314}\u{300}"), (0x1f1c, "\u{3b5}\u{313}\u{301}"),
+    (0x1f1d, "\u{3b5}\u{314}\u{301}"), (0x1f20, "\u{3b7}\u{313}"), (0x1f21, "\u{3b7}\u{314}"),
+    (0x1f22, "\u{3b7}\u{313}\u{300}"), (0x1f23, "\u{3b7}\u{314}\u{300}"),
+    (0x1f24, "\u{3b7}\u{313}\u{301}"), (0x1f25, "\u{3b7}\u{314}\u{301}"),
+    (0x1f26, "\u{3b7}\u{313}\u{342}"), (0x1f27, "\u{3b7}\u{314}\u{342}"),
+    (0x1f28, "\u{3b7}\u{313}"), (0x1f29, "\u{3b7}\u{314}"), (0x1f2a, "\u{3b7}\u{313}\u{300}"),
+    (0x1f2b, "\u{3b7}\u{314}\u{300}"), (0x1f2c, "\u{3b7}\u{313}\u{301}"),
+    (0x1f2d, "\u{3b7}\u{314}\u{301}"), (0x1f2e, "\u{3b7}\u{313}\u{342}"),
+    (0x1f2f, "\u{3b7}\u{314}\u{342}"), (0x1f30, "\u{3b9}\u{313}"), (0x1f31, "\u{3b9}\u{314}"),
+    (0x1f32, "\u{3b9}\u{313}\u{300}"), (0x1f33, "\u{3b9}\u{314}\u{300}"),
+    (0x1f34, "\u{3b9}\u{313}\u{301}"), (0x1f35, "\u{3b9}\u{314}\u{301}"),
+    (0x1f36, "\u{3b9}\u{313}\u{342}"), (0x1f37, "\u{3b9}\u{314}\u{342}"),
+    (0x1f38, "\u{3b9}\u{313}"), (0x1f39, "\u{3b9}\u{314}"), (0x1f3a, "\u{3b9}\u{313}\u{300}"),
+    (0x1f3b, "\u{3b9}\u{314}\u{300}"), (0x1f3c, "\u{3b9}\u{313}\u{301}"),
+    (0x1f3d, "\u{3b9}\u{314}\u{301}"), (0x1f3e, "\u{3b9}\u{313}\u{342}"),
+    (0x1f3f, "\u{3b9}\u{314}\u{342}"), (0x1f40, "\u{3bf}\u{313}"), (0x1f41, "\u{3bf}\u{314}"),
+    (0x1f42, "\u{3bf}\u{313}\u{300}"), (0x1f43, "\u{3bf}\u{314}\u{300}"),
+    (0x1f44, "\u{3bf}\u{313}\u{301}"), (0x1f45, "\u{3bf}\u{314}\u{301}"),
+    (0x1f48, "\u{3bf}\u{313}"), (0x1f49, "\u{3bf}\u{314}"), (0x1f4a, "\u{3bf}\u{313}\u{300}"),
+    (0x1f4b, "\u{3bf}\u{314}\u{300}"), (0x1f4c, "\u{3bf}\u{313}\u{301}"),
+    (0x1f4d, "\u{3bf}\u{314}\u{301}"), (0x1f50, "\u{3c5}\u{313}"), (0x1f51, "\u{3c5}\u{314}"),
+    (0x1f52, "\u{3c5}\u{313}\u{300}"), (0x1f53, "\u{3c5}\u{314}\u{300}"),
+    (0x1f54, "\u{3c5}\u{313}\u{301}"), (0x1f55, "\u{3c5}\u{314}\u{301}"),
+    (0x1f56, "\u{3c5}\u{313}\u{342}"), (0x1f57, "\u{3c5}\u{314}\u{342}"),
+    (0x1f59, "\u{3c5}\u{314}"), (0x1f5b, "\u{3c5}\u{314}\u{300}"),
+    (0x1f5d, "\u{3c5}\u{314}\u{301}"), (0x1f5f, "\u{3c5}\u{314}\u{342}"),
+    (0x1f60, "\u{3c9}\u{313}"), (0x1f61, "\u{3c9}\u{314}"), (0x1f62, "\u{3c9}\u{313}\u{300}"),
+    (0x1f63, "\u{3c9}\u{314}\u{300}"), (0x1f64, "\u{3c9}\u{313}\u{301}"),
+    (0x1f65, "\u{3c9}\u{314}\u{301}"), (0x1f66, "\u{3c9}\u{313}\u{342}"),
+    (0x1f67, "\u{3c9}\u{314}\u{342}"), (0x1f68, "\u{3c9}\u{313}"), (0x1f69, "\u{3c9}\u{314}"),
+    (0x1f6a, "\u{3c9}\u{313}\u{300}"), (0x1f6b, "\u{3c9}\u{314}\u{300}"),
+    (0x1f6c, "\u{3c9}\u{313}\u{301}"), (0x1f6d, "\u{3c9}\u{314}\u{301}"),
+    (0x1f6e, "\u{3c9}\u{313}\u{342}"), (0x1f6f, "\u{3c9}\u{314}\u{342}"),
+    (0x1f70, "\u{3b1}\u{300}"), (0x1f71, "\u{3b1}\u{301}"), (0x1f72, "\u{3b5}\u{300}"),
+    (0x1f73, "\u{3b5}\u{301}"), (0x1f74, "\u{3b7}\u{300}"), (0x1f75, "\u{3b7}\u{301}"),
+    (0x1f76, "\u{3b9}\u{300}"), (0x1f77, "\u{3b9}\u{301}"), (0x1f78, "\u{3bf}\u{300}"),
+    (0x1f79, "\u{3bf}\u{301}"), (0x1f7a, "\u{3c5}\u{300}"), (0x1f7b, "\u{3c5}\u{301}"),
+    (0x1f7c, "\u{3c9}\u{300}"), (0x1f7d, "\u{3c9}\u{301}"), (0x1f80, "\u{3b1}\u{313}\u{3b9}"),
+    (0x1f81, "\u{3b1}\u{314}\u{3b9}"), (0x1f82, "\u{3b1}\u{313}\u{300}\u{3b9}"),
+    (0x1f83, "\u{3b1}\u{314}\u{300}\u{3b9}"), (0x1f84, "\u{3b1}\u{313}\u{301}\u{3b9}"),
+    (0x1f85, "\u{3b1}\u{314}\u{301}\u{3b9}"), (0x1f86, "\u{3b1}\u{313}\u{342}\u{3b9}"),
+    (0x1f87, "\u{3b1}\u{314}\u{342}\u{3b9}"), (0x1f88, "\u{3b1}\u{313}\u{3b9}"),
+    (0x1f89, "\u{3b1}\u{314}\u{3b9}"), (0x1f8a, "\u{3b1}\u{313}\u{300}\u{3b9}"),
+    (0x1f8b, "\u{3b1}\u{314}\u{300}\u{3b9}"), (0x1f8c, "\u{3b1}\u{313}\u{301}\u{3b9}"),
+    (0x1f8d, "\u{3b1}\u{314}\u{301}\u{3b9}"), (0x1f8e, "\u{3b1}\u{313}\u{342}\u{3b9}"),
+    (0x1f8f, "\u{3b1}\u{314}\u{342}\u{3b9}"), (0x1f90, "\u{3b7}\u{313}\u{3b9}"),
+    (0x1f91, "\u{3b7}\u{314}\u{3b9}"), (0x1f92, "\u{3b7}\u{313}\u{300}\u{3b9}"),
+    (0x1f93, "\u{3b7}\u{314}\u{300}\u{3b9}"), (0x1f94, "\u{3b7}\u{313}\u{301}\u{3b9}"),
+    (0x1f95, "\u{3b7}\u{314}\u{301}\u{3b9}"), (0x1f96, "\u{3b7}\u{313}\u{342}\u{3b9}"),
+    (0x1f97, "\u{3b7}\u{314}\u{342}\u{3b9}"), (0x1f98, "\u{3b7}\u{313}\u{3b9}"),
+    (0x1f99, "\u{3b7}\u{314}\u{3b9}"), (0x1f9a, "\u{3b7}\u{313}\u{300}\u{3b9}"),
+    (0x1f9b, "\u{3b7}\u{314}\u{300}\u{3b9}"), (0x1f9c, "\u{3b7}\u{313}\u{301}\u{3b9}"),
+    (0x1f9d, "\u{3b7}\u{314}\u{301}\u{3b9}"), (0x1f9e, "\u{3b7}\u{313}\u{342}\u{3b9}"),
+    (0x1f9f, "\u{3b7}\u{314}\u{342}\u{3b9}"), (0x1fa0, "\u{3c9}\u{313}\u{3b9}"),
+    (0x1fa1, "\u{3c9}\u{314}\u{3b9}"), (0x1fa2, "\u{3c9}\u{313}\u{300}\u{3b9}"),
+    (0x1fa3, "\u{3c9}\u{314}\u{300}\u{3b9}"), (0x1fa4, "\u{3c9}\u{313}\u{301}\u{3b9}"),
+    (0x1fa5, "\u{3c9}\u{314}\u{301}\u{3b9}"), (0x1fa6, "\u{3c9}\u{313}\u{342}\u{3b9}"),
+    (0x1fa7, "\u{3c9}\u{314}\u{342}\u{3b9}"), (0x1fa8, "\u{3c9}\u{313}\u{3b9}"),
+    (0x1fa9, "\u{3c9}\u{314}\u{3b9}"), (0x1faa, "\u{3c9}\u{313}\u{300}\u{3b9}"),
+    (0x1fab, "\u{3c9}\u{314}\u{300}\u{3b9}"), (0x1fac, "\u{3c9}\u{313}\u{301}\u{3b9}"),
+    (0x1fad, "\u{3c9}\u{314}\u{301}\u{3b9}"), (0x1fae, "\u{3c9}\u{313}\u{342}\u{3b9}"),
+    (0x1faf, "\u{3c9}\u{314}\u{342}\u{3b9}"), (0x1fb0, "\u{3b1}\u{306}"),
+    (0x1fb1, "\u{3b1}\u{304}"), (0x1fb2, "\u{3b1}\u{300}\u{3b9}"), (0x1fb3, "\u{3b1}\u{3b9}"),
+    (0x1fb4, "\u{3b1}\u{301}\u{3b9}"), (0x1fb6, "\u{3b1}\u{342}"),
+    (0x1fb7, "\u{3b1}\u{342}\u{3b9}"), (0x1fb8, "\u{3b1}\u{306}"), (0x1fb9, "\u{3b1}\u{304}"),
+    (0x1fba, "\u{3b1}\u{300}"), (0x1fbb, "\u{3b1}\u{301}"), (0x1fbc, "\u{3b1}\u{3b9}"),
+    (0x1fbe, "\u{3b9}"), (0x1fc1, "\u{a8}\u{342}"), (0x1fc2, "\u{3b7}\u{300}\u{3b9}"),
+    (0x1fc3, "\u{3b7}\u{3b9}"), (0x1fc4, "\u{3b7}\u{301}\u{3b9}"), (0x1fc6, "\u{3b7}\u{342}"),
+    (0x1fc7, "\u{3b7}\u{342}\u{3b9}"), (0x1fc8, "\u{3b5}\u{300}"), (0x1fc9, "\u{3b5}\u{301}"),
+    (0x1fca, "\u{3b7}\u{300}"), (0x1fcb, "\u{3b7}\u{301}"), (0x1fcc, "\u{3b7}\u{3b9}"),
+    (0x1fcd, "\u{1fbf}\u{300}"), (0x1fce, "\u{1fbf}\u{301}"), (0x1fcf, "\u{1fbf}\u{342}"),
+    (0x1fd0, "\u{3b9}\u{306}"), (0x1fd1, "\u{3b9}\u{304}"), (0x1fd2, "\u{3b9}\u{308}\u{300}"),
+    (0x1fd3, "\u{3b9}\u{308}\u{301}"), (0x1fd6, "\u{3b9}\u{342}"),
+    (0x1fd7, "\u{3b9}\u{308}\u{342}"), (0x1fd8, "\u{3b9}\u{306}"), (0x1fd9, "\u{3b9}\u{304}"),
+    (0x1fda, "\u{3b9}\u{300}"), (0x1fdb, "\u{3b9}\u{301}"), (0x1fdd, "\u{1ffe}\u{300}"),
+    (0x1fde, "\u{1ffe}\u{301}"), (0x1fdf, "\u{1ffe}\u{342}"), (0x1fe0, "\u{3c5}\u{306}"),
+    (0x1fe1, "\u{3c5}\u{304}"), (0x1fe2, "\u{3c5}\u{308}\u{300}"),
+    (0x1fe3, "\u{3c5}\u{308}\u{301}"), (0x1fe4, "\u{3c1}\u{313}"), (0x1fe5, "\u{3c1}\u{314}"),
+    (0x1fe6, "\u{3c5}\u{342}"), (0x1fe7, "\u{3c5}\u{308}\u{342}"), (0x1fe8, "\u{3c5}\u{306}"),
+    (0x1fe9, "\u{3c5}\u{304}"), (0x1fea, "\u{3c5}\u{300}"), (0x1feb, "\u{3c5}\u{301}"),
+    (0x1fec, "\u{3c1}\u{314}"), (0x1fed, "\u{a8}\u{300}"), (0x1fee, "\u{a8}\u{301}"),
+    (0x1fef, "`"), (0x1ff2, "\u{3c9}\u{300}\u{3b9}"), (0x1ff3, "\u{3c9}\u{3b9}"),
+    (0x1ff4, "\u{3c9}\u{301}\u{3b9}"), (0x1ff6, "\u{3c9}\u{342}"),
+    (0x1ff7, "\u{3c9}\u{342}\u{3b9}"), (0x1ff8, "\u{3bf}\u{300}"), (0x1ff9, "\u{3bf}\u{301}"),
+    (0x1ffa, "\u{3c9}\u{300}"), (0x1ffb, "\u{3c9}\u{301}"), (0x1ffc, "\u{3c9}\u{3b9}"),
+    (0x1ffd, "\u{b4}"), (0x2000, "\u{2002}"), (0x2001, "\u{2003}"), (0x2126, "\u{3c9}"),
+    (0x212a, "k"), (0x212b, "a\u{30a}"), (0x2132, "\u{214e}"), (0x2160, "\u{2170}"),
+    (0x2161, "\u{2171}"), (0x2162, "\u{2172}"), (0x2163, "\u{2173}"), (0x2164, "\u{2174}"),
+    (0x2165, "\u{2175}"), (0x2166, "\u{2176}"), (0x2167, "\u{2177}"), (0x2168, "\u{2178}"),
+    (0x2169, "\u{2179}"), (0x216a, "\u{217a}"), (0x216b, "\u{217b}"), (0x216c, "\u{217c}"),
+    (0x216d, "\u{217d}"), (0x216e, "\u{217e}"), (0x216f, "\u{217f}"), (0x2183, "\u{2184}"),
+    (0x219a, "\u{2190}\u{338}"), (0x219b, "\u{2192}\u{338}"), (0x21ae, "\u{2194}\u{338}"),
+    (0x21cd, "\u{21d0}\u{338}"), (0x21ce, "\u{21d4}\u{338}"), (0x21cf, "\u{21d2}\u{338}"),
+    (0x2204, "\u{2203}\u{338}"), (0x2209, "\u{2208}\u{338}"), (0x220c, "\u{220b}\u{338}"),
+    (0x2224, "\u{2223}\u{338}"), (0x2226, "\u{2225}\u{338}"), (0x2241, "\u{223c}\u{338}"),
+    (0x2244, "\u{2243}\u{338}"), (0x2247, "\u{2245}\u{338}"), (0x2249, "\u{2248}\u{338}"),
+    (0x2260, "=\u{338}"), (0x2262, "\u{2261}\u{338}"), (0x226d, "\u{224d}\u{338}"),
+    (0x226e, "<\u{338}"), (0x226f, ">\u{338}"), (0x2270, "\u{2264}\u{338}"),
+    (0x2271, "\u{2265}\u{338}"), (0x2274, "\u{2272}\u{338}"), (0x2275, "\u{2273}\u{338}"),
+    (0x2278, "\u{2276}\u{338}"), (0x2279, "\u{2277}\u{338}"), (0x2280, "\u{227a}\u{338}"),
+    (0x2281, "\u{227b}\u{338}"), (0x2284, "\u{2282}\u{338}"), (0x2285, "\u{2283}\u{338}"),
+    (0x2288, "\u{2286}\u{338}"), (0x2289, "\u{2287}\u{338}"), (0x22ac, "\u{22a2}\u{338}"),
+    (0x22ad, "\u{22a8}\u{338}"), (0x22ae, "\u{22a9}\u{338}"), (0x22af, "\u{22ab}\u{338}"),
+    (0x22e0, "\u{227c}\u{338}"), (0x22e1, "\u{227d}\u{338}"), (0x22e2, "\u{2291}\u{338}"),
+    (0x22e3, "\u{2292}\u{338}"), (0x22ea, "\u{22b2}\u{338}"), (0x22eb, "\u{22b3}\u{338}"),
+    (0x22ec, "\u{22b4}\u{338}"), (0x22ed, "\u{22b5}\u{338}"), (0x2329, "\u{3008}"),
+    (0x232a, "\u{3009}"), (0x24b6, "\u{24d0}"), (0x24b7, "\u{24d1}"), (0x24b8, "\u{24d2}"),
+    (0x24b9, "\u{24d3}"), (0x24ba, "\u{24d4}"), (0x24bb, "\u{24d5}"), (0x24bc, "\u{24d6}"),
+    (0x24bd, "\u{24d7}"), (0x24be, "\u{24d8}"), (0x24bf, "\u{24d9}"), (0x24c0, "\u{24da}"),
+    (0x24c1, "\u{24db}"), (0x24c2, "\u{24dc}"), (0x24c3, "\u{24dd}"), (0x24c4, "\u{24de}"),
+    (0x24c5, "\u{24df}"), (0x24c6, "\u{24e0}"), (0x24c7, "\u{24e1}"), (0x24c8, "\u{24e2}"),
+    (0x24c9, "\u{24e3}"), (0x24ca, "\u{24e4}"), (0x24cb, "\u{24e5}"), (0x24cc, "\u{24e6}"),
+    (0x24cd, "\u{24e7}"), (0x24ce, "\u{24e8}"), (0x24cf, "\u{24e9}"),
+    (0x2adc, "\u{2add}\u{338}"), (0x2c00, "\u{2c30}"), (0x2c01, "\u{2c31}"),
+    (0x2c02, "\u{2c32}"), (0x2c03, "\u{2c33}"), (0x2c04, "\u{2c34}"), (0x2c05, "\u{2c35}"),
+    (0x2c06, "\u{2c36}"), (0x2c07, "\u{2c37}"), (0x2c08, "\u{2c38}"), (0x2c09, "\u{2c39}"),
+    (0x2c0a, "\u{2c3a}"), (0x2c0b, "\u{2c3b}"), (0x2c0c, "\u{2c3c}"), (0x2c0d, "\u{2c3d}"),
+    (0x2c0e, "\u{2c3e}"), (0x2c0f, "\u{2c3f}"), (0x2c10, "\u{2c40}"), (0x2c11, "\u{2c41}"),
+    (0x2c12, "\u{2c42}"), (0x2c13, "\u{2c43}"), (0x2c14, "\u{2c44}"), (0x2c15, "\u{2c45}"),
+    (0x2c16, "\u{2c46}"), (0x2c17, "\u{2c47}"), (0x2c18, "\u{2c48}"), (0x2c19, "\u{2c49}"),
+    (0x2c1a, "\u{2c4a}"), (0x2c1b, "\u{2c4b}"), (0x2c1c, "\u{2c4c}"), (0x2c1d, "\u{2c4d}"),
+    (0x2c1e, "\u{2c4e}"), (0x2c1f, "\u{2c4f}"), (0x2c20, "\u{2c50}"), (0x2c21, "\u{2c51}"),
+    (0x2c22, "\u{2c52}"), (0x2c23, "\u{2c53}"), (0x2c24, "\u{2c54}"), (0x2c25, "\u{2c55}"),
+    (0x2c26, "\u{2c56}"), (0x2c27, "\u{2c57}"), (0x2c28, "\u{2c58}"), (0x2c29, "\u{2c59}"),
+    (0x2c2a, "\u{2c5a}"), (0x2c2b, "\u{2c5b}"), (0x2c2c, "\u{2c5c}"), (0x2c2d, "\u{2c5d}"),
+    (0x2c2e, "\u{2c5e}"), (0x2c60, "\u{2c61}"), (0x2c62, "\u{26b}"), (0x2c63, "\u{1d7d}"),
+    (0x2c64, "\u{27d}"), (0x2c67, "\u{2c68}"), (0x2c69, "\u{2c6a}"), (0x2c6b, "\u{2c6c}"),
+    (0x2c6d, "\u{251}"), (0x2c6e, "\u{271}"), (0x2c6f, "\u{250}"), (0x2c70, "\u{252}"),
+    (0x2c72, "\u{2c73}"), (0x2c75, "\u{2c76}"), (0x2c7e, "\u{23f}"), (0x2c7f, "\u{240}"),
+    (0x2c80, "\u{2c81}"), (0x2c82, "\u{2c83}"), (0x2c84, "\u{2c85}"), (0x2c86, "\u{2c87}"),
+    (0x2c88, "\u{2c89}"), (0x2c8a, "\u{2c8b}"), (0x2c8c, "\u{2c8d}"), (0x2c8e, "\u{2c8f}"),
+    (0x2c90, "\u{2c91}"), (0x2c92, "\u{2c93}"), (0x2c94, "\u{2c95}"), (0x2c96, "\u{2c97}"),
+    (0x2c98, "\u{2c99}"), (0x2c9a, "\u{2c9b}"), (0x2c9c, "\u{2c9d}"), (0x2c9e, "\u{2c9f}"),
+    (0x2ca0, "\u{2ca1}"), (0x2ca2, "\u{2ca3}"), (0x2ca4, "\u{2ca5}"), (0x2ca6, "\u{2ca7}"),
+    (0x2ca8, "\u{2ca9}"), (0x2caa, "\u{2cab}"), (0x2cac, "\u{2cad}"), (0x2cae, "\u{2caf}"),
+    (0x2cb0, "\u{2cb1}"), (0x2cb2, "\u{2cb3}"), (0x2cb4, "\u{2cb5}"), (0x2cb6, "\u{2cb7}"),
+    (0x2cb8, "\u{2cb9}"), (0x2cba, "\u{2cbb}"), (0x2cbc, "\u{2cbd}"), (0x2cbe, "\u{2cbf}"),
+    (0x2cc0, "\u{2cc1}"), (0x2cc2, "\u{2cc3}"), (0x2cc4, "\u{2cc5}"), (0x2cc6, "\u{2cc7}"),
+    (0x2cc8, "\u{2cc9}"), (0x2cca, "\u{2ccb}"), (0x2ccc, "\u{2ccd}"), (0x2cce, "\u{2ccf}"),
+    (0x2cd0, "\u{2cd1}"), (0x2cd2, "\u{2cd3}"), (0x2cd4, "\u{2cd5}"), (0x2cd6, "\u{2cd7}"),
+    (0x2cd8, "\u{2cd9}"), (0x2cda, "\u{2cdb}"), (0x2cdc, "\u{2cdd}"), (0x2cde, "\u{2cdf}"),
+    (0x2ce0, "\u{2ce1}"), (0x2ce2, "\u{2ce3}"), (0x2ceb, "\u{2cec}"), (0x2ced, "\u{2cee}"),
+    (0x2cf2, "\u{2cf3}"), (0x304c, "\u{304b}\u{3099}"), (0x304e, "\u{304d}\u{3099}"),
+    (0x3050, "\u{304f}\u{3099}"), (0x3052, "\u{3051}\u{3099}"), (0x3054, "\u{3053}\u{3099}"),
+    (0x3056, "\u{3055}\u{3099}"), (0x3058, "\u{3057}\u{3099}"), (0x305a, "\u{3059}\u{3099}"),
+    (0x305c, "\u{305b}\u{3099}"), (0x305e, "\u{305d}\u{3099}"), (0x3060, "\u{305f}\u{3099}"),
+    (0x3062, "\u{3061}\u{3099}"), (0x3065, "\u{3064}\u{3099}"), (0x3067, "\u{3066}\u{3099}"),
+    (0x3069, "\u{3068}\u{3099}"), (0x3070, "\u{306f}\u{3099}"), (0x3071, "\u{306f}\u{309a}"),
+    (0x3073, "\u{3072}\u{3099}"), (0x3074, "\u{3072}\u{309a}"), (0x3076, "\u{3075}\u{3099}"),
+    (0x3077, "\u{3075}\u{309a}"), (0x3079, "\u{3078}\u{3099}"), (0x307a, "\u{3078}\u{309a}"),
+    (0x307c, "\u{307b}\u{3099}"), (0x307d, "\u{307b}\u{309a}"), (0x3094, "\u{3046}\u{3099}"),
+    (0x309e, "\u{309d}\u{3099}"), (0x30ac, "\u{30ab}\u{3099}"), (0x30ae, "\u{30ad}\u{3099}"),
+    (0x30b0, "\u{30af}\u{3099}"), (0x30b2, "\u{30b1}\u{3099}"), (0x30b4, "\u{30b3}\u{3099}"),
+    (0x30b6, "\u{30b5}\u{3099}"), (0x30b8, "\u{30b7}\u{3099}"), (0x30ba, "\u{30b9}\u{3099}"),
+    (0x30bc, "\u{30bb}\u{3099}"), (0x30be, "\u{30bd}\u{3099}"), (0x30c0, "\u{30bf}\u{3099}"),
+    (0x30c2, "\u{30c1}\u{3099}"), (0x30c5, "\u{30c4}\u{3099}"), (0x30c7, "\u{30c6}\u{3099}"),
+    (0x30c9, "\u{30c8}\u{3099}"), (0x30d0, "\u{30cf}\u{3099}"), (0x30d1, "\u{30cf}\u{309a}"),
+    (0x30d3, "\u{30d2}\u{3099}"), (0x30d4, "\u{30d2}\u{309a}"), (0x30d6, "\u{30d5}\u{3099}"),
+    (0x30d7, "\u{30d5}\u{309a}"), (0x30d9, "\u{30d8}\u{3099}"), (0x30da, "\u{30d8}\u{309a}"),
+    (0x30dc, "\u{30db}\u{3099}"), (0x30dd, "\u{30db}\u{309a}"), (0x30f4, "\u{30a6}\u{3099}"),
+    (0x30f7, "\u{30ef}\u{3099}"), (0x30f8, "\u{30f0}\u{3099}"), (0x30f9, "\u{30f1}\u{3099}"),
+    (0x30fa, "\u{30f2}\u{3099}"), (0x30fe, "\u{30fd}\u{3099}"), (0xa640, "\u{a641}"),
+    (0xa642, "\u{a643}"), (0xa644, "\u{a645}"), (0xa646, "\u{a647}"), (0xa648, "\u{a649}"),
+    (0xa64a, "\u{a64b}"), (0xa64c, "\u{a64d}"), (0xa64e, "\u{a64f}"), (0xa650, "\u{a651}"),
+    (0xa652, "\u{a653}"), (0xa654, "\u{a655}"), (0xa656, "\u{a657}"), (0xa658, "\u{a659}"),
+    (0xa65a, "\u{a65b}"), (0xa65c, "\u{a65d}"), (0xa65e, "\u{a65f}"), (0xa660, "\u{a661}"),
+    (0xa662, "\u{a663}"), (0xa664, "\u{a665}"), (0xa666, "\u{a667}"), (0xa668, "\u{a669}"),
+    (0xa66a, "\u{a66b}"), (0xa66c, "\u{a66d}"), (0xa680, "\u{a681}"), (0xa682, "\u{a683}"),
+    (0xa684, "\u{a685}"), (0xa686, "\u{a687}"), (0xa688, "\u{a689}"), (0xa68a, "\u{a68b}"),
+    (0xa68c, "\u{a68d}"), (0xa68e, "\u{a68f}"), (0xa690, "\u{a691}"), (0xa692, "\u{a693}"),
+    (0xa694, "\u{a695}"), (0xa696, "\u{a697}"), (0xa698, "\u{a699}"), (0xa69a, "\u{a69b}"),
+    (0xa722, "\u{a723}"), (0xa724, "\u{a725}"), (0xa726, "\u{a727}"), (0xa728, "\u{a729}"),
+    (0xa72a, "\u{a72b}"), (0xa72c, "\u{a72d}"), (0xa72e, "\u{a72f}"), (0xa732, "\u{a733}"),
+    (0xa734, "\u{a735}"), (0xa736, "\u{a737}"), (0xa738, "\u{a739}"), (0xa73a, "\u{a73b}"),
+    (0xa73c, "\u{a73d}"), (0xa73e, "\u{a73f}"), (0xa740, "\u{a741}"), (0xa742, "\u{a743}"),
+    (0xa744, "\u{a745}"), (0xa746, "\u{a747}"), (0xa748, "\u{a749}"), (0xa74a, "\u{a74b}"),
+    (0xa74c, "\u{a74d}"), (0xa74e, "\u{a74f}"), (0xa750, "\u{a751}"), (0xa752, "\u{a753}"),
+    (0xa754, "\u{a755}"), (0xa756, "\u{a757}"), (0xa758, "\u{a759}"), (0xa75a, "\u{a75b}"),
+    (0xa75c, "\u{a75d}"), (0xa75e, "\u{a75f}"), (0xa760, "\u{a761}"), (0xa762, "\u{a763}"),
+    (0xa764, "\u{a765}"), (0xa766, "\u{a767}"), (0xa768, "\u{a769}"), (0xa76a, "\u{a76b}"),
+    (0xa76c, "\u{a76d}"), (0xa76e, "\u{a76f}"), (0xa779, "\u{a77a}"), (0xa77b, "\u{a77c}"),
+    (0xa77d, "\u{1d79}"), (0xa77e, "\u{a77f}"), (0xa780, "\u{a781}"), (0xa782, "\u{a783}"),
+    (0xa784, "\u{a785}"), (0xa786, "\u{a787}"), (0xa78b, "\u{a78c}"), (0xa78d, "\u{265}"),
+    (0xa790, "\u{a791}"), (0xa792, "\u{a793}"), (0xa796, "\u{a797}"), (0xa798, "\u{a799}"),
+    (0xa79a, "\u{a79b}"), (0xa79c, "\u{a79d}"), (0xa79e, "\u{a79f}"), (0xa7a0, "\u{a7a1}"),
+    (0xa7a2, "\u{a7a3}"), (0xa7a4, "\u{a7a5}"), (0xa7a6, "\u{a7a7}"), (0xa7a8, "\u{a7a9}"),
+    (0xa7aa, "\u{266}"), (0xa7ab, "\u{25c}"), (0xa7ac, "\u{261}"), (0xa7ad, "\u{26c}"),
+    (0xa7ae, "\u{26a}"), (0xa7b0, "\u{29e}"), (0xa7b1, "\u{287}"), (0xa7b2, "\u{29d}"),
+    (0xa7b3, "\u{ab53}"), (0xa7b4, "\u{a7b5}"), (0xa7b6, "\u{a7b7}"), (0xa7b8, "\u{a7b9}"),
+    (0xa7ba, "\u{a7bb}"), (0xa7bc, "\u{a7bd}"), (0xa7be, "\u{a7bf}"), (0xa7c2, "\u{a7c3}"),
+    (0xa7c4, "\u{a794}"), (0xa7c5, "\u{282}"), (0xa7c6, "\u{1d8e}"), (0xab70, "\u{13a0}"),
+    (0xab71, "\u{13a1}"), (0xab72, "\u{13a2}"), (0xab73, "\u{13a3}"), (0xab74, "\u{13a4}"),
+    (0xab75, "\u{13a5}"), (0xab76, "\u{13a6}"), (0xab77, "\u{13a7}"), (0xab78, "\u{13a8}"),
+    (0xab79, "\u{13a9}"), (0xab7a, "\u{13aa}"), (0xab7b, "\u{13ab}"), (0xab7c, "\u{13ac}"),
+    (0xab7d, "\u{13ad}"), (0xab7e, "\u{13ae}"), (0xab7f, "\u{13af}"), (0xab80, "\u{13b0}"),
+    (0xab81, "\u{13b1}"), (0xab82, "\u{13b2}"), (0xab83, "\u{13b3}"), (0xab84, "\u{13b4}"),
+    (0xab85, "\u{13b5}"), (0xab86, "\u{13b6}"), (0xab87, "\u{13b7}"), (0xab88, "\u{13b8}"),
+    (0xab89, "\u{13b9}"), (0xab8a, "\u{13ba}"), (0xab8b, "\u{13bb}"), (0xab8c, "\u{13bc}"),
+    (0xab8d, "\u{13bd}"), (0xab8e, "\u{13be}"), (0xab8f, "\u{13bf}"), (0xab90, "\u{13c0}"),
+    (0xab91, "\u{13c1}"), (0xab92, "\u{13c2}"), (0xab93, "\u{13c3}"), (0xab94, "\u{13c4}"),
+    (0xab95, "\u{13c5}"), (0xab96, "\u{13c6}"), (0xab97, "\u{13c7}"), (0xab98, "\u{13c8}"),
+    (0xab99, "\u{13c9}"), (0xab9a, "\u{13ca}"), (0xab9b, "\u{13cb}"), (0xab9c, "\u{13cc}"),
+    (0xab9d, "\u{13cd}"), (0xab9e, "\u{13ce}"), (0xab9f, "\u{13cf}"), (0xaba0, "\u{13d0}"),
+    (0xaba1, "\u{13d1}"), (0xaba2, "\u{13d2}"), (0xaba3, "\u{13d3}"), (0xaba4, "\u{13d4}"),
+    (0xaba5, "\u{13d5}"), (0xaba6, "\u{13d6}"), (0xaba7, "\u{13d7}"), (0xaba8, "\u{13d8}"),
+    (0xaba9, "\u{13d9}"), (0xabaa, "\u{13da}"), (0xabab, "\u{13db}"), (0xabac, "\u{13dc}"),
+    (0xabad, "\u{13dd}"), (0xabae, "\u{13de}"), (0xabaf, "\u{13df}"), (0xabb0, "\u{13e0}"),
+    (0xabb1, "\u{13e1}"), (0xabb2, "\u{13e2}"), (0xabb3, "\u{13e3}"), (0xabb4, "\u{13e4}"),
+    (0xabb5, "\u{13e5}"), (0xabb6, "\u{13e6}"), (0xabb7, "\u{13e7}"), (0xabb8, "\u{13e8}"),
+    (0xabb9, "\u{13e9}"), (0xabba, "\u{13ea}"), (0xabbb, "\u{13eb}"), (0xabbc, "\u{13ec}"),
+    (0xabbd, "\u{13ed}"), (0xabbe, "\u{13ee}"), (0xabbf, "\u{13ef}"), (0xf900, "\u{8c48}"),
+    (0xf901, "\u{66f4}"), (0xf902, "\u{8eca}"), (0xf903, "\u{8cc8}"), (0xf904, "\u{6ed1}"),
+    (0xf905, "\u{4e32}"), (0xf906, "\u{53e5}"), (0xf907, "\u{9f9c}"), (0xf908, "\u{9f9c}"),
+    (0xf909, "\u{5951}"), (0xf90a, "\u{91d1}"), (0xf90b, "\u{5587}"), (0xf90c, "\u{5948}"),
+    (0xf90d, "\u{61f6}"), (0xf90e, "\u{7669}"), (0xf90f, "\u{7f85}"), (0xf910, "\u{863f}"),
+    (0xf911, "\u{87ba}"), (0xf912, "\u{88f8}"), (0xf913, "\u{908f}"), (0xf914, "\u{6a02}"),
+    (0xf915, "\u{6d1b}"), (0xf916, "\u{70d9}"), (0xf917, "\u{73de}"), (0xf918, "\u{843d}"),
+    (0xf919, "\u{916a}"), (0xf91a, "\u{99f1}"), (0xf91b, "\u{4e82}"), (0xf91c, "\u{5375}"),
+    (0xf91d, "\u{6b04}"), (0xf91e, "\u{721b}"), (0xf91f, "\u{862d}"), (0xf920, "\u{9e1e}"),
+    (0xf921, "\u{5d50}"), (0xf922, "\u{6feb}"), (0xf923, "\u{85cd}"), (0xf924, "\u{8964}"),
+    (0xf925, "\u{62c9}"), (0xf926, "\u{81d8}"), (0xf927, "\u{881f}"), (0xf928, "\u{5eca}"),
+    (0xf929, "\u{6717}"), (0xf92a, "\u{6d6a}"), (0xf92b, "\u{72fc}"), (0xf92c, "\u{90ce}"),
+    (0xf92d, "\u{4f86}"), (0xf92e, "\u{51b7}"), (0xf92f, "\u{52de}"), (0xf930, "\u{64c4}"),
+    (0xf931, "\u{6ad3}"), (0xf932, "\u{7210}"), (0xf933, "\u{76e7}"), (0xf934, "\u{8001}"),
+    (0xf935, "\u{8606}"), (0xf936, "\u{865c}"), (0xf937, "\u{8def}"), (0xf938, "\u{9732}"),
+    (0xf939, "\u{9b6f}"), (0xf93a, "\u{9dfa}"), (0xf93b, "\u{788c}"), (0xf93c, "\u{797f}"),
+    (0xf93d, "\u{7da0}"), (0xf93e, "\u{83c9}"), (0xf93f, "\u{9304}"), (0xf940, "\u{9e7f}"),
+    (0xf941, "\u{8ad6}"), (0xf942, "\u{58df}"), (0xf943, "\u{5f04}"), (0xf944, "\u{7c60}"),
+    (0xf945, "\u{807e}"), (0xf946, "\u{7262}"), (0xf947, "\u{78ca}"), (0xf948, "\u{8cc2}"),
+    (0xf949, "\u{96f7}"), (0xf94a, "\u{58d8}"), (0xf94b, "\u{5c62}"), (0xf94c, "\u{6a13}"),
+    (0xf94d, "\u{6dda}"), (0xf94e, "\u{6f0f}"), (0xf94f, "\u{7d2f}"), (0xf950, "\u{7e37}"),
+    (0xf951, "\u{964b}"), (0xf952, "\u{52d2}"), (0xf953, "\u{808b}"), (0xf954, "\u{51dc}"),
+    (0xf955, "\u{51cc}"), (0xf956, "\u{7a1c}"), (0xf957, "\u{7dbe}"), (0xf958, "\u{83f1}"),
+    (0xf959, "\u{9675}"), (0xf95a, "\u{8b80}"), (0xf95b, "\u{62cf}"), (0xf95c, "\u{6a02}"),
+    (0xf95d, "\u{8afe}"), (0xf95e, "\u{4e39}"), (0xf95f, "\u{5be7}"), (0xf960, "\u{6012}"),
+    (0xf961, "\u{7387}"), (0xf962, "\u{7570}"), (0xf963, "\u{5317}"), (0xf964, "\u{78fb}"),
+    (0xf965, "\u{4fbf}"), (0xf966, "\u{5fa9}"), (0xf967, "\u{4e0d}"), (0xf968, "\u{6ccc}"),
+    (0xf969, "\u{6578}"), (0xf96a, "\u{7d22}"), (0xf96b, "\u{53c3}"), (0xf96c, "\u{585e}"),
+    (0xf96d, "\u{7701}"), (0xf96e, "\u{8449}"), (0xf96f, "\u{8aaa}"), (0xf970, "\u{6bba}"),
+    (0xf971, "\u{8fb0}"), (0xf972, "\u{6c88}"), (0xf973, "\u{62fe}"), (0xf974, "\u{82e5}"),
+    (0xf975, "\u{63a0}"), (0xf976, "\u{7565}"), (0xf977, "\u{4eae}"), (0xf978, "\u{5169}"),
+    (0xf979, "\u{51c9}"), (0xf97a, "\u{6881}"), (0xf97b, "\u{7ce7}"), (0xf97c, "\u{826f}"),
+    (0xf97d, "\u{8ad2}"), (0xf97e, "\u{91cf}"), (0xf97f, "\u{52f5}"), (0xf980, "\u{5442}"),
+    (0xf981, "\u{5973}"), (0xf982, "\u{5eec}"), (0xf983, "\u{65c5}"), (0xf984, "\u{6ffe}"),
+    (0xf985, "\u{792a}"), (0xf986, "\u{95ad}"), (0xf987, "\u{9a6a}"), (0xf988, "\u{9e97}"),
+    (0xf989, "\u{9ece}"), (0xf98a, "\u{529b}"), (0xf98b, "\u{66c6}"), (0xf98c, "\u{6b77}"),
+    (0xf98d, "\u{8f62}"), (0xf98e, "\u{5e74}"), (0xf98f, "\u{6190}"), (0xf990, "\u{6200}"),
+    (0xf991, "\u{649a}"), (0xf992, "\u{6f23}"), (0xf993, "\u{7149}"), (0xf994, "\u{7489}"),
+    (0xf995, "\u{79ca}"), (0xf996, "\u{7df4}"), (0xf997, "\u{806f}"), (0xf998, "\u{8f26}"),
+    (0xf999, "\u{84ee}"), (0xf99a, "\u{9023}"), (0xf99b, "\u{934a}"), (0xf99c, "\u{5217}"),
+    (0xf99d, "\u{52a3}"), (0xf99e, "\u{54bd}"), (0xf99f, "\u{70c8}"), (0xf9a0, "\u{88c2}"),
+    (0xf9a1, "\u{8aaa}"), (0xf9a2, "\u{5ec9}"), (0xf9a3, "\u{5ff5}"), (0xf9a4, "\u{637b}"),
+    (0xf9a5, "\u{6bae}"), (0xf9a6, "\u{7c3e}"), (0xf9a7, "\u{7375}"), (0xf9a8, "\u{4ee4}"),
+    (0xf9a9, "\u{56f9}"), (0xf9aa, "\u{5be7}"), (0xf9ab, "\u{5dba}"), (0xf9ac, "\u{601c}"),
+    (0xf9ad, "\u{73b2}"), (0xf9ae, "\u{7469}"), (0xf9af, "\u{7f9a}"), (0xf9b0, "\u{8046}"),
+    (0xf9b1, "\u{9234}"), (0xf9b2, "\u{96f6}"), (0xf9b3, "\u{9748}"), (0xf9b4, "\u{9818}"),
+    (0xf9b5, "\u{4f8b}"), (0xf9b6, "\u{79ae}"), (0xf9b7, "\u{91b4}"), (0xf9b8, "\u{96b8}"),
+    (0xf9b9, "\u{60e1}"), (0xf9ba, "\u{4e86}"), (0xf9bb, "\u{50da}"), (0xf9bc, "\u{5bee}"),
+    (0xf9bd, "\u{5c3f}"), (0xf9be, "\u{6599}"), (0xf9bf, "\u{6a02}"), (0xf9c0, "\u{71ce}"),
+    (0xf9c1, "\u{7642}"), (0xf9c2, "\u{84fc}"), (0xf9c3, "\u{907c}"), (0xf9c4, "\u{9f8d}"),
+    (0xf9c5, "\u{6688}"), (0xf9c6, "\u{962e}"), (0xf9c7, "\u{5289}"), (0xf9c8, "\u{677b}"),
+    (0xf9c9, "\u{67f3}"), (0xf9ca, "\u{6d41}"), (0xf9cb, "\u{6e9c}"), (0xf9cc, "\u{7409}"),
+    (0xf9cd, "\u{7559}"), (0xf9ce, "\u{786b}"), (0xf9cf, "\u{7d10}"), (0xf9d0, "\u{985e}"),
+    (0xf9d1, "\u{516d}"), (0xf9d2, "\u{622e}"), (0xf9d3, "\u{9678}"), (0xf9d4, "\u{502b}"),
+    (0xf9d5, "\u{5d19}"), (0xf9d6, "\u{6dea}"), (0xf9d7, "\u{8f2a}"), (0xf9d8, "\u{5f8b}"),
+    (0xf9d9, "\u{6144}"), (0xf9da, "\u{6817}"), (0xf9db, "\u{7387}"), (0xf9dc, "\u{9686}"),
+    (0xf9dd, "\u{5229}"), (0xf9de, "\u{540f}"), (0xf9df, "\u{5c65}"), (0xf9e0, "\u{6613}"),
+    (0xf9e1, "\u{674e}"), (0xf9e2, "\u{68a8}"), (0xf9e3, "\u{6ce5}"), (0xf9e4, "\u{7406}"),
+    (0xf9e5, "\u{75e2}"), (0xf9e6, "\u{7f79}"), (0xf9e7, "\u{88cf}"), (0xf9e8, "\u{88e1}"),
+    (0xf9e9, "\u{91cc}"), (0xf9ea, "\u{96e2}"), (0xf9eb, "\u{533f}"), (0xf9ec, "\u{6eba}"),
+    (0xf9ed, "\u{541d}"), (0xf9ee, "\u{71d0}"), (0xf9ef, "\u{7498}"), (0xf9f0, "\u{85fa}"),
+    (0xf9f1, "\u{96a3}"), (0xf9f2, "\u{9c57}"), (0xf9f3, "\u{9e9f}"), (0xf9f4, "\u{6797}"),
+    (0xf9f5, "\u{6dcb}"), (0xf9f6, "\u{81e8}"), (0xf9f7, "\u{7acb}"), (0xf9f8, "\u{7b20}"),
+    (0xf9f9, "\u{7c92}"), (0xf9fa, "\u{72c0}"), (0xf9fb, "\u{7099}"), (0xf9fc, "\u{8b58}"),
+    (0xf9fd, "\u{4ec0}"), (0xf9fe, "\u{8336}"), (0xf9ff, "\u{523a}"), (0xfa00, "\u{5207}"),
+    (0xfa01, "\u{5ea6}"), (0xfa02, "\u{62d3}"), (0xfa03, "\u{7cd6}"), (0xfa04, "\u{5b85}"),
+    (0xfa05, "\u{6d1e}"), (0xfa06, "\u{66b4}"), (0xfa07, "\u{8f3b}"), (0xfa08, "\u{884c}"),
+    (0xfa09, "\u{964d}"), (0xfa0a, "\u{898b}"), (0xfa0b, "\u{5ed3}"), (0xfa0c, "\u{5140}"),
+    (0xfa0d, "\u{55c0}"), (0xfa10, "\u{585a}"), (0xfa12, "\u{6674}"), (0xfa15, "\u{51de}"),
+    (0xfa16, "\u{732a}"), (0xfa17, "\u{76ca}"), (0xfa18, "\u{793c}"), (0xfa19, "\u{795e}"),
+    (0xfa1a, "\u{7965}"), (0xfa1b, "\u{798f}"), (0xfa1c, "\u{9756}"), (0xfa1d, "\u{7cbe}"),
+    (0xfa1e, "\u{7fbd}"), (0xfa20, "\u{8612}"), (0xfa22, "\u{8af8}"), (0xfa25, "\u{9038}"),
+    (0xfa26, "\u{90fd}"), (0xfa2a, "\u{98ef}"), (0xfa2b, "\u{98fc}"), (0xfa2c, "\u{9928}"),
+    (0xfa2d, "\u{9db4}"), (0xfa2e, "\u{90de}"), (0xfa2f, "\u{96b7}"), (0xfa30, "\u{4fae}"),
+    (0xfa31, "\u{50e7}"), (0xfa32, "\u{514d}"), (0xfa33, "\u{52c9}"), (0xfa34, "\u{52e4}"),
+    (0xfa35, "\u{5351}"), (0xfa36, "\u{559d}"), (0xfa37, "\u{5606}"), (0xfa38, "\u{5668}"),
+    (0xfa39, "\u{5840}"), (0xfa3a, "\u{58a8}"), (0xfa3b, "\u{5c64}"), (0xfa3c, "\u{5c6e}"),
+    (0xfa3d, "\u{6094}"), (0xfa3e, "\u{6168}"), (0xfa3f, "\u{618e}"), (0xfa40, "\u{61f2}"),
+    (0xfa41, "\u{654f}"), (0xfa42, "\u{65e2}"), (0xfa43, "\u{6691}"), (0xfa44, "\u{6885}"),
+    (0xfa45, "\u{6d77}"), (0xfa46, "\u{6e1a}"), (0xfa47, "\u{6f22}"), (0xfa48, "\u{716e}"),
+    (0xfa49, "\u{722b}"), (0xfa4a, "\u{7422}"), (0xfa4b, "\u{7891}"), (0xfa4c, "\u{793e}"),
+    (0xfa4d, "\u{7949}"), (0xfa4e, "\u{7948}"), (0xfa4f, "\u{7950}"), (0xfa50, "\u{7956}"),
+    (0xfa51, "\u{795d}"), (0xfa52, "\u{798d}"), (0xfa53, "\u{798e}"), (0xfa54, "\u{7a40}"),
+    (0xfa55, "\u{7a81}"), (0xfa56, "\u{7bc0}"), (0xfa57, "\u{7df4}"), (0xfa58, "\u{7e09}"),
+    (0xfa59, "\u{7e41}"), (0xfa5a, "\u{7f72}"), (0xfa5b, "\u{8005}"), (0xfa5c, "\u{81ed}"),
+    (0xfa5d, "\u{8279}"), (0xfa5e, "\u{8279}"), (0xfa5f, "\u{8457}"), (0xfa60, "\u{8910}"),
+    (0xfa61, "\u{8996}"), (0xfa62, "\u{8b01}"), (0xfa63, "\u{8b39}"), (0xfa64, "\u{8cd3}"),
+    (0xfa65, "\u{8d08}"), (0xfa66, "\u{8fb6}"), (0xfa67, "\u{9038}"), (0xfa68, "\u{96e3}"),
+    (0xfa69, "\u{97ff}"), (0xfa6a, "\u{983b}"), (0xfa6b, "\u{6075}"), (0xfa6c, "\u{242ee}"),
+    (0xfa6d, "\u{8218}"), (0xfa70, "\u{4e26}"), (0xfa71, "\u{51b5}"), (0xfa72, "\u{5168}"),
+    (0xfa73, "\u{4f80}"), (0xfa74, "\u{5145}"), (0xfa75, "\u{5180}"), (0xfa76, "\u{52c7}"),
+    (0xfa77, "\u{52fa}"), (0xfa78, "\u{559d}"), (0xfa79, "\u{5555}"), (0xfa7a, "\u{5599}"),
+    (0xfa7b, "\u{55e2}"), (0xfa7c, "\u{585a}"), (0xfa7d, "\u{58b3}"), (0xfa7e, "\u{5944}"),
+    (0xfa7f, "\u{5954}"), (0xfa80, "\u{5a62}"), (0xfa81, "\u{5b28}"), (0xfa82, "\u{5ed2}"),
+    (0xfa83, "\u{5ed9}"), (0xfa84, "\u{5f69}"), (0xfa85, "\u{5fad}"), (0xfa86, "\u{60d8}"),
+    (0xfa87, "\u{614e}"), (0xfa88, "\u{6108}"), (0xfa89, "\u{618e}"), (0xfa8a, "\u{6160}"),
+    (0xfa8b, "\u{61f2}"), (0xfa8c, "\u{6234}"), (0xfa8d, "\u{63c4}"), (0xfa8e, "\u{641c}"),
+    (0xfa8f, "\u{6452}"), (0xfa90, "\u{6556}"), (0xfa91, "\u{6674}"), (0xfa92, "\u{6717}"),
+    (0xfa93, "\u{671b}"), (0xfa94, "\u{6756}"), (0xfa95, "\u{6b79}"), (0xfa96, "\u{6bba}"),
+    (0xfa97, "\u{6d41}"), (0xfa98, "\u{6edb}"), (0xfa99, "\u{6ecb}"), (0xfa9a, "\u{6f22}"),
+    (0xfa9b, "\u{701e}"), (0xfa9c, "\u{716e}"), (0xfa9d, "\u{77a7}"), (0xfa9e, "\u{7235}"),
+    (0xfa9f, "\u{72af}"), (0xfaa0, "\u{732a}"), (0xfaa1, "\u{7471}"), (0xfaa2, "\u{7506}"),
+    (0xfaa3, "\u{753b}"), (0xfaa4, "\u{761d}"), (0xfaa5, "\u{761f}"), (0xfaa6, "\u{76ca}"),
+    (0xfaa7, "\u{76db}"), (0xfaa8, "\u{76f4}"), (0xfaa9, "\u{774a}"), (0xfaaa, "\u{7740}"),
+    (0xfaab, "\u{78cc}"), (0xfaac, "\u{7ab1}"), (0xfaad, "\u{7bc0}"), (0xfaae, "\u{7c7b}"),
+    (0xfaaf, "\u{7d5b}"), (0xfab0, "\u{7df4}"), (0xfab1, "\u{7f3e}"), (0xfab2, "\u{8005}"),
+    (0xfab3, "\u{8352}"), (0xfab4, "\u{83ef}"), (0xfab5, "\u{8779}"), (0xfab6, "\u{8941}"),
+    (0xfab7, "\u{8986}"), (0xfab8, "\u{8996}"), (0xfab9, "\u{8abf}"), (0xfaba, "\u{8af8}"),
+    (0xfabb, "\u{8acb}"), (0xfabc, "\u{8b01}"), (0xfabd, "\u{8afe}"), (0xfabe, "\u{8aed}"),
+    (0xfabf, "\u{8b39}"), (0xfac0, "\u{8b8a}"), (0xfac1, "\u{8d08}"), (0xfac2, "\u{8f38}"),
+    (0xfac3, "\u{9072}"), (0xfac4, "\u{9199}"), (0xfac5, "\u{9276}"), (0xfac6, "\u{967c}"),
+    (0xfac7, "\u{96e3}"), (0xfac8, "\u{9756}"), (0xfac9, "\u{97db}"), (0xfaca, "\u{97ff}"),
+    (0xfacb, "\u{980b}"), (0xfacc, "\u{983b}"), (0xfacd, "\u{9b12}"), (0xface, "\u{9f9c}"),
+    (0xfacf, "\u{2284a}"), (0xfad0, "\u{22844}"), (0xfad1, "\u{233d5}"), (0xfad2, "\u{3b9d}"),
+    (0xfad3, "\u{4018}"), (0xfad4, "\u{4039}"), (0xfad5, "\u{25249}"), (0xfad6, "\u{25cd0}"),
+    (0xfad7, "\u{27ed3}"), (0xfad8, "\u{9f43}"), (0xfad9, "\u{9f8e}"), (0xfb00, "ff"),
+    (0xfb01, "fi"), (0xfb02, "fl"), (0xfb03, "ffi"), (0xfb04, "ffl"), (0xfb05, "st"),
+    (0xfb06, "st"), (0xfb13, "\u{574}\u{576}"), (0xfb14, "\u{574}\u{565}"),
+    (0xfb15, "\u{574}\u{56b}"), (0xfb16, "\u{57e}\u{576}"), (0xfb17, "\u{574}\u{56d}"),
+    (0xfb1d, "\u{5d9}\u{5b4}"), (0xfb1f, "\u{5f2}\u{5b7}"), (0xfb2a, "\u{5e9}\u{5c1}"),
+    (0xfb2b, "\u{5e9}\u{5c2}"), (0xfb2c, "\u{5e9}\u{5bc}\u{5c1}"),
+    (0xfb2d, "\u{5e9}\u{5bc}\u{5c2}"), (0xfb2e, "\u{5d0}\u{5b7}"), (0xfb2f, "\u{5d0}\u{5b8}"),
+    (0xfb30, "\u{5d0}\u{5bc}"), (0xfb31, "\u{5d1}\u{5bc}"), (0xfb32, "\u{5d2}\u{5bc}"),
+    (0xfb33, "\u{5d3}\u{5bc}"), (0xfb34, "\u{5d4}\u{5bc}"), (0xfb35, "\u{5d5}\u{5bc}"),
+    (0xfb36, "\u{5d6}\u{5bc}"), (0xfb38, "\u{5d8}\u{5bc}"), (0xfb39, "\u{5d9}\u{5bc}"),
+    (0xfb3a, "\u{5da}\u{5bc}"), (0xfb3b, "\u{5db}\u{5bc}"), (0xfb3c, "\u{5dc}\u{5bc}"),
+    (0xfb3e, "\u{5de}\u{5bc}"), (0xfb40, "\u{5e0}\u{5bc}"), (0xfb41, "\u{5e1}\u{5bc}"),
+    (0xfb43, "\u{5e3}\u{5bc}"), (0xfb44, "\u{5e4}\u{5bc}"), (0xfb46, "\u{5e6}\u{5bc}"),
+    (0xfb47, "\u{5e7}\u{5bc}"), (0xfb48, "\u{5e8}\u{5bc}"), (0xfb49, "\u{5e9}\u{5bc}"),
+    (0xfb4a, "\u{5ea}\u{5bc}"), (0xfb4b, "\u{5d5}\u{5b9}"), (0xfb4c, "\u{5d1}\u{5bf}"),
+    (0xfb4d, "\u{5db}\u{5bf}"), (0xfb4e, "\u{5e4}\u{5bf}"), (0xff21, "\u{ff41}"),
+    (0xff22, "\u{ff42}"), (0xff23, "\u{ff43}"), (0xff24, "\u{ff44}"), (0xff25, "\u{ff45}"),
+    (0xff26, "\u{ff46}"), (0xff27, "\u{ff47}"), (0xff28, "\u{ff48}"), (0xff29, "\u{ff49}"),
+    (0xff2a, "\u{ff4a}"), (0xff2b, "\u{ff4b}"), (0xff2c, "\u{ff4c}"), (0xff2d, "\u{ff4d}"),
+    (0xff2e, "\u{ff4e}"), (0xff2f, "\u{ff4f}"), (0xff30, "\u{ff50}"), (0xff31, "\u{ff51}"),
+    (0xff32, "\u{ff52}"), (0xff33, "\u{ff53}"), (0xff34, "\u{ff54}"), (0xff35, "\u{ff55}"),
+    (0xff36, "\u{ff56}"), (0xff37, "\u{ff57}"), (0xff38, "\u{ff58}"), (0xff39, "\u{ff59}"),
+    (0xff3a, "\u{ff5a}"), (0x10400, "\u{10428}"), (0x10401, "\u{10429}"),
+    (0x10402, "\u{1042a}"), (0x10403, "\u{1042b}"), (0x10404, "\u{1042c}"),
+    (0x10405, "\u{1042d}"), (0x10406, "\u{1042e}"), (0x10407, "\u{1042f}"),
+    (0x10408, "\u{10430}"), (0x10409, "\u{10431}"), (0x1040a, "\u{10432}"),
+    (0x1040b, "\u{10433}"), (0x1040c, "\u{10434}"), (0x1040d, "\u{10435}"),
+    (0x1040e, "\u{10436}"), (0x1040f, "\u{10437}"), (0x10410, "\u{10438}"),
+    (0x10411, "\u{10439}"), (0x10412, "\u{1043a}"), (0x10413, "\u{1043b}"),
+    (0x10414, "\u{1043c}"), (0x10415, "\u{1043d}"), (0x10416, "\u{1043e}"),
+    (0x10417, "\u{1043f}"), (0x10418, "\u{10440}"), (0x10419, "\u{10441}"),
+    (0x1041a, "\u{10442}"), (0x1041b, "\u{10443}"), (0x1041c, "\u{10444}"),
+    (0x1041d, "\u{10445}"), (0x1041e, "\u{10446}"), (0x1041f, "\u{10447}"),
+    (0x10420, "\u{10448}"), (0x10421, "\u{10449}"), (0x10422, "\u{1044a}"),
+    (0x10423, "\u{1044b}"), (0x10424, "\u{1044c}"), (0x10425, "\u{1044d}"),
+    (0x10426, "\u{1044e}"), (0x10427, "\u{1044f}"), (0x104b0, "\u{104d8}"),
+    (0x104b1, "\u{104d9}"), (0x104b2, "\u{104da}"), (0x104b3, "\u{104db}"),
+    (0x104b4, "\u{104dc}"), (0x104b5, "\u{104dd}"), (0x104b6, "\u{104de}"),
+    (0x104b7, "\u{104df}"), (0x104b8, "\u{104e0}"), (0x104b9, "\u{104e1}"),
+    (0x104ba, "\u{104e2}"), (0x104bb, "\u{104e3}"), (0x104bc, "\u{104e4}"),
+    (0x104bd, "\u{104e5}"), (0x104be, "\u{104e6}"), (0x104bf, "\u{104e7}"),
+    (0x104c0, "\u{104e8}"), (0x104c1, "\u{104e9}"), (0x104c2, "\u{104ea}"),
+    (0x104c3, "\u{104eb}"), (0x104c4, "\u{104ec}"), (0x104c5, "\u{104ed}"),
+    (0x104c6, "\u{104ee}"), (0x104c7, "\u{104ef}"), (0x104c8, "\u{104f0}"),
+    (0x104c9, "\u{104f1}"), (0x104ca, "\u{104f2}"), (0x104cb, "\u{104f3}"),
+    (0x104cc, "\u{104f4}"), (0x104cd, "\u{104f5}"), (0x104ce, "\u{104f6}"),
+    (0x104cf, "\u{104f7}"), (0x104d0, "\u{104f8}"), (0x104d1, "\u{104f9}"),
+    (0x104d2, "\u{104fa}"), (0x104d3, "\u{104fb}"), (0x10c80, "\u{10cc0}"),
+    (0x10c81, "\u{10cc1}"), (0x10c82, "\u{10cc2}"), (0x10c83, "\u{10cc3}"),
+    (0x10c84, "\u{10cc4}"), (0x10c85, "\u{10cc5}"), (0x10c86, "\u{10cc6}"),
+    (0x10c87, "\u{10cc7}"), (0x10c88, "\u{10cc8}"), (0x10c89, "\u{10cc9}"),
+    (0x10c8a, "\u{10cca}"), (0x10c8b, "\u{10ccb}"), (0x10c8c, "\u{10ccc}"),
+    (0x10c8d, "\u{10ccd}"), (0x10c8e, "\u{10cce}"), (0x10c8f, "\u{10ccf}"),
+    (0x10c90, "\u{10cd0}"), (0x10c91, "\u{10cd1}"), (0x10c92, "\u{10cd2}"),
+    (0x10c93, "\u{10cd3}"), (0x10c94, "\u{10cd4}"), (0x10c95, "\u{10cd5}"),
+    (0x10c96, "\u{10cd6}"), (0x10c97, "\u{10cd7}"), (0x10c98, "\u{10cd8}"),
+    (0x10c99, "\u{10cd9}"), (0x10c9a, "\u{10cda}"), (0x10c9b, "\u{10cdb}"),
+    (0x10c9c, "\u{10cdc}"), (0x10c9d, "\u{10cdd}"), (0x10c9e, "\u{10cde}"),
+    (0x10c9f, "\u{10cdf}"), (0x10ca0, "\u{10ce0}"), (0x10ca1, "\u{10ce1}"),
+    (0x10ca2, "\u{10ce2}"), (0x10ca3, "\u{10ce3}"), (0x10ca4, "\u{10ce4}"),
+    (0x10ca5, "\u{10ce5}"), (0x10ca6, "\u{10ce6}"), (0x10ca7, "\u{10ce7}"),
+    (0x10ca8, "\u{10ce8}"), (0x10ca9, "\u{10ce9}"), (0x10caa, "\u{10cea}"),
+    (0x10cab, "\u{10ceb}"), (0x10cac, "\u{10cec}"), (0x10cad, "\u{10ced}"),
+    (0x10cae, "\u{10cee}"), (0x10caf, "\u{10cef}"), (0x10cb0, "\u{10cf0}"),
+    (0x10cb1, "\u{10cf1}"), (0x10cb2, "\u{10cf2}"), (0x1109a, "\u{11099}\u{110ba}"),
+    (0x1109c, "\u{1109b}\u{110ba}"), (0x110ab, "\u{110a5}\u{110ba}"),
+    (0x1112e, "\u{11131}\u{11127}"), (0x1112f, "\u{11132}\u{11127}"),
+    (0x1134b, "\u{11347}\u{1133e}"), (0x1134c, "\u{11347}\u{11357}"),
+    (0x114bb, "\u{114b9}\u{114ba}"), (0x114bc, "\u{114b9}\u{114b0}"),
+    (0x114be, "\u{114b9}\u{114bd}"), (0x115ba, "\u{115b8}\u{115af}"),
+    (0x115bb, "\u{115b9}\u{115af}"), (0x118a0, "\u{118c0}"), (0x118a1, "\u{118c1}"),
+    (0x118a2, "\u{118c2}"), (0x118a3, "\u{118c3}"), (0x118a4, "\u{118c4}"),
+    (0x118a5, "\u{118c5}"), (0x118a6, "\u{118c6}"), (0x118a7, "\u{118c7}"),
+    (0x118a8, "\u{118c8}"), (0x118a9, "\u{118c9}"), (0x118aa, "\u{118ca}"),
+    (0x118ab, "\u{118cb}"), (0x118ac, "\u{118cc}"), (0x118ad, "\u{118cd}"),
+    (0x118ae, "\u{118ce}"), (0x118af, "\u{118cf}"), (0x118b0, "\u{118d0}"),
+    (0x118b1, "\u{118d1}"), (0x118b2, "\u{118d2}"), (0x118b3, "\u{118d3}"),
+    (0x118b4, "\u{118d4}"), (0x118b5, "\u{118d5}"), (0x118b6, "\u{118d6}"),
+    (0x118b7, "\u{118d7}"), (0x118b8, "\u{118d8}"), (0x118b9, "\u{118d9}"),
+    (0x118ba, "\u{118da}"), (0x118bb, "\u{118db}"), (0x118bc, "\u{118dc}"),
+    (0x118bd, "\u{118dd}"), (0x118be, "\u{118de}"), (0x118bf, "\u{118df}"),
+    (0x16e40, "\u{16e60}"), (0x16e41, "\u{16e61}"), (0x16e42, "\u{16e62}"),
+    (0x16e43, "\u{16e63}"), (0x16e44, "\u{16e64}"), (0x16e45, "\u{16e65}"),
+    (0x16e46, "\u{16e66}"), (0x16e47, "\u{16e67}"), (0x16e48, "\u{16e68}"),
+    (0x16e49, "\u{16e69}"), (0x16e4a, "\u{16e6a}"), (0x16e4b, "\u{16e6b}"),
+    (0x16e4c, "\u{16e6c}"), (0x16e4d, "\u{16e6d}"), (0x16e4e, "\u{16e6e}"),
+    (0x16e4f, "\u{16e6f}"), (0x16e50, "\u{16e70}"), (0x16e51, "\u{16e71}"),
+    (0x16e52, "\u{16e72}"), (0x16e53, "\u{16e73}"), (0x16e54, "\u{16e74}"),
+    (0x16e55, "\u{16e75}"), (0x16e56, "\u{16e76}"), (0x16e57, "\u{16e77}"),
+    (0x16e58, "\u{16e78}"), (0x16e59, "\u{16e79}"), (0x16e5a, "\u{16e7a}"),
+    (0x16e5b, "\u{16e7b}"), (0x16e5c, "\u{16e7c}"), (0x16e5d, "\u{16e7d}"),
+    (0x16e5e, "\u{16e7e}"), (0x16e5f, "\u{16e7f}"), (0x1d15e, "\u{1d157}\u{1d165}"),
+    (0x1d15f, "\u{1d158}\u{1d165}"), (0x1d160, "\u{1d158}\u{1d165}\u{1d16e}"),
+    (0x1d161, "\u{1d158}\u{1d165}\u{1d16f}"), (0x1d162, "\u{1d158}\u{1d165}\u{1d170}"),
+    (0x1d163, "\u{1d158}\u{1d165}\u{1d171}"), (0x1d164, "\u{1d158}\u{1d165}\u{1d172}"),
+    (0x1d1bb, "\u{1d1b9}\u{1d165}"), (0x1d1bc, "\u{1d1ba}\u{1d165}"),
+    (0x1d1bd, "\u{1d1b9}\u{1d165}\u{1d16e}"), (0x1d1be, "\u{1d1ba}\u{1d165}\u{1d16e}"),
+    (0x1d1bf, "\u{1d1b9}\u{1d165}\u{1d16f}"), (0x1d1c0, "\u{1d1ba}\u{1d165}\u{1d16f}"),
+    (0x1e900, "\u{1e922}"), (0x1e901, "\u{1e923}"), (0x1e902, "\u{1e924}"),
+    (0x1e903, "\u{1e925}"), (0x1e904, "\u{1e926}"), (0x1e905, "\u{1e927}"),
+    (0x1e906, "\u{1e928}"), (0x1e907, "\u{1e929}"), (0x1e908, "\u{1e92a}"),
+    (0x1e909, "\u{1e92b}"), (0x1e90a, "\u{1e92c}"), (0x1e90b, "\u{1e92d}"),
+    (0x1e90c, "\u{1e92e}"), (0x1e90d, "\u{1e92f}"), (0x1e90e, "\u{1e930}"),
+    (0x1e90f, "\u{1e931}"), (0x1e910, "\u{1e932}"), (0x1e911, "\u{1e933}"),
+    (0x1e912, "\u{1e934}"), (0x1e913, "\u{1e935}"), (0x1e914, "\u{1e936}"),
+    (0x1e915, "\u{1e937}"), (0x1e916, "\u{1e938}"), (0x1e917, "\u{1e939}"),
+    (0x1e918, "\u{1e93a}"), (0x1e919, "\u{1e93b}"), (0x1e91a, "\u{1e93c}"),
+    (0x1e91b, "\u{1e93d}"), (0x1e91c, "\u{1e93e}"), (0x1e91d, "\u{1e93f}"),
+    (0x1e91e, "\u{1e940}"), (0x1e91f, "\u{1e941}"), (0x1e920, "\u{1e942}"),
+    (0x1e921, "\u{1e943}"), (0x2f800, "\u{4e3d}"), (0x2f801, "\u{4e38}"), (0x2f802, "\u{4e41}"),
+    (0x2f803, "\u{20122}"), (0x2f804, "\u{4f60}"), (0x2f805, "\u{4fae}"), (0x2f806, "\u{4fbb}"),
+    (0x2f807, "\u{5002}"), (0x2f808, "\u{507a}"), (0x2f809, "\u{5099}"), (0x2f80a, "\u{50e7}"),
+    (0x2f80b, "\u{50cf}"), (0x2f80c, "\u{349e}"), (0x2f80d, "\u{2063a}"), (0x2f80e, "\u{514d}"),
+    (0x2f80f, "\u{5154}"), (0x2f810, "\u{5164}"), (0x2f811, "\u{5177}"), (0x2f812, "\u{2051c}"),
+    (0x2f813, "\u{34b9}"), (0x2f814, "\u{5167}"), (0x2f815, "\u{518d}"), (0x2f816, "\u{2054b}"),
+    (0x2f817, "\u{5197}"), (0x2f818, "\u{51a4}"), (0x2f819, "\u{4ecc}"), (0x2f81a, "\u{51ac}"),
+    (0x2f81b, "\u{51b5}"), (0x2f81c, "\u{291df}"), (0x2f81d, "\u{51f5}"), (0x2f81e, "\u{5203}"),
+    (0x2f81f, "\u{34df}"), (0x2f820, "\u{523b}"), (0x2f821, "\u{5246}"), (0x2f822, "\u{5272}"),
+    (0x2f823, "\u{5277}"), (0x2f824, "\u{3515}"), (0x2f825, "\u{52c7}"), (0x2f826, "\u{52c9}"),
+    (0x2f827, "\u{52e4}"), (0x2f828, "\u{52fa}"), (0x2f829, "\u{5305}"), (0x2f82a, "\u{5306}"),
+    (0x2f82b, "\u{5317}"), (0x2f82c, "\u{5349}"), (0x2f82d, "\u{5351}"), (0x2f82e, "\u{535a}"),
+    (0x2f82f, "\u{5373}"), (0x2f830, "\u{537d}"), (0x2f831, "\u{537f}"), (0x2f832, "\u{537f}"),
+    (0x2f833, "\u{537f}"), (0x2f834, "\u{20a2c}"), (0x2f835, "\u{7070}"), (0x2f836, "\u{53ca}"),
+    (0x2f837, "\u{53df}"), (0x2f838, "\u{20b63}"), (0x2f839, "\u{53eb}"), (0x2f83a, "\u{53f1}"),
+    (0x2f83b, "\u{5406}"), (0x2f83c, "\u{549e}"), (0x2f83d, "\u{5438}"), (0x2f83e, "\u{5448}"),
+    (0x2f83f, "\u{5468}"), (0x2f840, "\u{54a2}"), (0x2f841, "\u{54f6}"), (0x2f842, "\u{5510}"),
+    (0x2f843, "\u{5553}"), (0x2f844, "\u{5563}"), (0x2f845, "\u{5584}"), (0x2f846, "\u{5584}"),
+    (0x2f847, "\u{5599}"), (0x2f848, "\u{55ab}"), (0x2f849, "\u{55b3}"), (0x2f84a, "\u{55c2}"),
+    (0x2f84b, "\u{5716}"), (0x2f84c, "\u{5606}"), (0x2f84d, "\u{5717}"), (0x2f84e, "\u{5651}"),
+    (0x2f84f, "\u{5674}"), (0x2f850, "\u{5207}"), (0x2f851, "\u{58ee}"), (0x2f852, "\u{57ce}"),
+    (0x2f853, "\u{57f4}"), (0x2f854, "\u{580d}"), (0x2f855, "\u{578b}"), (0x2f856, "\u{5832}"),
+    (0x2f857, "\u{5831}"), (0x2f858, "\u{58ac}"), (0x2f859, "\u{214e4}"), (0x2f85a, "\u{58f2}"),
+    (0x2f85b, "\u{58f7}"), (0x2f85c, "\u{5906}"), (0x2f85d, "\u{591a}"), (0x2f85e, "\u{5922}"),
+    (0x2f85f, "\u{5962}"), (0x2f860, "\u{216a8}"), (0x2f861, "\u{216ea}"),
+    (0x2f862, "\u{59ec}"), (0x2f863, "\u{5a1b}"), (0x2f864, "\u{5a27}"), (0x2f865, "\u{59d8}"),
+    (0x2f866, "\u{5a66}"), (0x2f867, "\u{36ee}"), (0x2f868, "\u{36fc}"), (0x2f869, "\u{5b08}"),
+    (0x2f86a, "\u{5b3e}"), (0x2f86b, "\u{5b3e}"), (0x2f86c, "\u{219c8}"), (0x2f86d, "\u{5bc3}"),
+    (0x2f86e, "\u{5bd8}"), (0x2f86f, "\u{5be7}"), (0x2f870, "\u{5bf3}"), (0x2f871, "\u{21b18}"),
+    (0x2f872, "\u{5bff}"), (0x2f873, "\u{5c06}"), (0x2f874, "\u{5f53}"), (0x2f875, "\u{5c22}"),
+    (0x2f876, "\u{3781}"), (0x2f877, "\u{5c60}"), (0x2f878, "\u{5c6e}"), (0x2f879, "\u{5cc0}"),
+    (0x2f87a, "\u{5c8d}"), (0x2f87b, "\u{21de4}"), (0x2f87c, "\u{5d43}"),
+    (0x2f87d, "\u{21de6}"), (0x2f87e, "\u{5d6e}"), (0x2f87f, "\u{5d6b}"), (0x2f880, "\u{5d7c}"),
+    (0x2f881, "\u{5de1}"), (0x2f882, "\u{5de2}"), (0x2f883, "\u{382f}"), (0x2f884, "\u{5dfd}"),
+    (0x2f885, "\u{5e28}"), (0x2f886, "\u{5e3d}"), (0x2f887, "\u{5e69}"), (0x2f888, "\u{3862}"),
+    (0x2f889, "\u{22183}"), (0x2f88a, "\u{387c}"), (0x2f88b, "\u{5eb0}"), (0x2f88c, "\u{5eb3}"),
+    (0x2f88d, "\u{5eb6}"), (0x2f88e, "\u{5eca}"), (0x2f88f, "\u{2a392}"), (0x2f890, "\u{5efe}"),
+    (0x2f891, "\u{22331}"), (0x2f892, "\u{22331}"), (0x2f893, "\u{8201}"),
+    (0x2f894, "\u{5f22}"), (0x2f895, "\u{5f22}"), (0x2f896, "\u{38c7}"), (0x2f897, "\u{232b8}"),
+    (0x2f898, "\u{261da}"), (0x2f899, "\u{5f62}"), (0x2f89a, "\u{5f6b}"), (0x2f89b, "\u{38e3}"),
+    (0x2f89c, "\u{5f9a}"), (0x2f89d, "\u{5fcd}"), (0x2f89e, "\u{5fd7}"), (0x2f89f, "\u{5ff9}"),
+    (0x2f8a0, "\u{6081}"), (0x2f8a1, "\u{393a}"), (0x2f8a2, "\u{391c}"), (0x2f8a3, "\u{6094}"),
+    (0x2f8a4, "\u{226d4}"), (0x2f8a5, "\u{60c7}"), (0x2f8a6, "\u{6148}"), (0x2f8a7, "\u{614c}"),
+    (0x2f8a8, "\u{614e}"), (0x2f8a9, "\u{614c}"), (0x2f8aa, "\u{617a}"), (0x2f8ab, "\u{618e}"),
+    (0x2f8ac, "\u{61b2}"), (0x2f8ad, "\u{61a4}"), (0x2f8ae, "\u{61af}"), (0x2f8af, "\u{61de}"),
+    (0x2f8b0, "\u{61f2}"), (0x2f8b1, "\u{61f6}"), (0x2f8b2, "\u{6210}"), (0x2f8b3, "\u{621b}"),
+    (0x2f8b4, "\u{625d}"), (0x2f8b5, "\u{62b1}"), (0x2f8b6, "\u{62d4}"), (0x2f8b7, "\u{6350}"),
+    (0x2f8b8, "\u{22b0c}"), (0x2f8b9, "\u{633d}"), (0x2f8ba, "\u{62fc}"), (0x2f8bb, "\u{6368}"),
+    (0x2f8bc, "\u{6383}"), (0x2f8bd, "\u{63e4}"), (0x2f8be, "\u{22bf1}"), (0x2f8bf, "\u{6422}"),
+    (0x2f8c0, "\u{63c5}"), (0x2f8c1, "\u{63a9}"), (0x2f8c2, "\u{3a2e}"), (0x2f8c3, "\u{6469}"),
+    (0x2f8c4, "\u{647e}"), (0x2f8c5, "\u{649d}"), (0x2f8c6, "\u{6477}"), (0x2f8c7, "\u{3a6c}"),
+    (0x2f8c8, "\u{654f}"), (0x2f8c9, "\u{656c}"), (0x2f8ca, "\u{2300a}"), (0x2f8cb, "\u{65e3}"),
+    (0x2f8cc, "\u{66f8}"), (0x2f8cd, "\u{6649}"), (0x2f8ce, "\u{3b19}"), (0x2f8cf, "\u{6691}"),
+    (0x2f8d0, "\u{3b08}"), (0x2f8d1, "\u{3ae4}"), (0x2f8d2, "\u{5192}"), (0x2f8d3, "\u{5195}"),
+    (0x2f8d4, "\u{6700}"), (0x2f8d5, "\u{669c}"), (0x2f8d6, "\u{80ad}"), (0x2f8d7, "\u{43d9}"),
+    (0x2f8d8, "\u{6717}"), (0x2f8d9, "\u{671b}"), (0x2f8da, "\u{6721}"), (0x2f8db, "\u{675e}"),
+    (0x2f8dc, "\u{6753}"), (0x2f8dd, "\u{233c3}"), (0x2f8de, "\u{3b49}"), (0x2f8df, "\u{67fa}"),
+    (0x2f8e0, "\u{6785}"), (0x2f8e1, "\u{6852}"), (0x2f8e2, "\u{6885}"), (0x2f8e3, "\u{2346d}"),
+    (0x2f8e4, "\u{688e}"), (0x2f8e5, "\u{681f}"), (0x2f8e6, "\u{6914}"), (0x2f8e7, "\u{3b9d}"),
+    (0x2f8e8, "\u{6942}"), (0x2f8e9, "\u{69a3}"), (0x2f8ea, "\u{69ea}"), (0x2f8eb, "\u{6aa8}"),
+    (0x2f8ec, "\u{236a3}"), (0x2f8ed, "\u{6adb}"), (0x2f8ee, "\u{3c18}"), (0x2f8ef, "\u{6b21}"),
+    (0x2f8f0, "\u{238a7}"), (0x2f8f1, "\u{6b54}"), (0x2f8f2, "\u{3c4e}"), (0x2f8f3, "\u{6b72}"),
+    (0x2f8f4, "\u{6b9f}"), (0x2f8f5, "\u{6bba}"), (0x2f8f6, "\u{6bbb}"), (0x2f8f7, "\u{23a8d}"),
+    (0x2f8f8, "\u{21d0b}"), (0x2f8f9, "\u{23afa}"), (0x2f8fa, "\u{6c4e}"),
+    (0x2f8fb, "\u{23cbc}"), (0x2f8fc, "\u{6cbf}"), (0x2f8fd, "\u{6ccd}"), (0x2f8fe, "\u{6c67}"),
+    (0x2f8ff, "\u{6d16}"), (0x2f900, "\u{6d3e}"), (0x2f901, "\u{6d77}"), (0x2f902, "\u{6d41}"),
+    (0x2f903, "\u{6d69}"), (0x2f904, "\u{6d78}"), (0x2f905, "\u{6d85}"), (0x2f906, "\u{23d1e}"),
+    (0x2f907, "\u{6d34}"), (0x2f908, "\u{6e2f}"), (0x2f909, "\u{6e6e}"), (0x2f90a, "\u{3d33}"),
+    (0x2f90b, "\u{6ecb}"), (0x2f90c, "\u{6ec7}"), (0x2f90d, "\u{23ed1}"), (0x2f90e, "\u{6df9}"),
+    (0x2f90f, "\u{6f6e}"), (0x2f910, "\u{23f5e}"), (0x2f911, "\u{23f8e}"),
+    (0x2f912, "\u{6fc6}"), (0x2f913, "\u{7039}"), (0x2f914, "\u{701e}"), (0x2f915, "\u{701b}"),
+    (0x2f916, "\u{3d96}"), (0x2f917, "\u{704a}"), (0x2f918, "\u{707d}"), (0x2f919, "\u{7077}"),
+    (0x2f91a, "\u{70ad}"), (0x2f91b, "\u{20525}"), (0x2f91c, "\u{7145}"),
+    (0x2f91d, "\u{24263}"), (0x2f91e, "\u{719c}"), (0x2f91f, "\u{243ab}"),
+    (0x2f920, "\u{7228}"), (0x2f921, "\u{7235}"), (0x2f922, "\u{7250}"), (0x2f923, "\u{24608}"),
+    (0x2f924, "\u{7280}"), (0x2f925, "\u{7295}"), (0x2f926, "\u{24735}"),
+    (0x2f927, "\u{24814}"), (0x2f928, "\u{737a}"), (0x2f929, "\u{738b}"), (0x2f92a, "\u{3eac}"),
+    (0x2f92b, "\u{73a5}"), (0x2f92c, "\u{3eb8}"), (0x2f92d, "\u{3eb8}"), (0x2f92e, "\u{7447}"),
+    (0x2f92f, "\u{745c}"), (0x2f930, "\u{7471}"), (0x2f931, "\u{7485}"), (0x2f932, "\u{74ca}"),
+    (0x2f933, "\u{3f1b}"), (0x2f934, "\u{7524}"), (0x2f935, "\u{24c36}"), (0x2f936, "\u{753e}"),
+    (0x2f937, "\u{24c92}"), (0x2f938, "\u{7570}"), (0x2f939, "\u{2219f}"),
+    (0x2f93a, "\u{7610}"), (0x2f93b, "\u{24fa1}"), (0x2f93c, "\u{24fb8}"),
+    (0x2f93d, "\u{25044}"), (0x2f93e, "\u{3ffc}"), (0x2f93f, "\u{4008}"), (0x2f940, "\u{76f4}"),
+    (0x2f941, "\u{250f3}"), (0x2f942, "\u{250f2}"), (0x2f943, "\u{25119}"),
+    (0x2f944, "\u{25133}"), (0x2f945, "\u{771e}"), (0x2f946, "\u{771f}"), (0x2f947, "\u{771f}"),
+    (0x2f948, "\u{774a}"), (0x2f949, "\u{4039}"), (0x2f94a, "\u{778b}"), (0x2f94b, "\u{4046}"),
+    (0x2f94c, "\u{4096}"), (0x2f94d, "\u{2541d}"), (0x2f94e, "\u{784e}"), (0x2f94f, "\u{788c}"),
+    (0x2f950, "\u{78cc}"), (0x2f951, "\u{40e3}"), (0x2f952, "\u{25626}"), (0x2f953, "\u{7956}"),
+    (0x2f954, "\u{2569a}"), (0x2f955, "\u{256c5}"), (0x2f956, "\u{798f}"),
+    (0x2f957, "\u{79eb}"), (0x2f958, "\u{412f}"), (0x2f959, "\u{7a40}"), (0x2f95a, "\u{7a4a}"),
+    (0x2f95b, "\u{7a4f}"), (0x2f95c, "\u{2597c}"), (0x2f95d, "\u{25aa7}"),
+    (0x2f95e, "\u{25aa7}"), (0x2f95f, "\u{7aee}"), (0x2f960, "\u{4202}"),
+    (0x2f961, "\u{25bab}"), (0x2f962, "\u{7bc6}"), (0x2f963, "\u{7bc9}"), (0x2f964, "\u{4227}"),
+    (0x2f965, "\u{25c80}"), (0x2f966, "\u{7cd2}"), (0x2f967, "\u{42a0}"), (0x2f968, "\u{7ce8}"),
+    (0x2f969, "\u{7ce3}"), (0x2f96a, "\u{7d00}"), (0x2f96b, "\u{25f86}"), (0x2f96c, "\u{7d63}"),
+    (0x2f96d, "\u{4301}"), (0x2f96e, "\u{7dc7}"), (0x2f96f, "\u{7e02}"), (0x2f970, "\u{7e45}"),
+    (0x2f971, "\u{4334}"), (0x2f972, "\u{26228}"), (0x2f973, "\u{26247}"),
+    (0x2f974, "\u{4359}"), (0x2f975, "\u{262d9}"), (0x2f976, "\u{7f7a}"),
+    (0x2f977, "\u{2633e}"), (0x2f978, "\u{7f95}"), (0x2f979, "\u{7ffa}"), (0x2f97a, "\u{8005}"),
+    (0x2f97b, "\u{264da}"), (0x2f97c, "\u{26523}"), (0x2f97d, "\u{8060}"),
+    (0x2f97e, "\u{265a8}"), (0x2f97f, "\u{8070}"), (0x2f980, "\u{2335f}"),
+    (0x2f981, "\u{43d5}"), (0x2f982, "\u{80b2}"), (0x2f983, "\u{8103}"), (0x2f984, "\u{440b}"),
+    (0x2f985, "\u{813e}"), (0x2f986, "\u{5ab5}"), (0x2f987, "\u{267a7}"),
+    (0x2f988, "\u{267b5}"), (0x2f989, "\u{23393}"), (0x2f98a, "\u{2339c}"),
+    (0x2f98b, "\u{8201}"), (0x2f98c, "\u{8204}"), (0x2f98d, "\u{8f9e}"), (0x2f98e, "\u{446b}"),
+    (0x2f98f, "\u{8291}"), (0x2f990, "\u{828b}"), (0x2f991, "\u{829d}"), (0x2f992, "\u{52b3}"),
+    (0x2f993, "\u{82b1}"), (0x2f994, "\u{82b3}"), (0x2f995, "\u{82bd}"), (0x2f996, "\u{82e6}"),
+    (0x2f997, "\u{26b3c}"), (0x2f998, "\u{82e5}"), (0x2f999, "\u{831d}"), (0x2f99a, "\u{8363}"),
+    (0x2f99b, "\u{83ad}"), (0x2f99c, "\u{8323}"), (0x2f99d, "\u{83bd}"), (0x2f99e, "\u{83e7}"),
+    (0x2f99f, "\u{8457}"), (0x2f9a0, "\u{8353}"), (0x2f9a1, "\u{83ca}"), (0x2f9a2, "\u{83cc}"),
+    (0x2f9a3, "\u{83dc}"), (0x2f9a4, "\u{26c36}"), (0x2f9a5, "\u{26d6b}"),
+    (0x2f9a6, "\u{26cd5}"), (0x2f9a7, "\u{452b}"), (0x2f9a8, "\u{84f1}"), (0x2f9a9, "\u{84f3}"),
+    (0x2f9aa, "\u{8516}"), (0x2f9ab, "\u{273ca}"), (0x2f9ac, "\u{8564}"),
+    (0x2f9ad, "\u{26f2c}"), (0x2f9ae, "\u{455d}"), (0x2f9af, "\u{4561}"),
+    (0x2f9b0, "\u{26fb1}"), (0x2f9b1, "\u{270d2}"), (0x2f9b2, "\u{456b}"),
+    (0x2f9b3, "\u{8650}"), (0x2f9b4, "\u{865c}"), (0x2f9b5, "\u{8667}"), (0x2f9b6, "\u{8669}"),
+    (0x2f9b7, "\u{86a9}"), (0x2f9b8, "\u{8688}"), (0x2f9b9, "\u{870e}"), (0x2f9ba, "\u{86e2}"),
+    (0x2f9bb, "\u{8779}"), (0x2f9bc, "\u{8728}"), (0x2f9bd, "\u{876b}"), (0x2f9be, "\u{8786}"),
+    (0x2f9bf, "\u{45d7}"), (0x2f9c0, "\u{87e1}"), (0x2f9c1, "\u{8801}"), (0x2f9c2, "\u{45f9}"),
+    (0x2f9c3, "\u{8860}"), (0x2f9c4, "\u{8863}"), (0x2f9c5, "\u{27667}"), (0x2f9c6, "\u{88d7}"),
+    (0x2f9c7, "\u{88de}"), (0x2f9c8, "\u{4635}"), (0x2f9c9, "\u{88fa}"), (0x2f9ca, "\u{34bb}"),
+    (0x2f9cb, "\u{278ae}"), (0x2f9cc, "\u{27966}"), (0x2f9cd, "\u{46be}"),
+    (0x2f9ce, "\u{46c7}"), (0x2f9cf, "\u{8aa0}"), (0x2f9d0, "\u{8aed}"), (0x2f9d1, "\u{8b8a}"),
+    (0x2f9d2, "\u{8c55}"), (0x2f9d3, "\u{27ca8}"), (0x2f9d4, "\u{8cab}"), (0x2f9d5, "\u{8cc1}"),
+    (0x2f9d6, "\u{8d1b}"), (0x2f9d7, "\u{8d77}"), (0x2f9d8, "\u{27f2f}"),
+    (0x2f9d9, "\u{20804}"), (0x2f9da, "\u{8dcb}"), (0x2f9db, "\u{8dbc}"), (0x2f9dc, "\u{8df0}"),
+    (0x2f9dd, "\u{208de}"), (0x2f9de, "\u{8ed4}"), (0x2f9df, "\u{8f38}"),
+    (0x2f9e0, "\u{285d2}"), (0x2f9e1, "\u{285ed}"), (0x2f9e2, "\u{9094}"),
+    (0x2f9e3, "\u{90f1}"), (0x2f9e4, "\u{9111}"), (0x2f9e5, "\u{2872e}"), (0x2f9e6, "\u{911b}"),
+    (0x2f9e7, "\u{9238}"), (0x2f9e8, "\u{92d7}"), (0x2f9e9, "\u{92d8}"), (0x2f9ea, "\u{927c}"),
+    (0x2f9eb, "\u{93f9}"), (0x2f9ec, "\u{9415}"), (0x2f9ed, "\u{28bfa}"), (0x2f9ee, "\u{958b}"),
+    (0x2f9ef, "\u{4995}"), (0x2f9f0, "\u{95b7}"), (0x2f9f1, "\u{28d77}"), (0x2f9f2, "\u{49e6}"),
+    (0x2f9f3, "\u{96c3}"), (0x2f9f4, "\u{5db2}"), (0x2f9f5, "\u{9723}"), (0x2f9f6, "\u{29145}"),
+    (0x2f9f7, "\u{2921a}"), (0x2f9f8, "\u{4a6e}"), (0x2f9f9, "\u{4a76}"), (0x2f9fa, "\u{97e0}"),
+    (0x2f9fb, "\u{2940a}"), (0x2f9fc, "\u{4ab2}"), (0x2f9fd, "\u{29496}"),
+    (0x2f9fe, "\u{980b}"), (0x2f9ff, "\u{980b}"), (0x2fa00, "\u{9829}"), (0x2fa01, "\u{295b6}"),
+    (0x2fa02, "\u{98e2}"), (0x2fa03, "\u{4b33}"), (0x2fa04, "\u{9929}"), (0x2fa05, "\u{99a7}"),
+    (0x2fa06, "\u{99c2}"), (0x2fa07, "\u{99fe}"), (0x2fa08, "\u{4bce}"), (0x2fa09, "\u{29b30}"),
+    (0x2fa0a, "\u{9b12}"), (0x2fa0b, "\u{9c40}"), (0x2fa0c, "\u{9cfd}"), (0x2fa0d, "\u{4cce}"),
+    (0x2fa0e, "\u{4ced}"), (0x2fa0f, "\u{9d67}"), (0x2fa10, "\u{2a0ce}"), (0x2fa11, "\u{4cf8}"),
+    (0x2fa12, "\u{2a105}"), (0x2fa13, "\u{2a20e}"), (0x2fa14, "\u{2a291}"),
+    (0x2fa15, "\u{9ebb}"), (0x2fa16, "\u{4d56}"), (0x2fa17, "\u{9ef9}"), (0x2fa18, "\u{9efe}"),
+    (0x2fa19, "\u{9f05}"), (0x2fa1a, "\u{9f0f}"), (0x2fa1b, "\u{9f16}"), (0x2fa1c, "\u{9f3b}"),
+    (0x2fa1d, "\u{2a600}"),
+];
+
+/// The canonical combining classes other than 0, as sorted ranges of
+/// code points.
+#[rustfmt::skip]
+const CCC_TABLE: &[(u32, u32, u8)] = &[
+    (0x300, 0x314, 230), (0x315, 0x315, 232), (0x316, 0x319, 220), (0x31a, 0x31a, 232),
+    (0x31b, 0x31b, 216), (0x31c, 0x320, 220), (0x321, 0x322, 202), (0x323, 0x326, 220),
+    (0x327, 0x328, 202), (0x329, 0x333, 220), (0x334, 0x338, 1), (0x339, 0x33c, 220),
+    (0x33d, 0x344, 230), (0x345, 0x345, 240), (0x346, 0x346, 230), (0x347, 0x349, 220),
+    (0x34a, 0x34c, 230), (0x34d, 0x34e, 220), (0x350, 0x352, 230), (0x353, 0x356, 220),
+    (0x357, 0x357, 230), (0x358, 0x358, 232), (0x359, 0x35a, 220), (0x35b, 0x35b, 230),
+    (0x35c, 0x35c, 233), (0x35d, 0x35e, 234), (0x35f, 0x35f, 233), (0x360, 0x361, 234),
+    (0x362, 0x362, 233), (0x363, 0x36f, 230), (0x483, 0x487, 230), (0x591, 0x591, 220),
+    (0x592, 0x595, 230), (0x596, 0x596, 220), (0x597, 0x599, 230), (0x59a, 0x59a, 222),
+    (0x59b, 0x59b, 220), (0x59c, 0x5a1, 230), (0x5a2, 0x5a7, 220), (0x5a8, 0x5a9, 230),
+    (0x5aa, 0x5aa, 220), (0x5ab, 0x5ac, 230), (0x5ad, 0x5ad, 222), (0x5ae, 0x5ae, 228),
+    (0x5af, 0x5af, 230), (0x5b0, 0x5b0, 10), (0x5b1, 0x5b1, 11), (0x5b2, 0x5b2, 12),
+    (0x5b3, 0x5b3, 13), (0x5b4, 0x5b4, 14), (0x5b5, 0x5b5, 15), (0x5b6, 0x5b6, 16),
+    (0x5b7, 0x5b7, 17), (0x5b8, 0x5b8, 18), (0x5b9, 0x5ba, 19), (0x5bb, 0x5bb, 20),
+    (0x5bc, 0x5bc, 21), (0x5bd, 0x5bd, 22), (0x5bf, 0x5bf, 23), (0x5c1, 0x5c1, 24),
+    (0x5c2, 0x5c2, 25), (0x5c4, 0x5c4, 230), (0x5c5, 0x5c5, 220), (0x5c7, 0x5c7, 18),
+    (0x610, 0x617, 230), (0x618, 0x618, 30), (0x619, 0x619, 31), (0x61a, 0x61a, 32),
+    (0x64b, 0x64b, 27), (0x64c, 0x64c, 28), (0x64d, 0x64d, 29), (0x64e, 0x64e, 30),
+    (0x64f, 0x64f, 31), (0x650, 0x650, 32), (0x651, 0x651, 33), (0x652, 0x652, 34),
+    (0x653, 0x654, 230), (0x655, 0x656, 220), (0x657, 0x65b, 230), (0x65c, 0x65c, 220),
+    (0x65d, 0x65e, 230), (0x65f, 0x65f, 220), (0x670, 0x670, 35), (0x6d6, 0x6dc, 230),
+    (0x6df, 0x6e2, 230), (0x6e3, 0x6e3, 220), (0x6e4, 0x6e4, 230), (0x6e7, 0x6e8, 230),
+    (0x6ea, 0x6ea, 220), (0x6eb, 0x6ec, 230), (0x6ed, 0x6ed, 220), (0x711, 0x711, 36),
+    (0x730, 0x730, 230), (0x731, 0x731, 220), (0x732, 0x733, 230), (0x734, 0x734, 220),
+    (0x735, 0x736, 230), (0x737, 0x739, 220), (0x73a, 0x73a, 230), (0x73b, 0x73c, 220),
+    (0x73d, 0x73d, 230), (0x73e, 0x73e, 220), (0x73f, 0x741, 230), (0x742, 0x742, 220),
+    (0x743, 0x743, 230), (0x744, 0x744, 220), (0x745, 0x745, 230), (0x746, 0x746, 220),
+    (0x747, 0x747, 230), (0x748, 0x748, 220), (0x749, 0x74a, 230), (0x7eb, 0x7f1, 230),
+    (0x7f2, 0x7f2, 220), (0x7f3, 0x7f3, 230), (0x7fd, 0x7fd, 220), (0x816, 0x819, 230),
+    (0x81b, 0x823, 230), (0x825, 0x827, 230), (0x829, 0x82d, 230), (0x859, 0x85b, 220),
+    (0x8d3, 0x8d3, 220), (0x8d4, 0x8e1, 230), (0x8e3, 0x8e3, 220), (0x8e4, 0x8e5, 230),
+    (0x8e6, 0x8e6, 220), (0x8e7, 0x8e8, 230), (0x8e9, 0x8e9, 220), (0x8ea, 0x8ec, 230),
+    (0x8ed, 0x8ef, 220), (0x8f0, 0x8f0, 27), (0x8f1, 0x8f1, 28), (0x8f2, 0x8f2, 29),
+    (0x8f3, 0x8f5, 230), (0x8f6, 0x8f6, 220), (0x8f7, 0x8f8, 230), (0x8f9, 0x8fa, 220),
+    (0x8fb, 0x8ff, 230), (0x93c, 0x93c, 7), (0x94d, 0x94d, 9), (0x951, 0x951, 230),
+    (0x952, 0x952, 220), (0x953, 0x954, 230), (0x9bc, 0x9bc, 7), (0x9cd, 0x9cd, 9),
+    (0x9fe, 0x9fe, 230), (0xa3c, 0xa3c, 7), (0xa4d, 0xa4d, 9), (0xabc, 0xabc, 7),
+    (0xacd, 0xacd, 9), (0xb3c, 0xb3c, 7), (0xb4d, 0xb4d, 9), (0xbcd, 0xbcd, 9),
+    (0xc4d, 0xc4d, 9), (0xc55, 0xc55, 84), (0xc56, 0xc56, 91), (0xcbc, 0xcbc, 7),
+    (0xccd, 0xccd, 9), (0xd3b, 0xd3c, 9), (0xd4d, 0xd4d, 9), (0xdca, 0xdca, 9),
+    (0xe38, 0xe39, 103), (0xe3a, 0xe3a, 9), (0xe48, 0xe4b, 107), (0xeb8, 0xeb9, 118),
+    (0xeba, 0xeba, 9), (0xec8, 0xecb, 122), (0xf18, 0xf19, 220), (0xf35, 0xf35, 220),
+    (0xf37, 0xf37, 220), (0xf39, 0xf39, 216), (0xf71, 0xf71, 129), (0xf72, 0xf72, 130),
+    (0xf74, 0xf74, 132), (0xf7a, 0xf7d, 130), (0xf80, 0xf80, 130), (0xf82, 0xf83, 230),
+    (0xf84, 0xf84, 9), (0xf86, 0xf87, 230), (0xfc6, 0xfc6, 220), (0x1037, 0x1037, 7),
+    (0x1039, 0x103a, 9), (0x108d, 0x108d, 220), (0x135d, 0x135f, 230), (0x1714, 0x1714, 9),
+    (0x1734, 0x1734, 9), (0x17d2, 0x17d2, 9), (0x17dd, 0x17dd, 230), (0x18a9, 0x18a9, 228),
+    (0x1939, 0x1939, 222), (0x193a, 0x193a, 230), (0x193b, 0x193b, 220), (0x1a17, 0x1a17, 230),
+    (0x1a18, 0x1a18, 220), (0x1a60, 0x1a60, 9), (0x1a75, 0x1a7c, 230), (0x1a7f, 0x1a7f, 220),
+    (0x1ab0, 0x1ab4, 230), (0x1ab5, 0x1aba, 220), (0x1abb, 0x1abc, 230), (0x1abd, 0x1abd, 220),
+    (0x1b34, 0x1b34, 7), (0x1b44, 0x1b44, 9), (0x1b6b, 0x1b6b, 230), (0x1b6c, 0x1b6c, 220),
+    (0x1b6d, 0x1b73, 230), (0x1baa, 0x1bab, 9), (0x1be6, 0x1be6, 7), (0x1bf2, 0x1bf3, 9),
+    (0x1c37, 0x1c37, 7), (0x1cd0, 0x1cd2, 230), (0x1cd4, 0x1cd4, 1), (0x1cd5, 0x1cd9, 220),
+    (0x1cda, 0x1cdb, 230), (0x1cdc, 0x1cdf, 220), (0x1ce0, 0x1ce0, 230), (0x1ce2, 0x1ce8, 1),
+    (0x1ced, 0x1ced, 220), (0x1cf4, 0x1cf4, 230), (0x1cf8, 0x1cf9, 230), (0x1dc0, 0x1dc1, 230),
+    (0x1dc2, 0x1dc2, 220), (0x1dc3, 0x1dc9, 230), (0x1dca, 0x1dca, 220), (0x1dcb, 0x1dcc, 230),
+    (0x1dcd, 0x1dcd, 234), (0x1dce, 0x1dce, 214), (0x1dcf, 0x1dcf, 220), (0x1dd0, 0x1dd0, 202),
+    (0x1dd1, 0x1df5, 230), (0x1df6, 0x1df6, 232), (0x1df7, 0x1df8, 228), (0x1df9, 0x1df9, 220),
+    (0x1dfb, 0x1dfb, 230), (0x1dfc, 0x1dfc, 233), (0x1dfd, 0x1dfd, 220), (0x1dfe, 0x1dfe, 230),
+    (0x1dff, 0x1dff, 220), (0x20d0, 0x20d1, 230), (0x20d2, 0x20d3, 1), (0x20d4, 0x20d7, 230),
+    (0x20d8, 0x20da, 1), (0x20db, 0x20dc, 230), (0x20e1, 0x20e1, 230), (0x20e5, 0x20e6, 1),
+    (0x20e7, 0x20e7, 230), (0x20e8, 0x20e8, 220), (0x20e9, 0x20e9, 230), (0x20ea, 0x20eb, 1),
+    (0x20ec, 0x20ef, 220), (0x20f0, 0x20f0, 230), (0x2cef, 0x2cf1, 230), (0x2d7f, 0x2d7f, 9),
+    (0x2de0, 0x2dff, 230), (0x302a, 0x302a, 218), (0x302b, 0x302b, 228), (0x302c, 0x302c, 232),
+    (0x302d, 0x302d, 222), (0x302e, 0x302f, 224), (0x3099, 0x309a, 8), (0xa66f, 0xa66f, 230),
+    (0xa674, 0xa67d, 230), (0xa69e, 0xa69f, 230), (0xa6f0, 0xa6f1, 230), (0xa806, 0xa806, 9),
+    (0xa8c4, 0xa8c4, 9), (0xa8e0, 0xa8f1, 230), (0xa92b, 0xa92d, 220), (0xa953, 0xa953, 9),
+    (0xa9b3, 0xa9b3, 7), (0xa9c0, 0xa9c0, 9), (0xaab0, 0xaab0, 230), (0xaab2, 0xaab3, 230),
+    (0xaab4, 0xaab4, 220), (0xaab7, 0xaab8, 230), (0xaabe, 0xaabf, 230), (0xaac1, 0xaac1, 230),
+    (0xaaf6, 0xaaf6, 9), (0xabed, 0xabed, 9), (0xfb1e, 0xfb1e, 26), (0xfe20, 0xfe26, 230),
+    (0xfe27, 0xfe2d, 220), (0xfe2e, 0xfe2f, 230), (0x101fd, 0x101fd, 220),
+    (0x102e0, 0x102e0, 220), (0x10376, 0x1037a, 230), (0x10a0d, 0x10a0d, 220),
+    (0x10a0f, 0x10a0f, 230), (0x10a38, 0x10a38, 230), (0x10a39, 0x10a39, 1),
+    (0x10a3a, 0x10a3a, 220), (0x10a3f, 0x10a3f, 9), (0x10ae5, 0x10ae5, 230),
+    (0x10ae6, 0x10ae6, 220), (0x10d24, 0x10d27, 230), (0x10f46, 0x10f47, 220),
+    (0x10f48, 0x10f4a, 230), (0x10f4b, 0x10f4b, 220), (0x10f4c, 0x10f4c, 230),
+    (0x10f4d, 0x10f50, 220), (0x11046, 0x11046, 9), (0x1107f, 0x1107f, 9),
+    (0x110b9, 0x110b9, 9), (0x110ba, 0x110ba, 7), (0x11100, 0x11102, 230),
+    (0x11133, 0x11134, 9), (0x11173, 0x11173, 7), (0x111c0, 0x111c0, 9), (0x111ca, 0x111ca, 7),
+    (0x11235, 0x11235, 9), (0x11236, 0x11236, 7), (0x112e9, 0x112e9, 7), (0x112ea, 0x112ea, 9),
+    (0x1133b, 0x1133c, 7), (0x1134d, 0x1134d, 9), (0x11366, 0x1136c, 230),
+    (0x11370, 0x11374, 230), (0x11442, 0x11442, 9), (0x11446, 0x11446, 7),
+    (0x1145e, 0x1145e, 230), (0x114c2, 0x114c2, 9), (0x114c3, 0x114c3, 7),
+    (0x115bf, 0x115bf, 9), (0x115c0, 0x115c0, 7), (0x1163f, 0x1163f, 9), (0x116b6, 0x116b6, 9),
+    (0x116b7, 0x116b7, 7), (0x1172b, 0x1172b, 9), (0x11839, 0x11839, 9), (0x1183a, 0x1183a, 7),
+    (0x119e0, 0x119e0, 9), (0x11a34, 0x11a34, 9), (0x11a47, 0x11a47, 9), (0x11a99, 0x11a99, 9),
+    (0x11c3f, 0x11c3f, 9), (0x11d42, 0x11d42, 7), (0x11d44, 0x11d45, 9), (0x11d97, 0x11d97, 9),
+    (0x16af0, 0x16af4, 1), (0x16b30, 0x16b36, 230), (0x1bc9e, 0x1bc9e, 1),
+    (0x1d165, 0x1d166, 216), (0x1d167, 0x1d169, 1), (0x1d16d, 0x1d16d, 226),
+    (0x1d16e, 0x1d172, 216), (0x1d17b, 0x1d182, 220), (0x1d185, 0x1d189, 230),
+    (0x1d18a, 0x1d18b, 220), (0x1d1aa, 0x1d1ad, 230), (0x1d242, 0x1d244, 230),
+    (0x1e000, 0x1e006, 230), (0x1e008, 0x1e018, 230), (0x1e01b, 0x1e021, 230),
+    (0x1e023, 0x1e024, 230), (0x1e026, 0x1e02a, 230), (0x1e130, 0x1e136, 230),
+    (0x1e2ec, 0x1e2ef, 230), (0x1e8d0, 0x1e8d6, 220), (0x1e944, 0x1e949, 230),
+    (0x1e94a, 0x1e94a, 7),
+];
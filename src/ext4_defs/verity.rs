@@ -0,0 +1,241 @@
+//! fs-verity, the same on-disk format as Linux ext4.
+//!
+//! A verity file keeps its Merkle tree and descriptor past the end of
+//! its data, where they cannot be read as data:
+//!
+//! - the tree starts at the first 64 KiB boundary after the data. Its
+//!   levels are stored from the root down, each a run of blocks of
+//!   SHA-256 hashes of the blocks in the level below. The lowest level
+//!   hashes the data blocks, and the top level is a single block whose
+//!   hash is the root hash.
+//! - the descriptor is in the block after the tree, and its size is in
+//!   the last 4 bytes of that block.
+//!
+//! Partial blocks are zero-padded before hashing, both at the end of the
+//! data and at the end of each level.
+
+use super::sha::Sha256;
+use super::AsBytes;
+use crate::constants::*;
+use crate::prelude::*;
+
+/// Alignment of the Merkle tree past the end of the data.
+const VERITY_METADATA_ALIGN: u64 = 65536;
+
+/// Version of the descriptor.
+const FS_VERITY_VERSION: u8 = 1;
+
+/// The SHA-256 hash algorithm.
+const FS_VERITY_HASH_ALG_SHA256: u8 = 1;
+
+/// Size of a SHA-256 digest in bytes.
+pub const FS_VERITY_DIGEST_SIZE: usize = 32;
+
+/// Number of hashes in a tree block.
+const HASHES_PER_BLOCK: usize = BLOCK_SIZE / FS_VERITY_DIGEST_SIZE;
+
+/// The descriptor of a verity file, whose digest measures the file.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VerityDescriptor {
+    version: u8,
+    hash_algorithm: u8,
+    log_blocksize: u8,
+    salt_size: u8,
+    /// Size of the signature after the descriptor, always 0 here.
+    sig_size: u32,
+    data_size: u64,
+    root_hash: [u8; 64],
+    salt: [u8; 32],
+    reserved: [u8; 144],
+}
+unsafe impl AsBytes for VerityDescriptor {}
+
+impl VerityDescriptor {
+    /// Create an unsalted SHA-256 descriptor of a file with `data_size`
+    /// bytes of data.
+    pub fn new(data_size: u64) -> Self {
+        Self {
+            version: FS_VERITY_VERSION,
+            hash_algorithm: FS_VERITY_HASH_ALG_SHA256,
+            log_blocksize: BLOCK_SIZE.trailing_zeros() as u8,
+            salt_size: 0,
+            sig_size: 0,
+            data_size: data_size.to_le(),
+            root_hash: [0; 64],
+            salt: [0; 32],
+            reserved: [0; 144],
+        }
+    }
+
+    /// Check that the file is hashed with SHA-256 in blocks of
+    /// `BLOCK_SIZE`, the only parameters supported.
+    pub fn is_supported(&self) -> bool {
+        self.version == FS_VERITY_VERSION
+            && self.hash_algorithm == FS_VERITY_HASH_ALG_SHA256
+            && self.log_blocksize as u32 == BLOCK_SIZE.trailing_zeros()
+            && self.salt_size as usize <= self.salt.len()
+    }
+
+    /// Read the descriptor from the last block of a verity file, which
+    /// ends with the size of the descriptor.
+    ///
+    /// # Return
+    ///
+    /// The descriptor, or `None` if its size is invalid.
+    pub fn from_block(block: &[u8; BLOCK_SIZE]) -> Option<Self> {
+        let size = u32::from_le_bytes(block[BLOCK_SIZE - 4..].try_into().unwrap()) as usize;
+        if size < size_of::<Self>() || size > BLOCK_SIZE - 4 {
+            return None;
+        }
+        Some(Self::from_bytes(&block[..size_of::<Self>()]))
+    }
+
+    /// Build the last block of a verity file, with the descriptor at the
+    /// start and its size at the end.
+    pub fn to_block(self) -> [u8; BLOCK_SIZE] {
+        let mut block = [0; BLOCK_SIZE];
+        block[..size_of::<Self>()].copy_from_slice(self.to_bytes());
+        block[BLOCK_SIZE - 4..].copy_from_slice(&(size_of::<Self>() as u32).to_le_bytes());
+        block
+    }
+
+    /// Get the size of the data in bytes.
+    pub fn data_size(&self) -> u64 {
+        u64::from_le(self.data_size)
+    }
+
+    /// Get the root hash of the Merkle tree.
+    pub fn root_hash(&self) -> [u8; FS_VERITY_DIGEST_SIZE] {
+        self.root_hash[..FS_VERITY_DIGEST_SIZE].try_into().unwrap()
+    }
+
+    pub fn set_root_hash(&mut self, hash: &[u8; FS_VERITY_DIGEST_SIZE]) {
+        self.root_hash[..FS_VERITY_DIGEST_SIZE].copy_from_slice(hash);
+    }
+
+    /// Get the digest of the file, the hash of the descriptor without
+    /// its signature.
+    pub fn digest(&self) -> [u8; FS_VERITY_DIGEST_SIZE] {
+        let mut desc = *self;
+        desc.sig_size = 0;
+        Sha256::digest(desc.to_bytes())
+    }
+
+    /// Hash a data or tree block, prefixed with the salt zero-padded to
+    /// the SHA-256 block size.
+    pub fn hash_block(&self, data: &[u8]) -> [u8; FS_VERITY_DIGEST_SIZE] {
+        let mut sha = Sha256::new();
+        if self.salt_size > 0 {
+            let size = self.salt_size as usize;
+            let mut salt = [0; 64];
+            salt[..size].copy_from_slice(&self.salt[..size]);
+            sha.update(&salt);
+        }
+        sha.update(data);
+        sha.finish()
+    }
+}
+
+/// The layout of the Merkle tree of a file.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// Number of blocks of each level, from the lowest.
+    level_blocks: Vec<u64>,
+    /// Index of the first block of each level in the tree, from the lowest.
+    level_start: Vec<u64>,
+    /// The first logical block of the tree.
+    first_block: LBlockId,
+}
+
+impl MerkleTree {
+    /// The layout of the tree of a file with `data_size` bytes of data.
+    /// A file of at most one block has no tree blocks.
+    pub fn new(data_size: u64) -> Self {
+        let mut level_blocks = Vec::new();
+        let mut blocks = data_size.div_ceil(BLOCK_SIZE as u64);
+        while blocks > 1 {
+            blocks = blocks.div_ceil(HASHES_PER_BLOCK as u64);
+            level_blocks.push(blocks);
+        }
+        // The levels are stored from the root down
+        let mut level_start = vec![0; level_blocks.len()];
+        let mut start = 0;
+        for level in (0..level_blocks.len()).rev() {
+            level_start[level] = start;
+            start += level_blocks[level];
+        }
+        let metadata_pos = data_size.next_multiple_of(VERITY_METADATA_ALIGN);
+        Self {
+            level_blocks,
+            level_start,
+            first_block: (metadata_pos / BLOCK_SIZE as u64) as LBlockId,
+        }
+    }
+
+    /// Get the number of levels.
+    pub fn levels(&self) -> usize {
+        self.level_blocks.len()
+    }
+
+    /// Get the number of tree blocks.
+    pub fn block_count(&self) -> u64 {
+        self.level_blocks.iter().sum()
+    }
+
+    /// Get the first logical block of the tree, past the data.
+    pub fn first_block(&self) -> LBlockId {
+        self.first_block
+    }
+
+    /// Get the logical block of block `index` of `level`.
+    pub fn tree_block(&self, level: usize, index: u64) -> LBlockId {
+        self.first_block + (self.level_start[level] + index) as LBlockId
+    }
+
+    /// Get the logical block of the descriptor, right after the tree.
+    pub fn descriptor_block(&self) -> LBlockId {
+        self.first_block + self.block_count() as LBlockId
+    }
+
+    /// Locate the hash of data block `index` in level 0, or of block
+    /// `index` of a level in the level above.
+    ///
+    /// # Return
+    ///
+    /// The index of the block in its level that holds the hash, and the
+    /// offset of the hash in it.
+    pub fn locate(&self, index: u64) -> (u64, usize) {
+        let offset = (index % HASHES_PER_BLOCK as u64) as usize * FS_VERITY_DIGEST_SIZE;
+        (index / HASHES_PER_BLOCK as u64, offset)
+    }
+
+    /// Build the tree from the hashes of the data blocks.
+    ///
+    /// # Return
+    ///
+    /// The tree blocks in order, and the root hash.
+    pub fn build(
+        &self,
+        desc: &VerityDescriptor,
+        data_hashes: Vec<[u8; FS_VERITY_DIGEST_SIZE]>,
+    ) -> (Vec<[u8; BLOCK_SIZE]>, [u8; FS_VERITY_DIGEST_SIZE]) {
+        let mut blocks = vec![[0; BLOCK_SIZE]; self.block_count() as usize];
+        let mut hashes = data_hashes;
+        for level in 0..self.levels() {
+            let start = self.level_start[level] as usize;
+            for (index, hash) in hashes.iter().enumerate() {
+                let (block, offset) = self.locate(index as u64);
+                blocks[start + block as usize][offset..offset + FS_VERITY_DIGEST_SIZE]
+                    .copy_from_slice(hash);
+            }
+            hashes = blocks[start..start + self.level_blocks[level] as usize]
+                .iter()
+                .map(|block| desc.hash_block(block))
+                .collect();
+        }
+        // An empty file has a zero root hash
+        let root = hashes.first().copied().unwrap_or_default();
+        (blocks, root)
+    }
+}
@@ -6,8 +6,10 @@
 //! entry. The second place where extended attributes can be found is in the block
 //! pointed to by `inode.file_acl`.
 //!
-//! We only implement the seperate data block storage of extended attributes.
+//! Both places are supported. Values are 4-byte aligned, and each entry
+//! carries the hash of its name and value, as e2fsck checks.
 
+use super::crc::*;
 use super::{AsBytes, Block};
 use crate::constants::*;
 use crate::prelude::*;
@@ -23,7 +25,7 @@ pub struct XattrHeader {
     refcount: u32,
     /// Number of disk blocks used.
     blocks: u32,
-    /// Hash value of all attributes. (UNUSED by now)
+    /// Hash value of all attributes.
     hash: u32,
     /// Checksum of the extended attribute block.
     checksum: u32,
@@ -71,7 +73,7 @@ pub struct XattrEntry {
     value_inum: u32,
     /// Length of attribute value.
     value_size: u32,
-    /// Hash value of attribute name and attribute value (UNUSED by now)
+    /// Hash value of attribute name and attribute value.
     hash: u32,
     /// Attribute name, max 255 bytes.
     name: [u8; 255],
@@ -141,11 +143,12 @@ impl XattrEntry {
     pub fn name(&self) -> String {
         let prefix = match self.name_index {
             1 => "user.",
-            2 => "system.posix_acl_access.",
-            3 => "system.posix_acl_default.",
+            2 => "system.posix_acl_access",
+            3 => "system.posix_acl_default",
             4 => "trusted.",
             6 => "security.",
             7 => "system.",
+            9 => "encryption.",
             _ => "",
         };
         let name_bytes = &self.name[..self.name_len as usize];
@@ -153,19 +156,34 @@ impl XattrEntry {
         prefix.to_string() + &name
     }
 
-    /// Get the required size to save a xattr entry, 4-byte aligned
-    pub fn required_size(name: &str) -> usize {
-        let (_, name) = Self::match_name(name);
-        let name_len = name.len();
-        // FakeXattrEntry + name -> align to 4
-        (core::mem::size_of::<FakeXattrEntry>() + name_len + 3) / 4 * 4
-    }
-
     /// Get the used size of this xattr entry, 4-bytes alighed
     pub fn used_size(&self) -> usize {
         (core::mem::size_of::<FakeXattrEntry>() + self.name_len as usize + 3) / 4 * 4
     }
 
+    /// Calc the hash of the entry name and its value.
+    fn calc_hash(&self, value: &[u8]) -> u32 {
+        let mut hash = 0u32;
+        for &c in &self.name[..self.name_len as usize] {
+            hash = hash.rotate_left(5) ^ c as u32;
+        }
+        for word in value.chunks(4) {
+            let mut bytes = [0; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            hash = hash.rotate_left(16) ^ u32::from_le_bytes(bytes);
+        }
+        hash
+    }
+
+    /// Compare the names of two xattr entries, the order of entries in a block
+    fn compare(&self, other: &XattrEntry) -> Ordering {
+        (self.name_index, self.name_len)
+            .cmp(&(other.name_index, other.name_len))
+            .then_with(|| {
+                self.name[..self.name_len as usize].cmp(&other.name[..other.name_len as usize])
+            })
+    }
+
     /// Compare the name of the xattr entry with a given name
     pub fn compare_name(&self, name: &str) -> Ordering {
         let (name_index, name) = Self::match_name(name);
@@ -182,11 +200,18 @@ impl XattrEntry {
 
     /// Match the attribute name prefix to get name index. If one is found,
     /// return the name index and the string with the prefix removed.
+    ///
+    /// POSIX ACLs are stored under a name index with an empty name, and
+    /// the encryption context of an inode is `c` of the encryption index.
     fn match_name(name: &str) -> (u8, &str) {
+        match name {
+            "system.posix_acl_access" => return (2, ""),
+            "system.posix_acl_default" => return (3, ""),
+            "encryption.c" => return (9, "c"),
+            _ => {}
+        }
         let prefixes = [
             ("user.", 1),
-            ("system.posix_acl_access.", 2),
-            ("system.posix_acl_default.", 3),
             ("trusted.", 4),
             ("security.", 6),
             ("system.", 7),
@@ -228,13 +253,73 @@ impl XattrBlock {
         self.0.write_offset_as(0, &header);
     }
 
+    /// Calc and set block checksum
+    pub fn set_checksum(&mut self, uuid: &[u8]) {
+        let mut header: XattrHeader = self.0.read_offset_as(0);
+        header.checksum = self.calc_checksum(uuid);
+        self.0.write_offset_as(0, &header);
+    }
+
+    /// Check block checksum
+    pub fn verify_checksum(&self, uuid: &[u8]) -> bool {
+        let header: XattrHeader = self.0.read_offset_as(0);
+        header.checksum == self.calc_checksum(uuid)
+    }
+
+    /// crc32c(uuid+blocknr+block), with the checksum field zeroed
+    fn calc_checksum(&self, uuid: &[u8]) -> u32 {
+        let mut block = self.0;
+        let mut header: XattrHeader = block.read_offset_as(0);
+        header.checksum = 0;
+        block.write_offset_as(0, &header);
+        let mut csum = crc32(CRC32_INIT, uuid);
+        csum = crc32(csum, &block.id.to_le_bytes());
+        crc32(csum, &block.data)
+    }
+
+    /// Check the block: the magic, entries and values inside the block,
+    /// 4-byte aligned values, and the hashes of the entries and the block.
+    pub fn verify(&self) -> bool {
+        let header: XattrHeader = self.0.read_offset_as(0);
+        if header.magic != XattrHeader::XATTR_MAGIC {
+            return false;
+        }
+        let Some(entries) = xattr_parse(&self.0.data, size_of::<XattrHeader>(), 0) else {
+            return false;
+        };
+        entries.iter().all(|(entry, value)| {
+            entry.value_offset.is_multiple_of(4) && entry.hash == entry.calc_hash(value)
+        }) && header.hash == xattr_block_hash(&entries)
+    }
+
+    /// Rewrite the entries of the block in order, with aligned values and
+    /// correct hashes. Return false if the entries can't be read, leaving
+    /// the block unchanged.
+    pub fn rehash(&mut self) -> bool {
+        let header: XattrHeader = self.0.read_offset_as(0);
+        if header.magic != XattrHeader::XATTR_MAGIC {
+            return false;
+        }
+        let Some(mut entries) = xattr_parse(&self.0.data, size_of::<XattrHeader>(), 0) else {
+            return false;
+        };
+        entries.sort_by(|(a, _), (b, _)| a.compare(b));
+        self.rebuild(entries)
+    }
+
+    /// Check whether the entry table ends at `offset`. The table is
+    /// terminated by 4 zero bytes, since entries with an empty name
+    /// (e.g. POSIX ACLs) have `name_len` 0.
+    fn is_last_entry(&self, offset: usize) -> bool {
+        self.0.data[offset..offset + 4].iter().all(|&b| b == 0)
+    }
+
     /// Get a xattr by name, return the value.
     pub fn get(&self, name: &str) -> Option<&[u8]> {
         let mut entry_start = size_of::<XattrHeader>();
         // Iterate over entry table
         while entry_start < BLOCK_SIZE {
-            // Check `name_len`, 0 indicates the end of the entry table.
-            if self.0.data[entry_start] == 0 {
+            if self.is_last_entry(entry_start) {
                 // Target xattr not found
                 break;
             }
@@ -255,8 +340,7 @@ impl XattrBlock {
         let mut names = Vec::new();
         // Iterate over entry table
         while entry_start < BLOCK_SIZE {
-            // Check `name_len`, 0 indicates the end of the entry table.
-            if self.0.data[entry_start] == 0 {
+            if self.is_last_entry(entry_start) {
                 break;
             }
             let entry: XattrEntry = self.0.read_offset_as(entry_start);
@@ -266,152 +350,279 @@ impl XattrBlock {
         names
     }
 
-    /// Insert a xattr entry into the block. Return true if success.
+    /// Check whether the block holds no xattr.
+    pub fn is_empty(&self) -> bool {
+        self.is_last_entry(size_of::<XattrHeader>())
+    }
+
+    /// Insert a xattr entry into the block, replacing the one with the same
+    /// name. Return true if success.
     pub fn insert(&mut self, name: &str, value: &[u8]) -> bool {
-        let mut p_entry = size_of::<XattrHeader>();
-        let mut p_value = BLOCK_SIZE;
-
-        let mut is_ins_pos_found = false;
-        let mut ins_entry_pos = p_entry;
-        let mut ins_value_pos = p_value;
-        let ins_entry_size = XattrEntry::required_size(name);
-        let ins_value_size = value.len();
-
-        // Iterate over entry table, find the position to insert entry
-        // and the end of entry table
-        while p_entry < BLOCK_SIZE {
-            // Check `name_len`, 0 indicates the end of the entry table.
-            if self.0.data[p_entry] == 0 {
-                // Reach the end of table
+        let mut entries = self.entries();
+        entries.retain(|(entry, _)| !entry.compare_name(name).is_eq());
+        entries.push((XattrEntry::new(name, value.len(), 0), value.to_vec()));
+        // Entries in a block are sorted by name
+        entries.sort_by(|(a, _), (b, _)| a.compare(b));
+        self.rebuild(entries)
+    }
+
+    /// Remove a xattr entry from the block. Return true if success.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let mut entries = self.entries();
+        let len = entries.len();
+        entries.retain(|(entry, _)| !entry.compare_name(name).is_eq());
+        entries.len() != len && self.rebuild(entries)
+    }
+
+    /// Get all entries in the block with their values.
+    fn entries(&self) -> Vec<(XattrEntry, Vec<u8>)> {
+        let mut entry_start = size_of::<XattrHeader>();
+        let mut entries = Vec::new();
+        while entry_start < BLOCK_SIZE {
+            if self.is_last_entry(entry_start) {
                 break;
             }
-            let entry: XattrEntry = self.0.read_offset_as(p_entry);
-            if !is_ins_pos_found && entry.compare_name(name).is_gt() {
-                // Insert before this entry
-                ins_entry_pos = p_entry;
-                ins_value_pos = p_value;
-                is_ins_pos_found = true;
-            }
-            p_value = entry.value_offset as usize;
-            p_entry += entry.used_size();
-        }
-        if !is_ins_pos_found {
-            // Insert at the end of table
-            ins_entry_pos = p_entry;
-            ins_value_pos = p_value;
+            let entry: XattrEntry = self.0.read_offset_as(entry_start);
+            let offset = entry.value_offset as usize;
+            let size = entry.value_size as usize;
+            let value = self.0.data.get(offset..offset + size).unwrap_or_default();
+            entry_start += entry.used_size();
+            entries.push((entry, value.to_vec()));
         }
+        entries
+    }
 
-        // `ins_entry_pos` points to the position to insert entry,
-        // `ins_value_pos - ins_value_size` points to the position to insert value.
-        // `p_entry` points to the start of blank area,
-        // `p_value` points to the last value,
-        // `[p_entry, p_value)` is the blank area.
-        
-        // Check space, '+1' is reserved for blank area
-        if p_value - p_entry < ins_entry_size + ins_value_size + 1 {
-            // Not enough space
+    /// Rewrite the entries and values of the block, and the hash in the
+    /// header. Return false if they don't fit, leaving the block unchanged.
+    fn rebuild(&mut self, mut entries: Vec<(XattrEntry, Vec<u8>)>) -> bool {
+        let mut data = self.0.data;
+        if !xattr_layout(&mut data, size_of::<XattrHeader>(), 0, &mut entries) {
             return false;
         }
+        self.0.data = data;
+        let mut header: XattrHeader = self.0.read_offset_as(0);
+        header.hash = xattr_block_hash(&entries);
+        self.0.write_offset_as(0, &header);
+        true
+    }
+}
 
-        // Move the entries from `ins_entry_pos` to `p_entry`
-        // Copy `[ins_entry_pos, p_entry)` to `[ins_entry_pos+ins_entry_size, p_entry+ins_entry_size)`
-        self.0
-            .data
-            .copy_within(ins_entry_pos..p_entry, ins_entry_pos + ins_entry_size);
-        // Set `[ins_entry_pos..ins_entry_pos+entry_req_size)` to 0
-        self.0.data[ins_entry_pos..ins_entry_pos + ins_entry_size].fill(0);
+/// The hash of an xattr block, 0 if an entry has no hash, as the kernel
+/// computes it.
+fn xattr_block_hash(entries: &[(XattrEntry, Vec<u8>)]) -> u32 {
+    let mut hash = 0u32;
+    for (entry, _) in entries {
+        if entry.hash == 0 {
+            return 0;
+        }
+        hash = hash.rotate_left(16) ^ entry.hash;
+    }
+    hash
+}
 
-        // Move the corresponding values
-        // Copy `[p_value, ins_value_pos)` to `[p_value-ins_value_size, ins_value_pos-ins_value_size)`
-        self.0
-            .data
-            .copy_within(p_value..ins_value_pos, p_value - ins_value_size);
-        // Set `[ins_value_pos-ins_value_size, ins_value_pos)` to 0
-        self.0.data[ins_value_pos - ins_value_size..ins_value_pos].fill(0);
-
-        // Update the value offset of the moved entries
-        let mut p_entry2 = ins_entry_pos + ins_entry_size;
-        while p_entry2 < p_entry + ins_entry_size {
-            let mut entry: XattrEntry = self.0.read_offset_as(p_entry2);
-            entry.value_offset -= ins_value_size as u16;
-            self.0.write_offset_as(p_entry2, &entry);
-            p_entry2 += entry.used_size();
+/// Read the entries of an area with their values. The entry table starts
+/// at `table` and must be terminated by 4 zero bytes, value offsets are
+/// relative to `base`. Return `None` if an entry or a value is outside
+/// the area, a value overlaps the entry table, or a value is stored in
+/// another inode, which is not supported.
+fn xattr_parse(area: &[u8], table: usize, base: usize) -> Option<Vec<(XattrEntry, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    let mut offset = table;
+    while area.get(offset..offset + 4)? != [0; 4] {
+        let name_offset = offset + size_of::<FakeXattrEntry>();
+        let name_len = *area.get(offset)? as usize;
+        if name_offset + name_len > area.len() {
+            return None;
         }
+        let entry = XattrEntry::from_bytes(&area[offset..]);
+        offset += entry.used_size();
+        entries.push(entry);
+    }
+    let table_end = offset + 4;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let start = base + entry.value_offset as usize;
+            let end = start + entry.value_size as usize;
+            if entry.value_inum != 0 || (end > start && start < table_end) || end > area.len() {
+                return None;
+            }
+            Some((entry, area[start..end].to_vec()))
+        })
+        .collect()
+}
 
-        // Insert entry to `[ins_entry_pos, ins_entry_pos+ins_entry_size)`
-        let entry = XattrEntry::new(name, value.len(), ins_value_pos - ins_value_size);
-        self.0.write_offset_as(ins_entry_pos, &entry);
-        // Insert value to `[ins_value_pos-ins_value_size, ins_value_pos)`
-        self.0.write_offset(ins_value_pos - ins_value_size, value);
+/// Write xattr entries to an area. The entry table starts at `table` and
+/// is terminated by 4 zero bytes, the values are 4-byte aligned at the end
+/// of the area, with offsets relative to `base`. Entry offsets and hashes
+/// are updated. Return false if the area is too small.
+fn xattr_layout(
+    area: &mut [u8],
+    table: usize,
+    base: usize,
+    entries: &mut [(XattrEntry, Vec<u8>)],
+) -> bool {
+    let table_size: usize = entries.iter().map(|(entry, _)| entry.used_size()).sum();
+    let values_size: usize = entries
+        .iter()
+        .map(|(_, value)| value.len().next_multiple_of(4))
+        .sum();
+    if table + table_size + 4 + values_size > area.len() {
+        return false;
+    }
+    area[table..].fill(0);
+    let (mut p_entry, mut p_value) = (table, area.len());
+    for (entry, value) in entries.iter_mut() {
+        p_value -= value.len().next_multiple_of(4);
+        area[p_value..p_value + value.len()].copy_from_slice(value);
+        entry.value_offset = (p_value - base) as u16;
+        entry.value_size = value.len() as u32;
+        entry.hash = entry.calc_hash(value);
+        let bytes = entry.to_bytes();
+        area[p_entry..p_entry + bytes.len()].copy_from_slice(bytes);
+        p_entry += entry.used_size();
+    }
+    true
+}
 
-        true
+/// The extended attributes stored in the body of an inode, after the
+/// fields counted by `extra_isize`. The area starts with the magic
+/// number, followed by the entry table. Values are stored at the end of
+/// the area, their offsets are relative to the first entry.
+pub struct XattrIbody(Vec<u8>);
+
+impl XattrIbody {
+    /// Wrap the in-inode xattr area.
+    pub fn new(area: Vec<u8>) -> Self {
+        Self(area)
     }
 
-    /// Remove a xattr entry from the block. Return true if success.
+    /// Get the wrapped area.
+    pub fn area(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Get a xattr by name, return the value.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        let entry = self
+            .entries()
+            .into_iter()
+            .find(|entry| entry.compare_name(name).is_eq())?;
+        let start = 4 + entry.value_offset as usize;
+        self.0.get(start..start + entry.value_size as usize)
+    }
+
+    /// List all xattr names.
+    pub fn list(&self) -> Vec<String> {
+        self.entries().iter().map(XattrEntry::name).collect()
+    }
+
+    /// Set a xattr, replacing the one with the same name. Return false if
+    /// the area doesn't have enough space.
+    pub fn set(&mut self, name: &str, value: &[u8]) -> bool {
+        let Some(mut entries) = self.values() else {
+            return false;
+        };
+        entries.retain(|(entry, _)| !entry.compare_name(name).is_eq());
+        entries.push((XattrEntry::new(name, value.len(), 0), value.to_vec()));
+        self.rebuild(entries)
+    }
+
+    /// Remove a xattr. Return true if success.
     pub fn remove(&mut self, name: &str) -> bool {
-        let mut p_entry = size_of::<XattrHeader>();
-        let mut p_value = BLOCK_SIZE;
-
-        let mut is_rem_pos_found = false;
-        let mut rem_entry_pos = p_entry;
-        let mut rem_value_pos = p_value;
-        let mut rem_entry_size = 0;
-        let mut rem_value_size = 0;
-
-        // Iterate over entry table, find the entry to remove
-        while p_entry < BLOCK_SIZE {
-            // Check `name_len`, 0 indicates the end of the entry table.
-            if self.0.data[p_entry] == 0 {
-                break;
-            }
-            let entry: XattrEntry = self.0.read_offset_as(p_entry);
-            p_value = entry.value_offset as usize;
-            // Compare name
-            if !is_rem_pos_found && entry.compare_name(name).is_eq() {
-                rem_entry_pos = p_entry;
-                rem_value_pos = p_value;
-                rem_entry_size = entry.used_size();
-                rem_value_size = entry.value_size as usize;
-                is_rem_pos_found = true;
-            }
-            p_entry += entry.used_size();
+        let Some(mut entries) = self.values() else {
+            return false;
+        };
+        let len = entries.len();
+        entries.retain(|(entry, _)| !entry.compare_name(name).is_eq());
+        entries.len() != len && self.rebuild(entries)
+    }
+
+    /// Check the entries of the area: entries and values inside the area,
+    /// 4-byte aligned values and the hashes of the entries. Entries of old
+    /// kernels may have no hash.
+    pub fn verify(&self) -> bool {
+        if !self.has_magic() {
+            return true;
         }
-        if !is_rem_pos_found {
+        let Some(entries) = xattr_parse(&self.0, 4, 4) else {
             return false;
+        };
+        entries.iter().all(|(entry, value)| {
+            entry.value_offset.is_multiple_of(4)
+                && (entry.hash == 0 || entry.hash == entry.calc_hash(value))
+        })
+    }
+
+    /// Rewrite the entries of the area with aligned values and correct
+    /// hashes. Return false if the entries can't be read, leaving the area
+    /// unchanged.
+    pub fn rehash(&mut self) -> bool {
+        if !self.has_magic() {
+            return true;
         }
+        match xattr_parse(&self.0, 4, 4) {
+            Some(entries) => self.rebuild(entries),
+            None => false,
+        }
+    }
 
-        // `rem_entry_pos` points to the entry to remove,
-        // `rem_value_pos` points to the value to remove.
-        // `p_entry` points to the start of blank area,
-        // `p_value` points to the last value,
-        // `[p_entry, p_value)` is the blank area.
+    /// Clear the area, removing all xattrs in it.
+    pub fn clear(&mut self) {
+        self.0.fill(0);
+    }
 
-        // Move the following entries
-        // Copy `[rem_entry_pos + rem_entry_size, p_entry)` to `[rem_entry_pos, p_entry - rem_entry_size)`
-        self.0
-            .data
-            .copy_within(rem_entry_pos + rem_entry_size..p_entry, rem_entry_pos);
-        // Set `[p_entry - rem_entry_size, p_entry)` to 0
-        self.0.data[p_entry - rem_entry_size..p_entry].fill(0);
-
-        // Move the corresponding values
-        // Copy `[p_value, rem_value_pos)` to `[p_value + rem_value_size, rem_value_pos + rem_value_size)`
-        self.0.data.copy_within(
-            p_value..rem_value_pos,
-            p_value + rem_value_size,
-        );
-        // Set `[p_value, p_value + rem_value_size)` to 0
-        self.0.data[p_value..p_value + rem_value_size].fill(0);
-
-        // Update the value offset of the moved entries
-        let mut p_entry2 = rem_entry_pos;
-        while p_entry2 < p_entry - rem_entry_size {
-            let mut entry: XattrEntry = self.0.read_offset_as(p_entry2);
-            entry.value_offset += rem_value_size as u16;
-            self.0.write_offset_as(p_entry2, &entry);
-            p_entry2 += entry.used_size();
+    /// Check whether the area starts with the magic number.
+    fn has_magic(&self) -> bool {
+        self.0.len() >= 4 && self.0[..4] == XattrHeader::XATTR_MAGIC.to_le_bytes()
+    }
+
+    /// Get all entries in the area with their values, `None` if a value
+    /// is out of the area.
+    fn values(&self) -> Option<Vec<(XattrEntry, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for entry in self.entries() {
+            let start = 4 + entry.value_offset as usize;
+            let value = self
+                .0
+                .get(start..start + entry.value_size as usize)?
+                .to_vec();
+            entries.push((entry, value));
         }
+        Some(entries)
+    }
 
+    /// Rewrite the magic, the entries and the values of the area. Return
+    /// false if they don't fit, leaving the area unchanged.
+    fn rebuild(&mut self, mut entries: Vec<(XattrEntry, Vec<u8>)>) -> bool {
+        let mut area = vec![0; self.0.len()];
+        if area.len() < 4 || !xattr_layout(&mut area, 4, 4, &mut entries) {
+            return false;
+        }
+        area[..4].copy_from_slice(&XattrHeader::XATTR_MAGIC.to_le_bytes());
+        self.0 = area;
         true
     }
+
+    /// Get all entries in the area.
+    fn entries(&self) -> Vec<XattrEntry> {
+        let mut res = Vec::new();
+        if !self.has_magic() {
+            return res;
+        }
+        let entries = &self.0[4..];
+        let mut offset = 0;
+        while offset + size_of::<FakeXattrEntry>() <= entries.len()
+            && entries[offset..offset + 4] != [0; 4]
+        {
+            let fake_entry = FakeXattrEntry::from_bytes(&entries[offset..]);
+            if offset + size_of::<FakeXattrEntry>() + fake_entry.name_len as usize > entries.len() {
+                break;
+            }
+            let entry = XattrEntry::from_bytes(&entries[offset..]);
+            offset += entry.used_size();
+            res.push(entry);
+        }
+        res
+    }
 }
@@ -5,10 +5,17 @@ mod constants;
 mod error;
 mod ext4;
 mod ext4_defs;
-mod jbd2;
 mod prelude;
+mod sync;
 
 pub use constants::{BLOCK_SIZE, EXT4_ROOT_INO, INODE_BLOCK_SIZE};
 pub use error::{ErrCode, Ext4Error};
-pub use ext4::Ext4;
-pub use ext4_defs::{Block, BlockDevice, DirEntry, FileAttr, FileType, Inode, InodeMode, InodeRef};
+pub use ext4::{
+    AccessMode, AtimePolicy, Capabilities, CheckProblem, CheckReport, Credentials, DataMode,
+    DefragStats, Ext4, FormatFeatures, FormatOptions, MountOptions, RepairAction, RepairReport,
+};
+pub use ext4_defs::{
+    AclEntry, AclTag, AclType, Block, BlockDevice, DirEntry, EncryptionPolicy, FileAttr, FileType,
+    FixedTime, Inode, InodeFlags, InodeMode, InodeRef, PosixAcl, Quota, QuotaLimits, QuotaType,
+    SetAttr, StatFs, TimeSource, Timestamp,
+};